@@ -408,7 +408,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         TransientPolicy::Atomic(FwPolicy::Reachable(r.router_id(), first_prefix))
                     }));
 
-                compute_equivalence_class(&trace, &analyzer.scheduled_fw, &policies)
+                compute_equivalence_class(
+                    &trace,
+                    &analyzer.scheduled_fw,
+                    &policies,
+                    &analyzer.equivalent_egresses,
+                )
             }).collect();
 
             // keep the number of explored fw time series after each sample
@@ -531,19 +531,10 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             last_dp_reaction_dedup.insert((mac_src_filter.clone(), prober_src_ip, prefix_filter.clone()), dp_reaction);
                             Some(dp_reaction)
                         });
-                    let min = cp_reactions.iter().copied().min_by(|a, b| a.total_cmp(b));
-                    let max = cp_reactions.iter().copied().max_by(|a, b| a.total_cmp(b));
                     reaction_times
                         .entry((notify_last.prefixes.len(), 0, peers.len(), *prefix))
                         .or_default()
-                        .push(ReactionTime {
-                            first_cp_reaction: min,
-                            last_cp_reaction: max,
-                            cp_reaction_increment: min
-                                .zip(max)
-                                .map(|(min, max)| (max - min) / (peers.len() - 1) as f64),
-                            dp_reaction,
-                        });
+                        .push(ReactionTime::from_cp_reactions(&cp_reactions, dp_reaction));
 
                     // get timestamps of all responses received by the peers
                     for peer in peers.iter() {
@@ -648,6 +639,7 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     last_cp_reaction: None,
                                     cp_reaction_increment: None,
                                     dp_reaction: Some(dp_reaction),
+                                    num_responding_peers: None,
                                 });
                         }
                     }
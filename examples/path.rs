@@ -215,7 +215,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         0.05,
     )?;
 
-    let result = analyzer.analyze();
+    let result = analyzer.analyze().unwrap();
 
     println!("{}", result);
 
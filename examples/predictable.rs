@@ -99,7 +99,7 @@ fn one_eights(precision: f64) -> Result<(), Box<dyn std::error::Error>> {
             0.95,
             precision,
         )?;
-        let result = analyzer.analyze();
+        let result = analyzer.analyze().unwrap();
         println!("{}", result);
 
         let diff = (result.p_satisfied - (p_exp)).abs();
@@ -178,7 +178,7 @@ fn half_half(precision: f64) -> Result<(), Box<dyn std::error::Error>> {
             0.95,
             precision,
         )?;
-        let result = analyzer.analyze();
+        let result = analyzer.analyze().unwrap();
         println!("{}", result);
 
         let diff = (result.p_satisfied - (p_exp)).abs();
@@ -126,7 +126,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         log::trace!("policies:\n{policies:?}");
         analyzer.set_policies(policies);
 
-        let violation_times = analyzer.analyze().violation_time_distributions;
+        let violation_times = analyzer.analyze().unwrap().violation_time_distributions;
         let mut data: HashMap<(String, usize), Vec<f64>> = HashMap::new();
         for (rid, prefix) in violation_times.keys().sorted() {
             let simulated_distribution = violation_times.get(&(*rid, *prefix)).unwrap();
@@ -0,0 +1,285 @@
+// TRIX: Inference of Transient Violation Times from Logged Routing Events or Collected BGP Messages
+// Copyright (C) 2024-2025 Roland Schmid <roschmi@ethz.ch> and Tibor Schneider <sctibor@ethz.ch>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//! Compare the simulated distribution of violation times with the violation times measured in a
+//! single sample.
+
+use std::collections::HashMap;
+
+use bgpsim::{
+    event::{EventQueue, FmtPriority},
+    forwarding_state::ForwardingState,
+    policies::Policy,
+    prelude::*,
+};
+use itertools::Itertools;
+
+use crate::{
+    records::FWRecord,
+    timing_model::DelayableQueue,
+    transient_specification::{
+        compute_violation_times, EvaluationError, TotalDuration, TransientPolicy,
+    },
+    MultiPrefixConvergenceTrace, Prefix,
+};
+
+use super::Analyzer;
+
+/// Comparison of the simulated and measured violation times of a single policy.
+#[derive(Clone, Debug)]
+pub struct PolicyComparison {
+    /// The compared policy.
+    pub policy: TransientPolicy,
+    /// Violation time measured in the sample, in seconds.
+    pub measured: f64,
+    /// Simulated violation times, in ascending order.
+    pub simulated: Vec<f64>,
+    /// Fraction of simulated samples whose violation time is at most the measured one.
+    pub percentile: f64,
+    /// Whether the measured violation time lies within the central `confidence` interval of the
+    /// simulated distribution.
+    pub agrees: bool,
+}
+
+/// Report comparing the simulated and measured violation times of all policies of an `Analyzer`.
+#[derive(Clone, Debug, Default)]
+pub struct ComparisonReport {
+    /// Comparison of each policy, in the order of `Analyzer::policies`.
+    pub policies: Vec<PolicyComparison>,
+}
+
+impl ComparisonReport {
+    /// Iterate over all policies whose measured violation time diverges from the simulation.
+    pub fn diverging(&self) -> impl Iterator<Item = &PolicyComparison> {
+        self.policies.iter().filter(|comparison| !comparison.agrees)
+    }
+}
+
+/// Build a time series of forwarding updates from measured `FWRecord`s. Records before
+/// `event_start - 1.0` and records that do not change a router's next hops are skipped. The
+/// previous next hops of each update are taken from the preceding updates, or from `fw_state` for
+/// the first update of a router.
+pub fn records_to_trace(
+    fw_state: &ForwardingState<Prefix>,
+    event_start: f64,
+    records: &[FWRecord],
+) -> MultiPrefixConvergenceTrace {
+    let mut trace = MultiPrefixConvergenceTrace::new();
+
+    for record in records.iter().sorted_by(|a, b| a.time.total_cmp(&b.time)) {
+        if record.time < event_start - 1.0 {
+            log::trace!("skipping pre-event changes: {record:?}");
+            continue;
+        }
+
+        let prefix = Prefix::from(record.prefix);
+        let prefix_trace = trace.entry(prefix).or_default();
+
+        let old_nhs = prefix_trace
+            .iter()
+            .rev()
+            // check if nhs have been changed already
+            .find_map(|(fw_deltas, _time)| {
+                fw_deltas.iter().find_map(|(rid, _old_nhs, new_nhs)| {
+                    (*rid == record.src).then_some(new_nhs.clone())
+                })
+            })
+            // or query the original fw_state
+            .unwrap_or(fw_state.get_next_hops(record.src, prefix).to_vec());
+        let new_nhs: Vec<_> = record.next_hop.into_iter().collect();
+        if old_nhs == new_nhs {
+            log::trace!(
+                "skipping unchanged next hops {old_nhs:?} at {:?}",
+                record.src
+            );
+            continue;
+        }
+        let fw_delta = (record.src, old_nhs, new_nhs);
+
+        prefix_trace.push((vec![fw_delta], Some(record.time).into()));
+    }
+
+    trace
+}
+
+/// Compare the simulated distribution of violation times with the violation times measured in a
+/// single sample, given by its forwarding updates `measured_records` and its `event_start`.
+///
+/// The simulated distribution of each policy is obtained from `num_samples` samples, drawn as in
+/// `Analyzer::analyze` (see `Analyzer::sample_traces`), such that policies of the same
+/// `(RouterId, Prefix)` pair are compared separately. A measured violation time agrees with the
+/// simulation if it lies within the central interval of the simulated distribution containing a
+/// fraction of `analyzer.confidence` of all samples. Fails if any sample cannot be evaluated.
+pub fn compare_sample<Q>(
+    analyzer: &Analyzer<Q>,
+    num_samples: usize,
+    event_start: f64,
+    measured_records: &[FWRecord],
+) -> Result<ComparisonReport, EvaluationError>
+where
+    Q: EventQueue<Prefix> + DelayableQueue + Clone + Send + Sync + std::fmt::Debug + PartialEq,
+    Q::Priority: Default + FmtPriority + Clone,
+{
+    // simulated distribution, indexed by sample
+    let traces = analyzer.sample_traces(num_samples);
+    let simulated = analyzer.recheck_policies(&traces, &analyzer.policies)?;
+
+    // measured violation times
+    let trace = records_to_trace(&analyzer.original_fw, event_start, measured_records);
+    let mut queue = analyzer.build_queue();
+    let mut fw_state = analyzer.original_fw.clone();
+    let measured = compute_violation_times(
+        &analyzer.original_net,
+        &mut queue,
+        &mut fw_state,
+        &trace,
+        analyzer.grouped_policies(),
+        None::<&std::path::Path>,
+        &TotalDuration,
+    )?
+    .times;
+
+    let alpha = 1.0 - analyzer.confidence;
+    let policies = analyzer
+        .policies
+        .iter()
+        .map(|policy| {
+            let mut simulated = simulated.get(policy).cloned().unwrap_or_default();
+            simulated.sort_by(|a, b| a.total_cmp(b));
+            let measured = measured.get(policy).copied().unwrap_or_default();
+            compare_policy(policy.clone(), measured, simulated, alpha)
+        })
+        .collect();
+
+    Ok(ComparisonReport { policies })
+}
+
+/// Compare a measured violation time with the sorted `simulated` ones.
+fn compare_policy(
+    policy: TransientPolicy,
+    measured: f64,
+    simulated: Vec<f64>,
+    alpha: f64,
+) -> PolicyComparison {
+    let quantile = |q: f64| -> Option<f64> {
+        let idx = (q * (simulated.len() - 1) as f64).round() as usize;
+        simulated.get(idx).copied()
+    };
+
+    let (percentile, agrees) = if simulated.is_empty() {
+        (0.0, false)
+    } else {
+        let below = simulated.iter().filter(|&&t| t <= measured).count();
+        let lower = quantile(alpha / 2.0).unwrap();
+        let upper = quantile(1.0 - alpha / 2.0).unwrap();
+        (
+            below as f64 / simulated.len() as f64,
+            lower <= measured && measured <= upper,
+        )
+    };
+
+    PolicyComparison {
+        policy,
+        measured,
+        simulated,
+        percentile,
+        agrees,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::{analyzer::test::path_analyzer, transient_specification::PolicyKind};
+
+    #[test]
+    fn compare_policy_agreement() {
+        let policy = PolicyKind::LoopFree.instantiate(0.into(), Prefix::from(0));
+        let simulated = (0..=100).map(|i| i as f64 / 100.0).collect_vec();
+
+        let comparison = compare_policy(policy.clone(), 0.5, simulated.clone(), 0.1);
+        assert!(comparison.agrees);
+        assert!((comparison.percentile - 51.0 / 101.0).abs() < 1e-9);
+
+        let comparison = compare_policy(policy.clone(), 0.99, simulated.clone(), 0.1);
+        assert!(!comparison.agrees);
+
+        let comparison = compare_policy(policy, 0.0, vec![], 0.1);
+        assert!(!comparison.agrees);
+    }
+
+    #[test]
+    fn compare_measured_sample() {
+        let mut analyzer = path_analyzer(1);
+        let net = &analyzer.original_net;
+        let [r0, r1, r2, r2_ext] =
+            ["r0", "r1", "r2", "r2_ext"].map(|r| net.get_router_id(r).unwrap());
+        let prefix = analyzer.event.prefixes()[0];
+        // two policies of the same router and prefix
+        let reachable = PolicyKind::Reachable.instantiate(r0, prefix);
+        let loop_free = PolicyKind::LoopFree.instantiate(r0, prefix);
+        analyzer.set_policies(vec![reachable.clone(), loop_free.clone()]);
+        analyzer.set_seed(42);
+
+        // r0 loses its route, and all routers eventually switch to r2_ext
+        let event_start = 10.0;
+        let record = |time: f64, src: RouterId, next_hop: Option<RouterId>| FWRecord {
+            time,
+            src,
+            src_name: None,
+            prefix: Ipv4Addr::from(prefix),
+            seq: None,
+            next_hop,
+            next_hop_name: None,
+        };
+        let records = vec![
+            record(5.0, r1, Some(r0)),
+            record(10.0, r0, None),
+            record(10.001, r2, Some(r2_ext)),
+            record(10.002, r1, Some(r2)),
+            record(10.003, r0, Some(r1)),
+        ];
+
+        // pre-event and unchanged records are skipped
+        let trace = records_to_trace(&analyzer.original_fw, event_start, &records);
+        assert_eq!(trace[&prefix].len(), 4);
+
+        let num_samples = 20;
+        let report = compare_sample(&analyzer, num_samples, event_start, &records).unwrap();
+        assert_eq!(report.policies.len(), 2);
+        let [reachable_cmp, loop_free_cmp] = [&report.policies[0], &report.policies[1]];
+        assert_eq!(reachable_cmp.policy, reachable);
+        assert_eq!(loop_free_cmp.policy, loop_free);
+        // r0 drops traffic for at least 3ms, but never forwards in a loop
+        assert!(reachable_cmp.measured >= 0.003 - 1e-9);
+        assert_eq!(loop_free_cmp.measured, 0.0);
+        for comparison in &report.policies {
+            assert_eq!(comparison.simulated.len(), num_samples);
+            assert!(comparison.simulated.is_sorted_by(|a, b| a <= b));
+        }
+        assert_eq!(
+            report.diverging().count(),
+            report.policies.iter().filter(|c| !c.agrees).count()
+        );
+
+        // the simulated distributions are reproducible with the same seed
+        let again = compare_sample(&analyzer, num_samples, event_start, &records).unwrap();
+        for (a, b) in report.policies.iter().zip(again.policies.iter()) {
+            assert_eq!(a.simulated, b.simulated);
+        }
+    }
+}
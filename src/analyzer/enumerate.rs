@@ -0,0 +1,300 @@
+// TRIX: Inference of Transient Violation Times from Logged Routing Events or Collected BGP Messages
+// Copyright (C) 2024-2025 Roland Schmid <roschmi@ethz.ch> and Tibor Schneider <sctibor@ethz.ch>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//! Exhaustive enumeration of the message orderings of small networks, computing all equivalence
+//! classes that sampling can possibly observe.
+
+use std::collections::{HashMap, HashSet};
+
+use bgpsim::{
+    event::{Event, EventQueue, FmtPriority},
+    prelude::*,
+    types::{NetworkDevice, PhysicalNetwork, StepUpdate},
+};
+
+use super::{compute_equivalence_class, Analyzer};
+use crate::{timing_model::DelayableQueue, MultiPrefixConvergenceTrace, Prefix};
+
+/// Maximum number of complete message orderings explored by
+/// `Analyzer::enumerate_equivalence_classes`.
+pub const MAX_ENUMERATED_ORDERINGS: usize = 100_000;
+
+/// Event queue without a notion of time, which processes the event selected with `select` next.
+/// Messages of the same session are always processed in the order they were sent.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct ExplorationQueue {
+    events: Vec<Event<Prefix, ()>>,
+    next: usize,
+}
+
+impl ExplorationQueue {
+    /// Indices of all events that may be processed next, i.e., the oldest pending message of each
+    /// session.
+    fn choices(&self) -> Vec<usize> {
+        let mut sessions = HashSet::new();
+        self.events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| match event {
+                Event::Bgp { src, dst, .. } => sessions.insert((*src, *dst)),
+                _ => true,
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Select the event to be processed with the next call to `pop`.
+    fn select(&mut self, next: usize) {
+        self.next = next;
+    }
+}
+
+impl EventQueue<Prefix> for ExplorationQueue {
+    type Priority = ();
+
+    fn push<Ospf: OspfProcess>(
+        &mut self,
+        event: Event<Prefix, Self::Priority>,
+        _routers: &HashMap<RouterId, NetworkDevice<Prefix, Ospf>>,
+        _net: &PhysicalNetwork,
+    ) {
+        self.events.push(event);
+    }
+
+    fn pop(&mut self) -> Option<Event<Prefix, Self::Priority>> {
+        let next = std::mem::take(&mut self.next);
+        (next < self.events.len()).then(|| self.events.remove(next))
+    }
+
+    fn peek(&self) -> Option<&Event<Prefix, Self::Priority>> {
+        self.events.get(self.next)
+    }
+
+    fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.events.clear();
+        self.next = 0;
+    }
+
+    fn get_time(&self) -> Option<f64> {
+        None
+    }
+
+    fn update_params<Ospf: OspfProcess>(
+        &mut self,
+        _routers: &HashMap<RouterId, NetworkDevice<Prefix, Ospf>>,
+        _net: &PhysicalNetwork,
+    ) {
+    }
+
+    unsafe fn clone_events(&self, _conquered: Self) -> Self {
+        self.clone()
+    }
+}
+
+impl DelayableQueue for ExplorationQueue {
+    /// The `ExplorationQueue` has no notion of time, all orderings are explored anyway.
+    fn shift_time(&mut self, _delay: f64) {}
+}
+
+impl<Q> Analyzer<Q>
+where
+    Q: EventQueue<Prefix> + DelayableQueue + Clone + Send + Sync + std::fmt::Debug + PartialEq,
+    Q::Priority: Default + FmtPriority + Clone,
+{
+    /// Exhaustively explore all message orderings of the event and return the set of equivalence
+    /// classes they yield (see `compute_equivalence_class`). Comparing `n_unique_equiv` of an
+    /// `AnalysisResult` to the size of this set gives the coverage achieved by sampling.
+    ///
+    /// The number of orderings grows exponentially with the number of concurrent messages, so this
+    /// is only feasible for small networks. The exploration stops after
+    /// `MAX_ENUMERATED_ORDERINGS` complete orderings, in which case the returned set may be
+    /// incomplete.
+    pub fn enumerate_equivalence_classes(&self) -> HashSet<u64> {
+        let mut classes = HashSet::new();
+
+        let Ok(mut net) = self
+            .original_net
+            .clone()
+            .swap_queue(ExplorationQueue::default())
+        else {
+            log::error!("Cannot enumerate message orderings of a network that has not converged!");
+            return classes;
+        };
+        net.manual_simulation();
+        if let Err(e) = self.event.trigger(&mut net) {
+            log::error!("Cannot trigger the event for enumerating message orderings: {e}");
+            return classes;
+        }
+
+        // start with the same trace as `build_trace`
+        let trace = self.initial_trace();
+
+        let mut num_orderings = 0;
+        self.explore_orderings(net, trace, &mut classes, &mut num_orderings);
+        if num_orderings >= MAX_ENUMERATED_ORDERINGS {
+            log::warn!(
+                "Stopped enumerating message orderings after {num_orderings} orderings, the {} equivalence classes found may be incomplete!",
+                classes.len()
+            );
+        }
+        classes
+    }
+
+    /// Recursively process each possible next event, collecting the equivalence class of each
+    /// complete ordering in `classes`.
+    fn explore_orderings(
+        &self,
+        net: Network<Prefix, ExplorationQueue>,
+        trace: MultiPrefixConvergenceTrace,
+        classes: &mut HashSet<u64>,
+        num_orderings: &mut usize,
+    ) {
+        let choices = net.queue().choices();
+        if choices.is_empty() {
+            *num_orderings += 1;
+            classes.insert(compute_equivalence_class(
+                &trace,
+                &self.scheduled_fw,
+                &self.policies,
+                &self.equivalent_egresses,
+            ));
+            return;
+        }
+
+        for choice in choices {
+            if *num_orderings >= MAX_ENUMERATED_ORDERINGS {
+                return;
+            }
+            let mut net = net.clone();
+            let mut trace = trace.clone();
+            net.queue_mut().select(choice);
+            let Some((step, event)) = net.simulate_step().unwrap() else {
+                unreachable!("the selected event must exist");
+            };
+            match step {
+                StepUpdate::Unchanged => {}
+                StepUpdate::Single(delta) => {
+                    trace
+                        .entry(delta.prefix)
+                        .or_default()
+                        .push((vec![(event.router(), delta.old, delta.new)], None.into()));
+                }
+                StepUpdate::Multiple => {
+                    unreachable!("not sure if this is expected. ignoring step update making multiple fw state changes at once");
+                }
+            }
+            self.explore_orderings(net, trace, classes, num_orderings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bgpsim::policies::FwPolicy;
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        analyzer::test::path_analyzer,
+        experiments::{list_custom_topologies, list_path_scenarios, ScenarioPrefix},
+        timing_model::TimingModel,
+        transient_specification::{PolicyKind, TransientPolicy},
+    };
+
+    #[test]
+    fn path02_full_mesh() {
+        let prefixes = ScenarioPrefix::SinglePrefix.prefixes();
+        let (_, net, geo_location, _, external_routers) = list_custom_topologies(&prefixes)
+            .into_iter()
+            .find(|(name, ..)| *name == "Path02_FullMesh")
+            .unwrap();
+        let geo_location = geo_location.unwrap();
+        let (_, event) = list_path_scenarios(&net, &prefixes, &external_routers)
+            .into_iter()
+            .find(|(name, _)| *name == "WithdrawE1")
+            .unwrap();
+        let policies = net
+            .internal_indices()
+            .map(|r| TransientPolicy::Atomic(FwPolicy::Reachable(r, prefixes[0])))
+            .collect_vec();
+
+        let net = net
+            .swap_queue(TimingModel::from_geo_location(&geo_location))
+            .unwrap();
+        let analyzer = Analyzer::new(net, event, policies, 0.95, 0.01).unwrap();
+
+        // r0 drops traffic until r1 switches to e2 and announces its route back to r0, which
+        // happens in the same order regardless of the message ordering.
+        let classes = analyzer.enumerate_equivalence_classes();
+        assert_eq!(classes.len(), 1);
+
+        // the class of a sampled ordering is among the enumerated ones
+        let mut net = analyzer.scheduled_net.clone();
+        let trace = analyzer.build_trace(&mut net);
+        let sampled = compute_equivalence_class(
+            &trace,
+            &analyzer.scheduled_fw,
+            &analyzer.policies,
+            &analyzer.equivalent_egresses,
+        );
+        assert!(classes.contains(&sampled));
+        assert_eq!(analyzer.analyze().unwrap().n_unique_equiv, classes.len());
+    }
+
+    #[test]
+    fn path3_full_mesh() {
+        let mut analyzer = path_analyzer(1);
+        let prefix = analyzer.event.prefixes()[0];
+        let policies = analyzer
+            .original_net
+            .internal_indices()
+            .map(|r| PolicyKind::Reachable.instantiate(r, prefix))
+            .collect_vec();
+        analyzer.set_policies(policies);
+        analyzer.set_seed(42);
+
+        // r1 drops traffic if it processes the withdraw of r0 before the announcement of r2, and
+        // directly switches to r2 otherwise.
+        let classes = analyzer.enumerate_equivalence_classes();
+        assert!(classes.len() > 1);
+
+        // all sampled classes are among the enumerated ones
+        let num_samples = 50;
+        let sampled: HashSet<u64> = analyzer
+            .sample_traces(num_samples)
+            .iter()
+            .map(|trace| {
+                compute_equivalence_class(
+                    trace,
+                    &analyzer.scheduled_fw,
+                    &analyzer.policies,
+                    &analyzer.equivalent_egresses,
+                )
+            })
+            .collect();
+        assert!(sampled.is_subset(&classes));
+        let result = analyzer.analyze_with(num_samples, 1).unwrap();
+        assert_eq!(result.n_unique_equiv, sampled.len());
+        assert!(result.n_unique_equiv <= classes.len());
+    }
+}
@@ -15,22 +15,28 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 //! Module that performs the probabilistic convergence analysis
 use std::{
-    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
     fs,
     hash::Hasher,
     io::Write,
+    net::Ipv4Addr,
     path::Path,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     time::{Duration, Instant},
 };
 
 use geoutils::Location;
 use itertools::Itertools;
+use rand::prelude::*;
 use serde::{Deserialize, Serialize};
 use time::{format_description, OffsetDateTime};
 use tokio::time::timeout;
 
 use bgpsim::{
+    bgp::BgpRibEntry,
     event::{EventQueue, FmtPriority},
     export::Addressor,
     forwarding_state::ForwardingState,
@@ -40,35 +46,48 @@ use bgpsim::{
     types::StepUpdate,
 };
 pub use router_lab::hardware_mapping::HardwareMapping;
-use router_lab::{export_capture_to_csv, RouterLab};
+use router_lab::{export_capture_to_csv, Active, RouterLab};
 
 // pub use to keep dependencies working where stuff was originally defined in this file
-pub use trix_utils::serde::CiscoAnalyzerData;
+pub use trix_utils::serde::{
+    CiscoAnalyzerData, CISCO_ANALYZER_SCHEMA_VERSION, DEFAULT_IPERF_CLIENTS,
+};
 
 use crate::{
     event::AnalyzerEvent,
-    timing_model::TimingModel,
-    transient_specification::{compute_violation_times, TransientPolicy},
-    MultiPrefixConvergenceTrace, Prefix,
+    records::SampleRecord,
+    routing_inputs::UpdateOrdering,
+    timing_model::{DelayableQueue, ImportanceSampling, TimingModel},
+    transient_specification::{
+        compute_convergence_violations, compute_round_trip_violation_times,
+        compute_violation_times, group_policies, paths_from_fw_state, EvaluationError, PolicyKind,
+        TotalDuration, TransientPolicy,
+    },
+    util, MultiPrefixConvergenceTrace, Prefix,
 };
 
 pub mod analyzer_script;
 pub mod bgp_log_parser;
+pub mod compare;
 pub mod cpu_monitor;
+mod enumerate;
 pub mod ipfib_log_parser;
 mod log_parser;
 mod prefix;
 mod result;
+mod stats_store;
 pub mod ufdm_log_parser;
 pub mod urib_log_parser;
 
 use analyzer_script::*;
 use bgp_log_parser::{BgpPrefixesLogParser, BgpUribLogParser};
 use cpu_monitor::*;
+pub use enumerate::MAX_ENUMERATED_ORDERINGS;
 use ipfib_log_parser::IpfibLogParser;
 use log_parser::{setup_parsers, store_logs};
 pub use prefix::AnalyzerPrefix;
 pub use result::*;
+pub use stats_store::{StatsStore, STATS_STORE_FLUSH_INTERVAL};
 use ufdm_log_parser::UfdmLogParser;
 use urib_log_parser::UribLogParser;
 
@@ -84,13 +103,49 @@ pub fn num_workers() -> usize {
     // 100
 }
 
-/// Type for the stats structure
-type Stats = HashMap<u64, Vec<Vec<f64>>>;
+/// Type for the stats structure, storing the importance weight and the violation times of each
+/// sample per equivalence class.
+type Stats = HashMap<u64, Vec<(f64, Vec<f64>)>>;
+
+/// Worker-local state that `Analyzer::simulate_once` updates with each sample. Use
+/// `Analyzer::sample_context` to create it.
+pub struct SampleContext {
+    /// Timing model used to compute the violation times of the traces.
+    pub queue: TimingModel<Prefix>,
+    /// Importance weight and violation times of each sample, grouped by equivalence class.
+    pub stats: Stats,
+    /// Traces with the largest total violation time (see `Analyzer::retain_worst_traces`).
+    pub worst_traces: WorstTraces,
+    /// Final egress counts (see `Analyzer::record_final_egresses`).
+    pub final_egresses: FinalEgresses,
+}
+
+/// Outcome of a single sample simulated by `Analyzer::simulate_once`.
+pub struct SampleOutcome<Q> {
+    /// The network, reset to `scheduled_net` to be reused by the next sample.
+    pub net: Network<Prefix, Q>,
+    /// The forwarding state, reset to `original_fw` to be reused by the next sample.
+    pub fw_state: ForwardingState<Prefix>,
+    pub t_simulate: Duration,
+    pub t_checking: Duration,
+    pub t_clone: Duration,
+    /// Time of the last event relative to `time_offset`, including the event jitter.
+    pub convergence_time: f64,
+    /// Number of forwarding updates in the trace, summed across all prefixes.
+    pub trace_length: usize,
+    /// Whether events remain in the queue after simulating (see `max_steps`), in which case the
+    /// convergence and violation times are not valid, or whether the violation times could not be
+    /// evaluated, in which case the sample is not recorded.
+    pub incomplete: bool,
+    /// Violation time of each policy in `Analyzer::policies`, or `None` if the sample could not be
+    /// evaluated.
+    pub violation_times: Option<Vec<f64>>,
+}
 
 #[derive(Clone, Deserialize, Serialize)]
 #[serde(
     bound(
-        deserialize = "Q: for<'a> serde::Deserialize<'a> + EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq, Q::Priority: Default + FmtPriority + Clone"
+        deserialize = "Q: for<'a> serde::Deserialize<'a> + EventQueue<Prefix> + DelayableQueue + Clone + Send + Sync + std::fmt::Debug + PartialEq, Q::Priority: Default + FmtPriority + Clone"
     ),
     try_from = "SerializedAnalyzer<Q>"
 )]
@@ -108,8 +163,12 @@ pub struct Analyzer<Q> {
     /// The time offset of the network at the beginning of the convergence
     /// recordings (if applicable)
     pub time_offset: f64,
-    /// The set of policies to verify.
+    /// The set of policies to verify. Use `set_policies` to modify them.
     pub policies: Vec<TransientPolicy>,
+    /// The set of policies to verify, grouped by the `(RouterId, Prefix)` pair they concern.
+    /// Computed once whenever the policies are set, to avoid regrouping them for every sample.
+    #[serde(skip)]
+    grouped_policies: HashMap<(RouterId, Prefix), Vec<TransientPolicy>>,
     /// Confidence (1-alpha), typically 95% or 99%.
     pub confidence: f64,
     /// Precision, how accurate the final result should be.
@@ -123,6 +182,214 @@ pub struct Analyzer<Q> {
     /// The network's nodes' delays, if available.
     #[serde(default, with = "crate::serde_generic_hashmap::in_option")]
     pub delays: Option<HashMap<(RouterId, RouterId), f64>>,
+    /// Background traffic generated with iperf while collecting measurements on the hardware.
+    #[serde(default)]
+    pub iperf_load: IperfLoad,
+    /// Maximum number of events to simulate per sample. Samples that have not converged after
+    /// that many events are marked as incomplete.
+    #[serde(default)]
+    pub max_steps: Option<usize>,
+    /// Whether `analyze` records the number of forwarding updates of each sample.
+    #[serde(default)]
+    pub record_trace_lengths: bool,
+    /// Whether `analyze` records the final egress of each `(router, prefix)` pair of each sample.
+    #[serde(default)]
+    pub record_final_egresses: bool,
+    /// Canonical representative of each egress router that is equivalent to other egress routers
+    /// for all policies. Use `set_equivalent_egresses` to modify them.
+    #[serde(default, with = "crate::serde_generic_hashmap")]
+    pub equivalent_egresses: HashMap<RouterId, RouterId>,
+    /// Maximum jitter (in seconds) of the event time. If set, the event of each sample is delayed
+    /// by a time sampled uniformly from `[0, event_jitter]`, instead of occurring at exactly
+    /// `time_offset`. The jitter shifts the whole sample: all messages are delayed by the same
+    /// time, so the jitter never changes the order in which they are processed.
+    #[serde(default)]
+    pub event_jitter: Option<f64>,
+    /// Seed for the random draws of each sample (e.g., the event jitter). The draws of a sample
+    /// only depend on the seed and on the index of the sample, such that the results of `analyze`
+    /// do not depend on how the samples are divided among the workers. If unset, each sample draws
+    /// from fresh entropy.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Order in which the route updates of the event are scheduled. Use `set_update_ordering` to
+    /// modify it.
+    #[serde(default)]
+    pub update_ordering: UpdateOrdering,
+    /// Number of samples with the largest total violation time whose traces are retained by
+    /// `analyze`. Use `set_retain_worst_traces` to modify it.
+    #[serde(default)]
+    pub retain_worst_traces: Option<usize>,
+    /// Whether `analyze_router_lab` stores the hardware mapping of each sample gzipped, as
+    /// `hardware_mapping_{timestamp}.json.gz`.
+    #[serde(default)]
+    pub compress_hardware_mapping: bool,
+    /// Store to which `analyze` writes the violation times of all samples, instead of keeping them
+    /// in memory. Use `set_stats_store` to modify it.
+    #[serde(skip)]
+    pub stats_store: Option<StatsStore>,
+    /// Flag to abort `analyze` and `analyze_router_lab` early. It is checked between samples, and
+    /// once it is set, both return the samples collected so far. Use `set_cancellation_flag` to
+    /// modify it.
+    #[serde(skip)]
+    pub cancellation_flag: Option<Arc<AtomicBool>>,
+    /// How long `analyze_router_lab` waits for the testbed to converge, and how often it retries.
+    /// Use `set_convergence_retry` to modify it.
+    #[serde(default)]
+    pub convergence_retry: ConvergenceRetry,
+    /// Return prefix of each router, towards which the return traffic of that router is sent. If
+    /// not empty, `analyze` computes the round-trip violation of each policy (see
+    /// `compute_round_trip_violation_times`). Use `set_return_prefixes` to modify it.
+    #[serde(default, with = "crate::serde_generic_hashmap")]
+    pub return_prefixes: HashMap<RouterId, Prefix>,
+    /// Writer to which `analyze` streams the violation time of each policy in each sample, as a
+    /// `SampleRecord`. Use `set_sample_export` to modify it.
+    #[serde(skip)]
+    pub sample_export: Option<Arc<Mutex<csv::Writer<fs::File>>>>,
+    /// Interface of the server on which `analyze_router_lab` captures the BGP messages of the
+    /// event to detect its timestamp. Use `set_monitoring_iface` to modify it.
+    #[serde(default = "default_monitoring_iface")]
+    pub monitoring_iface: String,
+}
+
+/// Default interface on which `analyze_router_lab` captures the BGP messages of the event.
+pub const DEFAULT_MONITORING_IFACE: &str = "enp132s0f1";
+
+fn default_monitoring_iface() -> String {
+    DEFAULT_MONITORING_IFACE.to_string()
+}
+
+/// Background data-plane traffic generated with iperf during `Analyzer::analyze_router_lab`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IperfLoad {
+    /// Number of parallel iperf clients, each replicating roughly 1Gbps of traffic. Setting this
+    /// to zero disables the background traffic entirely.
+    pub num_clients: usize,
+    /// Whether to generate UDP instead of TCP traffic.
+    pub udp: bool,
+}
+
+impl Default for IperfLoad {
+    /// Replicate 1Gbps of TCP traffic.
+    fn default() -> Self {
+        Self {
+            num_clients: DEFAULT_IPERF_CLIENTS,
+            udp: false,
+        }
+    }
+}
+
+impl IperfLoad {
+    /// Do not generate any background traffic.
+    pub fn disabled() -> Self {
+        Self {
+            num_clients: 0,
+            udp: false,
+        }
+    }
+
+    /// Check whether any background traffic should be generated.
+    pub fn is_enabled(&self) -> bool {
+        self.num_clients > 0
+    }
+}
+
+/// Timeout and number of retries when waiting for the testbed to converge during
+/// `Analyzer::analyze_router_lab`. If the testbed does not converge after all retries, the next
+/// sample is skipped instead of aborting all remaining samples, unless the testbed did not converge
+/// for `max_consecutive_skips` samples in a row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ConvergenceRetry {
+    /// Maximum time to wait for convergence in a single attempt.
+    pub timeout: Duration,
+    /// Number of additional attempts after the first one timed out or failed.
+    pub retries: usize,
+    /// Number of consecutive skipped samples after which all remaining samples are aborted, as
+    /// the testbed is unlikely to recover.
+    #[serde(default = "default_max_consecutive_skips")]
+    pub max_consecutive_skips: usize,
+}
+
+fn default_max_consecutive_skips() -> usize {
+    3
+}
+
+impl Default for ConvergenceRetry {
+    /// Wait for up to 10 minutes, retrying twice, and abort after 3 consecutive skipped samples.
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(600),
+            retries: 2,
+            max_consecutive_skips: default_max_consecutive_skips(),
+        }
+    }
+}
+
+/// How to proceed after an attempt to wait for the testbed to converge, see
+/// `ConvergenceRetry::decide`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// The testbed converged, continue with the next sample.
+    Converged,
+    /// Wait for convergence again.
+    Retry,
+    /// Give up and skip the next sample.
+    Skip,
+}
+
+impl ConvergenceRetry {
+    /// Decide how to proceed after the given (zero-based) `attempt` to wait for convergence,
+    /// depending on whether the testbed `converged` within the timeout.
+    pub fn decide(&self, attempt: usize, converged: bool) -> RetryDecision {
+        if converged {
+            RetryDecision::Converged
+        } else if attempt < self.retries {
+            RetryDecision::Retry
+        } else {
+            RetryDecision::Skip
+        }
+    }
+
+    /// Check whether all remaining samples should be aborted after `consecutive_skips` samples
+    /// were skipped in a row.
+    pub fn abort(&self, consecutive_skips: usize) -> bool {
+        consecutive_skips >= self.max_consecutive_skips
+    }
+}
+
+/// Summary of the samples collected by `Analyzer::analyze_router_lab`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RouterLabSummary {
+    /// Number of samples collected and written to `cisco_analyzer.csv`.
+    pub n_samples: usize,
+    /// Number of samples that were skipped because the testbed did not converge, including the
+    /// samples that were aborted.
+    pub n_skipped: usize,
+    /// Whether the remaining samples were aborted, as the testbed did not converge for
+    /// `ConvergenceRetry::max_consecutive_skips` samples in a row.
+    pub aborted: bool,
+}
+
+/// Change of the selected BGP route of a router for a prefix, computed by
+/// `Analyzer::bgp_rib_diff`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RibChange {
+    /// The router selects a route, but did not select any before.
+    Added {
+        router: RouterId,
+        new: BgpRibEntry<Prefix>,
+    },
+    /// The router no longer selects any route.
+    Removed {
+        router: RouterId,
+        old: BgpRibEntry<Prefix>,
+    },
+    /// The router selects a different route, or the same route with different attributes (e.g.,
+    /// a different IGP cost).
+    Changed {
+        router: RouterId,
+        old: BgpRibEntry<Prefix>,
+        new: BgpRibEntry<Prefix>,
+    },
 }
 
 /// Type used to deserialize an `Analyzer<Q>` whilst ignoring `scheduled_net` and `scheduled_fw`
@@ -151,11 +418,42 @@ pub struct SerializedAnalyzer<Q> {
     /// The network's nodes' delays, if available.
     #[serde(default, with = "crate::serde_generic_hashmap::in_option")]
     pub delays: Option<HashMap<(RouterId, RouterId), f64>>,
+    /// Background traffic generated with iperf while collecting measurements on the hardware.
+    #[serde(default)]
+    pub iperf_load: IperfLoad,
+    #[serde(default)]
+    pub num_samples: Option<usize>,
+    #[serde(default)]
+    pub max_steps: Option<usize>,
+    #[serde(default)]
+    pub record_trace_lengths: bool,
+    #[serde(default)]
+    pub record_final_egresses: bool,
+    #[serde(default, with = "crate::serde_generic_hashmap")]
+    pub equivalent_egresses: HashMap<RouterId, RouterId>,
+    #[serde(default)]
+    pub event_jitter: Option<f64>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Order in which the route updates of the event are scheduled when recomputing
+    /// `scheduled_net`.
+    #[serde(default)]
+    pub update_ordering: UpdateOrdering,
+    #[serde(default)]
+    pub retain_worst_traces: Option<usize>,
+    #[serde(default)]
+    pub compress_hardware_mapping: bool,
+    #[serde(default)]
+    pub convergence_retry: ConvergenceRetry,
+    #[serde(default, with = "crate::serde_generic_hashmap")]
+    pub return_prefixes: HashMap<RouterId, Prefix>,
+    #[serde(default = "default_monitoring_iface")]
+    pub monitoring_iface: String,
 }
 
 impl<Q> TryFrom<SerializedAnalyzer<Q>> for Analyzer<Q>
 where
-    Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
+    Q: EventQueue<Prefix> + DelayableQueue + Clone + Send + Sync + std::fmt::Debug + PartialEq,
     Q::Priority: Default + FmtPriority + Clone,
 {
     type Error = NetworkError;
@@ -165,7 +463,9 @@ where
         scheduled_net.manual_simulation();
 
         // perform the event
-        analyzer.event.trigger(&mut scheduled_net)?;
+        analyzer
+            .event
+            .trigger_ordered(&mut scheduled_net, analyzer.update_ordering)?;
 
         // get the scheduled forwarding state
         let scheduled_fw = scheduled_net.get_forwarding_state();
@@ -177,20 +477,65 @@ where
             original_fw: analyzer.original_fw,
             scheduled_fw,
             time_offset: analyzer.time_offset,
+            grouped_policies: group_policies(&analyzer.policies),
             policies: analyzer.policies,
-            confidence: analyzer.confidence,
-            num_samples: None,
-            precision: analyzer.precision,
+            confidence: clamp_param("confidence", analyzer.confidence),
+            num_samples: analyzer.num_samples,
+            precision: clamp_param("precision", analyzer.precision),
             geo_location: analyzer.geo_location,
             delays: analyzer.delays,
+            iperf_load: analyzer.iperf_load,
+            max_steps: analyzer.max_steps,
+            record_trace_lengths: analyzer.record_trace_lengths,
+            record_final_egresses: analyzer.record_final_egresses,
+            equivalent_egresses: analyzer.equivalent_egresses,
+            event_jitter: analyzer.event_jitter,
+            seed: analyzer.seed,
+            update_ordering: analyzer.update_ordering,
+            retain_worst_traces: analyzer.retain_worst_traces,
+            compress_hardware_mapping: analyzer.compress_hardware_mapping,
+            stats_store: None,
+            cancellation_flag: None,
+            convergence_retry: analyzer.convergence_retry,
+            return_prefixes: analyzer.return_prefixes,
+            sample_export: None,
+            monitoring_iface: analyzer.monitoring_iface,
         })
     }
 }
 
+impl Analyzer<TimingModel<Prefix>> {
+    /// Draw the processing times of all samples from the processing times measured on hardware
+    /// routers (see `TimingModel::set_measured_processing_times`). Like the importance sampling,
+    /// the setting is stored on the queues of the analyzer.
+    pub fn set_measured_processing_times(&mut self, measured: bool) {
+        self.original_net
+            .queue_mut()
+            .set_measured_processing_times(measured);
+        self.scheduled_net
+            .queue_mut()
+            .set_measured_processing_times(measured);
+    }
+
+    /// Bias the processing times of all samples towards orderings that stress a policy, and weight
+    /// the samples accordingly (see `ImportanceSampling`). The tilts only apply to the measured
+    /// processing times (see `set_measured_processing_times`). Pass `None` to disable importance
+    /// sampling. The setting is stored on the queue of the `original_net`, such that it is kept
+    /// when (de-)serializing the analyzer or rescheduling the event.
+    pub fn set_importance_sampling(&mut self, importance_sampling: Option<ImportanceSampling>) {
+        self.original_net
+            .queue_mut()
+            .set_importance_sampling(importance_sampling.clone());
+        self.scheduled_net
+            .queue_mut()
+            .set_importance_sampling(importance_sampling);
+    }
+}
+
 #[allow(dead_code)]
 impl<Q> Analyzer<Q>
 where
-    Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
+    Q: EventQueue<Prefix> + DelayableQueue + Clone + Send + Sync + std::fmt::Debug + PartialEq,
     Q::Priority: Default + FmtPriority + Clone,
 {
     /// Create a new analyzer with the given parameters. The event is a callback function that
@@ -223,15 +568,51 @@ where
             original_fw,
             scheduled_fw,
             time_offset,
+            grouped_policies: group_policies(&policies),
             policies,
-            confidence,
+            confidence: clamp_param("confidence", confidence),
             num_samples: None,
-            precision,
+            precision: clamp_param("precision", precision),
             geo_location: None,
             delays: None,
+            iperf_load: IperfLoad::default(),
+            max_steps: None,
+            record_trace_lengths: false,
+            record_final_egresses: false,
+            equivalent_egresses: HashMap::new(),
+            event_jitter: None,
+            seed: None,
+            update_ordering: UpdateOrdering::default(),
+            retain_worst_traces: None,
+            compress_hardware_mapping: false,
+            stats_store: None,
+            cancellation_flag: None,
+            convergence_retry: ConvergenceRetry::default(),
+            return_prefixes: HashMap::new(),
+            sample_export: None,
+            monitoring_iface: default_monitoring_iface(),
         })
     }
 
+    /// Check whether the event causes any forwarding updates, by simulating a single sample. Events
+    /// without an effect (e.g., withdrawing a route that is not selected anywhere) result in zero
+    /// violation times for every sample, which is easily mistaken for a satisfied specification.
+    pub fn event_has_effect(&self) -> bool {
+        let mut net = self.scheduled_net.clone();
+        !self.build_trace(&mut net).is_empty()
+    }
+
+    /// Log a warning if the event has no effect (see `event_has_effect`). This simulates a single
+    /// sample, so it is not done implicitly when creating or deserializing an analyzer.
+    pub fn warn_if_event_has_no_effect(&self) {
+        if !self.event_has_effect() {
+            log::warn!(
+                "The event {:?} does not cause any forwarding updates! All violation times will be zero.",
+                self.event
+            );
+        }
+    }
+
     /// Return the number of routers required to execute this analyzer.
     pub fn num_routers(&self) -> usize {
         self.original_net.internal_routers().count()
@@ -247,6 +628,80 @@ where
         }
     }
 
+    /// Check the given `policies` on already collected `traces` (e.g., the traces retained in
+    /// `AnalysisResult::worst_traces`), without sampling the network again. Returns the violation
+    /// time of each policy in each trace, at the index of the trace. Policies that are not violated
+    /// in a trace have a violation time of zero. Fails if any trace cannot be evaluated.
+    pub fn recheck_policies(
+        &self,
+        traces: &[MultiPrefixConvergenceTrace],
+        policies: &[TransientPolicy],
+    ) -> Result<HashMap<TransientPolicy, Vec<f64>>, EvaluationError> {
+        let grouped_policies = group_policies(policies);
+        let mut queue = self.build_queue();
+        let mut fw_state = self.original_fw.clone();
+
+        let mut result: HashMap<TransientPolicy, Vec<f64>> = policies
+            .iter()
+            .map(|policy| (policy.clone(), vec![0.0; traces.len()]))
+            .collect();
+        for (i, trace) in traces.iter().enumerate() {
+            let mut violation_times = compute_violation_times(
+                &self.original_net,
+                &mut queue,
+                &mut fw_state,
+                trace,
+                &grouped_policies,
+                None::<&std::path::Path>,
+                &TotalDuration,
+            )?
+            .times;
+            violation_times.extend(
+                compute_convergence_violations(&self.original_net, &fw_state, trace, policies)
+                    .times,
+            );
+            for (policy, violation) in violation_times {
+                result.get_mut(&policy).unwrap()[i] = violation;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Sample the convergence time (in seconds) of `num_samples` samples, i.e., the time of the
+    /// last event relative to `time_offset` (including the `event_jitter`), without checking any
+    /// policies. The draws of each sample are the same as in `analyze`.
+    pub fn convergence_time_distribution(&self, num_samples: usize) -> Vec<f64> {
+        let mut net = self.scheduled_net.clone();
+        let mut convergence_times = Vec::with_capacity(num_samples);
+        for sample in 0..num_samples {
+            let jitter = self.prepare_sample(&mut net, sample);
+            self.build_trace(&mut net);
+            convergence_times.push(
+                net.queue()
+                    .get_time()
+                    .map(|x| x - self.time_offset + jitter)
+                    .unwrap_or_default(),
+            );
+            net = self.reset_to_scheduled(net);
+        }
+        convergence_times
+    }
+
+    /// Simulate the traces of `num_samples` samples without checking any policies. The draws of
+    /// each sample (including the `event_jitter`) are the same as in `analyze`.
+    pub fn sample_traces(&self, num_samples: usize) -> Vec<MultiPrefixConvergenceTrace> {
+        let mut net = self.scheduled_net.clone();
+        let mut traces = Vec::with_capacity(num_samples);
+        for sample in 0..num_samples {
+            let jitter = self.prepare_sample(&mut net, sample);
+            let mut trace = self.build_trace(&mut net);
+            shift_trace(&mut trace, jitter);
+            traces.push(trace);
+            net = self.reset_to_scheduled(net);
+        }
+        traces
+    }
+
     /// Perform the analysis, returning the probability that the property is satisfied (plus minus
     /// `self.imprecision` with confidence `self.confidence`). This will spawn threads and start
     /// sampling the network in parallel.
@@ -254,56 +709,157 @@ where
     /// If the features `router_lab` is enabled, this procedure also runs the same experiment on the
     /// routing testbed. This function assumes that the router-lab config is already created and
     /// that the required environment variables have been set. Check `main.rs` for an example.
-    pub fn analyze(&self) -> AnalysisResult {
+    ///
+    /// Returns an error if the `stats_store` cannot be read or written, or if it contains samples of
+    /// other policies (see `set_stats_store`).
+    pub fn analyze(&self) -> std::io::Result<AnalysisResult> {
+        // every worker collects the same number of samples
+        let workers = num_workers();
+        self.analyze_with(self.num_samples().div_ceil(workers) * workers, workers)
+    }
+
+    /// Same as `analyze`, but collecting `num_samples` samples divided among `workers` threads.
+    /// Each worker simulates a contiguous range of sample indices. If a `seed` is set, the result
+    /// is independent of the number of workers.
+    pub fn analyze_with(
+        &self,
+        num_samples: usize,
+        workers: usize,
+    ) -> std::io::Result<AnalysisResult> {
+        // the policies may have changed since setting the store
+        if let Some(store) = &self.stats_store {
+            store.check_header(&self.stats_store_header())?;
+        }
         let stats_mutex = Arc::new(Mutex::new(Stats::new()));
 
-        let workers = num_workers();
-        let iters_per_worker = ((self.num_samples() as f64) / (workers as f64)).ceil() as usize;
+        let workers = workers.max(1);
+        let samples_per_worker = num_samples.div_ceil(workers);
 
-        let result_mutex = Arc::new(Mutex::new(AnalysisResult {
-            confidence: self.confidence,
-            precision: self.precision,
-            n_samples: iters_per_worker * workers,
-            ..Default::default()
-        }));
+        // the workers add the number of samples they simulated, which is less than `num_samples` if
+        // the analysis is cancelled
+        let result_mutex = Arc::new(Mutex::new(AnalysisResult::new(
+            self.confidence,
+            self.precision,
+            0,
+        )));
 
         let start = Instant::now();
 
         crossbeam_utils::thread::scope(|s| {
-            (0..workers).for_each(|_| {
-                let result = result_mutex.clone();
-                let stats = stats_mutex.clone();
-                s.spawn(|_| self.worker(result, stats, iters_per_worker));
-            })
+            (0..workers)
+                .map(|i| {
+                    let result = result_mutex.clone();
+                    let stats = stats_mutex.clone();
+                    let samples = (i * samples_per_worker).min(num_samples)
+                        ..((i + 1) * samples_per_worker).min(num_samples);
+                    s.spawn(|_| self.worker(result, stats, samples))
+                })
+                .collect_vec()
+                .into_iter()
+                .try_for_each(|worker| worker.join().unwrap())
         })
-        .unwrap();
+        .unwrap()?;
 
         let stats = Arc::try_unwrap(stats_mutex).unwrap().into_inner().unwrap();
         let mut result = Arc::try_unwrap(result_mutex).unwrap().into_inner().unwrap();
 
-        let count_satisfied: usize = stats
-            .values()
-            .flatten()
-            .filter(|violation_times| violation_times.iter().all(|&t| t == 0.0))
-            .count();
-        let mut sample_iters: Vec<_> = stats.values().flatten().map(|s| s.iter()).collect();
+        // the convergence time only covers the samples of this run, and stays zero without any
+        if result.n_samples > 0 {
+            result.convergence_time /= result.n_samples as f64;
+        }
+
+        // Each sample stores the violation times at the index of the corresponding policy in
+        // `self.policies` (see `simulate_once`). Aggregate the samples one at a time, such that they
+        // can be streamed from the `stats_store`, which may also contain samples of previous runs.
+        // Samples are weighted by their importance weight (see `ImportanceSampling`), which is 1
+        // unless importance sampling is enabled.
+        let mut n_samples = 0;
+        let mut total_weight = 0.0;
+        let mut satisfied_weight = 0.0;
+        let mut weights = Vec::new();
+        let mut distributions = vec![Vec::new(); self.policies.len()];
+        let mut aggregate = |weight: f64, violation_times: &[f64]| {
+            n_samples += 1;
+            total_weight += weight;
+            weights.push(weight);
+            if violation_times.iter().all(|&t| t == 0.0) {
+                satisfied_weight += weight;
+            }
+            for (distribution, t) in distributions.iter_mut().zip(violation_times) {
+                distribution.push(*t);
+            }
+        };
+        result.n_unique_equiv = match &self.stats_store {
+            Some(store) => store
+                .for_each_weighted_sample(|_, weight, violation_times| {
+                    aggregate(weight, violation_times)
+                })
+                .and_then(|_| store.classes())
+                .map(|classes| classes.len())?,
+            None => {
+                for (weight, violation_times) in stats.values().flatten() {
+                    aggregate(*weight, violation_times);
+                }
+                stats.len()
+            }
+        };
 
-        result.p_satisfied = (count_satisfied as f64) / (result.n_samples as f64);
+        result.n_samples = n_samples;
+        let is_weighted = weights.iter().any(|w| *w != 1.0);
+        // without any samples (e.g., if cancelled right away), no sample is known to be satisfied
+        result.p_satisfied = if n_samples > 0 {
+            satisfied_weight / total_weight
+        } else {
+            0.0
+        };
         result.t_wall = start.elapsed();
-        result.convergence_time /= result.n_samples as f64;
-        result.violation_time_distributions = self
-            .policies
-            .iter()
-            .map(|p| {
-                let violation_times: Vec<f64> = sample_iters
-                    .iter_mut()
-                    .map(|s| s.next().copied().unwrap())
-                    .sorted_by(|a, b| a.total_cmp(b))
-                    .collect();
-                ((p.router().unwrap(), p.prefix().unwrap()), violation_times)
+        // Collect the distributions by the index of the policy, such that the association of
+        // samples to policies does not depend on the iteration order of `stats`. Policies
+        // concerning the same `(router, prefix)` pair share a single distribution, holding the
+        // largest violation time of any of them in each sample. Policies not concerning a
+        // dedicated router (e.g., convergence deadlines) only contribute to `p_satisfied`.
+        let mut policies_by_key: BTreeMap<(RouterId, Prefix), Vec<usize>> = BTreeMap::new();
+        for (i, policy) in self.policies.iter().enumerate() {
+            if let (Some(router), Some(prefix)) = (policy.router(), policy.prefix()) {
+                policies_by_key.entry((router, prefix)).or_default().push(i);
+            }
+        }
+        result.violation_time_distributions = policies_by_key
+            .into_iter()
+            .map(|(key, indices)| {
+                let violation_times = (0..n_samples)
+                    .map(|j| {
+                        indices
+                            .iter()
+                            .map(|i| distributions[*i][j])
+                            .fold(0.0, f64::max)
+                    })
+                    .collect_vec();
+                // order the samples by their violation time, keeping the weight of each sample
+                let order = (0..violation_times.len())
+                    .sorted_by(|a, b| violation_times[*a].total_cmp(&violation_times[*b]))
+                    .collect_vec();
+                if is_weighted {
+                    result.violation_time_weights.insert(
+                        key,
+                        order.iter().map(|j| weights[*j] / total_weight).collect(),
+                    );
+                }
+                let violation_times = order.iter().map(|j| violation_times[*j]).collect();
+                (key, violation_times)
             })
             .collect();
-        result.n_unique_equiv = stats.len();
+        if let Some(export) = &self.sample_export {
+            export.lock().unwrap().flush()?;
+        }
+        result.trace_lengths.sort();
+        // each worker retains its worst traces, keep the worst ones across all workers
+        result
+            .worst_traces
+            .sort_by(|a, b| b.total_violation_time.total_cmp(&a.total_violation_time));
+        result
+            .worst_traces
+            .truncate(self.retain_worst_traces.unwrap_or_default());
 
         /*
         log::debug!("STATUS UPDATE: simulator distribution");
@@ -320,11 +876,12 @@ where
         }
         */
 
-        result
+        Ok(result)
     }
 
     /// Collect measurements for `num_samples` on the hardware and store all gathered data in the
-    /// `data_path` directory (which is created if it doesn't exist yet).
+    /// `data_path` directory (which is created if it doesn't exist yet). Returns how many samples
+    /// were collected and skipped (see `ConvergenceRetry`).
     #[allow(unused)]
     pub async fn analyze_router_lab(
         &mut self,
@@ -332,8 +889,8 @@ where
         num_probes: usize,
         capture_frequency: u64,
         data_path: &Path,
-    ) -> Result<HashMap<(RouterId, Prefix), Vec<f64>>, Box<dyn std::error::Error>> {
-        let mut result = HashMap::new();
+    ) -> Result<RouterLabSummary, Box<dyn std::error::Error>> {
+        let mut summary = RouterLabSummary::default();
 
         // create the lab
         let mut lab = if let Some(physical_ext) = self.event.get_triggering_external() {
@@ -362,6 +919,9 @@ where
 
         self.event.prepare_initial_advertisements(&mut lab)?;
         for _ in 0..num_samples {
+            if self.is_cancelled() {
+                break;
+            }
             // advance exabgp time before event
             lab.step_external_time();
 
@@ -394,6 +954,27 @@ where
             }
         }
 
+        // override individual link delays as specified in the data directory
+        let link_delays_path = data_path.join(LINK_DELAYS_FILE);
+        if link_delays_path.exists() {
+            let overrides = parse_link_delay_overrides(
+                &fs::read_to_string(&link_delays_path)?,
+                &self.original_net,
+            )?;
+            for ((from, to), delay_us) in overrides.iter() {
+                lab.set_link_delay(*from, *to, (*delay_us) as u32);
+                // set the reverse option if it is not overridden differently
+                if !overrides.contains_key(&(*to, *from)) {
+                    lab.set_link_delay(*to, *from, (*delay_us) as u32);
+                }
+                log::debug!(
+                    "overriding link delay from {} to {}: {delay_us}",
+                    from.fmt(&self.original_net),
+                    to.fmt(&self.original_net)
+                );
+            }
+        }
+
         // connect the network
         log::debug!("[cisco-analyzer] Connecting to routing testbed...");
         let mut lab = lab.connect().await?;
@@ -440,6 +1021,7 @@ where
 
         let mut csv_path = data_path.to_path_buf();
         csv_path.push("cisco_analyzer.csv");
+        CiscoAnalyzerData::upgrade_file(&csv_path)?;
         let mut csv = csv::WriterBuilder::new()
             .has_headers(!csv_path.exists() || fs::metadata(&csv_path)?.len() == 0)
             .from_writer(
@@ -451,12 +1033,47 @@ where
             );
 
         log::debug!("[cisco-analyzer] Waiting for initial convergence...");
-        lab.wait_for_convergence().await?;
+        let mut converged = self.wait_for_lab_convergence(&mut lab).await;
 
-        // add some data-plane traffic by replicating 1Gbps TCP traffic
-        let iperf_handle = lab.start_iperf(1, false).await?;
+        // add some data-plane traffic by replicating 1Gbps traffic per iperf client
+        let iperf_handle = if self.iperf_load.is_enabled() {
+            Some(
+                lab.start_iperf(self.iperf_load.num_clients, self.iperf_load.udp)
+                    .await?,
+            )
+        } else {
+            log::debug!("[cisco-analyzer] Running without background traffic");
+            None
+        };
 
+        let mut consecutive_skips = 0;
         for i in 0..num_samples {
+            if self.is_cancelled() {
+                log::info!("[cisco-analyzer] cancelled after {i} of {num_samples} samples");
+                break;
+            }
+            // try again if the testbed did not converge after the previous sample
+            if !converged {
+                converged = self.wait_for_lab_convergence(&mut lab).await;
+                if !converged {
+                    consecutive_skips += 1;
+                    if self.convergence_retry.abort(consecutive_skips) {
+                        log::error!(
+                            "[cisco-analyzer] aborting the remaining {} samples, the testbed did not converge for {consecutive_skips} samples in a row",
+                            num_samples - i
+                        );
+                        summary.n_skipped += num_samples - i;
+                        summary.aborted = true;
+                        break;
+                    }
+                    log::warn!(
+                        "[cisco-analyzer] skipping sample {i}, the testbed did not converge"
+                    );
+                    summary.n_skipped += 1;
+                    continue;
+                }
+            }
+            consecutive_skips = 0;
             let execution_timestamp = OffsetDateTime::now_local()
                 .unwrap_or_else(|_| OffsetDateTime::now_utc())
                 .format(
@@ -502,103 +1119,49 @@ where
                 .await?;
             start_cpu_monitoring(&lab).await?;
 
-            let mut tokio_scope = unsafe { async_scoped::TokioScope::create() };
-            match &self.event {
-                AnalyzerEvent::AnnounceRoute(_, ext, _)
-                | AnalyzerEvent::WithdrawRoute(_, ext, _)
-                | AnalyzerEvent::PhysicalExternalAnnounceRoute(_, ext, _)
-                | AnalyzerEvent::PhysicalExternalWithdrawRoute(_, ext, _) => {
-                    let ssh = lab.get_server_session();
-                    let mut addressor = lab.addressor().clone();
-
-                    tokio_scope.spawn(async move {
-                        let ifaces = addressor.list_ifaces(*ext);
-                        // set up filter for non-keepalive (85 bytes) BGP packets, add a
-                        // null-statement in front to append all neighbors afterwards as a
-                        // disjunction
-                        let mut filter = String::from("\"port 179 and len > 85 and ((port 1 and port 2)");
-
-                        for (neighbor, ipv4) in ifaces.iter().map(|(neighbor, ipv4, _, _)| (neighbor, ipv4)).unique() {
-                            if let Ok(neighbor_ipv4) = addressor.iface_address(*neighbor, *ext) {
-                                filter.push_str(&format!(
-                                    " or (src {ipv4} and dst {neighbor_ipv4})"
-                                ));
-                            }
-                        }
-
-                        filter.push_str(")\"");
-
-                        let cmd = format!(
-                            "sudo tcpdump_pfring -i enp132s0f1 {filter} -c1 -w - 2>/dev/null | tshark -r - -T fields -e frame.time_epoch 2>/dev/null",
-                        );
-
-                        log::trace!("executing: {cmd}");
-
-                        ssh.execute_cmd_stdout(&[&cmd])
-                        .await
-                        .unwrap_or_else(|_| {
-                            log::warn!("Could not determine BGP event's timestamp!");
-                            String::from("0.0")
-                        })
-                    });
+            // collect the BGP sessions of all external routers to capture the event start
+            let mut addressor = lab.addressor().clone();
+            let mut bgp_sessions = HashMap::new();
+            for ext in self
+                .event
+                .external_routers()
+                .into_iter()
+                .map(|(rid, _)| rid)
+                .unique()
+            {
+                let ifaces = addressor.list_ifaces(ext);
+                let sessions = bgp_sessions.entry(ext).or_insert_with(Vec::new);
+                for (neighbor, ipv4, _, _) in ifaces.iter() {
+                    if let Ok(neighbor_ipv4) = addressor.iface_address(*neighbor, ext) {
+                        sessions.push((*ipv4, neighbor_ipv4));
+                    }
                 }
-                AnalyzerEvent::AnnounceRoutingInputs(inputs)
-                | AnalyzerEvent::WithdrawRoutingInputs(inputs)
-                | AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(inputs)
-                | AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(inputs)
-                | AnalyzerEvent::PhysicalExternalUpdateBetterRoutingInputs(inputs)
-                | AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(inputs) => {
-                    let ssh = lab.get_server_session();
-                    let mut addressor = lab.addressor().clone();
-
-                    tokio_scope.spawn(async move {
-                        // set up filter for non-keepalive (85 bytes) BGP packets, add a
-                        // null-statement in front to append all neighbors afterwards as a
-                        // disjunction
-                        let mut filter = String::from("\"port 179 and len > 85 and ((port 1 and port 2)");
-
-                        for ext in inputs.external_routers().iter().unique().map(|(rid, _)| rid) {
-                            let ifaces = addressor.list_ifaces(*ext);
-                            for (neighbor, ipv4, _, _) in ifaces.iter().unique() {
-                                if let Ok(neighbor_ipv4) = addressor.iface_address(*neighbor, *ext) {
-                                    filter.push_str(&format!(
-                                        " or (src {ipv4} and dst {neighbor_ipv4})"
-                                    ));
-                                }
-                            }
-                        }
-
-                        filter.push_str(")\"");
-
-                        let cmd = format!(
-                            "sudo tcpdump_pfring -i enp132s0f1 {filter} -c1 -w - 2>/dev/null | tshark -r - -T fields -e frame.time_epoch 2>/dev/null",
-                        );
-
-                        log::trace!("executing: {cmd}");
+            }
 
-                        ssh.execute_cmd_stdout(&[&cmd])
+            let mut tokio_scope = unsafe { async_scoped::TokioScope::create() };
+            let ssh = lab.get_server_session();
+            if let Some(filter) = self.event.tcpdump_filter(&bgp_sessions) {
+                let monitoring_iface = self.monitoring_iface.clone();
+                tokio_scope.spawn(async move {
+                    let cmd = event_start_capture_cmd(&monitoring_iface, &filter);
+
+                    log::trace!("executing: {cmd}");
+
+                    ssh.execute_cmd_stdout(&[&cmd]).await.unwrap_or_else(|_| {
+                        log::warn!("Could not determine BGP event's timestamp!");
+                        String::from("0.0")
+                    })
+                });
+            } else {
+                tokio_scope.spawn(async move {
+                    // use server time as a replacement for non-bgp events
+                    ssh.execute_cmd_stdout(&["date", "+%s.%N"])
                         .await
                         .unwrap_or_else(|_| {
-                            log::warn!("Could not determine BGP event's timestamp!");
+                            log::warn!("Could not determine event's timestamp!");
                             String::from("0.0")
                         })
-                    });
-                }
-                AnalyzerEvent::AddLink(_, _, _, _, _)
-                | AnalyzerEvent::RemoveLink(_, _, _)
-                | AnalyzerEvent::LowerLocalPref(_, _, _) => {
-                    let ssh = lab.get_server_session();
-
-                    tokio_scope.spawn(async move {
-                        // use server time as a replacement for non-bgp events
-                        ssh.execute_cmd_stdout(&["date", "+%s.%N"])
-                            .await
-                            .unwrap_or_else(|_| {
-                                log::warn!("Could not determine event's timestamp!");
-                                String::from("0.0")
-                            })
-                    });
-                }
+                });
             }
 
             tokio::time::sleep(Duration::from_secs(5)).await;
@@ -748,12 +1311,17 @@ where
 
             log::debug!("[cisco-analyzer] Writing hardware mapping");
             let hardware_mapping = lab.get_hardware_mapping();
-            let hardware_mapping_filename = format!("hardware_mapping_{execution_timestamp}.json");
+            let hardware_mapping_filename = if self.compress_hardware_mapping {
+                format!("hardware_mapping_{execution_timestamp}.json.gz")
+            } else {
+                format!("hardware_mapping_{execution_timestamp}.json")
+            };
             let mut hardware_mapping_path = data_path.to_path_buf();
             hardware_mapping_path.push(&hardware_mapping_filename);
-            let mut hardware_mapping_file = fs::File::create(hardware_mapping_path)?;
-            hardware_mapping_file
-                .write_all(serde_json::to_string_pretty(hardware_mapping)?.as_bytes())?;
+            util::write_maybe_gzipped(
+                &hardware_mapping_path,
+                serde_json::to_string_pretty(hardware_mapping)?.as_bytes(),
+            )?;
             // deserialize as Vec<(K, V)> and run `.into_iter().collect::<HashMap<...>>()`
 
             log::debug!("[cisco-analyzer] Writing CSV");
@@ -767,34 +1335,74 @@ where
                 capture_frequency,
                 hardware_mapping_filename,
                 packets_dropped,
+                iperf_clients: self.iperf_load.num_clients,
+                iperf_udp: self.iperf_load.udp,
+                schema_version: CISCO_ANALYZER_SCHEMA_VERSION,
             })?;
             csv.flush()?;
+            summary.n_samples += 1;
 
-            lab.wait_for_convergence().await?;
+            converged = self.wait_for_lab_convergence(&mut lab).await;
         }
 
-        lab.stop_iperf(iperf_handle).await?;
+        if let Some(iperf_handle) = iperf_handle {
+            lab.stop_iperf(iperf_handle).await?;
+        }
 
         // disconnect the network.
         print!("Network is disconnecting...");
         let _ = lab.disconnect().await?;
         log::debug!("[cisco-analyzer] Done.");
 
-        Ok(result)
+        Ok(summary)
+    }
+
+    /// Wait for the testbed to converge, with the timeout and retries of `convergence_retry`.
+    /// Returns whether the testbed converged.
+    async fn wait_for_lab_convergence<Ospf: OspfImpl>(
+        &self,
+        lab: &mut RouterLab<'_, Prefix, Q, Ospf, Active>,
+    ) -> bool {
+        let mut attempt = 0;
+        loop {
+            let converged =
+                match timeout(self.convergence_retry.timeout, lab.wait_for_convergence()).await {
+                    Ok(Ok(_)) => true,
+                    Ok(Err(e)) => {
+                        log::warn!("[cisco-analyzer] error while waiting for convergence: {e}");
+                        false
+                    }
+                    Err(_) => {
+                        log::warn!(
+                            "[cisco-analyzer] no convergence within {:?}",
+                            self.convergence_retry.timeout
+                        );
+                        false
+                    }
+                };
+            match self.convergence_retry.decide(attempt, converged) {
+                RetryDecision::Converged => return true,
+                RetryDecision::Retry => {
+                    log::info!("[cisco-analyzer] waiting for convergence again")
+                }
+                RetryDecision::Skip => return false,
+            }
+            attempt += 1;
+        }
     }
 
     fn worker(
         &self,
         result_global: Arc<Mutex<AnalysisResult>>,
         stats_global: Arc<Mutex<Stats>>,
-        iters: usize,
-    ) {
+        samples: std::ops::Range<usize>,
+    ) -> std::io::Result<()> {
         // thread-local copies of the network and fw_state
         let mut t = self.scheduled_net.clone();
         let mut fw_state = self.original_fw.clone();
 
-        // thread-local HashMap to keep track of the results
-        let mut stats = Stats::new();
+        // thread-local state to keep track of the results
+        let mut ctx = self.sample_context();
 
         // initialize durations for accumulative performance measurements per worker
         let mut sum_simulate = Duration::new(0, 0);
@@ -802,41 +1410,44 @@ where
         let mut sum_clone = Duration::new(0, 0);
 
         let mut convergence_time = 0.0;
+        let mut n_simulated = 0;
+        let mut n_incomplete = 0;
+        let mut trace_lengths = Vec::new();
 
-        let mut queue;
-        if let Some(geo_location) = &self.geo_location {
-            queue = TimingModel::from_geo_location(geo_location);
-        } else if let Some(delays) = &self.delays {
-            queue = TimingModel::from_delays(delays);
-        } else {
-            panic!("TimingModel cannot be initialized without geo_location and delays!");
-        }
-
-        for _ in 0..iters {
-            let step = self.simulate_once(t, fw_state, &mut queue, &mut stats);
-            t = step.0;
-            fw_state = step.1;
-            sum_simulate += step.2;
-            sum_checking += step.3;
-            sum_clone += step.4;
-            convergence_time += step.5;
+        for (i, sample) in samples.enumerate() {
+            if self.is_cancelled() {
+                break;
+            }
+            // bound the memory of the worker if the samples are stored on disk anyways
+            if self.stats_store.is_some() && i > 0 && i % STATS_STORE_FLUSH_INTERVAL == 0 {
+                self.merge_stats(&stats_global, std::mem::take(&mut ctx.stats))?;
+            }
+            let outcome = self.simulate_once(t, fw_state, &mut ctx, sample);
+            if let (Some(export), Some(violation_times)) =
+                (&self.sample_export, &outcome.violation_times)
+            {
+                self.export_sample(export, sample, violation_times)?;
+            }
+            t = outcome.net;
+            fw_state = outcome.fw_state;
+            sum_simulate += outcome.t_simulate;
+            sum_checking += outcome.t_checking;
+            sum_clone += outcome.t_clone;
+            convergence_time += outcome.convergence_time;
+            n_simulated += 1;
+            if self.record_trace_lengths {
+                trace_lengths.push(outcome.trace_length);
+            }
+            if outcome.incomplete {
+                n_incomplete += 1;
+            }
         }
 
         // make sure (only once per worker) that the partial_clone is actually safe
         assert_eq!(t, self.scheduled_net);
 
         let now = Instant::now();
-
-        // wait for exclusive access on global stats hashmap
-        let mut stats_global = stats_global.lock().unwrap();
-        // insert all values into the global hashmap
-        for (eq_class, violation_times) in stats.into_iter() {
-            stats_global
-                .entry(eq_class)
-                .or_default()
-                .extend(violation_times);
-        }
-
+        self.merge_stats(&stats_global, ctx.stats)?;
         let time_insert_global = now.elapsed();
 
         // collect the timing
@@ -846,29 +1457,98 @@ where
         result_global.t_simulate += sum_simulate;
         result_global.t_collect += time_insert_global;
         result_global.convergence_time += convergence_time;
+        result_global.n_samples += n_simulated;
+        result_global.n_incomplete += n_incomplete;
+        result_global.trace_lengths.extend(trace_lengths);
+        for (key, egresses) in ctx.final_egresses {
+            let tally = result_global.final_egresses.entry(key).or_default();
+            for (egress, count) in egresses {
+                *tally.entry(egress).or_default() += count;
+            }
+        }
+        result_global
+            .worst_traces
+            .extend(ctx.worst_traces.into_sorted_vec());
+        Ok(())
+    }
+
+    /// Merge the `stats` of a worker into the global stats, or append them to the `stats_store` if
+    /// set.
+    fn merge_stats(&self, stats_global: &Mutex<Stats>, stats: Stats) -> std::io::Result<()> {
+        // wait for exclusive access on global stats hashmap, which also serializes the appends to
+        // the files of the store
+        let mut stats_global = stats_global.lock().unwrap();
+        for (eq_class, violation_times) in stats.into_iter() {
+            match &self.stats_store {
+                Some(store) => store.append_weighted(eq_class, &violation_times)?,
+                None => stats_global
+                    .entry(eq_class)
+                    .or_default()
+                    .extend(violation_times),
+            }
+        }
+        Ok(())
+    }
+
+    /// Prepare the random draws of `sample` from `sample_rng(sample)`: Draw the jitter (in
+    /// seconds) by which the event is delayed uniformly from `[0, event_jitter]`, and let the queue
+    /// of `net` take its draws from the remaining stream. Returns the jitter.
+    fn prepare_sample(&self, net: &mut Network<Prefix, Q>, sample: usize) -> f64 {
+        let mut rng = self.sample_rng(sample);
+        let jitter = self
+            .event_jitter
+            .map(|max_jitter| rng.gen_range(0.0..=max_jitter))
+            .unwrap_or_default();
+        net.queue_mut().set_rng(StdRng::from_rng(rng).unwrap());
+        jitter
+    }
+
+    /// Create the worker-local state for `simulate_once`.
+    pub fn sample_context(&self) -> SampleContext {
+        SampleContext {
+            queue: self.build_queue(),
+            stats: Stats::new(),
+            worst_traces: WorstTraces::new(self.retain_worst_traces.unwrap_or_default()),
+            final_egresses: FinalEgresses::new(),
+        }
     }
 
-    /// Perform a single iteration on the worker
+    /// Perform a single iteration on the worker, recording the violation times of the sample in
+    /// `ctx.stats`. The trace of the sample is offered to `ctx.worst_traces`, and its final
+    /// egresses are counted in `ctx.final_egresses` if `record_final_egresses` is set. The random
+    /// draws of the sample are taken from `sample_rng(sample)`.
     pub fn simulate_once(
         &self,
         mut net: Network<Prefix, Q>,
         mut fw_state: ForwardingState<Prefix>,
-        queue: &mut TimingModel<Prefix>,
-        stats: &mut Stats,
-    ) -> (
-        Network<Prefix, Q>,
-        ForwardingState<Prefix>,
-        Duration,
-        Duration,
-        Duration,
-        f64,
-    ) {
+        ctx: &mut SampleContext,
+        sample: usize,
+    ) -> SampleOutcome<Q> {
         let now = Instant::now();
 
-        let trace = self.build_trace(&mut net);
+        let jitter = self.prepare_sample(&mut net, sample);
+        let mut trace = self.build_trace(&mut net);
+        let trace_length = trace.values().map(|prefix_trace| prefix_trace.len()).sum();
+        let weight = net.queue_mut().take_log_weight().exp();
+
+        shift_trace(&mut trace, jitter);
+
+        // detect samples that did not converge
+        let mut incomplete = !net.queue().is_empty();
+        if incomplete {
+            log::warn!(
+                "Convergence did not complete, {} events remain in the queue!",
+                net.queue().len()
+            );
+        }
 
         // compute equivalence classes with a canonical message ordering
-        let eq_class = compute_equivalence_class(&trace, &self.scheduled_fw, &self.policies);
+        let eq_class = compute_equivalence_class(
+            &trace,
+            &self.scheduled_fw,
+            &self.policies,
+            &self.equivalent_egresses,
+        );
 
         let t_simulate = now.elapsed();
         let now = Instant::now();
@@ -886,54 +1566,87 @@ where
         );
         */
 
-        let transient_policies: HashMap<(RouterId, Prefix), Vec<TransientPolicy>> = self
-            .policies
-            .iter()
-            .filter_map(|policy| {
-                let (Some(rid), Some(prefix)) = (policy.router(), policy.prefix()) else {
-                    return None;
-                };
-                Some((rid, prefix, policy.clone()))
-            })
-            .sorted_by(|(rid1, prefix1, _), (rid2, prefix2, _)| {
-                rid1.cmp(rid2).then(prefix1.cmp(prefix2))
-            })
-            .group_by(|(rid, prefix, _policy)| (*rid, *prefix))
-            .into_iter()
-            .map(|((rid, prefix), group)| {
-                (
-                    (rid, prefix),
-                    group
-                        .into_iter()
-                        .map(|(_, _, policy)| policy)
-                        .collect::<Vec<_>>(),
-                )
-            })
-            .collect();
         // check transient policies
-        let violation_times = compute_violation_times(
-            &self.original_net,
-            queue,
-            &mut fw_state,
-            &trace,
-            &transient_policies,
-            None::<&std::path::Path>,
-        );
+        let violation_times = if self.return_prefixes.is_empty() {
+            compute_violation_times(
+                &self.original_net,
+                &mut ctx.queue,
+                &mut fw_state,
+                &trace,
+                &self.grouped_policies,
+                None::<&std::path::Path>,
+                &TotalDuration,
+            )
+            .map(|violation_times| violation_times.times)
+        } else {
+            compute_round_trip_violation_times(
+                &self.original_net,
+                &mut ctx.queue,
+                &mut fw_state,
+                &trace,
+                &self.grouped_policies,
+                &self.return_prefixes,
+            )
+        };
+        let mut violation_times = match violation_times {
+            Ok(violation_times) => Some(violation_times),
+            Err(e) => {
+                log::error!("Cannot evaluate sample {sample}, dropping it: {e}");
+                incomplete = true;
+                None
+            }
+        };
+        if let Some(violation_times) = violation_times.as_mut() {
+            violation_times.extend(
+                compute_convergence_violations(
+                    &self.original_net,
+                    &fw_state,
+                    &trace,
+                    &self.policies,
+                )
+                .times,
+            );
+        }
 
         // reuse the original ForwardingState with the next sample
         //fw_state = recording.into_initial_fw_state();
 
+        if self.record_final_egresses {
+            for (key, egress) in self.final_egresses(&trace) {
+                *ctx.final_egresses
+                    .entry(key)
+                    .or_default()
+                    .entry(egress)
+                    .or_default() += 1;
+            }
+        }
+
         // fix the return type to the old vec-based implementation
-        let mut fixed_violation_times = vec![0.0; self.policies.len()];
-        for (policy, violation) in violation_times {
-            fixed_violation_times[self.policies.iter().position(|p| *p == policy).unwrap()] =
-                violation;
+        let violation_times = violation_times.map(|violation_times| {
+            let mut fixed_violation_times = vec![0.0; self.policies.len()];
+            for (policy, violation) in violation_times {
+                fixed_violation_times[self.policies.iter().position(|p| *p == policy).unwrap()] =
+                    violation;
+            }
+            fixed_violation_times
+        });
+
+        // a sample that cannot be evaluated is not recorded at all
+        if let Some(fixed_violation_times) = &violation_times {
+            if self.retain_worst_traces.is_some_and(|num| num > 0) {
+                ctx.worst_traces.push(RetainedTrace {
+                    total_violation_time: fixed_violation_times.iter().sum(),
+                    violation_times: fixed_violation_times.clone(),
+                    eq_class,
+                    trace,
+                });
+            }
+            // use entry syntax to avoid race condition for call to `contains_key`
+            ctx.stats
+                .entry(eq_class)
+                .or_default()
+                .push((weight, fixed_violation_times.clone()));
         }
-        // use entry syntax to avoid race condition for call to `contains_key`
-        stats
-            .entry(eq_class)
-            .or_default()
-            .push(fixed_violation_times);
 
         let t_checking = now.elapsed();
 
@@ -942,34 +1655,80 @@ where
         let convergence_time = net
             .queue()
             .get_time()
-            .map(|x| x - self.time_offset)
+            .map(|x| x - self.time_offset + jitter)
             .unwrap_or_default();
 
-        // reuse most parts of the network for faster cloning
-        net = unsafe {
-            PartialClone::new(&self.scheduled_net)
-                .reuse_config(true)
-                .reuse_igp_state(true)
-                .reuse_queue_params(true)
-                .conquer(net)
-        };
+        net = self.reset_to_scheduled(net);
 
         let t_clone = now.elapsed();
 
-        (
+        SampleOutcome {
             net,
             fw_state,
             t_simulate,
             t_checking,
             t_clone,
             convergence_time,
-        )
+            trace_length,
+            incomplete,
+            violation_times,
+        }
     }
 
-    pub fn build_trace(&self, net: &mut Network<Prefix, Q>) -> MultiPrefixConvergenceTrace {
-        let mut trace = MultiPrefixConvergenceTrace::new();
+    /// Write the violation time of each policy in `sample` to the `export` as a `SampleRecord`.
+    fn export_sample(
+        &self,
+        export: &Mutex<csv::Writer<fs::File>>,
+        sample: usize,
+        violation_times: &[f64],
+    ) -> std::io::Result<()> {
+        // hold the lock while writing all rows, such that the rows of a sample are consecutive
+        let mut export = export.lock().unwrap();
+        for (policy, (p, violation_time)) in self.policies.iter().zip(violation_times).enumerate() {
+            export.serialize(SampleRecord {
+                sample,
+                policy,
+                router: p.router().map(|r| r.fmt(&self.original_net).to_string()),
+                prefix: p.prefix().map(Ipv4Addr::from),
+                violation_time: *violation_time,
+            })?;
+        }
+        Ok(())
+    }
 
-        while let Some((step, event)) = net.simulate_step().unwrap() {
+    /// Simulate the network until it has converged (or until `max_steps` events were processed),
+    /// recording all forwarding updates.
+    ///
+    /// For `AnalyzerEvent::SetLinkWeight`, the trace starts with the forwarding updates caused
+    /// directly by changing the link weight (i.e., from `original_fw` to `scheduled_fw`) at time
+    /// zero (see `initial_trace`).
+    ///
+    /// If the event has `timed_changes` (e.g., `AnalyzerEvent::LinkFlap`), each of them is applied
+    /// right before processing the first event at or after its time, or as soon as the queue runs
+    /// empty before. The forwarding updates caused directly by a change are recorded at its time.
+    pub fn build_trace(&self, net: &mut Network<Prefix, Q>) -> MultiPrefixConvergenceTrace {
+        let mut trace = self.initial_trace();
+        let mut steps = 0;
+        let timed_changes = self.event.timed_changes();
+        let mut next_change = 0;
+
+        while self.max_steps.is_none_or(|max_steps| steps < max_steps) {
+            while let Some(t_change) = timed_changes.get(next_change).copied() {
+                let next = net
+                    .queue_mut()
+                    .next_event_time()
+                    .map(|x| x - self.time_offset);
+                if !net.queue().is_empty() && next.is_some_and(|next| next < t_change) {
+                    break;
+                }
+                let time = net.queue().get_time().map(|x| x - self.time_offset);
+                self.apply_timed_change(net, &mut trace, next_change, time, t_change);
+                next_change += 1;
+            }
+            let Some((step, event)) = net.simulate_step().unwrap() else {
+                break;
+            };
+            steps += 1;
             match step {
                 StepUpdate::Unchanged => {}
                 StepUpdate::Single(delta) => {
@@ -987,11 +1746,204 @@ where
         trace
     }
 
-    /// Replace all policies to analyze
+    /// Reset `net` to the `scheduled_net` after simulating a sample, reusing most parts of it for
+    /// faster cloning. If the event changes the network during the simulation (see `build_trace`),
+    /// the configuration, IGP state, and queue parameters differ from the `scheduled_net` and are
+    /// cloned as well.
+    fn reset_to_scheduled(&self, net: Network<Prefix, Q>) -> Network<Prefix, Q> {
+        let reuse = self.event.timed_changes().is_empty();
+        unsafe {
+            PartialClone::new(&self.scheduled_net)
+                .reuse_config(reuse)
+                .reuse_igp_state(reuse)
+                .reuse_queue_params(reuse)
+                .conquer(net)
+        }
+    }
+
+    /// Apply the timed change `i` of the event on `net` at `t_change` (see `build_trace`), where
+    /// `time` is the current time of the queue relative to `self.time_offset`.
+    fn apply_timed_change(
+        &self,
+        net: &mut Network<Prefix, Q>,
+        trace: &mut MultiPrefixConvergenceTrace,
+        i: usize,
+        time: Option<f64>,
+        t_change: f64,
+    ) {
+        // the queue ran empty before, so advance its time to when the change happens
+        if let Some(time) = time.filter(|time| *time < t_change) {
+            net.queue_mut().shift_time(t_change - time);
+        }
+        let time = time.map(|time| time.max(t_change));
+
+        let before = net.get_forwarding_state();
+        self.event
+            .apply_timed_change(i, net, &self.original_net)
+            .unwrap();
+        let after = net.get_forwarding_state();
+        self.push_fw_deltas(trace, &before, &after, time);
+    }
+
+    /// Get the start of the trace of each sample. Changing a link weight immediately shifts the
+    /// traffic of the routers whose IGP path changes, before any BGP message is exchanged. For
+    /// `AnalyzerEvent::SetLinkWeight`, these forwarding updates from `original_fw` to
+    /// `scheduled_fw` are thus recorded at time zero. For all other events, the trace starts
+    /// empty.
+    pub(crate) fn initial_trace(&self) -> MultiPrefixConvergenceTrace {
+        let mut trace = MultiPrefixConvergenceTrace::new();
+        if matches!(self.event, AnalyzerEvent::SetLinkWeight { .. }) {
+            self.push_fw_deltas(&mut trace, &self.original_fw, &self.scheduled_fw, Some(0.0));
+        }
+        trace
+    }
+
+    /// Append the forwarding updates of all internal routers from `before` to `after` to the
+    /// `trace`, at the given `time` relative to `self.time_offset`.
+    fn push_fw_deltas(
+        &self,
+        trace: &mut MultiPrefixConvergenceTrace,
+        before: &ForwardingState<Prefix>,
+        after: &ForwardingState<Prefix>,
+        time: Option<f64>,
+    ) {
+        for prefix in self.original_net.get_known_prefixes().copied() {
+            let deltas = self
+                .original_net
+                .internal_indices()
+                .filter_map(|r| {
+                    let old = before.get_next_hops(r, prefix);
+                    let new = after.get_next_hops(r, prefix);
+                    (old != new).then(|| (r, old.to_vec(), new.to_vec()))
+                })
+                .collect_vec();
+            if !deltas.is_empty() {
+                trace.entry(prefix).or_default().push((deltas, time.into()));
+            }
+        }
+    }
+
+    /// Get the egress router of each internal router for each prefix known in the original network,
+    /// once all forwarding updates of the `trace` are applied. The egress is `None` if the traffic
+    /// is finally dropped or caught in a loop. With multiple (ECMP) paths, the first path is used.
+    pub fn final_egresses(
+        &self,
+        trace: &MultiPrefixConvergenceTrace,
+    ) -> HashMap<(RouterId, Prefix), Option<RouterId>> {
+        let mut fw_state = self.fw_state_at(trace, f64::INFINITY);
+        let mut result = HashMap::new();
+        for prefix in self.original_net.get_known_prefixes().copied() {
+            for r in self.original_net.internal_indices() {
+                let egress = fw_state
+                    .get_paths(r, prefix)
+                    .ok()
+                    .and_then(|paths| paths.first()?.last().copied());
+                result.insert((r, prefix), egress);
+            }
+        }
+        result
+    }
+
+    /// Get the paths of each internal router towards each prefix known in the original network,
+    /// before the event occurs (see `paths_from_fw_state`). With multiple (ECMP) paths, all of
+    /// them are returned. If the traffic is dropped or caught in a loop, the black hole or the loop
+    /// is returned as the only path.
+    pub fn stable_paths(
+        &self,
+    ) -> HashMap<(RouterId, Prefix), Vec<crate::transient_specification::Path>> {
+        let mut fw_state = self.original_fw.clone();
+        let mut result = HashMap::new();
+        for prefix in self.original_net.get_known_prefixes().copied() {
+            for r in self.original_net.internal_indices() {
+                result.insert((r, prefix), paths_from_fw_state(r, prefix, &mut fw_state));
+            }
+        }
+        result
+    }
+
+    /// Compare the selected BGP route of each internal router for `prefix` in the `original_net`
+    /// and in the network after convergence, ordered by router. The converged network is simulated
+    /// from a clone of the `scheduled_net`, which only holds the state immediately after the
+    /// event was triggered. Unlike the forwarding state, this also reveals changes in the RIB that
+    /// do not affect forwarding.
+    pub fn bgp_rib_diff(&self, prefix: Prefix) -> Vec<RibChange> {
+        let mut converged_net = self.scheduled_net.clone();
+        self.build_trace(&mut converged_net);
+        let selected_route = |net: &Network<Prefix, Q>, router: RouterId| {
+            net.get_device(router)
+                .unwrap()
+                .unwrap_internal()
+                .bgp
+                .get_rib()
+                .get(&prefix)
+                .cloned()
+        };
+        self.original_net
+            .internal_indices()
+            .sorted()
+            .filter_map(|router| {
+                match (
+                    selected_route(&self.original_net, router),
+                    selected_route(&converged_net, router),
+                ) {
+                    (None, Some(new)) => Some(RibChange::Added { router, new }),
+                    (Some(old), None) => Some(RibChange::Removed { router, old }),
+                    (Some(old), Some(new)) if old != new => {
+                        Some(RibChange::Changed { router, old, new })
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Reconstruct the forwarding state at time `t` during convergence, i.e., the original
+    /// forwarding state with all forwarding updates of the `trace` applied that happen no later
+    /// than `t`. Timestamps are relative to `self.time_offset`, as produced by `build_trace`.
+    pub fn fw_state_at(
+        &self,
+        trace: &MultiPrefixConvergenceTrace,
+        t: f64,
+    ) -> ForwardingState<Prefix> {
+        let mut fw_state = self.original_fw.clone();
+        for (prefix, prefix_trace) in trace.iter() {
+            // prefix traces are in chronological order
+            for (fw_deltas, _) in prefix_trace
+                .iter()
+                .take_while(|(_, time)| time.into_inner().is_none_or(|time| time <= t))
+            {
+                for (router, _, new_nh) in fw_deltas {
+                    fw_state.update(*router, *prefix, new_nh.clone());
+                }
+            }
+        }
+        fw_state
+    }
+
+    /// Replace all policies to analyze. If a `stats_store` is set, `analyze` returns an error
+    /// unless it holds samples of the new policies.
     pub fn set_policies(&mut self, policies: Vec<TransientPolicy>) {
+        self.grouped_policies = group_policies(&policies);
         self.policies = policies
     }
 
+    /// Replace all policies to analyze by the policy template `kind` at `router`, instantiated for
+    /// every prefix known in the original network.
+    pub fn set_policy_template(&mut self, router: RouterId, kind: PolicyKind) {
+        let prefixes = self
+            .original_net
+            .get_known_prefixes()
+            .copied()
+            .sorted()
+            .collect_vec();
+        self.set_policies(TransientPolicy::for_all_prefixes(router, kind, &prefixes));
+    }
+
+    /// Get the policies to analyze, grouped by the `(RouterId, Prefix)` pair they concern.
+    pub fn grouped_policies(&self) -> &HashMap<(RouterId, Prefix), Vec<TransientPolicy>> {
+        &self.grouped_policies
+    }
+
     pub fn net(&self) -> &Network<Prefix, Q> {
         &self.original_net
     }
@@ -1002,7 +1954,7 @@ where
 
     /// Set the confidence (`1 - alpha`). Typically, this value should be 95% or 99%.
     pub fn set_confidence(&mut self, confidence: f64) {
-        self.confidence = confidence
+        self.confidence = clamp_param("confidence", confidence)
     }
 
     /// Set the number of collected samples explicitly.
@@ -1012,7 +1964,36 @@ where
 
     /// Set the precision.
     pub fn set_precision(&mut self, precision: f64) {
-        self.precision = precision
+        self.precision = clamp_param("precision", precision)
+    }
+
+    /// Get the AS of the external router `rid`, or `None` if `rid` is not an external router.
+    pub fn external_as_of(&self, rid: RouterId) -> Option<AsId> {
+        self.original_net
+            .external_routers()
+            .find(|r| r.router_id() == rid)
+            .map(|r| r.as_id())
+    }
+
+    /// Get all external routers that belong to the AS `as_id`, ordered by router id.
+    pub fn externals_of_as(&self, as_id: AsId) -> Vec<RouterId> {
+        self.original_net
+            .external_routers()
+            .filter(|r| r.as_id() == as_id)
+            .map(|r| r.router_id())
+            .sorted()
+            .collect()
+    }
+
+    /// Check whether `path` leaves the network towards an external router of the AS `as_id`, e.g.,
+    /// to verify that a data-plane path follows the expected AS path (whose first AS is the one of
+    /// the external router). Loops and black holes never match.
+    pub fn egress_matches_as(
+        &self,
+        path: &crate::transient_specification::Path,
+        as_id: AsId,
+    ) -> bool {
+        path.egress().and_then(|egress| self.external_as_of(egress)) == Some(as_id)
     }
 
     /// Set the geographic locations of the network's nodes on earth.
@@ -1020,17 +2001,222 @@ where
         self.geo_location = Some(geo_location.clone());
     }
 
+    /// Stream the violation time of each policy in each sample collected by `analyze` to a CSV
+    /// file at `path`, in long format (see `SampleRecord`). Samples are written as they are
+    /// collected, such that they are not ordered by their index. An existing file is overwritten.
+    pub fn set_sample_export(&mut self, path: impl AsRef<Path>) -> Result<(), csv::Error> {
+        self.sample_export = Some(Arc::new(Mutex::new(csv::Writer::from_path(path)?)));
+        Ok(())
+    }
+
     /// Set the router delays of the network's nodes.
     pub fn set_delays(&mut self, delays: HashMap<(RouterId, RouterId), f64>) {
         self.delays = Some(delays.clone());
     }
 
-    /// Compute the number of samples required to reach the given confidence and precision
+    /// Set the maximum number of events to simulate per sample.
+    pub fn set_max_steps(&mut self, max_steps: usize) {
+        self.max_steps = Some(max_steps)
+    }
+
+    /// Set whether `analyze` records the number of forwarding updates of each sample.
+    pub fn set_record_trace_lengths(&mut self, record_trace_lengths: bool) {
+        self.record_trace_lengths = record_trace_lengths
+    }
+
+    /// Set whether `analyze` records the final egress of each `(router, prefix)` pair of each
+    /// sample.
+    pub fn set_record_final_egresses(&mut self, record_final_egresses: bool) {
+        self.record_final_egresses = record_final_egresses
+    }
+
+    /// Set the maximum jitter (in seconds) of the event time of each sample. Each sample is shifted
+    /// as a whole by a single draw from `[0, event_jitter]` (see `event_jitter`), without
+    /// reordering its messages.
+    pub fn set_event_jitter(&mut self, event_jitter: f64) {
+        self.event_jitter = Some(event_jitter.max(0.0))
+    }
+
+    /// Write the violation times of all samples of `analyze` to `store` instead of keeping them in
+    /// memory. The results of `analyze` then include all samples in the store, including the ones
+    /// of previous runs, which allows resuming an analysis. Set the policies first: Returns an
+    /// error if the store contains samples of other policies (see `StatsStore::check_header`).
+    ///
+    /// The store only bounds the memory used while sampling, `analyze` still collects the violation
+    /// times of all stored samples in its result (see `StatsStore`).
+    pub fn set_stats_store(&mut self, store: StatsStore) -> std::io::Result<()> {
+        store.check_header(&self.stats_store_header())?;
+        self.stats_store = Some(store);
+        Ok(())
+    }
+
+    /// Header of the `stats_store`, identifying the policies the violation times are computed for.
+    fn stats_store_header(&self) -> String {
+        serde_json::to_string(&self.policies).unwrap()
+    }
+
+    /// Set the flag that cancels `analyze` and `analyze_router_lab` once it is set to `true`, e.g.,
+    /// from a signal handler or another thread.
+    pub fn set_cancellation_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.cancellation_flag = Some(flag)
+    }
+
+    /// Check whether the analysis was cancelled through the `cancellation_flag`.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_flag
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// Set the seed for the random draws of each sample.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed)
+    }
+
+    /// Get the random number generator of the sample with the given (global) index. With a `seed`,
+    /// each sample gets its own stream, determined by the seed and the index only.
+    pub fn sample_rng(&self, sample: usize) -> StdRng {
+        match self.seed {
+            // spread the indices before mixing them into the seed, such that consecutive seeds do
+            // not share the streams of shifted samples
+            Some(seed) => {
+                StdRng::seed_from_u64(seed ^ (sample as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            }
+            None => StdRng::from_rng(thread_rng()).unwrap(),
+        }
+    }
+
+    /// Set the order in which the route updates of the event are scheduled, and trigger the event
+    /// again on the `scheduled_net`.
+    pub fn set_update_ordering(&mut self, ordering: UpdateOrdering) -> Result<(), NetworkError> {
+        let mut scheduled_net = self.original_net.clone();
+        scheduled_net.manual_simulation();
+        self.event.trigger_ordered(&mut scheduled_net, ordering)?;
+
+        self.scheduled_fw = scheduled_net.get_forwarding_state();
+        self.scheduled_net = scheduled_net;
+        self.update_ordering = ordering;
+        Ok(())
+    }
+
+    /// Declare groups of egress routers as equivalent, i.e., traces that only differ in which
+    /// router of a group is chosen as the next hop satisfy the same policies. Such traces are
+    /// collapsed into the same equivalence class. The caller must make sure that the routers of
+    /// each group are indeed equivalent for all policies.
+    pub fn set_equivalent_egresses(&mut self, groups: &[Vec<RouterId>]) {
+        self.equivalent_egresses = groups
+            .iter()
+            .filter_map(|group| Some((group.iter().min()?, group)))
+            .flat_map(|(canonical, group)| group.iter().map(|rid| (*rid, *canonical)))
+            .collect();
+    }
+
+    /// Retain the traces of the `num` samples with the largest total violation time in
+    /// `AnalysisResult::worst_traces`.
+    pub fn set_retain_worst_traces(&mut self, num: usize) {
+        self.retain_worst_traces = Some(num)
+    }
+
+    /// Store the hardware mapping of each sample collected by `analyze_router_lab` gzipped.
+    pub fn set_compress_hardware_mapping(&mut self, compress: bool) {
+        self.compress_hardware_mapping = compress;
+    }
+
+    /// Set the timeout and retries when waiting for the testbed to converge.
+    pub fn set_convergence_retry(&mut self, convergence_retry: ConvergenceRetry) {
+        self.convergence_retry = convergence_retry
+    }
+
+    /// Set the interface of the server on which `analyze_router_lab` captures the BGP messages of
+    /// the event to detect its timestamp.
+    pub fn set_monitoring_iface(&mut self, iface: impl Into<String>) {
+        self.monitoring_iface = iface.into()
+    }
+
+    /// Analyze the round trip of each router, sending its return traffic towards the given return
+    /// prefix. Routers without a return prefix are only analyzed in the forward direction.
+    pub fn set_return_prefixes(&mut self, return_prefixes: HashMap<RouterId, Prefix>) {
+        self.return_prefixes = return_prefixes
+    }
+
+    /// Set the background traffic generated while running on the hardware.
+    pub fn set_iperf_load(&mut self, iperf_load: IperfLoad) {
+        self.iperf_load = iperf_load
+    }
+
+    /// Minimum number of samples collected by `analyze`, which is rounded up to the same number of
+    /// samples for each worker. Use `required_num_samples` to reach the given confidence and
+    /// precision.
     pub fn num_samples(&self) -> usize {
-        //self.num_samples
-        //    .unwrap_or(hoeffding(self.confidence, self.precision))
         1
     }
+
+    /// Get the number of samples set by `set_num_samples`, or otherwise the number of samples
+    /// required to estimate the probability of satisfying all policies within `precision` at the
+    /// given `confidence` (see `hoeffding`). Runners size `analyze_with` by it, such that the
+    /// confidence and precision of each experiment are respected.
+    pub fn required_num_samples(&self) -> usize {
+        self.num_samples
+            .unwrap_or_else(|| hoeffding(self.confidence, self.precision))
+    }
+}
+
+/// Name of the optional file in the `data_path` of `analyze_router_lab` that overrides individual
+/// link delays, applied on top of the delays from `geo_location` or `delays`.
+pub const LINK_DELAYS_FILE: &str = "link_delays.json";
+
+/// A single entry of the `LINK_DELAYS_FILE`.
+#[derive(Debug, Deserialize)]
+struct LinkDelayOverride {
+    from: String,
+    to: String,
+    delay_us: f64,
+}
+
+/// Parse the content of a `LINK_DELAYS_FILE`, which is a JSON list of link delays in microseconds
+/// between two routers identified by their names, e.g.:
+///
+/// ```json
+/// [
+///     { "from": "r0", "to": "r1", "delay_us": 1500.0 },
+///     { "from": "r1", "to": "r2", "delay_us": 800.0 }
+/// ]
+/// ```
+///
+/// As for `Analyzer::delays`, a delay also applies in the reverse direction unless that direction
+/// is given explicitly.
+pub fn parse_link_delay_overrides<Q>(
+    json: &str,
+    net: &Network<Prefix, Q>,
+) -> Result<HashMap<(RouterId, RouterId), f64>, Box<dyn std::error::Error>> {
+    let entries: Vec<LinkDelayOverride> = serde_json::from_str(json)?;
+    entries
+        .into_iter()
+        .map(|LinkDelayOverride { from, to, delay_us }| {
+            if !delay_us.is_finite() || delay_us < 0.0 {
+                return Err(format!("Invalid delay {delay_us} from {from} to {to}!").into());
+            }
+            Ok((
+                (net.get_router_id(&from)?, net.get_router_id(&to)?),
+                delay_us,
+            ))
+        })
+        .collect()
+}
+
+/// Smallest value accepted for the confidence and the precision.
+const MIN_PARAM: f64 = 0.001;
+/// Largest value accepted for the confidence and the precision.
+const MAX_PARAM: f64 = 0.999;
+
+/// Clamp the confidence or precision to `[MIN_PARAM, MAX_PARAM]`, such that `hoeffding` yields a
+/// finite number of samples. Logs a warning if the value is outside of `(0, 1)`.
+fn clamp_param(name: &str, value: f64) -> f64 {
+    let clamped = value.max(MIN_PARAM).min(MAX_PARAM);
+    if value.is_nan() || value <= 0.0 || value >= 1.0 {
+        log::warn!("Invalid {name} {value}, must be in (0, 1)! Using {clamped} instead.");
+    }
+    clamped
 }
 
 /// Compute the number of samples required to reach the given confidence and precision
@@ -1038,13 +2224,32 @@ pub fn hoeffding(confidence: f64, precision: f64) -> usize {
     (f64::ln(2.0 / (1.0 - confidence)) / (0.5 * precision * precision)).ceil() as usize
 }
 
+/// Build the command that captures the first packet on `iface` matching the tcpdump `filter`, and
+/// prints its timestamp (in seconds since the epoch).
+fn event_start_capture_cmd(iface: &str, filter: &str) -> String {
+    format!(
+        "sudo tcpdump_pfring -i {iface} {filter} -c1 -w - 2>/dev/null | tshark -r - -T fields -e frame.time_epoch 2>/dev/null",
+    )
+}
+
+/// Delay the event by a random `jitter`, shifting all times of the sample's `trace`.
+fn shift_trace(trace: &mut MultiPrefixConvergenceTrace, jitter: f64) {
+    if jitter > 0.0 {
+        for (_, time) in trace.values_mut().flatten() {
+            *time = time.into_inner().map(|t| t + jitter).into();
+        }
+    }
+}
+
 /// Allows to compute an equivalence class using a custom hasher. Will unify message orderings
 /// that are guaranteed to be equivalent upon reordering messages with respect to the given
-/// TransientPolicies.
+/// TransientPolicies. Next hops contained in `equivalent_egresses` are replaced by their canonical
+/// representative, collapsing traces that only differ in the choice among equivalent egresses.
 pub fn compute_equivalence_class(
     trace: &MultiPrefixConvergenceTrace,
     fw_state: &ForwardingState<Prefix>,
     transient_policies: &[TransientPolicy],
+    equivalent_egresses: &HashMap<RouterId, RouterId>,
 ) -> u64 {
     // compute equivalence classes with a custom hasher:
     let mut hasher = DefaultHasher::new();
@@ -1057,7 +2262,7 @@ pub fn compute_equivalence_class(
         let mut reachable_set: HashSet<RouterId> = HashSet::new();
         let mut todo_vec: Vec<RouterId> = transient_policies
             .iter()
-            .map(|x| x.router().unwrap())
+            .filter_map(|x| x.router())
             .collect();
         while let Some(current_node) = todo_vec.pop() {
             if reachable_set.insert(current_node) {
@@ -1108,9 +2313,13 @@ pub fn compute_equivalence_class(
                 // encode modified router
                 hasher.write_usize(fw_delta.0.index());
 
-                // encode next_hops
+                // encode next_hops, replacing equivalent egress routers by their representative
                 hasher.write_usize(fw_delta.2.len());
-                let mut next_hops = fw_delta.2.clone();
+                let mut next_hops = fw_delta
+                    .2
+                    .iter()
+                    .map(|x| *equivalent_egresses.get(x).unwrap_or(x))
+                    .collect_vec();
                 next_hops.sort();
                 for x in next_hops {
                     hasher.write_usize(x.index());
@@ -1120,3 +2329,1679 @@ pub fn compute_equivalence_class(
     }
     hasher.finish()
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    use bgpsim::policies::FwPolicy;
+
+    use crate::{
+        experiments::{
+            build_analyzer_from_experiment_description, list_custom_topologies,
+            list_path_scenarios, ExperimentDescription, ScenarioConfig, ScenarioPrefix,
+        },
+        routing_inputs::{RoutingInputs, SEQUENTIAL_UPDATE_SPACING},
+        timing_model::{TimingModelVariants, UPDATE_PROCESSING_TIME},
+        topology::{LinkDelayBuilder, Topology},
+        transient_specification::{
+            compute_violation_times_with_reset, FwStateReset, Path, ViolationTimes,
+        },
+    };
+
+    /// Build an analyzer on a path of three routers with externals at both ends, withdrawing the
+    /// preferred route at `r0_ext`.
+    pub(crate) fn path_analyzer(num_prefixes: usize) -> Analyzer<TimingModel<Prefix>> {
+        path_analyzer_with_delay(num_prefixes, 1_000.0)
+    }
+
+    /// Same as `path_analyzer`, but using the given link delay (in microseconds).
+    pub(crate) fn path_analyzer_with_delay(
+        num_prefixes: usize,
+        delay: f64,
+    ) -> Analyzer<TimingModel<Prefix>> {
+        let topo = Topology::Path(3);
+        let inputs = RoutingInputs::RepeatedPrefix {
+            inner: vec![
+                ("r0_ext".to_string(), vec![100.into(), 1000.into()]),
+                (
+                    "r2_ext".to_string(),
+                    vec![200.into(), 200.into(), 1000.into()],
+                ),
+            ],
+            num: num_prefixes,
+        };
+        let event_inputs = inputs.filter_route(|router, _| router == "r0_ext");
+        let (_, analyzer) = build_analyzer_from_experiment_description(ExperimentDescription {
+            topo,
+            topo_name: topo.fmt(),
+            scenario_name: format!("ExtAtEnds_FullMesh_Prefix{num_prefixes}_WithdrawAtR0"),
+            config: ScenarioConfig::FullMesh,
+            delays: LinkDelayBuilder::new().default_delay(delay),
+            static_routing_inputs: inputs,
+            event: AnalyzerEvent::WithdrawRoutingInputs(event_inputs),
+            confidence: None,
+            precision: None,
+        })
+        .unwrap();
+        analyzer
+    }
+
+    /// Simulate a single sample of `analyzer`, returning its trace.
+    pub(crate) fn sample_trace(
+        analyzer: &Analyzer<TimingModel<Prefix>>,
+    ) -> MultiPrefixConvergenceTrace {
+        let mut net = analyzer.scheduled_net.clone();
+        analyzer.build_trace(&mut net)
+    }
+
+    /// Compute the total violation time of the policies of `analyzer` during `trace` with the
+    /// interval algorithm, starting from `original_fw`.
+    pub(crate) fn total_violation_times(
+        analyzer: &Analyzer<TimingModel<Prefix>>,
+        queue: &mut TimingModel<Prefix>,
+        trace: &MultiPrefixConvergenceTrace,
+    ) -> Result<ViolationTimes<f64>, EvaluationError> {
+        compute_violation_times(
+            &analyzer.original_net,
+            queue,
+            &mut analyzer.original_fw.clone(),
+            trace,
+            analyzer.grouped_policies(),
+            None::<&std::path::Path>,
+            &TotalDuration,
+        )
+    }
+
+    /// Check that two forwarding states agree on all next hops of the internal routers.
+    pub(crate) fn assert_same_next_hops(
+        analyzer: &Analyzer<TimingModel<Prefix>>,
+        a: &ForwardingState<Prefix>,
+        b: &ForwardingState<Prefix>,
+    ) {
+        for prefix in analyzer.event.prefixes() {
+            for r in analyzer.original_net.internal_indices() {
+                assert_eq!(a.get_next_hops(r, prefix), b.get_next_hops(r, prefix));
+            }
+        }
+    }
+
+    #[test]
+    fn fw_state_at() {
+        let analyzer = path_analyzer(2);
+        let mut net = analyzer.scheduled_net.clone();
+        let trace = analyzer.build_trace(&mut net);
+        assert!(!trace.is_empty());
+
+        let times = trace
+            .values()
+            .flatten()
+            .filter_map(|(_, time)| time.into_inner())
+            .collect_vec();
+        let t_first = times.iter().copied().fold(f64::INFINITY, f64::min);
+        let t_last = times.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        // before the first update, we are still in the original state
+        assert_same_next_hops(
+            &analyzer,
+            &analyzer.fw_state_at(&trace, t_first - 1.0),
+            &analyzer.original_fw,
+        );
+        // after the last update, we have reached the converged state
+        assert_same_next_hops(
+            &analyzer,
+            &analyzer.fw_state_at(&trace, t_last),
+            &net.get_forwarding_state(),
+        );
+    }
+
+    #[test]
+    fn link_flap() {
+        let base = path_analyzer(1);
+        let r0 = base.original_net.get_router_id("r0").unwrap();
+        let r0_ext = base.original_net.get_router_id("r0_ext").unwrap();
+        let link_flap = |down_at: f64, up_at: f64| {
+            let event = AnalyzerEvent::LinkFlap {
+                a: r0,
+                b: r0_ext,
+                down_at,
+                up_at,
+            };
+            let mut analyzer =
+                Analyzer::new(base.original_net.clone(), event, vec![], 0.95, 0.01).unwrap();
+            analyzer.set_delays(base.delays.clone().unwrap());
+            analyzer
+        };
+
+        // the link fails at the time of the event
+        let analyzer = link_flap(0.0, 0.1);
+        let trace = sample_trace(&analyzer);
+        let (failover, failback): (Vec<f64>, Vec<f64>) = trace
+            .values()
+            .flatten()
+            .filter_map(|(_, time)| time.into_inner())
+            .partition(|time| *time < 0.1);
+
+        // the failover converges long before the link comes back up, followed by the failback
+        assert!(!failover.is_empty());
+        assert!(!failback.is_empty());
+        assert!(failover.iter().all(|time| *time < 0.05));
+        assert!(failback.iter().all(|time| *time >= 0.1));
+        assert_same_next_hops(
+            &analyzer,
+            &analyzer.fw_state_at(&trace, f64::INFINITY),
+            &analyzer.original_fw,
+        );
+
+        // each sample starts again from the intact link
+        assert_eq!(analyzer.analyze_with(3, 1).unwrap().n_samples, 3);
+
+        // the link fails after the event, such that both reconvergence phases are delayed
+        let analyzer = link_flap(0.05, 0.15);
+        let trace = sample_trace(&analyzer);
+        let times = trace
+            .values()
+            .flatten()
+            .filter_map(|(_, time)| time.into_inner())
+            .collect_vec();
+        let failover = times.iter().filter(|time| **time < 0.15).collect_vec();
+        let failback = times.iter().filter(|time| **time >= 0.15).collect_vec();
+        assert!(!failover.is_empty());
+        assert!(!failback.is_empty());
+        assert!(failover.iter().all(|time| (0.05..0.1).contains(*time)));
+        assert_same_next_hops(
+            &analyzer,
+            &analyzer.fw_state_at(&trace, f64::INFINITY),
+            &analyzer.original_fw,
+        );
+    }
+
+    #[test]
+    fn link_flap_restore_between_events() {
+        let base = path_analyzer(1);
+        let r0 = base.original_net.get_router_id("r0").unwrap();
+        let r0_ext = base.original_net.get_router_id("r0_ext").unwrap();
+        let link_flap = |up_at: f64| {
+            let event = AnalyzerEvent::LinkFlap {
+                a: r0,
+                b: r0_ext,
+                down_at: 0.0,
+                up_at,
+            };
+            let mut analyzer =
+                Analyzer::new(base.original_net.clone(), event, vec![], 0.95, 0.01).unwrap();
+            analyzer.set_delays(base.delays.clone().unwrap());
+            analyzer
+        };
+
+        // restore the link halfway through the failover, while events are still pending
+        let analyzer = link_flap(10.0);
+        let trace = analyzer.build_trace(&mut analyzer.scheduled_net.clone());
+        let (t_first, t_last) = trace
+            .values()
+            .flatten()
+            .filter_map(|(_, time)| time.into_inner())
+            .filter(|time| *time > 0.0)
+            .minmax()
+            .into_option()
+            .unwrap();
+        assert!(t_first < t_last);
+        let t_restore = (t_first + t_last) / 2.0;
+
+        let analyzer = link_flap(t_restore);
+        let trace = analyzer.build_trace(&mut analyzer.scheduled_net.clone());
+        for prefix_trace in trace.values() {
+            // the restore is recorded at the restore time, before the straddling event
+            let restore = prefix_trace
+                .iter()
+                .position(|(deltas, _)| {
+                    deltas
+                        .iter()
+                        .any(|(r, _, new)| *r == r0 && *new == [r0_ext])
+                })
+                .unwrap();
+            let t = prefix_trace[restore].1.into_inner().unwrap();
+            assert!((t - t_restore).abs() < 1e-9, "{t} vs. {t_restore}");
+            assert!(prefix_trace[..restore]
+                .iter()
+                .all(|(_, time)| time.into_inner().unwrap() < t_restore + 1e-9));
+        }
+    }
+
+    #[test]
+    fn router_processing_delay() {
+        let analyzer = path_analyzer(1);
+        let r1 = analyzer.original_net.get_router_id("r1").unwrap();
+        // time of the first forwarding update of r1
+        let first_update_of_r1 = |net: &mut Network<Prefix, TimingModel<Prefix>>| {
+            analyzer
+                .build_trace(net)
+                .values()
+                .flatten()
+                .filter(|(deltas, _)| deltas.iter().any(|(r, _, _)| *r == r1))
+                .filter_map(|(_, time)| time.into_inner())
+                .min_by(f64::total_cmp)
+                .unwrap()
+        };
+
+        let t_base = first_update_of_r1(&mut analyzer.scheduled_net.clone());
+        let mut net = analyzer.scheduled_net.clone();
+        net.queue_mut()
+            .set_router_processing_delay(r1, 10_000.0)
+            .unwrap();
+        // invalid delays are rejected and keep the current one
+        for delay_us in [-1.0, f64::NAN, f64::INFINITY] {
+            assert!(net
+                .queue_mut()
+                .set_router_processing_delay(r1, delay_us)
+                .is_err());
+        }
+        let t_slow = first_update_of_r1(&mut net);
+        assert!(t_slow >= t_base + 0.01 - 1e-9, "{t_slow} vs. {t_base}");
+    }
+
+    #[test]
+    fn effective_delays() {
+        let net = &path_analyzer(1).original_net;
+        let g = net.get_topology();
+        let num_links = g.edge_count();
+
+        let geo = net
+            .internal_indices()
+            .chain(net.external_indices())
+            .enumerate()
+            .map(|(i, r)| (r, Location::new(47.0 + i as f64, 8.0)))
+            .collect();
+        // only specify one direction, `from_delays` fills in the reverse one
+        let delays = g
+            .edge_indices()
+            .map(|e| (g.edge_endpoints(e).unwrap(), 1_000.0))
+            .collect();
+
+        for model in [
+            TimingModel::<Prefix>::from_geo_location(&geo),
+            TimingModel::<Prefix>::from_delays(&delays),
+        ] {
+            let effective = model.effective_delays(net);
+            // both directions of every link
+            assert_eq!(effective.len(), 2 * num_links);
+            for ((src, dst), delay) in effective {
+                assert!(g.contains_edge(src, dst));
+                assert_eq!(delay, model.get_delay(src, dst));
+                assert!(delay > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn queueing_timing_model() {
+        // All leaves of a star withdraw their route at once, such that each router receives a
+        // burst of withdraws over different sessions. The center eventually uses its backup route.
+        let topo = Topology::Star(9);
+        let mut inner = (0..8u32)
+            .map(|i| (format!("r{i}_ext"), vec![(100 + i).into(), 1000.into()]))
+            .collect_vec();
+        inner.push((
+            "center_ext".to_string(),
+            vec![300.into(), 300.into(), 1000.into()],
+        ));
+        let inputs = RoutingInputs::RepeatedPrefix { inner, num: 1 };
+        let event_inputs = inputs.filter_route(|router, _| router != "center_ext");
+        let (_, analyzer) = build_analyzer_from_experiment_description(ExperimentDescription {
+            topo,
+            topo_name: topo.fmt(),
+            scenario_name: "LeavesFullMesh_WithdrawAtLeaves".to_string(),
+            config: ScenarioConfig::FullMesh,
+            delays: LinkDelayBuilder::new().default_delay(1_000.0),
+            static_routing_inputs: inputs,
+            event: AnalyzerEvent::WithdrawRoutingInputs(event_inputs),
+            confidence: None,
+            precision: None,
+        })
+        .unwrap();
+
+        // serve each message as fast as the basic model processes an update
+        let service_us = UPDATE_PROCESSING_TIME * 1_000_000.0;
+        let convergence_time = |variant| {
+            let mut analyzer = analyzer.clone();
+            analyzer
+                .scheduled_net
+                .queue_mut()
+                .set_variant(variant)
+                .unwrap();
+            analyzer.convergence_time_distribution(1)[0]
+        };
+
+        // The basic model processes the messages of different sessions in parallel, whereas the
+        // queueing model serializes them at each router, even though each message is served faster.
+        let basic = convergence_time(TimingModelVariants::Basic);
+        let queueing = convergence_time(TimingModelVariants::Queueing { service_us });
+        assert!(queueing > basic, "queueing: {queueing}s, basic: {basic}s");
+        // the center serves the eight withdraws of the leaves one after another
+        assert!(queueing >= 8.0 * service_us / 1_000_000.0);
+    }
+
+    #[test]
+    fn queueing_invalid_service_time() {
+        let mut queue = path_analyzer(1).build_queue();
+        for service_us in [f64::NAN, -1.0, f64::INFINITY] {
+            assert_eq!(
+                queue
+                    .set_variant(TimingModelVariants::Queueing { service_us })
+                    .map_err(|e| e.0.to_bits()),
+                Err(service_us.to_bits())
+            );
+        }
+        queue
+            .set_variant(TimingModelVariants::Queueing { service_us: 0.0 })
+            .unwrap();
+
+        // invalid service times are also rejected upon deserialization
+        let mut serialized = serde_json::to_value(&queue).unwrap();
+        serialized["variant"] = serde_json::json!({ "Queueing": { "service_us": -1.0 } });
+        assert!(serde_json::from_value::<TimingModel<Prefix>>(serialized).is_err());
+    }
+
+    #[test]
+    fn round_trip_violations() {
+        let base = path_analyzer(2);
+        let r0_ext = base.original_net.get_router_id("r0_ext").unwrap();
+        let r2 = base.original_net.get_router_id("r2").unwrap();
+        let prefixes = base.event.prefixes();
+        let (forward, reverse) = (prefixes[0], prefixes[1]);
+
+        // only withdraw the return prefix, such that r0 (the egress of r2 towards the forward
+        // prefix) transiently drops the return traffic
+        let event =
+            AnalyzerEvent::WithdrawRoute(vec![reverse], r0_ext, vec![100.into(), 1000.into()]);
+        let policy = PolicyKind::Reachable.instantiate(r2, forward);
+        let mut analyzer = Analyzer::new(
+            base.original_net.clone(),
+            event,
+            vec![policy.clone()],
+            0.95,
+            0.01,
+        )
+        .unwrap();
+        analyzer.delays = base.delays.clone();
+        let return_prefixes = HashMap::from([(r2, reverse)]);
+
+        let mut queue = analyzer.build_queue();
+        for _ in 0..10 {
+            let trace = sample_trace(&analyzer);
+            assert!(!trace.contains_key(&forward));
+
+            let forward_only = total_violation_times(&analyzer, &mut queue, &trace)
+                .unwrap()
+                .times;
+            assert!(forward_only.is_empty());
+
+            let round_trip = compute_round_trip_violation_times(
+                &analyzer.original_net,
+                &mut queue,
+                &mut analyzer.original_fw.clone(),
+                &trace,
+                analyzer.grouped_policies(),
+                &return_prefixes,
+            )
+            .unwrap();
+            assert!(round_trip[&policy] > 0.0);
+        }
+
+        assert_eq!(analyzer.analyze_with(20, 1).unwrap().p_satisfied, 1.0);
+        analyzer.set_return_prefixes(return_prefixes);
+        assert_eq!(analyzer.analyze_with(20, 1).unwrap().p_satisfied, 0.0);
+    }
+
+    #[test]
+    fn importance_sampling() {
+        let mut analyzer = path_analyzer(1);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        let r1 = analyzer.original_net.get_router_id("r1").unwrap();
+        analyzer.set_policy_template(r0, PolicyKind::Reachable);
+        let key = (r0, analyzer.event.prefixes()[0]);
+
+        // with the constant processing times, tilting has no effect
+        let tilts = HashMap::from([(r1, 1.0)]);
+        analyzer.set_seed(42);
+        let constant = analyzer.analyze_with(50, 1).unwrap();
+        analyzer.set_importance_sampling(Some(ImportanceSampling::new(&tilts)));
+        let result = analyzer.analyze_with(50, 1).unwrap();
+        assert!(result.violation_time_weights.is_empty());
+        assert_eq!(
+            result.violation_time_distributions,
+            constant.violation_time_distributions
+        );
+
+        // without tilts, the samples are exactly the same as without importance sampling
+        analyzer.set_importance_sampling(None);
+        analyzer.set_measured_processing_times(true);
+        let nominal = analyzer.analyze_with(50, 1).unwrap();
+        analyzer.set_importance_sampling(Some(ImportanceSampling::new(&HashMap::new())));
+        let result = analyzer.analyze_with(50, 1).unwrap();
+        assert!(result.violation_time_weights.is_empty());
+        assert_eq!(result.p_satisfied, nominal.p_satisfied);
+        assert_eq!(
+            result.violation_time_distributions,
+            nominal.violation_time_distributions
+        );
+        let distribution = &result.violation_time_distributions[&key];
+        let n_satisfied = distribution.iter().filter(|t| **t == 0.0).count();
+        assert_eq!(result.p_satisfied, n_satisfied as f64 / 50.0);
+
+        // biasing r1 towards slow processing weights the samples
+        analyzer.set_importance_sampling(Some(ImportanceSampling::new(&tilts)));
+        let result = analyzer.analyze_with(50, 2).unwrap();
+        let weights = &result.violation_time_weights[&key];
+        assert_eq!(weights.len(), 50);
+        assert!(weights.iter().all(|w| *w > 0.0));
+        assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((0.0..=1.0).contains(&result.p_satisfied));
+
+        // the biased draws are taken from the seeded stream of each sample
+        let again = analyzer.analyze_with(50, 1).unwrap();
+        assert_eq!(
+            again.violation_time_distributions,
+            result.violation_time_distributions
+        );
+        assert!((again.p_satisfied - result.p_satisfied).abs() < 1e-12);
+
+        // the tilts are kept when reloading the analyzer
+        let reloaded: Analyzer<TimingModel<Prefix>> =
+            serde_json::from_str(&serde_json::to_string(&analyzer).unwrap()).unwrap();
+        let reloaded = reloaded.analyze_with(50, 1).unwrap();
+        assert_eq!(
+            reloaded.violation_time_distributions,
+            result.violation_time_distributions
+        );
+        assert_eq!(reloaded.violation_time_weights[&key].len(), 50);
+
+        // disabling importance sampling restores the nominal processing times
+        analyzer.set_importance_sampling(None);
+        let mut net = analyzer.scheduled_net.clone();
+        analyzer.build_trace(&mut net);
+        assert_eq!(net.queue_mut().take_log_weight(), 0.0);
+    }
+
+    #[test]
+    fn stable_paths() {
+        use crate::transient_specification::Path;
+
+        let mut analyzer = path_analyzer(1);
+        let id = |name| analyzer.original_net.get_router_id(name).unwrap();
+        let (r0, r1, r2, r0_ext) = (id("r0"), id("r1"), id("r2"), id("r0_ext"));
+        let prefix = analyzer.event.prefixes()[0];
+
+        assert_eq!(
+            analyzer.stable_paths(),
+            HashMap::from([
+                ((r0, prefix), vec![Path::Route(vec![r0, r0_ext])]),
+                ((r1, prefix), vec![Path::Route(vec![r1, r0, r0_ext])]),
+                ((r2, prefix), vec![Path::Route(vec![r2, r1, r0, r0_ext])]),
+            ])
+        );
+
+        // with load-balancing, all paths are returned, but the interval algorithm fails
+        analyzer.original_fw.update(r2, prefix, vec![r0, r1]);
+        let paths = analyzer.stable_paths().remove(&(r2, prefix)).unwrap();
+        assert_eq!(
+            paths.into_iter().collect::<HashSet<_>>(),
+            HashSet::from([
+                Path::Route(vec![r2, r0, r0_ext]),
+                Path::Route(vec![r2, r1, r0, r0_ext]),
+            ])
+        );
+        let trace = sample_trace(&analyzer);
+        let result = compute_violation_times_with_reset(
+            &analyzer.original_net,
+            &mut analyzer.build_queue(),
+            &mut analyzer.original_fw.clone(),
+            &trace,
+            analyzer.grouped_policies(),
+            None::<&std::path::Path>,
+            &TotalDuration,
+            FwStateReset::Clone,
+        );
+        assert!(matches!(
+            result,
+            Err(EvaluationError::LoadBalancing(r, p)) if r == r2 && p == prefix
+        ));
+    }
+
+    #[test]
+    fn event_has_effect() {
+        let analyzer = path_analyzer(1);
+        assert!(analyzer.event_has_effect());
+
+        // r2 prefers the route of r0_ext, such that withdrawing the route of r2_ext has no effect
+        let r2_ext = analyzer.original_net.get_router_id("r2_ext").unwrap();
+        let event = AnalyzerEvent::WithdrawRoute(
+            analyzer.event.prefixes(),
+            r2_ext,
+            vec![200.into(), 200.into(), 1000.into()],
+        );
+        let noop = Analyzer::new(
+            analyzer.original_net.clone(),
+            event,
+            analyzer.policies.clone(),
+            0.95,
+            0.01,
+        )
+        .unwrap();
+        assert!(!noop.event_has_effect());
+        assert_eq!(noop.analyze().unwrap().p_satisfied, 1.0);
+    }
+
+    #[test]
+    fn grouped_policies() {
+        let mut analyzer = path_analyzer(2);
+        let routers = analyzer.original_net.internal_indices().collect_vec();
+        let prefixes = analyzer.event.prefixes();
+        assert_eq!(
+            analyzer.grouped_policies().len(),
+            routers.len() * prefixes.len()
+        );
+
+        // replace the policies, the grouping must follow
+        let policies = vec![
+            TransientPolicy::Atomic(FwPolicy::Reachable(routers[0], prefixes[0])),
+            TransientPolicy::Atomic(FwPolicy::LoopFree(routers[0], prefixes[0])),
+            TransientPolicy::Atomic(FwPolicy::Reachable(routers[1], prefixes[1])),
+        ];
+        analyzer.set_policies(policies.clone());
+
+        assert_eq!(analyzer.grouped_policies().len(), 2);
+        assert_eq!(
+            analyzer.grouped_policies()[&(routers[0], prefixes[0])],
+            policies[0..2]
+        );
+        assert_eq!(
+            analyzer.grouped_policies()[&(routers[1], prefixes[1])],
+            policies[2..3]
+        );
+    }
+
+    #[test]
+    fn policy_template() {
+        let mut analyzer = path_analyzer(3);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        analyzer.set_policy_template(r0, PolicyKind::Reachable);
+
+        assert_eq!(analyzer.policies.len(), 3);
+        assert_eq!(analyzer.grouped_policies().len(), 3);
+        for prefix in analyzer.event.prefixes() {
+            assert_eq!(
+                analyzer.grouped_policies()[&(r0, prefix)],
+                vec![TransientPolicy::Atomic(FwPolicy::Reachable(r0, prefix))]
+            );
+        }
+    }
+
+    #[test]
+    fn incomplete_sample() {
+        let mut analyzer = path_analyzer(1);
+        let mut ctx = analyzer.sample_context();
+
+        let outcome = analyzer.simulate_once(
+            analyzer.scheduled_net.clone(),
+            analyzer.original_fw.clone(),
+            &mut ctx,
+            0,
+        );
+        assert!(!outcome.incomplete);
+
+        // stall the simulation after the first event
+        analyzer.set_max_steps(1);
+        let outcome = analyzer.simulate_once(outcome.net, outcome.fw_state, &mut ctx, 1);
+        assert!(outcome.incomplete);
+
+        let result = analyzer.analyze().unwrap();
+        assert_eq!(result.n_incomplete, result.n_samples);
+    }
+
+    #[test]
+    fn equivalent_egresses() {
+        let mut analyzer = path_analyzer(1);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        let r0_ext = analyzer.original_net.get_router_id("r0_ext").unwrap();
+        let r2_ext = analyzer.original_net.get_router_id("r2_ext").unwrap();
+        analyzer.set_policy_template(r0, PolicyKind::Reachable);
+
+        // two traces that only differ in the egress chosen by r0
+        let mut net = analyzer.scheduled_net.clone();
+        let (prefix, prefix_trace) = analyzer.build_trace(&mut net).into_iter().next().unwrap();
+        let time = prefix_trace[0].1.clone();
+        let traces = [r0_ext, r2_ext].map(|egress| {
+            HashMap::from([(
+                prefix,
+                vec![(vec![(r0, vec![r0_ext], vec![egress])], time.clone())],
+            )])
+        });
+
+        let count_classes = |analyzer: &Analyzer<TimingModel<Prefix>>| {
+            traces
+                .iter()
+                .map(|trace| {
+                    compute_equivalence_class(
+                        trace,
+                        &analyzer.scheduled_fw,
+                        &analyzer.policies,
+                        &analyzer.equivalent_egresses,
+                    )
+                })
+                .unique()
+                .count()
+        };
+
+        assert_eq!(count_classes(&analyzer), 2);
+        analyzer.set_equivalent_egresses(&[vec![r2_ext, r0_ext]]);
+        assert_eq!(analyzer.equivalent_egresses[&r2_ext], r0_ext.min(r2_ext));
+        assert_eq!(count_classes(&analyzer), 1);
+    }
+
+    #[test]
+    fn event_jitter() {
+        let mut analyzer = path_analyzer(1);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        analyzer.set_policy_template(r0, PolicyKind::NotReachable);
+        analyzer.set_seed(7);
+        let result = analyzer.analyze_with(200, 1).unwrap();
+        let nominal = analyzer.convergence_time_distribution(200);
+
+        analyzer.set_event_jitter(1.0);
+        let jittered = analyzer.analyze_with(200, 1).unwrap();
+        let jitters = analyzer
+            .convergence_time_distribution(200)
+            .into_iter()
+            .zip(nominal)
+            .map(|(t, t_nominal)| t - t_nominal)
+            .collect_vec();
+
+        // each sample is shifted by a jitter in [0, 1], which is 0.5s on average
+        assert!(jitters.iter().all(|j| (-1e-9..=1.0 + 1e-9).contains(j)));
+        let mean = jitters.iter().sum::<f64>() / 200.0;
+        assert!((0.4..0.6).contains(&mean), "mean jitter {mean}");
+        // `analyze` draws the same jitters
+        assert!((jittered.convergence_time - result.convergence_time - mean).abs() < 1e-6);
+        // the violation times are independent of the placement of the transient window
+        assert_eq!(jittered.p_satisfied, result.p_satisfied);
+    }
+
+    #[test]
+    fn seeded_samples_independent_of_workers() {
+        let mut analyzer = path_analyzer(1);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        analyzer.set_policy_template(r0, PolicyKind::Reachable);
+        analyzer.set_event_jitter(1.0);
+        analyzer.set_record_trace_lengths(true);
+        analyzer.set_seed(42);
+
+        let single = analyzer.analyze_with(20, 1).unwrap();
+        assert_eq!(single.n_samples, 20);
+        for workers in [3, 4] {
+            let parallel = analyzer.analyze_with(20, workers).unwrap();
+            assert_eq!(parallel.n_samples, single.n_samples);
+            assert_eq!(parallel.p_satisfied, single.p_satisfied);
+            assert_eq!(parallel.n_unique_equiv, single.n_unique_equiv);
+            assert_eq!(parallel.trace_lengths, single.trace_lengths);
+            assert_eq!(
+                parallel.violation_time_distributions,
+                single.violation_time_distributions
+            );
+            // the jitter drawn for each sample is the same, only summed in a different order
+            assert!((parallel.convergence_time - single.convergence_time).abs() < 1e-9);
+        }
+
+        // another seed draws different jitters
+        analyzer.set_seed(43);
+        let other = analyzer.analyze_with(20, 1).unwrap();
+        assert!((other.convergence_time - single.convergence_time).abs() > 1e-9);
+    }
+
+    #[test]
+    fn file_backed_stats() {
+        let mut analyzer = path_analyzer(1);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        analyzer.set_policy_template(r0, PolicyKind::Reachable);
+        let in_memory = analyzer.analyze_with(10, 2).unwrap();
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        analyzer
+            .set_stats_store(StatsStore::new(&dir).unwrap())
+            .unwrap();
+        let file_backed = analyzer.analyze_with(10, 2).unwrap();
+        assert_eq!(file_backed.n_samples, in_memory.n_samples);
+        assert_eq!(file_backed.p_satisfied, in_memory.p_satisfied);
+        assert_eq!(file_backed.n_unique_equiv, in_memory.n_unique_equiv);
+        assert_eq!(
+            file_backed.violation_time_distributions,
+            in_memory.violation_time_distributions
+        );
+
+        // analyzing again resumes from the samples already in the store
+        let resumed = analyzer.analyze_with(10, 2).unwrap();
+        assert_eq!(resumed.n_samples, 20);
+        assert_eq!(resumed.n_unique_equiv, in_memory.n_unique_equiv);
+        assert_eq!(resumed.p_satisfied, in_memory.p_satisfied);
+        assert!(resumed
+            .violation_time_distributions
+            .values()
+            .all(|distribution| distribution.len() == 20));
+
+        // the samples cannot be resumed with other policies
+        analyzer.set_policy_template(r0, PolicyKind::NotReachable);
+        assert!(analyzer.analyze_with(10, 2).is_err());
+        assert!(analyzer
+            .set_stats_store(StatsStore::new(&dir).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn sample_export() {
+        let mut analyzer = path_analyzer(2);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        analyzer.set_policy_template(r0, PolicyKind::Reachable);
+        let n_policies = analyzer.policies.len();
+        assert_eq!(n_policies, 2);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("samples.csv");
+        analyzer.set_sample_export(&path).unwrap();
+        let result = analyzer.analyze_with(10, 2).unwrap();
+
+        let records = csv::Reader::from_path(&path)
+            .unwrap()
+            .deserialize::<SampleRecord>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(records.len(), result.n_samples * n_policies);
+        assert_eq!(
+            records.iter().map(|r| r.sample).collect::<HashSet<_>>(),
+            (0..10).collect()
+        );
+        assert!(records
+            .iter()
+            .all(|r| r.router.as_deref() == Some("r0") && r.prefix.is_some()));
+    }
+
+    #[test]
+    fn convergence_deadline() {
+        let mut analyzer = path_analyzer(1);
+        let prefix = analyzer.event.prefixes()[0];
+        let trace = sample_trace(&analyzer);
+        let t_last = trace[&prefix].last().unwrap().1.into_inner().unwrap();
+        assert!(t_last > 0.0);
+
+        // the network converges after the first, but before the second deadline
+        let missed = TransientPolicy::ConvergenceDeadline {
+            prefix,
+            deadline_ms: 0,
+        };
+        let met = TransientPolicy::ConvergenceDeadline {
+            prefix,
+            deadline_ms: (t_last * 1000.0).ceil() as u64 + 1,
+        };
+        let policies = vec![missed.clone(), met.clone()];
+        let result = compute_convergence_violations(
+            &analyzer.original_net,
+            &analyzer.original_fw,
+            &trace,
+            &policies,
+        );
+        assert!((result.times[&missed] - t_last).abs() < 1e-9);
+        assert!(!result.times.contains_key(&met));
+        // all routers reach the prefix via r2_ext after convergence
+        assert!(result.persistent.is_empty());
+
+        // the deadline policies are also checked when analyzing the network
+        analyzer.set_policies(vec![met]);
+        assert_eq!(analyzer.analyze().unwrap().p_satisfied, 1.0);
+        analyzer.set_policies(vec![missed]);
+        let result = analyzer.analyze().unwrap();
+        assert_eq!(result.p_satisfied, 0.0);
+        assert!(result.violation_time_distributions.is_empty());
+    }
+
+    #[test]
+    fn external_as_mapping() {
+        let analyzer = path_analyzer(1);
+        let net = &analyzer.original_net;
+        let [r0, r1, r0_ext, r2_ext] =
+            ["r0", "r1", "r0_ext", "r2_ext"].map(|r| net.get_router_id(r).unwrap());
+
+        assert_eq!(analyzer.external_as_of(r0_ext), Some(AsId::from(100)));
+        assert_eq!(analyzer.external_as_of(r2_ext), Some(AsId::from(200)));
+        assert_eq!(analyzer.external_as_of(r0), None);
+        assert_eq!(analyzer.externals_of_as(100.into()), vec![r0_ext]);
+        assert!(analyzer.externals_of_as(1000.into()).is_empty());
+
+        let path = Path::Route(vec![r1, r0, r0_ext]);
+        assert!(analyzer.egress_matches_as(&path, 100.into()));
+        assert!(!analyzer.egress_matches_as(&path, 200.into()));
+        assert!(!analyzer.egress_matches_as(&Path::BlackHole(vec![r1, r0]), 100.into()));
+    }
+
+    #[test]
+    fn anycast_reachable() {
+        let mut analyzer = path_analyzer(1);
+        let r1 = analyzer.original_net.get_router_id("r1").unwrap();
+        let r0_ext = analyzer.original_net.get_router_id("r0_ext").unwrap();
+        let r2_ext = analyzer.original_net.get_router_id("r2_ext").unwrap();
+        let prefix = analyzer.event.prefixes()[0];
+        let only_r0_ext = TransientPolicy::AnycastReachable {
+            router: r1,
+            prefix,
+            acceptable_egresses: vec![r0_ext],
+        };
+        let any_ext = TransientPolicy::AnycastReachable {
+            router: r1,
+            prefix,
+            acceptable_egresses: vec![r0_ext, r2_ext],
+        };
+
+        // r1 initially leaves the network at r0_ext
+        let initial = Path::Route(vec![
+            r1,
+            analyzer.original_net.get_router_id("r0").unwrap(),
+            r0_ext,
+        ]);
+        for policy in [&only_r0_ext, &any_ext] {
+            assert!(policy.check(&mut analyzer.original_fw.clone()).is_ok());
+            assert!(policy.check_path(&initial));
+        }
+        assert!(!only_r0_ext.check_path(&Path::Route(vec![r1, r2_ext])));
+        assert!(any_ext.check_path(&Path::Route(vec![r1, r2_ext])));
+        assert!(!any_ext.check_path(&Path::BlackHole(vec![r1])));
+
+        analyzer.set_policies(vec![only_r0_ext.clone(), any_ext.clone()]);
+        let mut queue = analyzer.build_queue();
+        let trace = sample_trace(&analyzer);
+        let result = total_violation_times(&analyzer, &mut queue, &trace).unwrap();
+
+        // after the withdraw, r1 drops traffic until it shifts towards r2_ext
+        assert!(result.times[&only_r0_ext] > 0.0);
+        assert!(result.times[&any_ext] > 0.0);
+        assert_eq!(result.persistent, HashSet::from([only_r0_ext]));
+    }
+
+    #[test]
+    fn max_as_path_length() {
+        let mut analyzer = path_analyzer(1);
+        let r1 = analyzer.original_net.get_router_id("r1").unwrap();
+        let r0_ext = analyzer.original_net.get_router_id("r0_ext").unwrap();
+        let r2_ext = analyzer.original_net.get_router_id("r2_ext").unwrap();
+        let prefix = analyzer.event.prefixes()[0];
+        // r0_ext advertises an AS path of length 2, r2_ext one of length 3
+        let policy = TransientPolicy::max_as_path_length(&analyzer.original_net, r1, prefix, 2);
+        assert_eq!(
+            policy,
+            TransientPolicy::MaxAsPathLength {
+                router: r1,
+                prefix,
+                max: 2,
+                as_path_lengths: vec![(r0_ext, 2), (r2_ext, 3)]
+                    .into_iter()
+                    .sorted()
+                    .collect(),
+            }
+        );
+
+        // r1 initially leaves the network at r0_ext
+        assert!(policy.check(&mut analyzer.original_fw.clone()).is_ok());
+        assert!(policy.check_path(&Path::Route(vec![r1, r0_ext])));
+        assert!(!policy.check_path(&Path::Route(vec![r1, r2_ext])));
+        assert!(policy.check_path(&Path::BlackHole(vec![r1])));
+
+        // `check` and `check_path` agree that a black hole satisfies the policy
+        let mut black_hole = analyzer.original_fw.clone();
+        black_hole.update(r1, prefix, vec![]);
+        assert!(policy.check(&mut black_hole).is_ok());
+
+        // and that no path satisfies it if no egress advertises a short enough AS path
+        let too_short = TransientPolicy::max_as_path_length(&analyzer.original_net, r1, prefix, 1);
+        assert!(too_short.check(&mut analyzer.original_fw.clone()).is_err());
+        assert!(!too_short.check_path(&Path::Route(vec![r1, r0_ext])));
+        assert!(!too_short.check_path(&Path::Route(vec![r1, r2_ext])));
+        assert!(too_short.check(&mut black_hole).is_ok());
+        assert!(too_short.check_path(&Path::BlackHole(vec![r1])));
+
+        analyzer.set_policies(vec![policy.clone()]);
+        let mut queue = analyzer.build_queue();
+        let trace = sample_trace(&analyzer);
+        let result = total_violation_times(&analyzer, &mut queue, &trace).unwrap();
+
+        // after the withdraw, r1 shifts to r2_ext, whose AS path is too long
+        assert!(result.times[&policy] > 0.0);
+        assert_eq!(result.persistent, HashSet::from([policy]));
+    }
+
+    #[test]
+    fn link_delay_overrides() {
+        let analyzer = path_analyzer(1);
+        let net = &analyzer.original_net;
+        let [r0, r1, r2] = ["r0", "r1", "r2"].map(|r| net.get_router_id(r).unwrap());
+
+        let overrides = parse_link_delay_overrides(
+            r#"[
+                { "from": "r0", "to": "r1", "delay_us": 1500.0 },
+                { "from": "r2", "to": "r1", "delay_us": 800 }
+            ]"#,
+            net,
+        )
+        .unwrap();
+        assert_eq!(
+            overrides,
+            HashMap::from([((r0, r1), 1500.0), ((r2, r1), 800.0)])
+        );
+        assert!(parse_link_delay_overrides("[]", net).unwrap().is_empty());
+
+        // unknown routers, invalid delays, and malformed files are rejected
+        for json in [
+            r#"[{ "from": "r0", "to": "r9", "delay_us": 1500.0 }]"#,
+            r#"[{ "from": "r0", "to": "r1", "delay_us": -1.0 }]"#,
+            r#"[{ "from": "r0", "to": "r1" }]"#,
+            r#"{ "from": "r0", "to": "r1", "delay_us": 1500.0 }"#,
+        ] {
+            assert!(parse_link_delay_overrides(json, net).is_err(), "{json}");
+        }
+    }
+
+    #[test]
+    fn bgp_rib_diff() {
+        // r1 prefers the shorter AS path of r0_ext, independent of the weight towards r0
+        let topo = Topology::Path(3);
+        let inputs = RoutingInputs::SinglePrefix(vec![
+            ("r0_ext".to_string(), vec![100.into(), 1000.into()]),
+            (
+                "r2_ext".to_string(),
+                vec![200.into(), 200.into(), 1000.into()],
+            ),
+        ]);
+        let (_, analyzer) = build_analyzer_from_experiment_description(ExperimentDescription {
+            topo,
+            topo_name: topo.fmt(),
+            scenario_name: "ExtAtEnds_FullMesh_SetLinkWeightR1R0".to_string(),
+            config: ScenarioConfig::FullMesh,
+            delays: LinkDelayBuilder::new().default_delay(1_000.0),
+            static_routing_inputs: inputs.clone(),
+            event: AnalyzerEvent::SetLinkWeight {
+                prefixes: inputs.get_prefixes(),
+                a: "r1".to_string(),
+                b: "r0".to_string(),
+                weight: 10.0,
+            },
+            confidence: None,
+            precision: None,
+        })
+        .unwrap();
+        let r1 = analyzer.original_net.get_router_id("r1").unwrap();
+        let prefix = analyzer.event.prefixes()[0];
+
+        // forwarding is unchanged
+        assert_same_next_hops(&analyzer, &analyzer.original_fw, &analyzer.scheduled_fw);
+
+        // but the IGP cost of the route selected by r1 increases
+        let diff = analyzer.bgp_rib_diff(prefix);
+        let Some(RibChange::Changed { old, new, .. }) = diff
+            .iter()
+            .find(|change| matches!(change, RibChange::Changed { router, .. } if *router == r1))
+        else {
+            panic!("the RIB of r1 did not change: {diff:?}");
+        };
+        assert_eq!(old.route, new.route);
+        assert_ne!(old.igp_cost, new.igp_cost);
+        assert!(diff
+            .iter()
+            .all(|change| matches!(change, RibChange::Changed { .. })));
+    }
+
+    #[test]
+    fn bgp_rib_diff_after_convergence() {
+        let analyzer = path_analyzer(1);
+        let prefix = analyzer.event.prefixes()[0];
+        let selected_route = |net: &Network<Prefix, _>, router: RouterId| {
+            net.get_device(router)
+                .unwrap()
+                .unwrap_internal()
+                .bgp
+                .get_rib()
+                .get(&prefix)
+                .cloned()
+        };
+
+        // immediately after the withdraw is triggered, no internal router has processed it yet
+        for r in analyzer.original_net.internal_indices() {
+            assert_eq!(
+                selected_route(&analyzer.original_net, r),
+                selected_route(&analyzer.scheduled_net, r)
+            );
+        }
+
+        // after convergence, all internal routers select the route from r2_ext, whose AS path has
+        // length 3 (instead of 2 from r0_ext)
+        let diff = analyzer.bgp_rib_diff(prefix);
+        assert_eq!(diff.len(), analyzer.original_net.internal_indices().count());
+        for change in &diff {
+            let RibChange::Changed { new, .. } = change else {
+                panic!("unexpected change {change:?}");
+            };
+            assert_eq!(new.route.as_path.len(), 3);
+        }
+        assert_eq!(
+            diff.iter()
+                .map(|change| match change {
+                    RibChange::Changed { router, .. } => *router,
+                    _ => unreachable!(),
+                })
+                .collect_vec(),
+            analyzer
+                .original_net
+                .internal_indices()
+                .sorted()
+                .collect_vec()
+        );
+    }
+
+    #[test]
+    fn set_link_weight() {
+        // r1 reflects the route of r0_ext (which is closer than r3_ext) to its client r2
+        let topo = Topology::Path(4);
+        let inputs = RoutingInputs::SinglePrefix(vec![
+            ("r0_ext".to_string(), vec![100.into(), 1000.into()]),
+            ("r3_ext".to_string(), vec![300.into(), 1000.into()]),
+        ]);
+        let (_, analyzer) = build_analyzer_from_experiment_description(ExperimentDescription {
+            topo,
+            topo_name: topo.fmt(),
+            scenario_name: "ExtAtEnds_RouteReflectorR1_SetLinkWeightR1R0".to_string(),
+            config: ScenarioConfig::RouteReflectors(vec!["r1".to_string()]),
+            delays: LinkDelayBuilder::new().default_delay(1_000.0),
+            static_routing_inputs: inputs.clone(),
+            event: AnalyzerEvent::SetLinkWeight {
+                prefixes: inputs.get_prefixes(),
+                a: "r1".to_string(),
+                b: "r0".to_string(),
+                weight: 10.0,
+            },
+            confidence: None,
+            precision: None,
+        })
+        .unwrap();
+        let net = &analyzer.original_net;
+        let [r0, r1, r2] = ["r0", "r1", "r2"].map(|r| net.get_router_id(r).unwrap());
+        let prefix = analyzer.event.prefixes()[0];
+
+        // raising the weight immediately shifts r1 towards r3_ext
+        assert_eq!(analyzer.original_fw.get_next_hops(r1, prefix), [r0]);
+        assert_eq!(analyzer.scheduled_fw.get_next_hops(r1, prefix), [r2]);
+
+        // the trace starts with the immediate shift of r1 at the time of the event
+        let trace = sample_trace(&analyzer);
+        let (fw_deltas, time) = &trace[&prefix][0];
+        assert_eq!(fw_deltas, &vec![(r1, vec![r0], vec![r2])]);
+        assert_eq!(time.into_inner(), Some(0.0));
+
+        // r2 keeps forwarding towards r0 until it learns the new route from r1
+        assert!(trace[&prefix]
+            .iter()
+            .flat_map(|(fw_deltas, _)| fw_deltas)
+            .any(|(r, _, _)| *r == r2));
+
+        // in the meantime, traffic of r2 is trapped in a forwarding loop
+        let violation_times = compute_violation_times(
+            &analyzer.original_net,
+            &mut analyzer.build_queue(),
+            &mut analyzer.scheduled_fw.clone(),
+            &trace,
+            analyzer.grouped_policies(),
+            None::<&std::path::Path>,
+            &TotalDuration,
+        )
+        .unwrap()
+        .times;
+        let reachable = TransientPolicy::Atomic(FwPolicy::Reachable(r2, prefix));
+        assert!(violation_times[&reachable] > 0.0);
+    }
+
+    #[test]
+    fn update_ordering() {
+        // two prefixes, withdrawn from separate external routers at r0 and r1
+        let topo = Topology::Path(3);
+        let backup = (
+            "r2_ext".to_string(),
+            vec![200.into(), 200.into(), 1000.into()],
+        );
+        let inputs = RoutingInputs::MultiPrefix(vec![
+            vec![
+                ("r0_ext".to_string(), vec![100.into(), 1000.into()]),
+                backup.clone(),
+            ],
+            vec![
+                ("r1_ext".to_string(), vec![300.into(), 1000.into()]),
+                backup,
+            ],
+        ]);
+        let event_inputs = inputs.filter_route(|router, _| router != "r2_ext");
+        let (_, mut analyzer) = build_analyzer_from_experiment_description(ExperimentDescription {
+            topo,
+            topo_name: topo.fmt(),
+            scenario_name: "ExtAtEnds_FullMesh_Prefix2_WithdrawAtR0R1".to_string(),
+            config: ScenarioConfig::FullMesh,
+            delays: LinkDelayBuilder::new().default_delay(1_000.0),
+            static_routing_inputs: inputs,
+            event: AnalyzerEvent::WithdrawRoutingInputs(event_inputs),
+            confidence: None,
+            precision: None,
+        })
+        .unwrap();
+        let prefixes = analyzer.event.prefixes();
+
+        // time of the first forwarding update of each prefix, caused directly by the withdrawal
+        let first_updates = |analyzer: &Analyzer<TimingModel<Prefix>>| {
+            let trace = sample_trace(analyzer);
+            prefixes
+                .iter()
+                .map(|prefix| trace[prefix][0].1.into_inner().unwrap())
+                .collect_vec()
+        };
+
+        assert_eq!(analyzer.update_ordering, UpdateOrdering::Simultaneous);
+        let simultaneous = first_updates(&analyzer);
+        assert!(simultaneous.iter().all_equal());
+
+        analyzer
+            .set_update_ordering(UpdateOrdering::Sequential)
+            .unwrap();
+        let sequential = first_updates(&analyzer);
+        assert_eq!(sequential[0], simultaneous[0]);
+        assert!((sequential[1] - sequential[0] - SEQUENTIAL_UPDATE_SPACING).abs() < 1e-9);
+
+        // the same seed always results in the same order
+        analyzer
+            .set_update_ordering(UpdateOrdering::Randomized(42))
+            .unwrap();
+        let randomized = first_updates(&analyzer);
+        assert!(((randomized[1] - randomized[0]).abs() - SEQUENTIAL_UPDATE_SPACING).abs() < 1e-9);
+        assert_eq!(randomized, first_updates(&analyzer));
+
+        // reloading the analyzer schedules the updates in the same order
+        let reloaded: Analyzer<TimingModel<Prefix>> =
+            serde_json::from_str(&serde_json::to_string(&analyzer).unwrap()).unwrap();
+        assert_eq!(reloaded.update_ordering, UpdateOrdering::Randomized(42));
+        assert_eq!(first_updates(&reloaded), randomized);
+    }
+
+    #[test]
+    fn serde_settings() {
+        let mut analyzer = path_analyzer(2);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        let r2 = analyzer.original_net.get_router_id("r2").unwrap();
+        let prefix = analyzer.event.prefixes()[0];
+        analyzer.set_max_steps(1000);
+        analyzer.set_record_trace_lengths(true);
+        analyzer.set_record_final_egresses(true);
+        analyzer.set_equivalent_egresses(&[vec![r0, r2]]);
+        analyzer.set_event_jitter(0.5);
+        analyzer.set_seed(42);
+        analyzer.set_retain_worst_traces(3);
+        analyzer.set_compress_hardware_mapping(true);
+        analyzer.set_convergence_retry(ConvergenceRetry {
+            timeout: Duration::from_secs(60),
+            retries: 5,
+            max_consecutive_skips: 10,
+        });
+        analyzer.set_return_prefixes(HashMap::from([(r0, prefix)]));
+        analyzer.set_monitoring_iface("eth1");
+
+        let reloaded: Analyzer<TimingModel<Prefix>> =
+            serde_json::from_str(&serde_json::to_string(&analyzer).unwrap()).unwrap();
+        assert_eq!(reloaded.max_steps, Some(1000));
+        assert!(reloaded.record_trace_lengths);
+        assert!(reloaded.record_final_egresses);
+        assert_eq!(reloaded.equivalent_egresses, analyzer.equivalent_egresses);
+        assert_eq!(reloaded.event_jitter, Some(0.5));
+        assert_eq!(reloaded.seed, Some(42));
+        assert_eq!(reloaded.retain_worst_traces, Some(3));
+        assert!(reloaded.compress_hardware_mapping);
+        assert_eq!(reloaded.convergence_retry, analyzer.convergence_retry);
+        assert_eq!(reloaded.return_prefixes, analyzer.return_prefixes);
+        assert_eq!(reloaded.monitoring_iface, "eth1");
+    }
+
+    #[test]
+    fn confidence_precision_bounds() {
+        let mut analyzer = path_analyzer(1);
+
+        analyzer.set_confidence(0.95);
+        analyzer.set_precision(0.01);
+        assert_eq!(analyzer.confidence, 0.95);
+        assert_eq!(analyzer.precision, 0.01);
+
+        analyzer.set_confidence(1.0);
+        analyzer.set_precision(0.0);
+        assert_eq!(analyzer.confidence, MAX_PARAM);
+        assert_eq!(analyzer.precision, MIN_PARAM);
+
+        analyzer.set_confidence(0.0);
+        analyzer.set_precision(1.0);
+        assert_eq!(analyzer.confidence, MIN_PARAM);
+        assert_eq!(analyzer.precision, MAX_PARAM);
+
+        analyzer.set_confidence(-0.5);
+        analyzer.set_precision(f64::NAN);
+        assert_eq!(analyzer.confidence, MIN_PARAM);
+        assert_eq!(analyzer.precision, MIN_PARAM);
+
+        let analyzer = Analyzer::new(
+            analyzer.original_net.clone(),
+            analyzer.event.clone(),
+            vec![],
+            1.0,
+            0.0,
+        )
+        .unwrap();
+        assert_eq!(analyzer.confidence, MAX_PARAM);
+        assert_eq!(analyzer.precision, MIN_PARAM);
+        assert!(hoeffding(analyzer.confidence, analyzer.precision) < usize::MAX);
+
+        // invalid parameters of a serialized analyzer are clamped as well
+        let mut json = serde_json::to_value(&analyzer).unwrap();
+        json["confidence"] = 1.0.into();
+        json["precision"] = 0.0.into();
+        let analyzer: Analyzer<TimingModel<Prefix>> = serde_json::from_value(json).unwrap();
+        assert_eq!(analyzer.confidence, MAX_PARAM);
+        assert_eq!(analyzer.precision, MIN_PARAM);
+    }
+
+    #[test]
+    fn flap_event() {
+        let topo = Topology::Path(3);
+        let inputs = RoutingInputs::RepeatedPrefix {
+            inner: vec![("r0_ext".to_string(), vec![100.into(), 1000.into()])],
+            num: 1,
+        };
+        let (_, analyzer) = build_analyzer_from_experiment_description(ExperimentDescription {
+            topo,
+            topo_name: topo.fmt(),
+            scenario_name: "ExtAtR0_FullMesh_Prefix1_FlapAtR0".to_string(),
+            config: ScenarioConfig::FullMesh,
+            delays: LinkDelayBuilder::new().default_delay(1_000.0),
+            static_routing_inputs: inputs.clone(),
+            event: AnalyzerEvent::Flap {
+                inputs,
+                gap_ms: 1_000.0,
+            },
+            confidence: None,
+            precision: None,
+        })
+        .unwrap();
+        let prefix = analyzer.event.prefixes()[0];
+        let r2 = analyzer.original_net.get_router_id("r2").unwrap();
+
+        let trace = sample_trace(&analyzer);
+
+        // the forwarding updates happen in two bursts, separated by the gap of one second
+        let times = trace
+            .values()
+            .flatten()
+            .filter_map(|(_, time)| time.into_inner())
+            .sorted_by(|a, b| a.total_cmp(b))
+            .collect_vec();
+        assert_eq!(times.windows(2).filter(|w| w[1] - w[0] > 0.5).count(), 1);
+        assert!(times.iter().any(|t| *t < 0.5));
+        assert!(times.iter().any(|t| *t >= 1.0));
+        assert!(times.iter().all(|t| *t < 0.5 || *t >= 1.0));
+
+        // the prefix is unreachable in between, and reachable again after the re-announcement
+        assert!(analyzer
+            .fw_state_at(&trace, 0.5)
+            .get_paths(r2, prefix)
+            .is_err());
+        assert!(analyzer
+            .fw_state_at(&trace, *times.last().unwrap())
+            .get_paths(r2, prefix)
+            .is_ok());
+    }
+
+    #[test]
+    fn prepend_as_path_event() {
+        let topo = Topology::Path(3);
+        let inputs = RoutingInputs::SinglePrefix(vec![
+            ("r0_ext".to_string(), vec![100.into(), 1000.into()]),
+            (
+                "r2_ext".to_string(),
+                vec![200.into(), 200.into(), 1000.into()],
+            ),
+        ]);
+        let prefix = inputs.get_prefixes()[0];
+        let (_, analyzer) = build_analyzer_from_experiment_description(ExperimentDescription {
+            topo,
+            topo_name: topo.fmt(),
+            scenario_name: "ExtAtEnds_FullMesh_PrependAsPathAtR0".to_string(),
+            config: ScenarioConfig::FullMesh,
+            delays: LinkDelayBuilder::new().default_delay(1_000.0),
+            static_routing_inputs: inputs,
+            event: AnalyzerEvent::PrependAsPath {
+                external: "r0_ext".to_string(),
+                prefix,
+                times: 2,
+            },
+            confidence: None,
+            precision: None,
+        })
+        .unwrap();
+        let net = &analyzer.original_net;
+        let [r0, r1, r2, r2_ext] =
+            ["r0", "r1", "r2", "r2_ext"].map(|r| net.get_router_id(r).unwrap());
+
+        // initially, all routers leave the network at r0_ext with the shorter AS path
+        assert_eq!(analyzer.original_fw.get_next_hops(r1, prefix), [r0]);
+        assert_eq!(analyzer.original_fw.get_next_hops(r2, prefix), [r1]);
+
+        // after prepending, the route of r2_ext is shorter, shifting the egress
+        let trace = sample_trace(&analyzer);
+        let t_last = trace[&prefix].last().unwrap().1.into_inner().unwrap();
+        let mut fw_state = analyzer.fw_state_at(&trace, t_last);
+        assert_eq!(fw_state.get_next_hops(r0, prefix), [r1]);
+        assert_eq!(fw_state.get_next_hops(r1, prefix), [r2]);
+        assert_eq!(
+            fw_state.get_paths(r0, prefix).unwrap(),
+            [vec![r0, r1, r2, r2_ext]]
+        );
+    }
+
+    #[test]
+    fn analyze_marks_result_as_analyzed() {
+        let result = path_analyzer(1).analyze().unwrap();
+        assert!(result.is_analyzed());
+        assert!((0.0..=1.0).contains(&result.p_satisfied));
+    }
+
+    #[test]
+    fn cancellation() {
+        let mut analyzer = path_analyzer(1);
+        let flag = Arc::new(AtomicBool::new(true));
+        analyzer.set_cancellation_flag(flag.clone());
+
+        // a flag that is already set skips all samples, but still yields a valid result
+        let result = analyzer.analyze_with(1_000, 2).unwrap();
+        assert_eq!(result.n_samples, 0);
+        assert!(result.is_analyzed());
+        assert_eq!(result.p_satisfied, 0.0);
+        assert_eq!(result.convergence_time, 0.0);
+        for violation_times in result.violation_time_distributions.values() {
+            assert!(violation_times.is_empty());
+        }
+
+        // clearing the flag collects all samples again
+        let n = 20;
+        flag.store(false, Ordering::Relaxed);
+        let result = analyzer.analyze_with(n, 2).unwrap();
+        assert_eq!(result.n_samples, n);
+        for violation_times in result.violation_time_distributions.values() {
+            assert_eq!(violation_times.len(), n);
+        }
+    }
+
+    #[test]
+    fn convergence_retry() {
+        let retry = ConvergenceRetry {
+            timeout: Duration::from_secs(1),
+            retries: 2,
+            max_consecutive_skips: 3,
+        };
+        assert_eq!(retry.decide(0, true), RetryDecision::Converged);
+        assert_eq!(retry.decide(0, false), RetryDecision::Retry);
+        assert_eq!(retry.decide(1, false), RetryDecision::Retry);
+        assert_eq!(retry.decide(2, true), RetryDecision::Converged);
+        assert_eq!(retry.decide(2, false), RetryDecision::Skip);
+
+        // without retries, the first failure skips the sample
+        let no_retry = ConvergenceRetry {
+            retries: 0,
+            ..retry
+        };
+        assert_eq!(no_retry.decide(0, false), RetryDecision::Skip);
+
+        // the remaining samples are aborted after 3 consecutive skipped samples
+        assert!(!retry.abort(2));
+        assert!(retry.abort(3));
+
+        // configurations without the limit use the default
+        let reloaded: ConvergenceRetry =
+            serde_json::from_str(r#"{"timeout":{"secs":1,"nanos":0},"retries":2}"#).unwrap();
+        assert_eq!(reloaded, retry);
+
+        let mut analyzer = path_analyzer(1);
+        assert_eq!(analyzer.convergence_retry, ConvergenceRetry::default());
+        analyzer.set_convergence_retry(no_retry);
+        assert_eq!(analyzer.convergence_retry, no_retry);
+    }
+
+    #[test]
+    fn monitoring_iface() {
+        let mut analyzer = path_analyzer(1);
+        assert_eq!(analyzer.monitoring_iface, DEFAULT_MONITORING_IFACE);
+        assert_eq!(
+            event_start_capture_cmd(&analyzer.monitoring_iface, "tcp port 179"),
+            "sudo tcpdump_pfring -i enp132s0f1 tcp port 179 -c1 -w - 2>/dev/null | tshark -r - -T fields -e frame.time_epoch 2>/dev/null"
+        );
+
+        analyzer.set_monitoring_iface("eth1");
+        assert_eq!(
+            event_start_capture_cmd(&analyzer.monitoring_iface, "tcp port 179"),
+            "sudo tcpdump_pfring -i eth1 tcp port 179 -c1 -w - 2>/dev/null | tshark -r - -T fields -e frame.time_epoch 2>/dev/null"
+        );
+    }
+
+    #[test]
+    fn trace_lengths() {
+        let mut analyzer = path_analyzer(2);
+        let result = analyzer.analyze().unwrap();
+        assert!(result.trace_lengths.is_empty());
+
+        analyzer.set_record_trace_lengths(true);
+        let result = analyzer.analyze().unwrap();
+        assert_eq!(result.trace_lengths.len(), result.n_samples);
+        assert!(result.trace_lengths.iter().all(|len| *len > 0));
+    }
+
+    #[test]
+    fn final_egresses() {
+        let mut analyzer = path_analyzer(1);
+        let result = analyzer.analyze_with(4, 2).unwrap();
+        assert!(result.final_egresses.is_empty());
+
+        analyzer.set_record_final_egresses(true);
+        let result = analyzer.analyze_with(4, 2).unwrap();
+        let net = &analyzer.original_net;
+        let [r0, r2_ext] = ["r0", "r2_ext"].map(|r| net.get_router_id(r).unwrap());
+        let prefix = analyzer.event.prefixes()[0];
+        assert_eq!(result.final_egresses.len(), net.internal_indices().count());
+        for tally in result.final_egresses.values() {
+            assert_eq!(tally.values().sum::<usize>(), result.n_samples);
+        }
+        // after withdrawing the route at r0_ext, all traffic leaves the network at r2_ext
+        assert_eq!(
+            result.final_egresses[&(r0, prefix)],
+            HashMap::from([(Some(r2_ext), result.n_samples)])
+        );
+    }
+
+    #[test]
+    fn convergence_time_distribution() {
+        let prefixes = ScenarioPrefix::SinglePrefix.prefixes();
+        let mean_convergence_time = |topo: &str| {
+            let (_, net, _, delays, external_routers) = list_custom_topologies(&prefixes)
+                .into_iter()
+                .find(|(name, ..)| *name == topo)
+                .unwrap();
+            let delays = delays.unwrap();
+            let (_, event) = list_path_scenarios(&net, &prefixes, &external_routers)
+                .into_iter()
+                .find(|(name, _)| *name == "WithdrawE1")
+                .unwrap();
+            let net = net.swap_queue(TimingModel::from_delays(&delays)).unwrap();
+            let mut analyzer = Analyzer::new(net, event, vec![], 0.95, 0.01).unwrap();
+            analyzer.set_delays(delays);
+
+            let convergence_times = analyzer.convergence_time_distribution(10);
+            assert_eq!(convergence_times.len(), 10);
+            convergence_times.iter().sum::<f64>() / convergence_times.len() as f64
+        };
+
+        let path03 = mean_convergence_time("Path03_FullMesh_ExtAtEnds_3ms");
+        let path06 = mean_convergence_time("Path06_FullMesh_ExtAtEnds_3ms");
+        // the withdraw must at least traverse one link of 3ms
+        assert!(path03 > 0.003, "{path03}");
+        assert!(path06 > path03, "{path06} <= {path03}");
+    }
+
+    #[test]
+    fn worst_traces() {
+        let mut analyzer = path_analyzer(2);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        analyzer.set_policy_template(r0, PolicyKind::Reachable);
+        assert!(analyzer.analyze().unwrap().worst_traces.is_empty());
+
+        analyzer.set_retain_worst_traces(2);
+        let result = analyzer.analyze().unwrap();
+        assert_eq!(result.worst_traces.len(), result.n_samples.min(2));
+        assert!(result
+            .worst_traces
+            .windows(2)
+            .all(|w| w[0].total_violation_time >= w[1].total_violation_time));
+
+        // recomputing the violation times of the retained traces yields the recorded values
+        let mut queue = analyzer.build_queue();
+        for retained in result.worst_traces.iter() {
+            let violation_times = total_violation_times(&analyzer, &mut queue, &retained.trace)
+                .unwrap()
+                .times;
+            for (i, policy) in analyzer.policies.iter().enumerate() {
+                let t = violation_times.get(policy).copied().unwrap_or_default();
+                assert!((t - retained.violation_times[i]).abs() < 1e-9);
+            }
+            let total: f64 = retained.violation_times.iter().sum();
+            assert!((total - retained.total_violation_time).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn recheck_policies() {
+        let mut analyzer = path_analyzer(1);
+        let [r0, r1, r2] =
+            ["r0", "r1", "r2"].map(|r| analyzer.original_net.get_router_id(r).unwrap());
+        let prefix = analyzer.event.prefixes()[0];
+        analyzer.set_policy_template(r0, PolicyKind::Reachable);
+        analyzer.set_retain_worst_traces(1);
+        let result = analyzer.analyze().unwrap();
+        let retained = &result.worst_traces[0];
+        let traces = [retained.trace.clone()];
+
+        // re-checking the policies of the analysis yields the recorded violation times
+        let rechecked = analyzer
+            .recheck_policies(&traces, &analyzer.policies)
+            .unwrap();
+        for (i, policy) in analyzer.policies.iter().enumerate() {
+            assert!((rechecked[policy][0] - retained.violation_times[i]).abs() < 1e-9);
+        }
+
+        // r1 initially forwards via r0 towards r0_ext, and only passes r2 after switching to
+        // r2_ext, whereas traffic from r2 always passes r2 itself.
+        let via_r2 = PolicyKind::Waypoint(r2).instantiate(r1, prefix);
+        let from_r2 = PolicyKind::Waypoint(r2).instantiate(r2, prefix);
+        let rechecked = analyzer
+            .recheck_policies(&traces, &[via_r2.clone(), from_r2.clone()])
+            .unwrap();
+        assert_eq!(rechecked.len(), 2);
+        assert!(rechecked[&via_r2][0] > 0.0);
+        assert_eq!(rechecked[&from_r2], vec![0.0]);
+        let rechecked = analyzer.recheck_policies(&[], &[via_r2.clone()]).unwrap();
+        assert!(rechecked[&via_r2].is_empty());
+    }
+
+    #[test]
+    fn violation_time_distributions_keys() {
+        let mut analyzer = path_analyzer(2);
+        analyzer.set_seed(3);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        let r2 = analyzer.original_net.get_router_id("r2").unwrap();
+        let prefixes = analyzer.event.prefixes();
+        let reachable = TransientPolicy::for_all_prefixes(r0, PolicyKind::Reachable, &prefixes);
+
+        analyzer.set_policies(reachable.clone());
+        let reachable_only = analyzer.analyze_with(20, 1).unwrap();
+
+        // two policies concerning each pair at r0
+        let mut policies = TransientPolicy::for_all_prefixes(r2, PolicyKind::Reachable, &prefixes);
+        policies.extend(TransientPolicy::for_all_prefixes(
+            r0,
+            PolicyKind::LoopFree,
+            &prefixes,
+        ));
+        policies.extend(reachable);
+
+        let expected_keys = [r0, r2]
+            .into_iter()
+            .sorted()
+            .flat_map(|r| prefixes.iter().sorted().map(move |p| (r, *p)))
+            .collect_vec();
+        let mut results = Vec::new();
+        for policies in [policies.clone(), policies.into_iter().rev().collect()] {
+            analyzer.set_policies(policies);
+            let result = analyzer.analyze_with(20, 1).unwrap();
+            // one distribution per pair, ordered by `(router, prefix)`
+            assert_eq!(
+                result
+                    .violation_time_distributions
+                    .keys()
+                    .copied()
+                    .collect_vec(),
+                expected_keys
+            );
+            for distribution in result.violation_time_distributions.values() {
+                assert_eq!(distribution.len(), result.n_samples);
+                assert!(distribution.is_sorted_by(|a, b| a <= b));
+            }
+            // a forwarding loop also violates reachability, so the longest violation time of
+            // each sample at r0 is the one of the reachability policy
+            for prefix in &prefixes {
+                let distribution = &result.violation_time_distributions[&(r0, *prefix)];
+                assert_eq!(
+                    distribution,
+                    &reachable_only.violation_time_distributions[&(r0, *prefix)]
+                );
+                assert!(distribution.iter().any(|t| *t > 0.0));
+            }
+            results.push(result.violation_time_distributions);
+        }
+        // the association of samples to policies does not depend on the order of the policies
+        assert_eq!(results[0], results[1]);
+    }
+
+    #[test]
+    fn duplicate_policies() {
+        let mut analyzer = path_analyzer(1);
+        analyzer.set_seed(3);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        analyzer.set_policy_template(r0, PolicyKind::Reachable);
+        let single = analyzer.analyze_with(20, 1).unwrap();
+        assert!(single
+            .violation_time_distributions
+            .values()
+            .flatten()
+            .any(|t| *t > 0.0));
+
+        // a duplicated policy neither adds a distribution nor changes the existing one
+        let policies = analyzer.policies.clone();
+        analyzer.set_policies(policies.iter().chain(&policies).cloned().collect());
+        let duplicated = analyzer.analyze_with(20, 1).unwrap();
+        assert_eq!(duplicated.p_satisfied, single.p_satisfied);
+        assert_eq!(
+            duplicated.violation_time_distributions,
+            single.violation_time_distributions
+        );
+    }
+}
@@ -15,20 +15,29 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 //! Describes an experiment result.
 
-use std::{collections::HashMap, time::Duration};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, BinaryHeap, HashMap, HashSet},
+    time::Duration,
+};
 
 use serde::{Deserialize, Serialize};
 
 use bgpsim::prelude::*;
 
-use crate::Prefix;
+use crate::{MultiPrefixConvergenceTrace, Prefix};
+
+/// Number of samples in which the traffic of each `(router, prefix)` pair finally leaves the network
+/// via each egress router, or `None` if it is finally dropped or caught in a loop.
+pub type FinalEgresses = HashMap<(RouterId, Prefix), HashMap<Option<RouterId>, usize>>;
 
 /// Result of the analysis including the different wall times.
 #[derive(Clone, Debug, Default)]
 pub struct AnalysisResult {
-    /// Probability of the policies being satisfied.
+    /// Probability of the policies being satisfied. Zero if no samples were collected, e.g., if
+    /// the analysis was cancelled before the first sample.
     pub p_satisfied: f64,
-    /// Mean time it took to converge, in seconds
+    /// Mean time it took to converge, in seconds. Zero if no samples were collected.
     pub convergence_time: f64,
     /// confidence of the collected result
     pub confidence: f64,
@@ -38,6 +47,16 @@ pub struct AnalysisResult {
     pub n_samples: usize,
     /// Number of unique forwarding state equivalence classes.
     pub n_unique_equiv: usize,
+    /// Number of samples whose convergence did not complete, and whose results are thus invalid,
+    /// or whose violation times could not be evaluated.
+    pub n_incomplete: usize,
+    /// Number of forwarding updates (summed across prefixes) of each sample, in ascending order.
+    /// Only recorded if enabled with `Analyzer::set_record_trace_lengths`.
+    pub trace_lengths: Vec<usize>,
+    /// Distribution of the final egress of each `(router, prefix)` pair across the samples of this
+    /// run, i.e., after all forwarding updates of a sample are applied. Only recorded if enabled
+    /// with `Analyzer::set_record_final_egresses`.
+    pub final_egresses: FinalEgresses,
     /// Time it took for simulating the network in total (if it would have been executed on a single
     /// thread)
     pub t_simulate: Duration,
@@ -51,10 +70,538 @@ pub struct AnalysisResult {
     pub t_collect: Duration,
     /// Time to measure the time, from start to finish, using all k workers.
     pub t_wall: Duration,
-    /// distribution of violation times per forwarding policy
-    pub violation_time_distributions: HashMap<(RouterId, Prefix), Vec<f64>>,
+    /// distribution of violation times per forwarding policy, ordered by `(router, prefix)`. If
+    /// multiple policies concern the same pair, they share a single distribution holding the
+    /// largest violation time of any of them in each sample, instead of only the one of the last
+    /// such policy.
+    pub violation_time_distributions: BTreeMap<(RouterId, Prefix), Vec<f64>>,
+    /// Normalized importance weight of each sample in `violation_time_distributions`, at the same
+    /// index. Only recorded if any sample has a weight other than 1, i.e., with importance sampling
+    /// (see `ImportanceSampling`). Otherwise, all samples are weighted equally.
+    pub violation_time_weights: BTreeMap<(RouterId, Prefix), Vec<f64>>,
     /// distribution of violation times per forwarding policy as measured on the hardware routers
-    pub cisco_violation_time_distributions: HashMap<(RouterId, Prefix), Vec<f64>>,
+    pub cisco_violation_time_distributions: BTreeMap<(RouterId, Prefix), Vec<f64>>,
+    /// Traces of the samples with the largest total violation time, in descending order. Only
+    /// retained if enabled with `Analyzer::set_retain_worst_traces`.
+    pub worst_traces: Vec<RetainedTrace>,
+}
+
+/// Trace of a single simulated sample, together with its violation times.
+#[derive(Clone, Debug)]
+pub struct RetainedTrace {
+    /// Sum of the violation times of all policies.
+    pub total_violation_time: f64,
+    /// Violation time of each policy, at the index of the policy in `Analyzer::policies`.
+    pub violation_times: Vec<f64>,
+    /// Hash of the equivalence class of the trace (see `compute_equivalence_class`).
+    pub eq_class: u64,
+    /// The simulated trace, with times relative to `Analyzer::time_offset`.
+    pub trace: MultiPrefixConvergenceTrace,
+}
+
+/// Bounded collection keeping only the `capacity` traces with the largest total violation time.
+#[derive(Debug, Default)]
+pub struct WorstTraces {
+    capacity: usize,
+    heap: BinaryHeap<Reverse<ByViolationTime>>,
+}
+
+impl WorstTraces {
+    /// Create an empty collection retaining at most `capacity` traces.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            heap: BinaryHeap::with_capacity(capacity + 1),
+        }
+    }
+
+    /// Insert a trace, dropping the trace with the smallest total violation time if the capacity
+    /// is exceeded.
+    pub fn push(&mut self, trace: RetainedTrace) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.heap.push(Reverse(ByViolationTime(trace)));
+        if self.heap.len() > self.capacity {
+            self.heap.pop();
+        }
+    }
+
+    /// Get all retained traces, ordered by descending total violation time.
+    pub fn into_sorted_vec(self) -> Vec<RetainedTrace> {
+        // sorting `Reverse` in ascending order yields descending violation times
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(ByViolationTime(trace))| trace)
+            .collect()
+    }
+}
+
+/// Wrapper ordering `RetainedTrace`s by their total violation time.
+#[derive(Debug)]
+struct ByViolationTime(RetainedTrace);
+
+impl PartialEq for ByViolationTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ByViolationTime {}
+
+impl PartialOrd for ByViolationTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByViolationTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .total_violation_time
+            .total_cmp(&other.0.total_violation_time)
+    }
+}
+
+impl AnalysisResult {
+    /// Create an empty result for an analysis with the given parameters. The probability of the
+    /// policies being satisfied is `NaN` until the result is analyzed (see `is_analyzed`).
+    pub fn new(confidence: f64, precision: f64, n_samples: usize) -> Self {
+        Self {
+            p_satisfied: f64::NAN,
+            confidence,
+            precision,
+            n_samples,
+            ..Default::default()
+        }
+    }
+
+    /// Check whether the analysis has been performed, i.e., whether `p_satisfied` is valid.
+    pub fn is_analyzed(&self) -> bool {
+        !self.p_satisfied.is_nan()
+    }
+
+    /// Estimate how many more samples are needed to reach `confidence` and `precision`, based on
+    /// the observed `p_satisfied`. Unlike `hoeffding`, which assumes the worst-case variance of
+    /// `p = 0.5`, this uses Bernstein's inequality, which requires far fewer samples if the
+    /// policies are (almost) always or never satisfied. The variance is not the plug-in estimate
+    /// `p (1 - p)`, which is zero if all of a few samples agree, but the upper confidence bound
+    /// `sqrt(V_n) + sqrt(2 ln(2 / delta) / (n - 1))` of the standard deviation by Maurer and
+    /// Pontil (2009), with the sample variance `V_n`. Half of `delta = 1 - confidence` is spent on
+    /// the variance and half on the mean. The estimate never exceeds the worst-case bound, and equals it for
+    /// few samples. If the result is not analyzed yet, the remaining samples of the worst-case
+    /// bound are returned.
+    pub fn samples_remaining(&self) -> usize {
+        let worst_case = super::hoeffding(self.confidence, self.precision);
+        if !self.is_analyzed() || self.n_samples < 2 {
+            return worst_case.saturating_sub(self.n_samples);
+        }
+        let delta = 1.0 - self.confidence;
+        let n = self.n_samples as f64;
+        let sample_variance = n / (n - 1.0) * self.p_satisfied * (1.0 - self.p_satisfied);
+        // a Bernoulli variable has a standard deviation of at most 0.5.
+        let std_dev =
+            (sample_variance.sqrt() + (2.0 * f64::ln(2.0 / delta) / (n - 1.0)).sqrt()).min(0.5);
+        // `hoeffding` interprets the precision as the width of the confidence interval.
+        let half_width = 0.5 * self.precision;
+        let required = (f64::ln(4.0 / delta) * (2.0 * std_dev * std_dev + 2.0 * half_width / 3.0)
+            / (half_width * half_width))
+            .ceil() as usize;
+        required.min(worst_case).saturating_sub(self.n_samples)
+    }
+
+    /// Get all prefixes for which at least one simulated sample violates a policy, i.e., has a
+    /// nonzero violation time.
+    pub fn violating_prefixes(&self) -> HashSet<Prefix> {
+        self.violating_keys().map(|(_, prefix)| prefix).collect()
+    }
+
+    /// Get all routers for which at least one simulated sample violates a policy, i.e., has a
+    /// nonzero violation time.
+    pub fn violating_routers(&self) -> HashSet<RouterId> {
+        self.violating_keys().map(|(router, _)| router).collect()
+    }
+
+    /// Get the empirical CDF of the violation times of `key`, as points `(violation_time,
+    /// cumulative_probability)` with strictly increasing violation times, e.g., for plotting. The
+    /// samples are weighted by their `violation_time_weights`, if any. The distribution must be
+    /// sorted in ascending order, as done by `Analyzer::analyze`. Returns an empty vector if there
+    /// are no samples for `key`.
+    pub fn cdf(&self, key: (RouterId, Prefix)) -> Vec<(f64, f64)> {
+        let Some(violation_times) = self.violation_time_distributions.get(&key) else {
+            return Vec::new();
+        };
+        let n = violation_times.len() as f64;
+        let weights = self.violation_time_weights.get(&key);
+        let mut cumulative = 0.0;
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        for (i, t) in violation_times.iter().enumerate() {
+            let p = match weights {
+                Some(weights) => {
+                    cumulative += weights[i];
+                    cumulative
+                }
+                None => (i + 1) as f64 / n,
+            };
+            match points.last_mut() {
+                // keep only the last (largest) probability of equal violation times
+                Some((last_t, last_p)) if last_t == t => *last_p = p,
+                _ => points.push((*t, p)),
+            }
+        }
+        points
+    }
+
+    /// Get a histogram of the violation times of `key` with bins of `bin_width_ms` milliseconds,
+    /// as `(bin_start, count)` with `bin_start` in seconds, like the violation times. Only non-empty
+    /// bins are returned, in ascending order, such that a single large outlier does not produce a
+    /// large number of empty bins. Non-finite violation times are not assigned to any bin. If all
+    /// violation times are zero, this is a single bin at zero. Returns an empty vector if there are
+    /// no samples for `key`, or if `bin_width_ms` is not positive and finite. The counts ignore the
+    /// `violation_time_weights`; use `weighted_histogram` for the probability of each bin.
+    pub fn histogram(&self, key: (RouterId, Prefix), bin_width_ms: f64) -> Vec<(f64, usize)> {
+        let mut counts: BTreeMap<u64, usize> = BTreeMap::new();
+        for (bin, _) in self.histogram_bins(key, bin_width_ms) {
+            *counts.entry(bin).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .map(|(bin, count)| (bin as f64 * bin_width_ms / 1000.0, count))
+            .collect()
+    }
+
+    /// Same as `histogram`, but as `(bin_start, probability)`, where the probability of a bin is
+    /// the fraction of samples in it, weighted by their `violation_time_weights`, if any.
+    /// Non-finite violation times still count towards the total.
+    pub fn weighted_histogram(
+        &self,
+        key: (RouterId, Prefix),
+        bin_width_ms: f64,
+    ) -> Vec<(f64, f64)> {
+        let weights = self.violation_time_weights.get(&key);
+        let mut probabilities: BTreeMap<u64, f64> = BTreeMap::new();
+        for (bin, i) in self.histogram_bins(key, bin_width_ms) {
+            *probabilities.entry(bin).or_default() += weights.map_or(1.0, |weights| weights[i]);
+        }
+        // without weights, the bins contain the number of samples
+        let total = match weights {
+            Some(_) => 1.0,
+            None => self.violation_time_distributions[&key].len() as f64,
+        };
+        probabilities
+            .into_iter()
+            .map(|(bin, p)| (bin as f64 * bin_width_ms / 1000.0, p / total))
+            .collect()
+    }
+
+    /// Get the bin of width `bin_width_ms` and the index of each finite violation time of `key`.
+    fn histogram_bins(&self, key: (RouterId, Prefix), bin_width_ms: f64) -> Vec<(u64, usize)> {
+        if !bin_width_ms.is_finite() || bin_width_ms <= 0.0 {
+            log::warn!("Invalid bin width {bin_width_ms}ms for the histogram of {key:?}");
+            return Vec::new();
+        }
+        let Some(violation_times) = self.violation_time_distributions.get(&key) else {
+            return Vec::new();
+        };
+        violation_times
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| {
+                if !t.is_finite() {
+                    log::warn!(
+                        "Skipping non-finite violation time {t} of {key:?} in the histogram"
+                    );
+                    return None;
+                }
+                // bin in milliseconds to avoid rounding errors of the bin width in seconds
+                Some(((t * 1000.0 / bin_width_ms).floor().max(0.0) as u64, i))
+            })
+            .collect()
+    }
+
+    /// Get the simulated violation times of all policies pooled together, sorted in ascending
+    /// order.
+    pub fn pooled_violation_times(&self) -> Vec<f64> {
+        let mut samples: Vec<f64> = self
+            .violation_time_distributions
+            .values()
+            .flatten()
+            .copied()
+            .collect();
+        samples.sort_by(f64::total_cmp);
+        samples
+    }
+
+    /// Get the simulated violation times of all policies pooled together, sorted in ascending
+    /// order, together with the normalized weight of each sample. Each policy contributes in
+    /// proportion to its number of samples, within which the samples are weighted by their
+    /// `violation_time_weights`, if any. Without importance weights, all samples are weighted
+    /// equally.
+    pub fn pooled_weighted_violation_times(&self) -> Vec<(f64, f64)> {
+        let total = self
+            .violation_time_distributions
+            .values()
+            .map(Vec::len)
+            .sum::<usize>() as f64;
+        let mut samples: Vec<(f64, f64)> = self
+            .violation_time_distributions
+            .iter()
+            .flat_map(|(key, violation_times)| {
+                let n = violation_times.len() as f64;
+                let weights = match self.violation_time_weights.get(key) {
+                    Some(weights) => weights.iter().map(|w| w * n / total).collect(),
+                    None => vec![1.0 / total; violation_times.len()],
+                };
+                violation_times.iter().copied().zip(weights)
+            })
+            .collect();
+        samples.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        samples
+    }
+
+    /// Get the mean of the pooled violation times of all policies, weighted as in
+    /// `pooled_weighted_violation_times`. Returns `None` if there are no samples at all.
+    pub fn mean_violation_time(&self) -> Option<f64> {
+        let samples = self.pooled_weighted_violation_times();
+        if samples.is_empty() {
+            return None;
+        }
+        Some(samples.iter().map(|(t, weight)| t * weight).sum())
+    }
+
+    /// Get the given percentiles (in `0..=100`, see `percentile`) of the pooled violation times of
+    /// all policies (see `pooled_violation_times`), or the weighted percentiles (see
+    /// `weighted_percentile`) if the samples have `violation_time_weights`. Returns `None` if there
+    /// are no samples at all.
+    pub fn percentiles(&self, ps: &[usize]) -> Option<Vec<f64>> {
+        if !self.violation_time_weights.is_empty() {
+            let samples = self.pooled_weighted_violation_times();
+            return ps
+                .iter()
+                .map(|p| weighted_percentile(&samples, *p))
+                .collect();
+        }
+        let samples = self.pooled_violation_times();
+        if samples.is_empty() {
+            return None;
+        }
+        ps.iter().map(|p| percentile(&samples, *p)).collect()
+    }
+
+    /// Get a copy of the result without the outliers among the samples of each simulated and
+    /// measured violation time distribution, as decided by `strategy` (see `reject_outliers`).
+    /// The rejected samples are returned in an `OutlierReport` and logged, such that systematic
+    /// issues are not hidden silently. Simulated samples with `violation_time_weights` are judged
+    /// by their weighted distribution (see `reject_weighted_outliers`), and the weights of the kept
+    /// samples are normalized again. All other fields, in particular the aggregates `p_satisfied`,
+    /// `convergence_time`, `n_samples`, `n_incomplete`, `trace_lengths`, `final_egresses` and
+    /// `worst_traces`, are copied unchanged and thus still describe all samples before filtering:
+    /// the outliers are rejected per distribution, such that a sample may be rejected for one
+    /// policy but kept for another, and these aggregates cannot be recomputed from the kept samples.
+    pub fn reject_outliers(&self, strategy: OutlierStrategy) -> (Self, OutlierReport) {
+        let mut report = OutlierReport::default();
+        let filter = |distributions: &BTreeMap<(RouterId, Prefix), Vec<f64>>,
+                      weights: &BTreeMap<(RouterId, Prefix), Vec<f64>>,
+                      rejected: &mut HashMap<(RouterId, Prefix), Vec<usize>>| {
+            distributions
+                .iter()
+                .map(|(key, samples)| {
+                    let (kept, outliers) = match weights.get(key) {
+                        Some(weights) => reject_weighted_outliers(samples, weights, strategy),
+                        None => reject_outliers(samples, strategy),
+                    };
+                    if !outliers.is_empty() {
+                        log::warn!(
+                            "Rejected {} of {} samples of {key:?} as outliers: {:?}",
+                            outliers.len(),
+                            samples.len(),
+                            outliers.iter().map(|&i| samples[i]).collect::<Vec<_>>()
+                        );
+                        rejected.insert(*key, outliers);
+                    }
+                    (*key, kept)
+                })
+                .collect()
+        };
+        let violation_time_distributions = filter(
+            &self.violation_time_distributions,
+            &self.violation_time_weights,
+            &mut report.simulated,
+        );
+        let cisco_violation_time_distributions = filter(
+            &self.cisco_violation_time_distributions,
+            &BTreeMap::new(),
+            &mut report.measured,
+        );
+        // drop the weights of the rejected samples, and normalize the remaining ones
+        let violation_time_weights = self
+            .violation_time_weights
+            .iter()
+            .map(|(key, weights)| {
+                let rejected = report.simulated.get(key).cloned().unwrap_or_default();
+                let kept = weights
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !rejected.contains(i))
+                    .map(|(_, w)| *w)
+                    .collect::<Vec<_>>();
+                let total = kept.iter().sum::<f64>();
+                (*key, kept.into_iter().map(|w| w / total).collect())
+            })
+            .collect();
+        let result = Self {
+            violation_time_distributions,
+            violation_time_weights,
+            cisco_violation_time_distributions,
+            ..self.clone()
+        };
+        (result, report)
+    }
+
+    fn violating_keys(&self) -> impl Iterator<Item = (RouterId, Prefix)> + '_ {
+        self.violation_time_distributions
+            .iter()
+            .filter(|(_, violation_times)| violation_times.iter().any(|&t| t != 0.0))
+            .map(|(key, _)| *key)
+    }
+}
+
+/// Rule deciding which samples of a violation time distribution are outliers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutlierStrategy {
+    /// Reject samples further than the given number of standard deviations from the mean.
+    StdDev(f64),
+    /// Reject samples outside of `[q1 - k * iqr, q3 + k * iqr]` for the given `k`, where `q1` and
+    /// `q3` are the first and third quartile, and `iqr = q3 - q1` (typically, `k = 1.5`).
+    Iqr(f64),
+}
+
+/// Samples rejected by `AnalysisResult::reject_outliers`, given by their indices in the original
+/// distributions. Distributions without outliers are omitted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutlierReport {
+    /// Rejected samples of `AnalysisResult::violation_time_distributions`.
+    pub simulated: HashMap<(RouterId, Prefix), Vec<usize>>,
+    /// Rejected samples of `AnalysisResult::cisco_violation_time_distributions`.
+    pub measured: HashMap<(RouterId, Prefix), Vec<usize>>,
+}
+
+impl OutlierReport {
+    /// Total number of rejected samples.
+    pub fn num_rejected(&self) -> usize {
+        self.simulated
+            .values()
+            .chain(self.measured.values())
+            .map(Vec::len)
+            .sum()
+    }
+}
+
+/// Get the nearest-rank percentile `p` (in `0..=100`) of the `sorted` samples, i.e., the sample at
+/// index `p * len / 100`, clamped to the last sample. Returns `None` if there are no samples.
+pub fn percentile(sorted: &[f64], p: usize) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    Some(sorted[(p * sorted.len() / 100).min(last)])
+}
+
+/// Get the weighted percentile `p` (in `0..=100`) of the `sorted` samples with normalized weights,
+/// i.e., the first sample at which the cumulative weight exceeds `p / 100`, clamped to the last
+/// sample. With equal weights, this is the same as `percentile`. Returns `None` if there are no
+/// samples.
+pub fn weighted_percentile(sorted: &[(f64, f64)], p: usize) -> Option<f64> {
+    let (last, _) = sorted.last()?;
+    let mut cumulative = 0.0;
+    for (t, weight) in sorted {
+        cumulative += weight;
+        if cumulative > p as f64 / 100.0 {
+            return Some(*t);
+        }
+    }
+    Some(*last)
+}
+
+/// Split `samples` into the samples that are kept and the indices of the samples that are
+/// rejected as outliers according to `strategy`. The order of the kept samples is preserved.
+pub fn reject_outliers(samples: &[f64], strategy: OutlierStrategy) -> (Vec<f64>, Vec<usize>) {
+    if samples.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    let (low, high) = match strategy {
+        OutlierStrategy::StdDev(k) => {
+            let n = samples.len() as f64;
+            let mean = samples.iter().sum::<f64>() / n;
+            let std_dev = (samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt();
+            (mean - k * std_dev, mean + k * std_dev)
+        }
+        OutlierStrategy::Iqr(k) => {
+            let mut sorted = samples.to_vec();
+            sorted.sort_by(|a, b| a.total_cmp(b));
+            let (q1, q3) = (quantile(&sorted, 0.25), quantile(&sorted, 0.75));
+            (q1 - k * (q3 - q1), q3 + k * (q3 - q1))
+        }
+    };
+    split_outliers(samples, low, high)
+}
+
+/// Same as `reject_outliers`, but with each sample weighted by the normalized `weights` at the same
+/// index, e.g., the `violation_time_weights` of importance sampling. The mean and standard
+/// deviation (`OutlierStrategy::StdDev`) and the quartiles (`OutlierStrategy::Iqr`, without
+/// interpolation) are those of the weighted distribution.
+pub fn reject_weighted_outliers(
+    samples: &[f64],
+    weights: &[f64],
+    strategy: OutlierStrategy,
+) -> (Vec<f64>, Vec<usize>) {
+    if samples.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    let (low, high) = match strategy {
+        OutlierStrategy::StdDev(k) => {
+            let mean = samples.iter().zip(weights).map(|(x, w)| x * w).sum::<f64>();
+            let std_dev = samples
+                .iter()
+                .zip(weights)
+                .map(|(x, w)| w * (x - mean).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            (mean - k * std_dev, mean + k * std_dev)
+        }
+        OutlierStrategy::Iqr(k) => {
+            let mut sorted = samples
+                .iter()
+                .copied()
+                .zip(weights.iter().copied())
+                .collect::<Vec<_>>();
+            sorted.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+            let q1 = weighted_percentile(&sorted, 25).unwrap();
+            let q3 = weighted_percentile(&sorted, 75).unwrap();
+            (q1 - k * (q3 - q1), q3 + k * (q3 - q1))
+        }
+    };
+    split_outliers(samples, low, high)
+}
+
+/// Split `samples` into the samples within `[low, high]`, and the indices of all other samples.
+fn split_outliers(samples: &[f64], low: f64, high: f64) -> (Vec<f64>, Vec<usize>) {
+    let mut kept = Vec::with_capacity(samples.len());
+    let mut rejected = Vec::new();
+    for (i, &x) in samples.iter().enumerate() {
+        if (low..=high).contains(&x) {
+            kept.push(x);
+        } else {
+            rejected.push(i);
+        }
+    }
+    (kept, rejected)
+}
+
+/// Compute the `q`-quantile of a non-empty, sorted slice, interpolating linearly between the two
+/// closest ranks.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
 }
 
 impl std::fmt::Display for AnalysisResult {
@@ -90,3 +637,339 @@ pub struct AnalyzerData {
     /// Time to collect all stats from the different thread-local caches into a global cache
     pub t_collect: Duration,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn violating_prefixes_and_routers() {
+        let (r0, r1, r2) = (RouterId::from(0), RouterId::from(1), RouterId::from(2));
+        let (p0, p1, p2) = (Prefix::from(0), Prefix::from(1), Prefix::from(2));
+        let result = AnalysisResult {
+            violation_time_distributions: BTreeMap::from([
+                ((r0, p0), vec![0.0, 0.0, 0.5]),
+                ((r0, p1), vec![0.0, 0.0, 0.0]),
+                ((r1, p1), vec![0.0, 0.0, 0.0]),
+                ((r1, p2), vec![1.0, 2.0, 3.0]),
+                ((r2, p0), vec![]),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(result.violating_prefixes(), HashSet::from([p0, p2]));
+        assert_eq!(result.violating_routers(), HashSet::from([r0, r1]));
+        assert!(AnalysisResult::default().violating_prefixes().is_empty());
+    }
+
+    #[test]
+    fn cdf() {
+        let (r0, r1) = (RouterId::from(0), RouterId::from(1));
+        let p0 = Prefix::from(0);
+        let result = AnalysisResult {
+            violation_time_distributions: BTreeMap::from([
+                ((r0, p0), vec![0.0, 0.0, 0.5, 1.0, 1.0, 1.0, 2.0, 2.5]),
+                ((r1, p0), vec![]),
+            ]),
+            ..Default::default()
+        };
+
+        let cdf = result.cdf((r0, p0));
+        assert_eq!(
+            cdf,
+            vec![
+                (0.0, 0.25),
+                (0.5, 0.375),
+                (1.0, 0.75),
+                (2.0, 0.875),
+                (2.5, 1.0)
+            ]
+        );
+        assert_eq!(cdf.last().unwrap().1, 1.0);
+        assert!(result.cdf((r1, p0)).is_empty());
+        assert!(result.cdf((r1, Prefix::from(1))).is_empty());
+    }
+
+    #[test]
+    fn samples_remaining() {
+        let worst_case = crate::analyzer::hoeffding(0.95, 0.01);
+        let mut result = AnalysisResult::new(0.95, 0.01, 1000);
+        assert_eq!(result.samples_remaining(), worst_case - 1000);
+
+        // the observed variance is maximal, so the refined estimate is the worst-case bound
+        result.p_satisfied = 0.5;
+        assert_eq!(result.samples_remaining(), worst_case - 1000);
+
+        // the policies are almost always satisfied, so far fewer samples are needed
+        result.p_satisfied = 0.99;
+        let remaining = result.samples_remaining();
+        assert!(remaining > 0);
+        assert!(remaining * 4 < worst_case - 1000);
+        result.p_satisfied = 1.0;
+        let remaining_all_satisfied = result.samples_remaining();
+        assert!(remaining_all_satisfied > 0);
+        assert!(remaining_all_satisfied < remaining);
+
+        // observing the same probability over more samples tightens the variance bound, until no
+        // more samples are needed
+        result.n_samples = 2000;
+        assert_eq!(result.samples_remaining(), 0);
+
+        result.n_samples = worst_case;
+        assert_eq!(result.samples_remaining(), 0);
+    }
+
+    #[test]
+    fn samples_remaining_few_samples() {
+        let worst_case = crate::analyzer::hoeffding(0.95, 0.01);
+        // a handful of samples that all satisfy the policies (zero sample variance) does not
+        // justify stopping early.
+        for n_samples in [1, 2, 5, 10] {
+            let mut result = AnalysisResult::new(0.95, 0.01, n_samples);
+            result.p_satisfied = 1.0;
+            assert_eq!(result.samples_remaining(), worst_case - n_samples);
+            result.p_satisfied = 0.0;
+            assert_eq!(result.samples_remaining(), worst_case - n_samples);
+        }
+        // with 100 samples, the bound on the standard deviation is still loose
+        let mut result = AnalysisResult::new(0.95, 0.01, 100);
+        result.p_satisfied = 1.0;
+        assert!(result.samples_remaining() * 4 > worst_case);
+    }
+
+    #[test]
+    fn histogram() {
+        let (r0, r1, r2) = (RouterId::from(0), RouterId::from(1), RouterId::from(2));
+        let p0 = Prefix::from(0);
+        let result = AnalysisResult {
+            violation_time_distributions: BTreeMap::from([
+                ((r0, p0), vec![0.0, 0.0, 0.004, 0.009, 0.01, 0.0101, 0.03]),
+                ((r1, p0), vec![0.0, 0.0, 0.0]),
+                ((r2, p0), vec![]),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            result.histogram((r0, p0), 10.0),
+            vec![(0.0, 4), (0.01, 2), (0.03, 1)]
+        );
+        assert_eq!(
+            result.histogram((r0, p0), 5.0),
+            vec![(0.0, 3), (0.005, 1), (0.01, 2), (0.03, 1)]
+        );
+        assert_eq!(result.histogram((r1, p0), 10.0), vec![(0.0, 3)]);
+        assert!(result.histogram((r2, p0), 10.0).is_empty());
+        assert!(result.histogram((r0, Prefix::from(1)), 10.0).is_empty());
+
+        // invalid bin widths
+        for bin_width_ms in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(result.histogram((r0, p0), bin_width_ms).is_empty());
+            assert!(result.weighted_histogram((r0, p0), bin_width_ms).is_empty());
+        }
+
+        // without weights, the probability of a bin is the fraction of samples in it
+        let p = |count: usize| count as f64 / 7.0;
+        assert_eq!(
+            result.weighted_histogram((r0, p0), 10.0),
+            vec![(0.0, p(4)), (0.01, p(2)), (0.03, p(1))]
+        );
+        assert_eq!(result.weighted_histogram((r1, p0), 10.0), vec![(0.0, 1.0)]);
+        assert!(result.weighted_histogram((r2, p0), 10.0).is_empty());
+
+        // outliers do not allocate empty bins, and non-finite times are not binned
+        let result = AnalysisResult {
+            violation_time_distributions: BTreeMap::from([(
+                (r0, p0),
+                vec![0.0, 1e12, f64::INFINITY, f64::NAN],
+            )]),
+            ..Default::default()
+        };
+        assert_eq!(result.histogram((r0, p0), 1.0), vec![(0.0, 1), (1e12, 1)]);
+        assert_eq!(
+            result.weighted_histogram((r0, p0), 1.0),
+            vec![(0.0, 0.25), (1e12, 0.25)]
+        );
+    }
+
+    #[test]
+    fn percentiles() {
+        let sorted = (0..10).map(f64::from).collect::<Vec<_>>();
+        assert_eq!(percentile(&sorted, 0), Some(0.0));
+        assert_eq!(percentile(&sorted, 50), Some(5.0));
+        assert_eq!(percentile(&sorted, 99), Some(9.0));
+        assert_eq!(percentile(&sorted, 100), Some(9.0));
+        assert_eq!(percentile(&[], 50), None);
+
+        let (r0, r1, p0) = (RouterId::from(0), RouterId::from(1), Prefix::from(0));
+        let mut result = AnalysisResult::default();
+        assert_eq!(result.percentiles(&[0, 50, 100]), None);
+        result.violation_time_distributions = BTreeMap::from([((r0, p0), vec![])]);
+        assert_eq!(result.percentiles(&[0, 50, 100]), None);
+
+        result.violation_time_distributions =
+            BTreeMap::from([((r0, p0), vec![1.0, 3.0]), ((r1, p0), vec![0.0, 2.0])]);
+        assert_eq!(result.pooled_violation_times(), vec![0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(result.percentiles(&[0, 50, 100]), Some(vec![0.0, 2.0, 3.0]));
+    }
+
+    #[test]
+    fn weighted_statistics() {
+        let (r0, r1, p0) = (RouterId::from(0), RouterId::from(1), Prefix::from(0));
+        let mut result = AnalysisResult {
+            violation_time_distributions: BTreeMap::from([
+                ((r0, p0), vec![0.0, 0.001, 0.012, 0.02]),
+                ((r1, p0), vec![0.0, 0.0, 0.0, 0.0]),
+            ]),
+            violation_time_weights: BTreeMap::from([
+                ((r0, p0), vec![0.5, 0.25, 0.125, 0.125]),
+                ((r1, p0), vec![0.25; 4]),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            result.cdf((r0, p0)),
+            vec![(0.0, 0.5), (0.001, 0.75), (0.012, 0.875), (0.02, 1.0)]
+        );
+        assert_eq!(
+            result.weighted_histogram((r0, p0), 10.0),
+            vec![(0.0, 0.75), (0.01, 0.125), (0.02, 0.125)]
+        );
+        assert_eq!(
+            result.histogram((r0, p0), 10.0),
+            vec![(0.0, 2), (0.01, 1), (0.02, 1)]
+        );
+        // both policies have the same number of samples, so each contributes half of the weight
+        let pooled = result.pooled_weighted_violation_times();
+        assert_eq!(pooled.len(), 8);
+        let weight_at =
+            |t: f64| -> f64 { pooled.iter().filter(|(x, _)| *x == t).map(|(_, w)| w).sum() };
+        assert_eq!(weight_at(0.0), 0.75);
+        assert_eq!(weight_at(0.001), 0.125);
+        assert_eq!(weight_at(0.02), 0.0625);
+        assert_eq!(
+            result.percentiles(&[0, 70, 80, 95, 100]),
+            Some(vec![0.0, 0.0, 0.001, 0.02, 0.02])
+        );
+        assert!((result.mean_violation_time().unwrap() - 0.002125).abs() < 1e-12);
+
+        // a sample with a large weight is not an outlier, even though it is one unweighted
+        let samples = [0.0, 0.001, 0.002, 0.5];
+        assert_eq!(
+            reject_outliers(&samples, OutlierStrategy::Iqr(1.5)).1,
+            vec![3]
+        );
+        let weights = [0.5, 0.125, 0.125, 0.25];
+        let (kept, rejected) =
+            reject_weighted_outliers(&samples, &weights, OutlierStrategy::Iqr(1.5));
+        assert!(rejected.is_empty());
+        assert_eq!(kept, samples.to_vec());
+
+        // the weights of the rejected samples are dropped, and the others normalized again
+        result.violation_time_distributions = BTreeMap::from([((r0, p0), samples.to_vec())]);
+        result.violation_time_weights =
+            BTreeMap::from([((r0, p0), vec![0.625, 0.125, 0.125, 0.125])]);
+        let (filtered, report) = result.reject_outliers(OutlierStrategy::Iqr(1.5));
+        assert_eq!(report.simulated, HashMap::from([((r0, p0), vec![3])]));
+        assert_eq!(
+            filtered.violation_time_distributions[&(r0, p0)],
+            vec![0.0, 0.001, 0.002]
+        );
+        let weights = &filtered.violation_time_weights[&(r0, p0)];
+        assert_eq!(weights.len(), 3);
+        assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!((weights[0] - 0.625 / 0.875).abs() < 1e-12);
+
+        // with equal weights, the weighted percentile is the nearest-rank percentile
+        let sorted = (0..10).map(|x| (f64::from(x), 0.1)).collect::<Vec<_>>();
+        assert_eq!(weighted_percentile(&sorted, 0), Some(0.0));
+        assert_eq!(weighted_percentile(&sorted, 55), Some(5.0));
+        assert_eq!(weighted_percentile(&sorted, 100), Some(9.0));
+        assert_eq!(weighted_percentile(&[], 50), None);
+    }
+
+    #[test]
+    fn reject_outliers_iqr() {
+        let samples = [1.0, 1.2, 0.9, 1.1, 25.0, 1.0, 0.8, 1.3];
+        let (kept, rejected) = reject_outliers(&samples, OutlierStrategy::Iqr(1.5));
+        assert_eq!(rejected, vec![4]);
+        assert_eq!(kept, vec![1.0, 1.2, 0.9, 1.1, 1.0, 0.8, 1.3]);
+
+        // without outliers, nothing is rejected
+        let (kept, rejected) = reject_outliers(&kept, OutlierStrategy::Iqr(1.5));
+        assert!(rejected.is_empty());
+        assert_eq!(kept.len(), 7);
+        assert_eq!(
+            reject_outliers(&[], OutlierStrategy::StdDev(3.0)),
+            (vec![], vec![])
+        );
+
+        // the rejections are reported per distribution
+        let (r0, r1, p0) = (RouterId::from(0), RouterId::from(1), Prefix::from(0));
+        let result = AnalysisResult {
+            p_satisfied: 0.5,
+            n_samples: 8,
+            violation_time_distributions: BTreeMap::from([
+                ((r0, p0), samples.to_vec()),
+                ((r1, p0), vec![0.0; 4]),
+            ]),
+            cisco_violation_time_distributions: BTreeMap::from([((r0, p0), samples.to_vec())]),
+            ..Default::default()
+        };
+        let (filtered, report) = result.reject_outliers(OutlierStrategy::Iqr(1.5));
+        assert_eq!(report.simulated, HashMap::from([((r0, p0), vec![4])]));
+        assert_eq!(report.measured, HashMap::from([((r0, p0), vec![4])]));
+        assert_eq!(report.num_rejected(), 2);
+        assert_eq!(filtered.violation_time_distributions[&(r0, p0)].len(), 7);
+        assert_eq!(filtered.violation_time_distributions[&(r1, p0)].len(), 4);
+        assert_eq!(
+            filtered.cisco_violation_time_distributions[&(r0, p0)].len(),
+            7
+        );
+        // the aggregates still describe all samples before filtering
+        assert_eq!(filtered.p_satisfied, 0.5);
+        assert_eq!(filtered.n_samples, 8);
+    }
+
+    #[test]
+    fn new_is_not_analyzed() {
+        let result = AnalysisResult::new(0.95, 0.01, 100);
+        assert!(!result.is_analyzed());
+        assert_eq!(result.confidence, 0.95);
+        assert_eq!(result.precision, 0.01);
+        assert_eq!(result.n_samples, 100);
+        assert!(result.violation_time_distributions.is_empty());
+
+        let result = AnalysisResult {
+            p_satisfied: 0.0,
+            ..result
+        };
+        assert!(result.is_analyzed());
+    }
+
+    #[test]
+    fn worst_traces_bounded() {
+        let retained = |total_violation_time: f64| RetainedTrace {
+            total_violation_time,
+            violation_times: vec![total_violation_time],
+            eq_class: 0,
+            trace: Default::default(),
+        };
+
+        let mut worst = WorstTraces::new(3);
+        for t in [0.5, 2.0, 0.0, 1.0, 3.0, 0.1] {
+            worst.push(retained(t));
+        }
+        let totals: Vec<f64> = worst
+            .into_sorted_vec()
+            .iter()
+            .map(|trace| trace.total_violation_time)
+            .collect();
+        assert_eq!(totals, vec![3.0, 2.0, 1.0]);
+
+        let mut none = WorstTraces::new(0);
+        none.push(retained(1.0));
+        assert!(none.into_sorted_vec().is_empty());
+    }
+}
@@ -0,0 +1,262 @@
+// TRIX: Inference of Transient Violation Times from Logged Routing Events or Collected BGP Messages
+// Copyright (C) 2024-2025 Roland Schmid <roschmi@ethz.ch> and Tibor Schneider <sctibor@ethz.ch>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//! File-backed store of the violation times of all samples, grouped by equivalence class, for
+//! sample counts whose violation times do not fit into memory.
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+/// Number of samples after which each worker of `Analyzer::analyze` appends its violation times to
+/// the `StatsStore`, bounding the memory used by each worker.
+pub const STATS_STORE_FLUSH_INTERVAL: usize = 10_000;
+
+/// Name of the file identifying the policies of the samples in a `StatsStore`.
+const HEADER_FILE: &str = "header.json";
+
+/// Directory containing one file per equivalence class, named `{eq_class:016x}.csv`. Each line of a
+/// file holds the comma-separated violation times of a single sample, at the index of the
+/// corresponding policy in `Analyzer::policies`. The violation times of samples with an importance
+/// weight other than 1 are prefixed by the weight and a semicolon.
+///
+/// Samples are only ever appended, such that an analysis can be resumed by analyzing again with a
+/// store that already contains the samples of previous runs. The file `header.json` identifies the
+/// policies the samples were computed for (see `check_header`), such that samples of different
+/// policies are never mixed.
+///
+/// The store bounds the memory used while sampling. `Analyzer::analyze` streams the stored samples
+/// to aggregate them, but still collects the violation time distributions of all samples in its
+/// result, which requires 8 bytes per policy and sample.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsStore {
+    dir: PathBuf,
+}
+
+impl StatsStore {
+    /// Open the store in `dir`, creating the directory if it does not exist yet.
+    pub fn new(dir: impl AsRef<Path>) -> io::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Directory of the store.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Check that the samples in the store were computed for the policies described by `header`,
+    /// e.g., the serialized `Analyzer::policies`. A store without a header adopts `header`, unless
+    /// it already contains samples. Returns an `InvalidData` error if the store belongs to other
+    /// policies, as its violation times would be associated with the wrong policies otherwise.
+    pub fn check_header(&self, header: &str) -> io::Result<()> {
+        let path = self.dir.join(HEADER_FILE);
+        match fs::read_to_string(&path) {
+            Ok(stored) if stored == header => Ok(()),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{:?} contains samples of other policies", self.dir),
+            )),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                if !self.classes()?.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{:?} contains samples of unknown policies", self.dir),
+                    ));
+                }
+                fs::write(path, header)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn class_file(&self, eq_class: u64) -> PathBuf {
+        self.dir.join(format!("{eq_class:016x}.csv"))
+    }
+
+    /// Append the violation times of the given samples to the file of `eq_class`.
+    pub fn append(&self, eq_class: u64, samples: &[Vec<f64>]) -> io::Result<()> {
+        self.append_lines(eq_class, samples.iter().map(|s| (1.0, s.as_slice())))
+    }
+
+    /// Append the violation times of the given samples to the file of `eq_class`, together with
+    /// the importance weight of each sample.
+    pub fn append_weighted(&self, eq_class: u64, samples: &[(f64, Vec<f64>)]) -> io::Result<()> {
+        self.append_lines(eq_class, samples.iter().map(|(w, s)| (*w, s.as_slice())))
+    }
+
+    fn append_lines<'a>(
+        &self,
+        eq_class: u64,
+        samples: impl Iterator<Item = (f64, &'a [f64])>,
+    ) -> io::Result<()> {
+        let mut file = io::BufWriter::new(
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.class_file(eq_class))?,
+        );
+        for (weight, violation_times) in samples {
+            let line = violation_times
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>();
+            if weight != 1.0 {
+                write!(file, "{weight};")?;
+            }
+            writeln!(file, "{}", line.join(","))?;
+        }
+        file.flush()
+    }
+
+    /// Get all equivalence classes with at least one sample, in ascending order.
+    pub fn classes(&self) -> io::Result<Vec<u64>> {
+        let mut classes = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "csv") {
+                if let Some(eq_class) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| u64::from_str_radix(stem, 16).ok())
+                {
+                    classes.push(eq_class);
+                }
+            }
+        }
+        classes.sort();
+        Ok(classes)
+    }
+
+    /// Call `f` with the equivalence class and the violation times of every stored sample, reading
+    /// one sample at a time.
+    pub fn for_each_sample<F>(&self, mut f: F) -> io::Result<()>
+    where
+        F: FnMut(u64, &[f64]),
+    {
+        self.for_each_weighted_sample(|eq_class, _, violation_times| f(eq_class, violation_times))
+    }
+
+    /// Same as `for_each_sample`, but also passing the importance weight of each sample, which is 1
+    /// for samples appended without a weight.
+    pub fn for_each_weighted_sample<F>(&self, mut f: F) -> io::Result<()>
+    where
+        F: FnMut(u64, f64, &[f64]),
+    {
+        let parse = |t: &str| {
+            t.parse::<f64>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        };
+        for eq_class in self.classes()? {
+            let file = BufReader::new(fs::File::open(self.class_file(eq_class))?);
+            for line in file.lines() {
+                let line = line?;
+                let (weight, line) = match line.split_once(';') {
+                    Some((weight, line)) => (parse(weight)?, line),
+                    None => (1.0, line.as_str()),
+                };
+                let violation_times = line
+                    .split(',')
+                    .filter(|t| !t.is_empty())
+                    .map(parse)
+                    .collect::<io::Result<Vec<f64>>>()?;
+                f(eq_class, weight, &violation_times);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn append_and_stream() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let store = StatsStore::new(&dir).unwrap();
+        store.append(7, &[vec![0.0, 1.5]]).unwrap();
+        store.append(3, &[vec![0.1, 0.0], vec![0.0, 0.0]]).unwrap();
+        // appending to an existing class keeps the previous samples
+        store.append(7, &[vec![2.0, 1e-7]]).unwrap();
+        fs::write(dir.join("unrelated.txt"), "ignored").unwrap();
+
+        assert_eq!(store.classes().unwrap(), vec![3, 7]);
+        let mut samples = Vec::new();
+        store
+            .for_each_sample(|eq_class, violation_times| {
+                samples.push((eq_class, violation_times.to_vec()))
+            })
+            .unwrap();
+        assert_eq!(
+            samples,
+            vec![
+                (3, vec![0.1, 0.0]),
+                (3, vec![0.0, 0.0]),
+                (7, vec![0.0, 1.5]),
+                (7, vec![2.0, 1e-7]),
+            ]
+        );
+    }
+
+    #[test]
+    fn header() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let store = StatsStore::new(&dir).unwrap();
+        store.check_header("[\"a\"]").unwrap();
+        store.append(1, &[vec![0.5]]).unwrap();
+        // the header is not an equivalence class
+        assert_eq!(store.classes().unwrap(), vec![1]);
+
+        // reopening the store with the same policies resumes it
+        let store = StatsStore::new(&dir).unwrap();
+        store.check_header("[\"a\"]").unwrap();
+        let err = store.check_header("[\"b\"]").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // samples without a header cannot be attributed to any policies
+        fs::remove_file(dir.join(HEADER_FILE)).unwrap();
+        let err = store.check_header("[\"a\"]").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn weighted_samples() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let store = StatsStore::new(&dir).unwrap();
+        store.append(1, &[vec![0.5]]).unwrap();
+        store
+            .append_weighted(1, &[(0.25, vec![1.5]), (1.0, vec![0.0])])
+            .unwrap();
+
+        let mut samples = Vec::new();
+        store
+            .for_each_weighted_sample(|_, weight, violation_times| {
+                samples.push((weight, violation_times.to_vec()))
+            })
+            .unwrap();
+        assert_eq!(
+            samples,
+            vec![(1.0, vec![0.5]), (0.25, vec![1.5]), (1.0, vec![0.0])]
+        );
+    }
+}
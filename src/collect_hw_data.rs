@@ -18,7 +18,7 @@ use std::path::PathBuf;
 use clap::Parser;
 use tokio::process::Command;
 
-use trix::{experiments::*, util};
+use trix::{analyzer::IperfLoad, experiments::*, util};
 use trix_utils::other::send_slack_notification;
 
 #[derive(Parser, Debug)]
@@ -49,6 +49,16 @@ struct Args {
     /// Select the number of samples run.
     #[arg(short, long, default_value_t = 1000)]
     prober_frequency: u64,
+    /// Number of parallel iperf clients (~1Gbps each) generating background traffic. Set to 0 to
+    /// disable background traffic.
+    #[arg(long, default_value_t = 1)]
+    iperf_clients: usize,
+    /// Generate UDP instead of TCP background traffic.
+    #[arg(long)]
+    iperf_udp: bool,
+    /// Store the hardware mapping of each sample gzipped.
+    #[arg(long)]
+    compress_hardware_mapping: bool,
 }
 
 #[tokio::main]
@@ -74,6 +84,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             delays: _,
             static_routing_inputs: _,
             event: _,
+            confidence: _,
+            precision: _,
         },
         mut analyzer,
     ) in iterate_experiments(filter)
@@ -91,6 +103,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         data_path.push(&scenario_name);
         std::fs::create_dir_all(&data_path)?;
 
+        analyzer.set_iperf_load(IperfLoad {
+            num_clients: args.iperf_clients,
+            udp: args.iperf_udp,
+        });
+        analyzer.set_compress_hardware_mapping(args.compress_hardware_mapping);
+
         // attempt to clean up better before loading new topology / scenario
         log::debug!("cleanup exabgp...");
         log::trace!(
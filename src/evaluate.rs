@@ -29,19 +29,16 @@ use itertools::Itertools;
 use rand::thread_rng;
 use rand_distr::{Distribution, Normal};
 
-use bgpsim::{
-    formatter::NetworkFormatter, forwarding_state::ForwardingState, policies::Policy,
-    types::RouterId,
-};
+use bgpsim::{formatter::NetworkFormatter, forwarding_state::ForwardingState, types::RouterId};
 
 use trix::{
-    analyzer::{Analyzer, CiscoAnalyzerData},
+    analyzer::{compare::records_to_trace, Analyzer},
     experiments::Filter,
-    records::{EvaluationRecord, FWRecord, PathRecord, Router},
+    records::{EvaluationRecord, FWRecord, IntervalsMetadata, PathRecord, Router},
     timing_model::TimingModel,
     transient_specification::{
         check_path_updates, compute_baseline, compute_violation_times, EvaluationError,
-        TransientPolicy,
+        TotalDuration, TransientPolicy,
     },
     util::{self, PathBufExt},
     MultiPrefixConvergenceTrace, Prefix,
@@ -113,76 +110,24 @@ fn sample_normal_with_bound(bound: f64) -> f64 {
 
 /// Build a time series of forwarding updates from a csv of `FWRecord`s.
 fn build_trace_from_fw_updates(
-    analyzer: &Analyzer<TimingModel<Prefix>>,
     event_start: f64,
     prefixes: &HashSet<Prefix>,
     fw_updates_path: impl AsRef<Path>,
-    fw_state: &mut ForwardingState<Prefix>,
+    fw_state: &ForwardingState<Prefix>,
 ) -> Result<MultiPrefixConvergenceTrace, EvaluationError> {
     let mut rdr = csv::ReaderBuilder::new()
         .from_path(fw_updates_path.as_ref())
         .map_err(|_| EvaluationError::NoData)?;
-    // FwDelta = (rid: RouterId, old_nhs: Vec<RouterId>, new_nhs: Vec<RouterId>)
-    // prefix -> Vec<(Vec<FwDelta>, AlwaysEq<Option<f64>>)>
-    let mut trace = MultiPrefixConvergenceTrace::new();
 
-    // sort FW updates just in case...
     let records = rdr
         .deserialize()
         .collect::<Result<Vec<FWRecord>, _>>()
-        .unwrap();
-
-    for record in records
+        .unwrap()
         .into_iter()
         .filter(|r| prefixes.contains(&r.prefix.into()))
-        .sorted_by(|a, b| a.time.total_cmp(&b.time))
-    {
-        if record.time < event_start - 1.0 {
-            log::trace!("skipping pre-event changes: {record:?}");
-            continue;
-        }
-
-        let prefix = Prefix::from(record.prefix);
-        let prefix_trace = trace.entry(prefix).or_default();
-
-        // fix event start if necessary
-        if let Some(first) = prefix_trace.first() {
-            let t = first.1.into_inner().unwrap();
-            if record.time < t && t == event_start {
-                prefix_trace[0].1 = Some(record.time).into()
-            }
-        }
+        .collect_vec();
 
-        // for all other updates, ensure we are going in chronological order
-        if let Some(last) = prefix_trace.last() {
-            assert!(record.time >= last.1.into_inner().unwrap());
-        }
-
-        let old_nhs = prefix_trace
-            .iter()
-            .rev()
-            // check if nhs have been changed already
-            .find_map(|(fw_deltas, _time)| {
-                fw_deltas.iter().find_map(|(rid, _old_nhs, new_nhs)| {
-                    (*rid == record.src).then_some(new_nhs.clone())
-                })
-            })
-            // or query the original fw_state
-            .unwrap_or(fw_state.get_next_hops(record.src, prefix).to_vec());
-        let new_nhs: Vec<_> = record.next_hop.into_iter().collect();
-        if old_nhs == new_nhs {
-            log::trace!(
-                "skipping because old nhs {} == new nhs {} at router {}",
-                old_nhs.fmt(&analyzer.original_net),
-                new_nhs.fmt(&analyzer.original_net),
-                record.src.fmt(&analyzer.original_net),
-            );
-            continue;
-        }
-        let fw_delta = (record.src, old_nhs, new_nhs);
-
-        prefix_trace.push((vec![fw_delta], Some(record.time).into()));
-    }
+    let trace = records_to_trace(fw_state, event_start, &records);
 
     Ok(trace)
 }
@@ -192,6 +137,7 @@ fn build_trace_from_fw_updates(
 #[allow(clippy::type_complexity)]
 fn evaluate_time_series_of_fw_updates(
     analyzer: &Analyzer<TimingModel<Prefix>>,
+    scenario_name: &str,
     event_start: f64,
     evaluated_prefixes: &HashSet<Prefix>,
     transient_policies: &HashMap<(RouterId, Prefix), Vec<TransientPolicy>>,
@@ -205,13 +151,8 @@ fn evaluate_time_series_of_fw_updates(
     let mut fw_state = analyzer.original_fw.clone();
 
     // read the time series of forwarding states from the csv
-    let trace = build_trace_from_fw_updates(
-        analyzer,
-        event_start,
-        evaluated_prefixes,
-        &fw_updates_path,
-        &mut fw_state,
-    )?;
+    let trace =
+        build_trace_from_fw_updates(event_start, evaluated_prefixes, &fw_updates_path, &fw_state)?;
 
     let mut queue = analyzer.build_queue();
 
@@ -230,6 +171,21 @@ fn evaluate_time_series_of_fw_updates(
             path
         });
 
+    // make the intervals self-describing when sharing them
+    if let Some(intervals_csv_path) = &intervals_csv_path {
+        IntervalsMetadata {
+            scenario: scenario_name.to_string(),
+            event: analyzer.event.clone(),
+            timing_model: if analyzer.geo_location.is_some() {
+                "geo_location"
+            } else {
+                "delays"
+            }
+            .to_string(),
+        }
+        .write(intervals_csv_path)?;
+    }
+
     Ok((
         // call the baseline algorithm
         compute_baseline(
@@ -238,7 +194,7 @@ fn evaluate_time_series_of_fw_updates(
             &mut fw_state,
             &trace,
             transient_policies,
-        ),
+        )?,
         // call the interval algorithm
         compute_violation_times(
             &analyzer.original_net,
@@ -247,7 +203,9 @@ fn evaluate_time_series_of_fw_updates(
             &trace,
             transient_policies,
             intervals_csv_path,
-        ),
+            &TotalDuration,
+        )?
+        .times,
     ))
 }
 
@@ -264,8 +222,7 @@ fn evaluate_fw_updates(
         return; // `return;` in a `for_each(...)` loop is equivalent to `continue;`
     };
 
-    for record in util::get_records(eval_path).unwrap().deserialize() {
-        let record: CiscoAnalyzerData = record.unwrap();
+    for record in util::get_records(eval_path).unwrap() {
         log::trace!("Reading from CSV:\n{record:#?}");
 
         if !record.execution_timestamp.contains(sample_id) {
@@ -363,16 +320,10 @@ fn evaluate_fw_updates(
 
         // reduce considered transient policies to the probed prefixes
         let transient_policies: HashMap<(RouterId, Prefix), Vec<TransientPolicy>> = analyzer
-            .policies
+            .grouped_policies()
             .iter()
-            .cloned()
-            .into_group_map_by(|p| (p.router(), p.prefix()))
-            .into_iter()
-            .filter_map(|(k, v)| match k {
-                (Some(r), Some(p)) => Some(((r, p), v)),
-                _ => None,
-            })
             .filter(|((_, p), _)| evaluated_prefixes.contains(p))
+            .map(|(k, v)| (*k, v.clone()))
             .collect();
 
         log::debug!("Computing the ground truth from {path_updates_path:?}");
@@ -397,6 +348,12 @@ fn evaluate_fw_updates(
                 log::warn!("skipping sample {topo_name}/{scenario_name} -> {} due to missing data in {path_updates_path:?}", record.pcap_filename);
                 continue;
             }
+            Err(
+                e @ (EvaluationError::NegativeDuration(..) | EvaluationError::LoadBalancing(..)),
+            ) => {
+                log::warn!("skipping sample {topo_name}/{scenario_name} -> {} due to invalid data in {path_updates_path:?}: {e}", record.pcap_filename);
+                continue;
+            }
         };
 
         // load each time series of fw states and write `EvaluationRecord`s to a new csv
@@ -459,6 +416,7 @@ fn evaluate_fw_updates(
             let Ok((baseline_violations, interval_alg_violations)) =
                 evaluate_time_series_of_fw_updates(
                     &analyzer,
+                    scenario_name,
                     record.event_start,
                     &evaluated_prefixes,
                     &transient_policies,
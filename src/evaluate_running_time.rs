@@ -25,7 +25,7 @@ use std::{
 use anyhow::{Context, Result};
 use trix::{
     prelude::{TimingModel, TransientPolicy},
-    transient_specification::{compute_baseline, compute_violation_times},
+    transient_specification::{compute_baseline, compute_violation_times, TotalDuration},
     MultiPrefixConvergenceTrace,
 };
 use bgpsim::{
@@ -173,24 +173,27 @@ fn process_scenario<W: std::io::Write>(
 
     // analyze the trace
     let start = Instant::now();
-    let _ = compute_violation_times(
+    compute_violation_times(
         &initial_net,
         &mut queue,
         &mut fw_state.clone(),
         &trace,
         &transient_policies,
         None::<&Path>,
-    );
+        &TotalDuration,
+    )
+    .context("Cannot compute the violation times")?;
     let time_alg = start.elapsed().as_secs_f64();
 
     let start = Instant::now();
-    let _ = compute_baseline(
+    compute_baseline(
         &initial_net,
         &mut queue,
         &mut fw_state.clone(),
         &trace,
         &transient_policies,
-    );
+    )
+    .context("Cannot compute the baseline")?;
     let time_baseline = start.elapsed().as_secs_f64();
 
     let record = Record {
@@ -13,7 +13,7 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
-use std::{hash::Hash, time::Duration};
+use std::{collections::HashMap, hash::Hash, net::Ipv4Addr, time::Duration};
 
 use serde::{Deserialize, Serialize};
 
@@ -27,7 +27,16 @@ use bgpsim::{
 use router_lab::{Active, RouterLab, Inactive};
 use itertools::Itertools;
 
-use crate::{routing_inputs::RoutingInputs, Prefix};
+use crate::{
+    routing_inputs::{RoutingInputs, UpdateOrdering},
+    timing_model::DelayableQueue,
+    Prefix,
+};
+
+/// Error returned when an event cannot be executed on the routing testbed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{0} is not supported yet on the RouterLab!")]
+pub struct UnsupportedOnRouterLab(pub &'static str);
 
 /// The prepared event to be executed.
 ///
@@ -48,6 +57,44 @@ pub enum AnalyzerEvent<R> {
     PhysicalExternalWithdrawRoutingInputs(RoutingInputs<R>),
     PhysicalExternalUpdateBetterRoutingInputs(RoutingInputs<R>),
     PhysicalExternalUpdateWorseRoutingInputs(RoutingInputs<R>),
+    /// Withdraw the routing inputs and re-announce them `gap_ms` milliseconds later.
+    Flap {
+        inputs: RoutingInputs<R>,
+        gap_ms: f64,
+    },
+    /// Set the OSPF weight of the link from `a` to `b` to `weight`, affecting the given prefixes.
+    SetLinkWeight {
+        prefixes: Vec<Prefix>,
+        a: R,
+        b: R,
+        weight: LinkWeight,
+    },
+    /// Re-advertise the route of `external` for `prefix` with its own AS prepended `times` more
+    /// often to the AS path.
+    PrependAsPath {
+        external: R,
+        prefix: Prefix,
+        times: usize,
+    },
+    /// Re-advertise the route of `external` for `prefix` with up to `times` repetitions of its own
+    /// AS removed from the front of the AS path, i.e., undo a previous prepending. The first
+    /// occurrence of the AS is always kept.
+    TrimAsPath {
+        external: R,
+        prefix: Prefix,
+        times: usize,
+    },
+    /// Remove the link between `a` and `b` at `down_at`, and add it again with its original link
+    /// weights at `up_at`, both in seconds after the event. Both the failover and the failback
+    /// appear in the same trace (see `AnalyzerEvent::timed_changes`). Negative times are treated
+    /// as zero, and the link comes back up no earlier than it went down. The link may carry the
+    /// traffic of any prefix, so the event does not name the affected prefixes.
+    LinkFlap {
+        a: R,
+        b: R,
+        down_at: f64,
+        up_at: f64,
+    },
 }
 
 impl<R> AnalyzerEvent<R> {
@@ -60,13 +107,17 @@ impl<R> AnalyzerEvent<R> {
             | Self::AnnounceRoute(prefixes, _, _)
             | Self::WithdrawRoute(prefixes, _, _)
             | Self::PhysicalExternalAnnounceRoute(prefixes, _, _)
-            | Self::PhysicalExternalWithdrawRoute(prefixes, _, _) => prefixes.clone(),
+            | Self::PhysicalExternalWithdrawRoute(prefixes, _, _)
+            | Self::SetLinkWeight { prefixes, .. } => prefixes.clone(),
+            Self::LinkFlap { .. } => Vec::new(),
+            Self::PrependAsPath { prefix, .. } | Self::TrimAsPath { prefix, .. } => vec![*prefix],
             Self::AnnounceRoutingInputs(inputs)
             | Self::WithdrawRoutingInputs(inputs)
             | Self::PhysicalExternalAnnounceRoutingInputs(inputs)
             | Self::PhysicalExternalWithdrawRoutingInputs(inputs)
             | Self::PhysicalExternalUpdateBetterRoutingInputs(inputs)
-            | Self::PhysicalExternalUpdateWorseRoutingInputs(inputs) => inputs.get_prefixes(),
+            | Self::PhysicalExternalUpdateWorseRoutingInputs(inputs)
+            | Self::Flap { inputs, .. } => inputs.get_prefixes(),
         }
     }
 }
@@ -80,7 +131,11 @@ where
         match self {
             Self::AddLink(_prefixes, _, _, _, _)
             | Self::RemoveLink(_prefixes, _, _)
-            | Self::LowerLocalPref(_prefixes, _, _) => Default::default(),
+            | Self::LowerLocalPref(_prefixes, _, _)
+            | Self::SetLinkWeight { .. }
+            | Self::LinkFlap { .. }
+            | Self::PrependAsPath { .. }
+            | Self::TrimAsPath { .. } => Default::default(),
             Self::AnnounceRoute(_, r, path)
             | Self::WithdrawRoute(_, r, path)
             | Self::PhysicalExternalAnnounceRoute(_, r, path)
@@ -92,7 +147,8 @@ where
             | Self::PhysicalExternalAnnounceRoutingInputs(inputs)
             | Self::PhysicalExternalWithdrawRoutingInputs(inputs)
             | Self::PhysicalExternalUpdateBetterRoutingInputs(inputs)
-            | Self::PhysicalExternalUpdateWorseRoutingInputs(inputs) => inputs.external_routers(),
+            | Self::PhysicalExternalUpdateWorseRoutingInputs(inputs)
+            | Self::Flap { inputs, .. } => inputs.external_routers(),
         }
     }
 }
@@ -176,6 +232,50 @@ where
             AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(i) => {
                 AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(i.build(net)?)
             }
+            AnalyzerEvent::Flap { inputs, gap_ms } => AnalyzerEvent::Flap {
+                inputs: inputs.build(net)?,
+                gap_ms,
+            },
+            AnalyzerEvent::SetLinkWeight {
+                prefixes,
+                a,
+                b,
+                weight,
+            } => AnalyzerEvent::SetLinkWeight {
+                prefixes,
+                a: net.get_router_id(a)?,
+                b: net.get_router_id(b)?,
+                weight,
+            },
+            AnalyzerEvent::PrependAsPath {
+                external,
+                prefix,
+                times,
+            } => AnalyzerEvent::PrependAsPath {
+                external: net.get_router_id(external)?,
+                prefix,
+                times,
+            },
+            AnalyzerEvent::TrimAsPath {
+                external,
+                prefix,
+                times,
+            } => AnalyzerEvent::TrimAsPath {
+                external: net.get_router_id(external)?,
+                prefix,
+                times,
+            },
+            AnalyzerEvent::LinkFlap {
+                a,
+                b,
+                down_at,
+                up_at,
+            } => AnalyzerEvent::LinkFlap {
+                a: net.get_router_id(a)?,
+                b: net.get_router_id(b)?,
+                down_at,
+                up_at,
+            },
         })
     }
 }
@@ -220,12 +320,18 @@ impl AnalyzerEvent<RouterId> {
         lab: &mut RouterLab<'_, Prefix, Q, Ospf, Active>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match self {
-            Self::AddLink(_, _, _, _, _) => {
-                unimplemented!("AddLink not supported yet on the RouterLab!")
-            }
+            Self::AddLink(_, _, _, _, _) => return Err(UnsupportedOnRouterLab("AddLink").into()),
             Self::RemoveLink(_, _, _) => {}
             Self::LowerLocalPref(_, _, _) => {
-                unimplemented!("LowerLocalPref not supported yet on the RouterLab!")
+                return Err(UnsupportedOnRouterLab("LowerLocalPref").into())
+            }
+            Self::Flap { .. } => return Err(UnsupportedOnRouterLab("Flap").into()),
+            Self::LinkFlap { .. } => return Err(UnsupportedOnRouterLab("LinkFlap").into()),
+            Self::SetLinkWeight { .. } => {
+                return Err(UnsupportedOnRouterLab("SetLinkWeight").into())
+            }
+            Self::PrependAsPath { .. } | Self::TrimAsPath { .. } => {
+                return Err(UnsupportedOnRouterLab("Changing the AS path").into())
             }
             Self::AnnounceRoute(_, _, _)
             | Self::WithdrawRoute(_, _, _)
@@ -247,7 +353,21 @@ impl AnalyzerEvent<RouterId> {
     /// Triggers the specified event on the bgpsim simulator.
     pub fn trigger<Q>(&self, net: &mut Network<Prefix, Q>) -> Result<(), NetworkError>
     where
-        Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
+        Q: EventQueue<Prefix> + DelayableQueue + Clone + Send + Sync + std::fmt::Debug + PartialEq,
+        Q::Priority: Default + FmtPriority + Clone,
+    {
+        self.trigger_ordered(net, UpdateOrdering::default())
+    }
+
+    /// Triggers the specified event on the bgpsim simulator, scheduling the route updates of
+    /// events based on `RoutingInputs` according to `ordering`.
+    pub fn trigger_ordered<Q>(
+        &self,
+        net: &mut Network<Prefix, Q>,
+        ordering: UpdateOrdering,
+    ) -> Result<(), NetworkError>
+    where
+        Q: EventQueue<Prefix> + DelayableQueue + Clone + Send + Sync + std::fmt::Debug + PartialEq,
         Q::Priority: Default + FmtPriority + Clone,
     {
         match self {
@@ -259,6 +379,11 @@ impl AnalyzerEvent<RouterId> {
             Self::RemoveLink(_, a, b) => {
                 net.remove_link(*a, *b)?;
             }
+            // both transitions of the link happen later, see `timed_changes`
+            Self::LinkFlap { .. } => {}
+            Self::SetLinkWeight { a, b, weight, .. } => {
+                net.set_link_weight(*a, *b, *weight)?;
+            }
             Self::LowerLocalPref(_, r, ext) => {
                 net.set_bgp_route_map(
                     *r,
@@ -293,62 +418,167 @@ impl AnalyzerEvent<RouterId> {
             | Self::PhysicalExternalAnnounceRoutingInputs(inputs)
             | Self::PhysicalExternalUpdateBetterRoutingInputs(inputs)
             | Self::PhysicalExternalUpdateWorseRoutingInputs(inputs) => {
-                inputs.advertise_to(net);
+                inputs.advertise_to_ordered(net, ordering);
             }
             Self::WithdrawRoutingInputs(inputs)
             | Self::PhysicalExternalWithdrawRoutingInputs(inputs) => {
-                inputs.retract_from(net);
+                inputs.retract_from_ordered(net, ordering);
+            }
+            Self::PrependAsPath {
+                external,
+                prefix,
+                times,
+            } => readvertise_with_as_path(net, *external, *prefix, |path| {
+                prepend_as_path(path, *times)
+            })?,
+            Self::TrimAsPath {
+                external,
+                prefix,
+                times,
+            } => readvertise_with_as_path(net, *external, *prefix, |path| {
+                trim_as_path(path, *times)
+            })?,
+            Self::Flap { inputs, gap_ms } => {
+                inputs.retract_from_ordered(net, ordering);
+                // schedule the re-announcement `gap_ms` after the withdrawal
+                net.queue_mut().shift_time(gap_ms / 1000.0);
+                inputs.advertise_to_ordered(net, ordering);
+                net.queue_mut().shift_time(-gap_ms / 1000.0);
             }
         }
         Ok(())
     }
 
-    /// Triggers the specified event on the disconnected router_lab.
-    pub fn trigger_cisco_exabgp<Q, Ospf: OspfImpl>(
-        &self,
-        lab: &mut RouterLab<Prefix, Q, Ospf, Inactive>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    /// Times (in seconds after the event) at which the event changes the network while it is
+    /// still converging, in chronological order, e.g., when a flapping link goes down and comes
+    /// back up. Unlike BGP messages, link changes take effect immediately, so they cannot be
+    /// scheduled on the queue when triggering the event. Instead, `Analyzer::build_trace` calls
+    /// `apply_timed_change` with the index of each change once the convergence reaches its time.
+    pub fn timed_changes(&self) -> Vec<f64> {
         match self {
-            Self::AddLink(_, _, _, _, _) | Self::RemoveLink(_, _, _) => {}
-            Self::LowerLocalPref(_, _, _) => {
-                unimplemented!("LowerLocalPref not supported yet on the RouterLab!")
+            Self::LinkFlap { down_at, up_at, .. } => {
+                let down_at = down_at.max(0.0);
+                vec![down_at, up_at.max(down_at)]
             }
-            Self::AnnounceRoute(prefixes, r, path) => {
-                for prefix in prefixes.iter() {
-                    lab.advertise_route(
-                        *r,
-                        &BgpRoute::new(
-                            *r,
-                            *prefix,
-                            path,
-                            None,
-                            vec![bgpsim::types::Prefix::as_num(prefix)],
-                        ),
-                    )?;
-                }
-            }
-            Self::WithdrawRoute(prefixes, r, _) => {
-                for prefix in prefixes.iter() {
-                    lab.withdraw_route(*r, *prefix)?;
+            _ => Vec::new(),
+        }
+    }
+
+    /// Apply the change at index `i` of `timed_changes`, taking the link weights to restore from
+    /// `original_net`. Does nothing for events without such a change.
+    pub fn apply_timed_change<Q>(
+        &self,
+        i: usize,
+        net: &mut Network<Prefix, Q>,
+        original_net: &Network<Prefix, Q>,
+    ) -> Result<(), NetworkError>
+    where
+        Q: EventQueue<Prefix>,
+    {
+        match (self, i) {
+            (Self::LinkFlap { a, b, .. }, 0) => net.remove_link(*a, *b)?,
+            (Self::LinkFlap { a, b, .. }, 1) => {
+                net.add_link(*a, *b)?;
+                // links to external routers have no link weight
+                if original_net.get_device(*a)?.is_internal()
+                    && original_net.get_device(*b)?.is_internal()
+                {
+                    let ospf = original_net.ospf_network();
+                    net.set_link_weight(*a, *b, ospf.get_weight(*a, *b))?;
+                    net.set_link_weight(*b, *a, ospf.get_weight(*b, *a))?;
                 }
             }
-            Self::AnnounceRoutingInputs(inputs) => {
-                for (router, route) in inputs.all_routes() {
-                    lab.advertise_route(router, &route)?;
-                }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Route updates sent by the ExaBGP-controlled external routers to trigger the event on the
+    /// disconnected router_lab.
+    pub fn exabgp_trigger_updates(&self) -> Result<Vec<ExaBgpUpdate>, UnsupportedOnRouterLab> {
+        Ok(match self {
+            Self::AddLink(_, _, _, _, _) | Self::RemoveLink(_, _, _) => Vec::new(),
+            Self::LowerLocalPref(_, _, _) => return Err(UnsupportedOnRouterLab("LowerLocalPref")),
+            Self::Flap { .. } => return Err(UnsupportedOnRouterLab("Flap")),
+            Self::LinkFlap { .. } => return Err(UnsupportedOnRouterLab("LinkFlap")),
+            Self::SetLinkWeight { .. } => return Err(UnsupportedOnRouterLab("SetLinkWeight")),
+            Self::PrependAsPath { .. } | Self::TrimAsPath { .. } => {
+                return Err(UnsupportedOnRouterLab("Changing the AS path"))
             }
-            Self::WithdrawRoutingInputs(inputs) => {
-                for (router, route) in inputs.all_routes() {
-                    lab.withdraw_route(router, route.prefix)?;
-                }
+            Self::AnnounceRoute(prefixes, r, path) => prefixes
+                .iter()
+                .map(|prefix| ExaBgpUpdate::Advertise(*r, exabgp_route(*r, *prefix, path)))
+                .collect(),
+            Self::WithdrawRoute(prefixes, r, _) => prefixes
+                .iter()
+                .map(|prefix| ExaBgpUpdate::Withdraw(*r, *prefix))
+                .collect(),
+            Self::AnnounceRoutingInputs(inputs) => inputs
+                .all_routes()
+                .into_iter()
+                .map(|(router, route)| ExaBgpUpdate::Advertise(router, route))
+                .collect(),
+            Self::WithdrawRoutingInputs(inputs) => inputs
+                .all_routes()
+                .into_iter()
+                .map(|(router, route)| ExaBgpUpdate::Withdraw(router, route.prefix))
+                .collect(),
+            Self::PhysicalExternalAnnounceRoute(_, _, _)
+            | Self::PhysicalExternalWithdrawRoute(_, _, _)
+            | Self::PhysicalExternalAnnounceRoutingInputs(_)
+            | Self::PhysicalExternalWithdrawRoutingInputs(_)
+            | Self::PhysicalExternalUpdateBetterRoutingInputs(_)
+            | Self::PhysicalExternalUpdateWorseRoutingInputs(_) => Vec::new(),
+        })
+    }
+
+    /// Route updates sent by the ExaBGP-controlled external routers to revert the event on the
+    /// disconnected router_lab.
+    pub fn exabgp_revert_updates(&self) -> Result<Vec<ExaBgpUpdate>, UnsupportedOnRouterLab> {
+        Ok(match self {
+            Self::AddLink(_, _, _, _, _) | Self::RemoveLink(_, _, _) => Vec::new(),
+            Self::LowerLocalPref(_, _, _) => return Err(UnsupportedOnRouterLab("LowerLocalPref")),
+            Self::Flap { .. } => return Err(UnsupportedOnRouterLab("Flap")),
+            Self::LinkFlap { .. } => return Err(UnsupportedOnRouterLab("LinkFlap")),
+            Self::SetLinkWeight { .. } => return Err(UnsupportedOnRouterLab("SetLinkWeight")),
+            Self::PrependAsPath { .. } | Self::TrimAsPath { .. } => {
+                return Err(UnsupportedOnRouterLab("Changing the AS path"))
             }
+            Self::AnnounceRoute(prefixes, r, _) => prefixes
+                .iter()
+                .map(|prefix| ExaBgpUpdate::Withdraw(*r, *prefix))
+                .collect(),
+            Self::WithdrawRoute(prefixes, r, path) => prefixes
+                .iter()
+                .map(|prefix| ExaBgpUpdate::Advertise(*r, exabgp_route(*r, *prefix, path)))
+                .collect(),
+            Self::AnnounceRoutingInputs(inputs) => inputs
+                .all_routes()
+                .into_iter()
+                .map(|(router, route)| ExaBgpUpdate::Withdraw(router, route.prefix))
+                .collect(),
+            Self::WithdrawRoutingInputs(inputs) => inputs
+                .all_routes()
+                .into_iter()
+                .map(|(router, route)| ExaBgpUpdate::Advertise(router, route))
+                .collect(),
             Self::PhysicalExternalAnnounceRoute(_, _, _)
             | Self::PhysicalExternalWithdrawRoute(_, _, _)
             | Self::PhysicalExternalAnnounceRoutingInputs(_)
             | Self::PhysicalExternalWithdrawRoutingInputs(_)
             | Self::PhysicalExternalUpdateBetterRoutingInputs(_)
-            | Self::PhysicalExternalUpdateWorseRoutingInputs(_) => {}
-        };
+            | Self::PhysicalExternalUpdateWorseRoutingInputs(_) => Vec::new(),
+        })
+    }
+
+    /// Triggers the specified event on the disconnected router_lab.
+    pub fn trigger_cisco_exabgp<Q, Ospf: OspfImpl>(
+        &self,
+        lab: &mut RouterLab<Prefix, Q, Ospf, Inactive>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for update in self.exabgp_trigger_updates()? {
+            update.apply(lab)?;
+        }
         Ok(())
     }
 
@@ -357,64 +587,79 @@ impl AnalyzerEvent<RouterId> {
         &self,
         lab: &mut RouterLab<Prefix, Q, Ospf, Inactive>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        match self {
-            Self::AddLink(_, _, _, _, _) | Self::RemoveLink(_, _, _) => {}
-            Self::LowerLocalPref(_, _, _) => {
-                unimplemented!("LowerLocalPref not supported yet on the RouterLab!")
-            }
-            Self::AnnounceRoute(prefixes, r, _) => {
-                for prefix in prefixes.iter() {
-                    lab.withdraw_route(*r, *prefix)?;
-                }
-            }
-            Self::WithdrawRoute(prefixes, r, path) => {
-                for prefix in prefixes.iter() {
-                    lab.advertise_route(
-                        *r,
-                        &BgpRoute::new(
-                            *r,
-                            *prefix,
-                            path,
-                            None,
-                            vec![bgpsim::types::Prefix::as_num(prefix)],
-                        ),
-                    )?;
-                }
-            }
-            Self::AnnounceRoutingInputs(inputs) => {
-                for (router, route) in inputs.all_routes() {
-                    lab.withdraw_route(router, route.prefix)?;
-                }
-            }
-            Self::WithdrawRoutingInputs(inputs) => {
-                for (router, route) in inputs.all_routes() {
-                    lab.advertise_route(router, &route)?;
-                }
-            }
-            Self::PhysicalExternalAnnounceRoute(_, _, _)
-            | Self::PhysicalExternalWithdrawRoute(_, _, _)
-            | Self::PhysicalExternalAnnounceRoutingInputs(_)
-            | Self::PhysicalExternalWithdrawRoutingInputs(_)
-            | Self::PhysicalExternalUpdateBetterRoutingInputs(_)
-            | Self::PhysicalExternalUpdateWorseRoutingInputs(_) => {}
-        };
+        for update in self.exabgp_revert_updates()? {
+            update.apply(lab)?;
+        }
         Ok(())
     }
 
+    /// Build the tcpdump filter capturing the first non-keepalive BGP packet sent from any of the
+    /// event's external routers to its neighbors, used to determine the event's start time.
+    /// `sessions` maps each external router to the `(own address, neighbor address)` pairs of its
+    /// interfaces. Returns `None` for events that are not triggered by BGP messages.
+    pub fn tcpdump_filter(
+        &self,
+        sessions: &HashMap<RouterId, Vec<(Ipv4Addr, Ipv4Addr)>>,
+    ) -> Option<String> {
+        let externals = match self {
+            Self::AddLink(_, _, _, _, _)
+            | Self::RemoveLink(_, _, _)
+            | Self::LowerLocalPref(_, _, _)
+            | Self::SetLinkWeight { .. }
+            | Self::LinkFlap { .. } => return None,
+            Self::AnnounceRoute(_, ext, _)
+            | Self::WithdrawRoute(_, ext, _)
+            | Self::PhysicalExternalAnnounceRoute(_, ext, _)
+            | Self::PhysicalExternalWithdrawRoute(_, ext, _)
+            | Self::PrependAsPath { external: ext, .. }
+            | Self::TrimAsPath { external: ext, .. } => vec![*ext],
+            Self::AnnounceRoutingInputs(inputs)
+            | Self::WithdrawRoutingInputs(inputs)
+            | Self::PhysicalExternalAnnounceRoutingInputs(inputs)
+            | Self::PhysicalExternalWithdrawRoutingInputs(inputs)
+            | Self::PhysicalExternalUpdateBetterRoutingInputs(inputs)
+            | Self::PhysicalExternalUpdateWorseRoutingInputs(inputs)
+            | Self::Flap { inputs, .. } => inputs
+                .external_routers()
+                .into_iter()
+                .map(|(rid, _)| rid)
+                .unique()
+                .collect(),
+        };
+
+        // set up filter for non-keepalive (85 bytes) BGP packets, add a null-statement in front to
+        // append all neighbors afterwards as a disjunction
+        let mut filter = String::from("\"port 179 and len > 85 and ((port 1 and port 2)");
+        for ext in externals {
+            for (ipv4, neighbor_ipv4) in sessions.get(&ext).into_iter().flatten().unique() {
+                filter.push_str(&format!(" or (src {ipv4} and dst {neighbor_ipv4})"));
+            }
+        }
+        filter.push_str(")\"");
+
+        Some(filter)
+    }
+
     /// Triggers the specified event on the connected router_lab.
     pub async fn trigger_cisco_direct<Q: EventQueue<Prefix> + Clone, Ospf: OspfImpl>(
         &self,
         lab: &mut RouterLab<'_, Prefix, Q, Ospf, Active>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match self {
-            Self::AddLink(_, _a, _b, _, _) => {
-                unimplemented!("AddLink not supported yet on the RouterLab!")
-            }
+            Self::AddLink(_, _a, _b, _, _) => return Err(UnsupportedOnRouterLab("AddLink").into()),
             Self::RemoveLink(_, a, b) => {
                 lab.disable_link(*a, *b).await?;
             }
             Self::LowerLocalPref(_, _, _) => {
-                unimplemented!("LowerLocalPref not supported yet on the RouterLab!")
+                return Err(UnsupportedOnRouterLab("LowerLocalPref").into())
+            }
+            Self::Flap { .. } => return Err(UnsupportedOnRouterLab("Flap").into()),
+            Self::LinkFlap { .. } => return Err(UnsupportedOnRouterLab("LinkFlap").into()),
+            Self::SetLinkWeight { .. } => {
+                return Err(UnsupportedOnRouterLab("SetLinkWeight").into())
+            }
+            Self::PrependAsPath { .. } | Self::TrimAsPath { .. } => {
+                return Err(UnsupportedOnRouterLab("Changing the AS path").into())
             }
             Self::AnnounceRoute(_, _, _)
             | Self::WithdrawRoute(_, _, _)
@@ -488,15 +733,21 @@ route-map neighbor-out permit 10
         lab: &mut RouterLab<'_, Prefix, Q, Ospf, Active>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match self {
-            Self::AddLink(_, _, _, _, _) => {
-                unimplemented!("AddLink not supported yet on the RouterLab!")
-            }
+            Self::AddLink(_, _, _, _, _) => return Err(UnsupportedOnRouterLab("AddLink").into()),
             Self::RemoveLink(_, a, b) => {
                 lab.enable_link(*a, *b).await?;
                 tokio::time::sleep(Duration::from_secs(5)).await;
             }
             Self::LowerLocalPref(_, _, _) => {
-                unimplemented!("LowerLocalPref not supported yet on the RouterLab!")
+                return Err(UnsupportedOnRouterLab("LowerLocalPref").into())
+            }
+            Self::Flap { .. } => return Err(UnsupportedOnRouterLab("Flap").into()),
+            Self::LinkFlap { .. } => return Err(UnsupportedOnRouterLab("LinkFlap").into()),
+            Self::SetLinkWeight { .. } => {
+                return Err(UnsupportedOnRouterLab("SetLinkWeight").into())
+            }
+            Self::PrependAsPath { .. } | Self::TrimAsPath { .. } => {
+                return Err(UnsupportedOnRouterLab("Changing the AS path").into())
             }
             Self::AnnounceRoute(_, _, _)
             | Self::WithdrawRoute(_, _, _)
@@ -559,7 +810,12 @@ route-map neighbor-out permit 10
             | Self::PhysicalExternalAnnounceRoute(_, _, _)
             | Self::PhysicalExternalAnnounceRoutingInputs(_)
             | Self::PhysicalExternalUpdateBetterRoutingInputs(_)
-            | Self::PhysicalExternalUpdateWorseRoutingInputs(_) => true,
+            | Self::PhysicalExternalUpdateWorseRoutingInputs(_)
+            | Self::Flap { .. }
+            | Self::SetLinkWeight { .. }
+            | Self::LinkFlap { .. }
+            | Self::PrependAsPath { .. }
+            | Self::TrimAsPath { .. } => true,
             Self::WithdrawRoute(prefixes, r, _path)
             | Self::PhysicalExternalWithdrawRoute(prefixes, r, _path) => {
                 *t_rx < *event_start || ext != r || !prefixes.contains(&prefix)
@@ -572,12 +828,114 @@ route-map neighbor-out permit 10
     }
 }
 
+/// Route update sent by an ExaBGP-controlled external router on the disconnected router_lab.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExaBgpUpdate {
+    Advertise(RouterId, BgpRoute<Prefix>),
+    Withdraw(RouterId, Prefix),
+}
+
+impl ExaBgpUpdate {
+    /// Schedule the update on the disconnected router_lab.
+    pub fn apply<Q, Ospf: OspfImpl>(
+        self,
+        lab: &mut RouterLab<Prefix, Q, Ospf, Inactive>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::Advertise(router, route) => {
+                lab.advertise_route(router, &route)?;
+            }
+            Self::Withdraw(router, prefix) => {
+                lab.withdraw_route(router, prefix)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Re-advertise the route of `external` for `prefix`, with the AS path modified by `f`. Does
+/// nothing (except for logging a warning) if `external` does not advertise any route for `prefix`.
+fn readvertise_with_as_path<Q, F>(
+    net: &mut Network<Prefix, Q>,
+    external: RouterId,
+    prefix: Prefix,
+    f: F,
+) -> Result<(), NetworkError>
+where
+    Q: EventQueue<Prefix>,
+    F: FnOnce(&[AsId]) -> Vec<AsId>,
+{
+    let Some(route) = net
+        .get_device(external)?
+        .unwrap_external()
+        .get_advertised_routes()
+        .get(&prefix)
+        .cloned()
+    else {
+        log::warn!(
+            "{} does not advertise any route for {prefix:?}, leaving its AS path unchanged!",
+            external.fmt(net)
+        );
+        return Ok(());
+    };
+    if route.as_path.is_empty() {
+        log::warn!(
+            "{} advertises an empty AS path for {prefix:?}, leaving it unchanged!",
+            external.fmt(net)
+        );
+        return Ok(());
+    }
+    net.advertise_external_route(
+        external,
+        prefix,
+        f(&route.as_path),
+        route.med,
+        vec![bgpsim::types::Prefix::as_num(&prefix)],
+    )?;
+    Ok(())
+}
+
+/// Prepend the first AS of `path` (i.e., the AS of the advertising external router) `times` more
+/// often. An empty `path` is returned unchanged.
+fn prepend_as_path(path: &[AsId], times: usize) -> Vec<AsId> {
+    let Some(first) = path.first() else {
+        return Vec::new();
+    };
+    std::iter::repeat_n(*first, times)
+        .chain(path.iter().copied())
+        .collect()
+}
+
+/// Remove up to `times` repetitions of the first AS from the front of `path`, always keeping its
+/// first occurrence. An empty `path` is returned unchanged.
+fn trim_as_path(path: &[AsId], times: usize) -> Vec<AsId> {
+    let Some(first) = path.first() else {
+        return Vec::new();
+    };
+    let repetitions = path.iter().take_while(|x| *x == first).count() - 1;
+    path[times.min(repetitions)..].to_vec()
+}
+
+/// Build the route advertised by an external router for the given prefix.
+fn exabgp_route(router: RouterId, prefix: Prefix, path: &[AsId]) -> BgpRoute<Prefix> {
+    BgpRoute::new(
+        router,
+        prefix,
+        path,
+        None,
+        vec![bgpsim::types::Prefix::as_num(&prefix)],
+    )
+}
+
 #[allow(unused)]
 impl<R: std::fmt::Debug + Clone> std::fmt::Display for AnalyzerEvent<R> {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         let name = match self {
             Self::AddLink(_, a, b, w1, w2) => format!("AddLink({a:?}, {b:?}, {w1}, {w2})"),
             Self::RemoveLink(_, a, b) => format!("RemoveLink({a:?}, {b:?})"),
+            Self::SetLinkWeight { a, b, weight, .. } => {
+                format!("SetLinkWeight({a:?}, {b:?}, {weight})")
+            }
             Self::LowerLocalPref(_, a, b) => format!("LowerLocalPref({a:?}, {b:?})"),
             Self::AnnounceRoute(_, x, aspath) => format!("AnnounceRoute({x:?}, {aspath:?})"),
             Self::WithdrawRoute(_, x, aspath) => format!("Withdrawroute({x:?}, {aspath:?})"),
@@ -629,6 +987,29 @@ impl<R: std::fmt::Debug + Clone> std::fmt::Display for AnalyzerEvent<R> {
                     .map(|(prefix, router, path)| format!("({router:?}, {prefix:?}, {path:?})"))
                     .join(", ")
             ),
+            Self::PrependAsPath {
+                external,
+                prefix,
+                times,
+            } => format!("PrependAsPath({external:?}, {prefix:?}, {times})"),
+            Self::TrimAsPath {
+                external,
+                prefix,
+                times,
+            } => format!("TrimAsPath({external:?}, {prefix:?}, {times})"),
+            Self::LinkFlap {
+                a,
+                b,
+                down_at,
+                up_at,
+            } => format!("LinkFlap({a:?}, {b:?}, {down_at}s, {up_at}s)"),
+            Self::Flap { inputs, gap_ms } => format!(
+                "Flap({gap_ms}ms, {})",
+                inputs
+                    .unroll()
+                    .map(|(prefix, router, path)| format!("({router:?}, {prefix:?}, {path:?})"))
+                    .join(", ")
+            ),
         };
         fmt.write_str(&name);
         Ok(())
@@ -644,6 +1025,15 @@ impl<'a, 'n, Q, Ospf: OspfImpl> bgpsim::formatter::NetworkFormatter<'a, 'n, Pref
         match self {
             Self::AddLink(_, a, b, _, _) => format!("AddLink_{}_{}", a.fmt(net), b.fmt(net)),
             Self::RemoveLink(_, a, b) => format!("RemoveLink_{}_{}", a.fmt(net), b.fmt(net)),
+            Self::LinkFlap {
+                a,
+                b,
+                down_at,
+                up_at,
+            } => format!("LinkFlap_{}_{}_{down_at}s_{up_at}s", a.fmt(net), b.fmt(net)),
+            Self::SetLinkWeight { a, b, weight, .. } => {
+                format!("SetLinkWeight_{}_{}_{weight}", a.fmt(net), b.fmt(net))
+            }
             Self::LowerLocalPref(_, a, b) => {
                 format!("LowerLocalPref_{}_{}", a.fmt(net), b.fmt(net))
             }
@@ -669,6 +1059,121 @@ impl<'a, 'n, Q, Ospf: OspfImpl> bgpsim::formatter::NetworkFormatter<'a, 'n, Pref
             Self::PhysicalExternalUpdateWorseRoutingInputs(i) => {
                 format!("PhysUpdateWorseRoutingInputs_{}", i.simple_fmt())
             }
+            Self::Flap { inputs, gap_ms } => format!("Flap_{gap_ms}ms_{}", inputs.simple_fmt()),
+            Self::PrependAsPath {
+                external, times, ..
+            } => format!("PrependAsPath_{}_{times}", external.fmt(net)),
+            Self::TrimAsPath {
+                external, times, ..
+            } => format!("TrimAsPath_{}_{times}", external.fmt(net)),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sessions() -> HashMap<RouterId, Vec<(Ipv4Addr, Ipv4Addr)>> {
+        HashMap::from([
+            (
+                RouterId::from(10),
+                vec![
+                    (Ipv4Addr::new(1, 0, 0, 1), Ipv4Addr::new(1, 0, 0, 2)),
+                    (Ipv4Addr::new(1, 0, 1, 1), Ipv4Addr::new(1, 0, 1, 2)),
+                ],
+            ),
+            (
+                RouterId::from(11),
+                vec![(Ipv4Addr::new(2, 0, 0, 1), Ipv4Addr::new(2, 0, 0, 2))],
+            ),
+        ])
+    }
+
+    #[test]
+    fn tcpdump_filter_announce() {
+        let event = AnalyzerEvent::AnnounceRoutingInputs(RoutingInputs::SinglePrefix(vec![
+            (RouterId::from(10), vec![100.into(), 1000.into()]),
+            (RouterId::from(11), vec![200.into(), 1000.into()]),
+        ]));
+        assert_eq!(
+            event.tcpdump_filter(&sessions()).unwrap(),
+            "\"port 179 and len > 85 and ((port 1 and port 2) \
+             or (src 1.0.0.1 and dst 1.0.0.2) \
+             or (src 1.0.1.1 and dst 1.0.1.2) \
+             or (src 2.0.0.1 and dst 2.0.0.2))\""
+        );
+    }
+
+    #[test]
+    fn tcpdump_filter_withdraw() {
+        let event = AnalyzerEvent::WithdrawRoute(
+            vec![Prefix::from(0)],
+            RouterId::from(11),
+            vec![200.into(), 1000.into()],
+        );
+        assert_eq!(
+            event.tcpdump_filter(&sessions()).unwrap(),
+            "\"port 179 and len > 85 and ((port 1 and port 2) or (src 2.0.0.1 and dst 2.0.0.2))\""
+        );
+    }
+
+    #[test]
+    fn tcpdump_filter_add_link() {
+        let event = AnalyzerEvent::AddLink(
+            vec![Prefix::from(0)],
+            RouterId::from(0),
+            RouterId::from(1),
+            1.0,
+            1.0,
+        );
+        assert_eq!(event.tcpdump_filter(&sessions()), None);
+    }
+
+    #[test]
+    fn exabgp_updates() {
+        let (r, prefix, path) = (RouterId::from(11), Prefix::from(0), vec![200.into()]);
+        let event = AnalyzerEvent::WithdrawRoute(vec![prefix], r, path.clone());
+        assert_eq!(
+            event.exabgp_trigger_updates().unwrap(),
+            vec![ExaBgpUpdate::Withdraw(r, prefix)]
+        );
+        assert_eq!(
+            event.exabgp_revert_updates().unwrap(),
+            vec![ExaBgpUpdate::Advertise(r, exabgp_route(r, prefix, &path))]
+        );
+    }
+
+    #[test]
+    fn modify_as_path() {
+        let path: Vec<AsId> = vec![100.into(), 100.into(), 1000.into()];
+        assert_eq!(
+            prepend_as_path(&path, 2),
+            vec![100.into(), 100.into(), 100.into(), 100.into(), 1000.into()]
+        );
+        assert_eq!(trim_as_path(&path, 1), vec![100.into(), 1000.into()]);
+        // the AS of the external router itself is never removed
+        assert_eq!(trim_as_path(&path, 5), vec![100.into(), 1000.into()]);
+        assert_eq!(trim_as_path(&path[1..], 1), path[1..].to_vec());
+        // empty AS paths are left unchanged
+        assert_eq!(prepend_as_path(&[], 2), Vec::<AsId>::new());
+        assert_eq!(trim_as_path(&[], 2), Vec::<AsId>::new());
+    }
+
+    #[test]
+    fn unsupported_on_router_lab() {
+        let event = AnalyzerEvent::PrependAsPath {
+            external: RouterId::from(11),
+            prefix: Prefix::from(0),
+            times: 2,
+        };
+        assert_eq!(
+            event.exabgp_trigger_updates(),
+            Err(UnsupportedOnRouterLab("Changing the AS path"))
+        );
+        assert_eq!(
+            event.exabgp_revert_updates(),
+            Err(UnsupportedOnRouterLab("Changing the AS path"))
+        );
+    }
+}
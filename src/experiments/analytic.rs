@@ -0,0 +1,192 @@
+// TRIX: Inference of Transient Violation Times from Logged Routing Events or Collected BGP Messages
+// Copyright (C) 2024-2025 Roland Schmid <roschmi@ethz.ch> and Tibor Schneider <sctibor@ethz.ch>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//! Module computing the expected violation times of simple scenarios in closed form, to be used as
+//! an oracle for the interval algorithm and as a quick sanity estimate.
+
+use crate::timing_model::{UPDATE_PROCESSING_TIME, WITHDRAW_PROCESSING_TIME};
+
+/// Parse the name of a `PathNN_FullMesh_ExtAtEnds_Xms` topology of `list_custom_topologies`,
+/// returning the number of routers and the delay of the internal links in seconds.
+fn parse_path_topology(topo: &str) -> Option<(usize, f64)> {
+    let rest = topo.strip_prefix("Path")?;
+    let (num_routers, rest) = rest.split_once('_')?;
+    let delay_ms = rest
+        .strip_prefix("FullMesh_ExtAtEnds_")?
+        .strip_suffix("ms")?;
+    let num_routers: usize = num_routers.parse().ok()?;
+    let delay_ms: f64 = delay_ms.parse().ok()?;
+    (num_routers >= 2).then_some((num_routers, delay_ms / 1_000.0))
+}
+
+/// Compute the expected violation time [s] of the reachability policy of each internal router
+/// `r0, r1, ...` for the given topology and scenario name, as found in `list_custom_topologies`
+/// and `list_path_scenarios`. Returns `None` if there is no closed form for the combination.
+///
+/// Currently, only the scenario `WithdrawE1` on the `PathNN_FullMesh_ExtAtEnds_Xms` topologies is
+/// supported. With `m` hops between `r0` (connected to `e1`) and `r_m` (connected to `e2`) and a
+/// link delay of `d`, `r0` starts dropping traffic after processing the withdraw of `e1`. Its
+/// withdraw reaches `r_m` after `m * d`, which then selects the route of `e2` and announces it,
+/// reaching `r_k` after another `(m - k) * d`. Since a packet sent by `r_k` takes `k * d` to reach
+/// `r0`, the violation times are
+/// - `2 * m * d + p_w + p_u` for all `r_k` with `k < m`, and
+/// - `2 * m * d + p_w` for `r_m`,
+///
+/// where `p_w` and `p_u` are the processing times of a withdraw and an update, respectively.
+pub fn analytic_violation_times(topo: &str, event: &str) -> Option<Vec<f64>> {
+    let (num_routers, delay) = parse_path_topology(topo)?;
+    if event != "WithdrawE1" {
+        return None;
+    }
+
+    let m = (num_routers - 1) as f64;
+    let round_trip = 2.0 * m * delay + WITHDRAW_PROCESSING_TIME;
+    Some(
+        (0..num_routers)
+            .map(|k| {
+                if k + 1 < num_routers {
+                    round_trip + UPDATE_PROCESSING_TIME
+                } else {
+                    round_trip
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Compute the expected violation time [s] of the reachability policy of the worst router for the
+/// given topology and scenario name. See `analytic_violation_times` for the supported
+/// combinations.
+pub fn analytic_violation_time(topo: &str, event: &str) -> Option<f64> {
+    analytic_violation_times(topo, event)?
+        .into_iter()
+        .reduce(f64::max)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use bgpsim::{policies::FwPolicy, prelude::*};
+    use itertools::Itertools;
+
+    use crate::{
+        analyzer::{
+            test::{sample_trace, total_violation_times},
+            Analyzer,
+        },
+        experiments::{list_custom_topologies, list_path_scenarios, ScenarioPrefix},
+        timing_model::TimingModel,
+        transient_specification::TransientPolicy,
+    };
+
+    /// Run the interval algorithm on a single sample of the given path topology and scenario,
+    /// returning the violation times of the reachability policies of `r0, r1, ...`.
+    fn simulated_violation_times(topo: &str, scenario: &str) -> Vec<f64> {
+        let prefixes = ScenarioPrefix::SinglePrefix.prefixes();
+        let (_, net, _, delays, external_routers) = list_custom_topologies(&prefixes)
+            .into_iter()
+            .find(|(name, ..)| *name == topo)
+            .unwrap();
+        let delays = delays.unwrap();
+        let (_, event) = list_path_scenarios(&net, &prefixes, &external_routers)
+            .into_iter()
+            .find(|(name, _)| *name == scenario)
+            .unwrap();
+        let routers = net
+            .internal_routers()
+            .sorted_by(|a, b| a.name().cmp(b.name()))
+            .map(|r| r.router_id())
+            .collect_vec();
+
+        let net = net.swap_queue(TimingModel::from_delays(&delays)).unwrap();
+        let mut analyzer = Analyzer::new(net, event, vec![], 0.95, 0.01).unwrap();
+        analyzer.set_delays(delays);
+        let policies = routers
+            .iter()
+            .map(|r| TransientPolicy::Atomic(FwPolicy::Reachable(*r, prefixes[0])))
+            .collect_vec();
+        analyzer.set_policies(policies.clone());
+
+        let trace = sample_trace(&analyzer);
+        let result = total_violation_times(&analyzer, &mut analyzer.build_queue(), &trace).unwrap();
+        assert!(result.persistent.is_empty());
+
+        policies
+            .iter()
+            .map(|policy| result.times.get(policy).copied().unwrap_or_default())
+            .collect()
+    }
+
+    #[test]
+    fn parse_topology_names() {
+        assert_eq!(
+            parse_path_topology("Path03_FullMesh_ExtAtEnds_3ms"),
+            Some((3, 0.003))
+        );
+        assert_eq!(
+            parse_path_topology("Path12_FullMesh_ExtAtEnds_10ms"),
+            Some((12, 0.01))
+        );
+        assert_eq!(parse_path_topology("Path03_FullMesh_ExtAtEnds"), None);
+        assert_eq!(
+            parse_path_topology("Path03_FullMesh_ExtAtEndsAndCenter_3ms"),
+            None
+        );
+        assert_eq!(parse_path_topology("Path01_3ms"), None);
+    }
+
+    #[test]
+    fn unsupported_scenarios() {
+        assert_eq!(
+            analytic_violation_time("Path03_FullMesh_ExtAtEnds_3ms", "WithdrawE2"),
+            None
+        );
+        assert_eq!(
+            analytic_violation_time("Path03_FullMesh_ExtFrontAndCenter_3ms", "WithdrawE1"),
+            None
+        );
+    }
+
+    #[test]
+    fn path03_closed_form() {
+        let times =
+            analytic_violation_times("Path03_FullMesh_ExtAtEnds_3ms", "WithdrawE1").unwrap();
+        assert_eq!(times.len(), 3);
+        assert!((times[0] - 0.012633).abs() < 1e-9);
+        assert!((times[1] - 0.012633).abs() < 1e-9);
+        assert!((times[2] - 0.0124).abs() < 1e-9);
+        assert_eq!(
+            analytic_violation_time("Path03_FullMesh_ExtAtEnds_3ms", "WithdrawE1"),
+            Some(times[0])
+        );
+    }
+
+    #[test]
+    fn matches_interval_algorithm() {
+        for n in 3..=6 {
+            let topo = format!("Path{n:02}_FullMesh_ExtAtEnds_3ms");
+            let expected = analytic_violation_times(&topo, "WithdrawE1").unwrap();
+            let simulated = simulated_violation_times(&topo, "WithdrawE1");
+            assert_eq!(expected.len(), simulated.len());
+            for (k, (expected, simulated)) in expected.iter().zip(simulated.iter()).enumerate() {
+                assert!(
+                    (expected - simulated).abs() < 1e-6,
+                    "{topo}, r{k}: expected {expected}, simulated {simulated}"
+                );
+            }
+        }
+    }
+}
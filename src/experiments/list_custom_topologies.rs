@@ -21,6 +21,47 @@ use bgpsim::prelude::*;
 
 use crate::Prefix as P;
 
+/// Inconsistency between the geographic locations of a topology and its physical links.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeoDelayWarning {
+    /// Both endpoints of the link are at the same location, such that the geo-derived delay is 0.
+    ZeroDelay(RouterId, RouterId),
+    /// The router is connected to a link but has no geographic location.
+    MissingLocation(RouterId),
+}
+
+/// Check that the `geo` locations of a topology yield a non-zero delay for every physical link of
+/// `net`, computed in the same way as `TimingModel::from_geo_location`. Topologies with explicit
+/// link delays do not need to pass this check.
+pub fn validate_geo_delays<Q>(
+    net: &Network<P, Q>,
+    geo: &HashMap<RouterId, Location>,
+) -> Vec<GeoDelayWarning> {
+    let mut warnings = Vec::new();
+    let g = net.get_topology();
+    for e in g.edge_indices() {
+        let (a, b) = g.edge_endpoints(e).unwrap();
+        let (a, b) = (a.min(b), a.max(b));
+        let (Some(a_loc), Some(b_loc)) = (geo.get(&a), geo.get(&b)) else {
+            for r in [a, b] {
+                let warning = GeoDelayWarning::MissingLocation(r);
+                if !geo.contains_key(&r) && !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            }
+            continue;
+        };
+        let distance = a_loc
+            .distance_to(b_loc)
+            .unwrap_or_else(|_| a_loc.haversine_distance_to(b_loc))
+            .meters();
+        if distance == 0.0 {
+            warnings.push(GeoDelayWarning::ZeroDelay(a, b));
+        }
+    }
+    warnings
+}
+
 pub type TopologyDescription = (
     &'static str,                               // topo_name
     Network<P>,                                 // net
@@ -9814,3 +9855,51 @@ pub fn list_custom_topologies(prefixes: &[P]) -> [TopologyDescription; 128] {
         },
     ]
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn validate_geo_delays_coincident_link() {
+        let mut net: Network<P> = Network::new(BasicEventQueue::new());
+        let r0 = net.add_router("r0");
+        let r1 = net.add_router("r1");
+        let r2 = net.add_router("r2");
+        net.add_link(r0, r1).unwrap();
+        net.add_link(r1, r2).unwrap();
+
+        let geo = HashMap::from([
+            (r0, Location::new(47.3769, 8.5417)),
+            (r1, Location::new(46.9480, 7.4474)),
+            (r2, Location::new(46.9480, 7.4474)),
+        ]);
+        assert_eq!(
+            validate_geo_delays(&net, &geo),
+            vec![GeoDelayWarning::ZeroDelay(r1, r2)]
+        );
+
+        let geo = HashMap::from([
+            (r0, Location::new(47.3769, 8.5417)),
+            (r1, Location::new(46.9480, 7.4474)),
+        ]);
+        assert_eq!(
+            validate_geo_delays(&net, &geo),
+            vec![GeoDelayWarning::MissingLocation(r2)]
+        );
+    }
+
+    #[test]
+    fn validate_geo_delays_custom_topology() {
+        let prefixes = [P::from(0)];
+        let (topo_name, net, geo, _, _) = list_custom_topologies(&prefixes)
+            .into_iter()
+            .next()
+            .unwrap();
+        assert_eq!(topo_name, "Path01");
+        let r0 = net.get_router_id("r0").unwrap();
+        let e1 = net.get_router_id("e1").unwrap();
+        assert!(validate_geo_delays(&net, &geo.unwrap())
+            .contains(&GeoDelayWarning::ZeroDelay(r0.min(e1), r0.max(e1))));
+    }
+}
@@ -80,6 +80,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
 
                 // Remove link to external
@@ -97,6 +99,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                         "LosAngeles_ext".to_string(),
                         "LosAngeles".to_string(),
                     ),
+                    confidence: None,
+                    precision: None,
                 });
 
                 // route-reflection
@@ -110,6 +114,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
                 experiments.push(ExperimentDescription {
                     topo,
@@ -121,6 +127,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
                 experiments.push(ExperimentDescription {
                     topo,
@@ -132,6 +140,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
                 experiments.push(ExperimentDescription {
                     topo,
@@ -143,6 +153,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
                 experiments.push(ExperimentDescription {
                     topo,
@@ -154,6 +166,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
                 experiments.push(ExperimentDescription {
                     topo,
@@ -165,6 +179,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
                 experiments.push(ExperimentDescription {
                     topo,
@@ -176,6 +192,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
 
                 // full-mesh with backup still advertised
@@ -189,6 +207,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: equal_inputs.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(equal_event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
                 /*
                 // route reflection with backup still advertised
@@ -202,6 +222,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: equal_inputs.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(equal_event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
                 */
 
@@ -226,6 +248,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs_1_hop.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
 
                 let inputs_1_hop = RoutingInputs::RepeatedPrefix {
@@ -248,6 +272,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs_1_hop.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
 
                 let inputs_2_hop = RoutingInputs::RepeatedPrefix {
@@ -270,6 +296,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs_2_hop.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
 
                 let inputs_3_hop = RoutingInputs::RepeatedPrefix {
@@ -292,6 +320,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs_3_hop.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
 
                 let inputs_4_hop = RoutingInputs::RepeatedPrefix {
@@ -314,6 +344,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs_4_hop.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
 
                 let inputs_4_hop = RoutingInputs::RepeatedPrefix {
@@ -336,6 +368,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     delays: LinkDelayBuilder::new().default_delay(delay),
                     static_routing_inputs: inputs_4_hop.clone(),
                     event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                    confidence: None,
+                    precision: None,
                 });
 
                 // announce new best route
@@ -361,6 +395,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -372,6 +408,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -383,6 +421,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -394,6 +434,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -405,6 +447,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -416,6 +460,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -427,6 +473,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -438,6 +486,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -449,6 +499,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
                 // update better route
@@ -480,6 +532,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateBetterRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -491,6 +545,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateBetterRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -502,6 +558,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateBetterRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -513,6 +571,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateBetterRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
                 // update worse route
@@ -544,6 +604,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
                 // UpdateWorse + KeepOther
@@ -557,6 +619,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
                 // UpdateWorse + RR
@@ -570,6 +634,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -581,6 +647,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -592,6 +660,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -603,6 +673,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -614,6 +686,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -625,6 +699,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -636,6 +712,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
                 // UpdateWorse + RR + KeepOther
@@ -649,6 +727,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -660,6 +740,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
                 experiments.push(ExperimentDescription {
                 topo,
@@ -671,6 +753,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
                 // update worse + vary distance from event & backup
@@ -695,6 +779,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs_1_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
                 let inputs_1_hop = RoutingInputs::RepeatedPrefix {
@@ -714,6 +800,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs_1_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
                 let inputs_2_hop = RoutingInputs::RepeatedPrefix {
@@ -733,6 +821,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs_2_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
                 let inputs_3_hop = RoutingInputs::RepeatedPrefix {
@@ -752,6 +842,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs_3_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
                 let inputs_4_hop = RoutingInputs::RepeatedPrefix {
@@ -771,6 +863,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs_4_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
                 let inputs_4_hop = RoutingInputs::RepeatedPrefix {
@@ -790,6 +884,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new().default_delay(delay),
                 static_routing_inputs: inputs_4_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             }
         }
@@ -835,6 +931,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             // Remove link to external
@@ -852,6 +950,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                     "LosAngeles_ext".to_string(),
                     "LosAngeles".to_string(),
                 ),
+                confidence: None,
+                precision: None,
             });
 
             // route-reflection
@@ -865,6 +965,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -876,6 +978,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -887,6 +991,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -898,6 +1004,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -909,6 +1017,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -920,6 +1030,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -931,6 +1043,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             // full-mesh with backup still advertised
@@ -944,6 +1058,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(equal_event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             // route reflection with backup still advertised
@@ -957,6 +1073,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(equal_event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -968,6 +1086,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(equal_event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -979,6 +1099,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(equal_event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             // vary distance from event & backup
@@ -1002,6 +1124,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_1_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             let inputs_1_hop = RoutingInputs::RepeatedPrefix {
@@ -1024,6 +1148,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_1_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             let inputs_2_hop = RoutingInputs::RepeatedPrefix {
@@ -1046,6 +1172,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_2_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             let inputs_3_hop = RoutingInputs::RepeatedPrefix {
@@ -1068,6 +1196,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_3_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             let inputs_4_hop = RoutingInputs::RepeatedPrefix {
@@ -1090,6 +1220,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_4_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             let inputs_4_hop = RoutingInputs::RepeatedPrefix {
@@ -1112,6 +1244,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_4_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             // announce new best route
@@ -1137,6 +1271,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1148,6 +1284,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1159,6 +1297,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1170,6 +1310,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1181,6 +1323,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1192,6 +1336,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1203,6 +1349,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1214,6 +1362,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1225,6 +1375,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             // update better route
@@ -1256,6 +1408,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateBetterRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1267,6 +1421,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateBetterRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1278,6 +1434,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateBetterRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1289,6 +1447,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateBetterRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             // update worse route
@@ -1320,6 +1480,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             // UpdateWorse + KeepOther
@@ -1333,6 +1495,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             // UpdateWorse + RR
@@ -1346,6 +1510,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1357,6 +1523,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1368,6 +1536,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1379,6 +1549,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1390,6 +1562,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1401,6 +1575,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1412,6 +1588,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             // UpdateWorse + RR + KeepOther
@@ -1425,6 +1603,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1436,6 +1616,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
             experiments.push(ExperimentDescription {
                 topo,
@@ -1447,6 +1629,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: equal_inputs.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             // update worse + vary distance from event & backup
@@ -1471,6 +1655,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_1_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             let inputs_1_hop = RoutingInputs::RepeatedPrefix {
@@ -1490,6 +1676,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_1_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             let inputs_2_hop = RoutingInputs::RepeatedPrefix {
@@ -1509,6 +1697,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_2_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             let inputs_3_hop = RoutingInputs::RepeatedPrefix {
@@ -1528,6 +1718,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_3_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             let inputs_4_hop = RoutingInputs::RepeatedPrefix {
@@ -1547,6 +1739,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_4_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
 
             let inputs_4_hop = RoutingInputs::RepeatedPrefix {
@@ -1566,6 +1760,8 @@ pub fn list_experiments(filter_topo: impl AsRef<str>) -> Vec<ExperimentDescripti
                 delays: LinkDelayBuilder::new(),
                 static_routing_inputs: inputs_4_hop.clone(),
                 event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(event_inputs.clone()),
+                confidence: None,
+                precision: None,
             });
         }
     }
@@ -1582,6 +1778,8 @@ pub fn build_analyzer_from_experiment_description(
         delays,
         static_routing_inputs,
         event,
+        confidence,
+        precision,
     }: ExperimentDescription<String>,
 ) -> Option<(ExperimentDescription<RouterId>, Analyzer<TimingModel<P>>)> {
     // build topology
@@ -1643,9 +1841,16 @@ pub fn build_analyzer_from_experiment_description(
 
     // TODO handle the errors properly!
     let mut analyzer = Analyzer::new(net, event.clone(), policies, 0.95, 0.01).unwrap();
+    if let Some(confidence) = confidence {
+        analyzer.set_confidence(confidence);
+    }
+    if let Some(precision) = precision {
+        analyzer.set_precision(precision);
+    }
 
     // configure the delays
     analyzer.set_delays(net_delays);
+    analyzer.warn_if_event_has_no_effect();
 
     Some((
         ExperimentDescription {
@@ -1656,6 +1861,8 @@ pub fn build_analyzer_from_experiment_description(
             delays,
             static_routing_inputs,
             event,
+            confidence,
+            precision,
         },
         analyzer,
     ))
@@ -1689,6 +1896,7 @@ pub fn iterate_experiments(
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::analyzer::hoeffding;
     #[test]
     #[ignore]
     fn list_all_experiments() {
@@ -1700,6 +1908,8 @@ mod test {
             delays: _,
             static_routing_inputs: _,
             event: _,
+            confidence: _,
+            precision: _,
         } in list_experiments("")
         {
             println!("{topo_name}/{scenario_name}");
@@ -1718,6 +1928,8 @@ mod test {
                 delays: _,
                 static_routing_inputs: _,
                 event: _,
+                confidence: _,
+                precision: _,
             },
             _,
         ) in iterate_experiments(Filter::default())
@@ -1725,4 +1937,28 @@ mod test {
             println!("{topo_name}/{scenario_name}");
         }
     }
+
+    #[test]
+    fn accuracy_overrides() {
+        let mut experiments = list_experiments("Abilene").into_iter();
+        let default = experiments.next().unwrap();
+        let mut tight = experiments.next().unwrap();
+        tight.confidence = Some(0.99);
+        tight.precision = Some(0.001);
+
+        let (desc, analyzer) = build_analyzer_from_experiment_description(default).unwrap();
+        assert_eq!((desc.confidence, desc.precision), (None, None));
+        assert_eq!(analyzer.confidence, 0.95);
+        assert_eq!(analyzer.precision, 0.01);
+        let default_samples = analyzer.required_num_samples();
+        assert_eq!(default_samples, hoeffding(0.95, 0.01));
+
+        let (desc, analyzer) = build_analyzer_from_experiment_description(tight).unwrap();
+        assert_eq!((desc.confidence, desc.precision), (Some(0.99), Some(0.001)));
+        assert_eq!(analyzer.confidence, 0.99);
+        assert_eq!(analyzer.precision, 0.001);
+        // the tighter accuracy requires more samples
+        assert_eq!(analyzer.required_num_samples(), hoeffding(0.99, 0.001));
+        assert!(analyzer.required_num_samples() > default_samples);
+    }
 }
@@ -15,6 +15,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 //! Module facilitating to run experiments using the probabilistic transient analyzer for BGP events.
 
+pub mod analytic;
 pub mod experiment_serde;
 pub mod list_custom_topologies;
 pub mod list_experiments;
@@ -24,6 +25,7 @@ pub mod runner;
 pub mod sample;
 pub mod scenarios;
 
+pub use analytic::*;
 pub use experiment_serde::*;
 pub use list_custom_topologies::*;
 pub use list_experiments::*;
@@ -50,6 +52,39 @@ pub struct ExperimentDescription<R = RouterId> {
     pub delays: LinkDelayBuilder<R>,
     pub static_routing_inputs: RoutingInputs<R>,
     pub event: AnalyzerEvent<R>,
+    /// Overrides the default confidence of the analyzer for this experiment.
+    pub confidence: Option<f64>,
+    /// Overrides the default precision of the analyzer for this experiment.
+    pub precision: Option<f64>,
+}
+
+impl<R: AsRef<str> + Clone + Eq + std::hash::Hash> ExperimentDescription<R> {
+    /// Get the names and ids of all internal routers, as they are created when building the
+    /// network of this experiment.
+    pub fn internal_routers(&self) -> Vec<(String, RouterId)> {
+        self.topo.internal_routers()
+    }
+
+    /// Get the names and ids of all external routers, i.e., those of the topology followed by the
+    /// ones of the static routing inputs and the event, as they are created when building the
+    /// network of this experiment.
+    pub fn external_routers(&self) -> Vec<(String, RouterId)> {
+        let mut routers = self.topo.external_routers();
+        let mut next_id = self.topo.internal_routers().len() + routers.len();
+        for (router, _) in self
+            .static_routing_inputs
+            .external_routers()
+            .into_iter()
+            .chain(self.event.external_routers())
+        {
+            let name = router.as_ref();
+            if routers.iter().all(|(existing, _)| existing != name) {
+                routers.push((name.to_string(), RouterId::new(next_id)));
+                next_id += 1;
+            }
+        }
+        routers
+    }
 }
 
 /// allows filtering experiment data to be processed
@@ -60,3 +95,64 @@ pub struct Filter {
     pub scenario_end: String,
     pub sample_id: String,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn internal_and_external_routers() {
+        let e1_aspath = vec![100.into(), 100.into(), 1000.into()];
+        let e2_aspath = vec![200.into(), 200.into(), 200.into(), 1000.into()];
+        let e3_aspath = vec![300.into(), 300.into(), 300.into(), 300.into(), 1000.into()];
+        let inputs = RoutingInputs::RepeatedPrefix {
+            inner: vec![
+                ("r0_ext".to_string(), e1_aspath),
+                ("r2_ext".to_string(), e2_aspath),
+                ("r1_ext".to_string(), e3_aspath),
+            ],
+            num: 2,
+        };
+        let event_inputs = inputs.clone().filter_route(|router, _| router == "r0_ext");
+        let topo = Topology::Path(3);
+        let experiment = ExperimentDescription {
+            topo,
+            topo_name: topo.fmt(),
+            scenario_name: "Path03_FullMesh_ExtAtEndsAndCenter_WithdrawAtR0".to_string(),
+            config: ScenarioConfig::FullMesh,
+            delays: LinkDelayBuilder::new(),
+            static_routing_inputs: inputs,
+            event: AnalyzerEvent::WithdrawRoutingInputs(event_inputs),
+            confidence: None,
+            precision: None,
+        };
+
+        let internal = experiment.internal_routers();
+        let external = experiment.external_routers();
+        assert_eq!(
+            internal
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["r0", "r1", "r2"]
+        );
+        assert_eq!(
+            external
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["r0_ext", "r2_ext", "r1_ext"]
+        );
+
+        // the ids match the ones of the network built for the experiment
+        let net = experiment
+            .topo
+            .build_network(&experiment.static_routing_inputs, &experiment.event)
+            .unwrap();
+        for (name, rid) in internal.iter().chain(external.iter()) {
+            assert_eq!(net.get_router_id(name).unwrap(), *rid);
+        }
+        assert_eq!(net.internal_indices().count(), internal.len());
+        assert_eq!(net.external_indices().count(), external.len());
+    }
+}
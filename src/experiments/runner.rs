@@ -17,7 +17,7 @@
 
 use std::{
     fmt::Display,
-    path::PathBuf,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
@@ -27,8 +27,12 @@ use log::error;
 
 use bgpsim::{topology_zoo::TopologyZoo, types::Prefix};
 
-use super::scenarios::{Scenario, ScenarioError};
-use crate::analyzer::{hoeffding, num_workers, AnalysisResult};
+use super::scenarios::{Scenario, ScenarioError, ScenarioPrefix};
+use crate::{
+    analyzer::{hoeffding, num_workers, AnalysisResult, Analyzer},
+    timing_model::TimingModel,
+    Prefix as P,
+};
 
 pub fn run(out_file: &str, scenarios: Vec<Scenario>, confidences: Vec<f64>, precisions: Vec<f64>) {
     let topos = TopologyZoo::topologies_increasing_nodes()
@@ -100,16 +104,129 @@ pub fn get_data_point(
     confidence: f64,
     precision: f64,
 ) -> DataPoint {
+    build_data_point(topo, scenario, |analyzer| {
+        analyzer.set_precision(precision);
+        analyzer.set_confidence(confidence);
+    })
+}
+
+/// Run the `base_scenario` on `topo` once for every number of prefixes in `counts`, keeping the
+/// confidence and precision chosen by `Scenario::build_from`. If `num_samples` is given, each data
+/// point collects that many samples instead (see `Analyzer::set_num_samples`). The data points are
+/// returned in the order of `counts`, and can be written to a csv file with `write_data_points`.
+pub fn sweep_prefixes(
+    topo: TopologyZoo,
+    base_scenario: &Scenario,
+    counts: &[usize],
+    num_samples: Option<usize>,
+) -> Vec<DataPoint> {
+    counts
+        .iter()
+        .map(|num_prefixes| {
+            let scenario = Scenario {
+                prefix: ScenarioPrefix::MultiPrefix(*num_prefixes),
+                ..base_scenario.clone()
+            };
+            build_data_point(topo, scenario, |analyzer| {
+                if let Some(num_samples) = num_samples {
+                    analyzer.set_num_samples(num_samples);
+                }
+            })
+        })
+        .collect()
+}
+
+/// Write the `data_points` to a csv file at `path`, one row per data point.
+pub fn write_data_points(path: impl AsRef<Path>, data_points: &[DataPoint]) -> csv::Result<()> {
+    let mut csv_writer = csv::Writer::from_path(path)?;
+    csv_writer.write_record(DataPoint::record_title())?;
+    for data_point in data_points {
+        csv_writer.write_record(data_point.record())?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Run the `scenario` on every topology of TopologyZoo, in order of increasing size, keeping the
+/// confidence and precision chosen by `Scenario::build_from`. Topologies on which the event does
+/// not cause any forwarding updates are skipped. The data points can be written to a csv file with
+/// `write_data_points`.
+pub fn run_over_topology_zoo(scenario: &Scenario) -> Vec<DataPoint> {
+    run_over_topologies(TopologyZoo::topologies_increasing_nodes(), scenario, None)
+}
+
+/// Same as `run_over_topology_zoo`, but only running the `scenario` on the given `topos`. If
+/// `num_samples` is given, each data point collects that many samples instead of the number
+/// required by the confidence and precision (see `Analyzer::set_num_samples`).
+pub fn run_over_topologies(
+    topos: &[TopologyZoo],
+    scenario: &Scenario,
+    num_samples: Option<usize>,
+) -> Vec<DataPoint> {
+    topos
+        .iter()
+        .filter_map(|topo| {
+            let (maybe_analyzer, build_time) = build_analyzer(*topo, scenario);
+            if maybe_analyzer
+                .as_ref()
+                .is_ok_and(|analyzer| !analyzer.event_has_effect())
+            {
+                log::debug!("Skipping {topo:?}, as the event of {scenario:?} has no effect.");
+                return None;
+            }
+            Some(analyze_data_point(
+                *topo,
+                scenario.clone(),
+                maybe_analyzer,
+                build_time,
+                |analyzer| {
+                    if let Some(num_samples) = num_samples {
+                        analyzer.set_num_samples(num_samples);
+                    }
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Build the scenario on the topology, and analyze it after applying `configure` to the analyzer.
+fn build_data_point<F>(topo: TopologyZoo, scenario: Scenario, configure: F) -> DataPoint
+where
+    F: FnOnce(&mut Analyzer<TimingModel<P>>),
+{
+    let (maybe_analyzer, build_time) = build_analyzer(topo, &scenario);
+    analyze_data_point(topo, scenario, maybe_analyzer, build_time, configure)
+}
+
+/// Build the scenario on the topology, returning the analyzer and the time it took to build it.
+fn build_analyzer(
+    topo: TopologyZoo,
+    scenario: &Scenario,
+) -> (Result<Analyzer<TimingModel<P>>, ScenarioError>, Duration) {
     let now = Instant::now();
     let net = topo.build(bgpsim::prelude::BasicEventQueue::new());
     let geo_location = topo.geo_location();
     let maybe_analyzer = scenario.build_from(&net, &geo_location);
-    let build_time = now.elapsed();
+    (maybe_analyzer, now.elapsed())
+}
+
+/// Analyze the analyzer built from the scenario after applying `configure` to it.
+fn analyze_data_point<F>(
+    topo: TopologyZoo,
+    scenario: Scenario,
+    maybe_analyzer: Result<Analyzer<TimingModel<P>>, ScenarioError>,
+    build_time: Duration,
+    configure: F,
+) -> DataPoint
+where
+    F: FnOnce(&mut Analyzer<TimingModel<P>>),
+{
     match maybe_analyzer {
         Ok(mut analyzer) => {
-            analyzer.set_precision(precision);
-            analyzer.set_confidence(confidence);
-            let result = Ok(analyzer.analyze());
+            configure(&mut analyzer);
+            let result = analyzer
+                .analyze_with(analyzer.required_num_samples(), num_workers())
+                .map_err(ScenarioError::from);
             DataPoint {
                 topo,
                 scenario,
@@ -144,7 +261,7 @@ pub struct DataPoint {
     pub build_time: Duration,
 }
 
-const RECORD_SIZE: usize = 22;
+const RECORD_SIZE: usize = 23;
 
 impl DataPoint {
     pub fn result(self) -> Option<AnalysisResult> {
@@ -178,6 +295,7 @@ impl DataPoint {
             "t_clone",
             "t_collect",
             "dist_violation_times",
+            "scenario.num_prefixes",
         ]
     }
 
@@ -208,6 +326,7 @@ impl DataPoint {
             r_str(r, |r| r.t_cloning.as_secs_f64()),         // t_clone
             r_str(r, |r| r.t_collect.as_secs_f64()),         // t_collect
             "".to_string(), //lol_str(r, |r| &r.violation_time_distributions), // dist_violation_times
+            s.prefix.num_prefixes().to_string(), // scenario.num_prefixes
         ]
     }
 }
@@ -314,3 +433,84 @@ impl std::fmt::Display for DataPoint {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::experiments::{ScenarioConfig, ScenarioEvent, ScenarioPolicy};
+
+    #[test]
+    fn sweep_prefixes_small() {
+        let base_scenario = Scenario {
+            prefix: ScenarioPrefix::SinglePrefix,
+            config: ScenarioConfig::FullMesh,
+            event: ScenarioEvent::WithdrawBestRoute(1),
+            policy: ScenarioPolicy::Reachability(None),
+        };
+        let data_points = sweep_prefixes(TopologyZoo::Abilene, &base_scenario, &[1, 2], Some(8));
+
+        assert_eq!(data_points.len(), 2);
+        for (data_point, num_prefixes) in data_points.iter().zip([1, 2]) {
+            assert_eq!(data_point.scenario.prefix.num_prefixes(), num_prefixes);
+            assert_eq!(data_point.scenario.event, base_scenario.event);
+            let result = data_point.result.as_ref().unwrap();
+            assert!(result.is_analyzed());
+            assert_eq!(
+                result.n_samples,
+                8usize.div_ceil(num_workers()) * num_workers()
+            );
+            let prefixes = result
+                .violation_time_distributions
+                .keys()
+                .map(|(_, prefix)| *prefix)
+                .unique()
+                .count();
+            assert_eq!(prefixes, num_prefixes);
+            // appended as the last column to keep the positions of the existing columns
+            assert_eq!(
+                data_point.record()[RECORD_SIZE - 1],
+                num_prefixes.to_string()
+            );
+        }
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let path = dir.join("sweep.csv");
+        write_data_points(&path, &data_points).unwrap();
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        let headers = reader.headers().unwrap();
+        assert_eq!(headers.len(), RECORD_SIZE);
+        assert_eq!(&headers[3], "scenario.num_routes");
+        assert_eq!(&headers[4], "scenario.event");
+        assert_eq!(&headers[RECORD_SIZE - 1], "scenario.num_prefixes");
+        assert_eq!(reader.records().count(), 2);
+    }
+
+    #[test]
+    fn run_over_small_topologies() {
+        let scenario = Scenario {
+            prefix: ScenarioPrefix::SinglePrefix,
+            config: ScenarioConfig::FullMesh,
+            event: ScenarioEvent::WithdrawBestRoute(1),
+            policy: ScenarioPolicy::Reachability(None),
+        };
+        let topos = [TopologyZoo::Abilene, TopologyZoo::Sprint];
+        let data_points = run_over_topologies(&topos, &scenario, Some(8));
+
+        // withdrawing the best route always has an effect, so no topology is skipped
+        assert_eq!(
+            data_points.iter().map(|d| d.topo).collect_vec(),
+            topos.to_vec()
+        );
+        for data_point in data_points.iter() {
+            assert_eq!(data_point.scenario, scenario);
+            assert!(data_point.result.is_ok());
+            let result = data_point.result.as_ref().unwrap();
+            assert!(result.is_analyzed());
+            assert_eq!(
+                result.n_samples,
+                8usize.div_ceil(num_workers()) * num_workers()
+            );
+        }
+    }
+}
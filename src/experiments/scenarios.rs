@@ -49,12 +49,89 @@ pub struct Scenario {
 }
 
 impl Scenario {
-    /// Return a human-readable name for this scenario.
+    /// Return a human-readable name for this scenario, see `to_name`.
     pub fn name(&self) -> String {
-        format!(
-            "{:?}_{:?}_{:?}_{:?}",
-            self.prefix, self.config, self.event, self.policy
-        )
+        self.to_name()
+    }
+
+    /// Get the canonical name of the scenario, e.g.,
+    /// `ReflectorsSeattleNewYork_Prefix100_WithdrawBestRoute2_Reachability5`. The parts are encoded
+    /// like in the names of the experiments, i.e., the configuration as `FullMesh`,
+    /// `RouteReflection{k}` or `Reflectors{routers}`, and the prefixes as `Prefix{k}` (or
+    /// `SinglePrefix`). The events and policies are followed by their parameter, if any. Use
+    /// `from_name` instead of parsing these names by hand.
+    pub fn to_name(&self) -> String {
+        let config = match &self.config {
+            ScenarioConfig::FullMesh => "FullMesh".to_string(),
+            ScenarioConfig::RouteReflection(k) => format!("RouteReflection{k}"),
+            ScenarioConfig::RouteReflectors(rrs) => format!("Reflectors{}", rrs.join("")),
+        };
+        let prefix = match self.prefix {
+            ScenarioPrefix::SinglePrefix => "SinglePrefix".to_string(),
+            ScenarioPrefix::MultiPrefix(k) => format!("Prefix{k}"),
+        };
+        let event = match self.event {
+            ScenarioEvent::RemoveLink
+            | ScenarioEvent::AddLink
+            | ScenarioEvent::RemoveExternalLink
+            | ScenarioEvent::AddExternalLink => self.event.name().to_string(),
+            _ => format!("{}{}", self.event.name(), self.event.num_routes()),
+        };
+        let policy = match self.policy.num_routers() {
+            Some(k) => format!("{}{k}", self.policy.name()),
+            None => self.policy.name().to_string(),
+        };
+        format!("{config}_{prefix}_{event}_{policy}")
+    }
+
+    /// Parse a scenario name created by `to_name`. The names of the route reflectors are
+    /// concatenated in the name, so `routers` must contain the names of the routers that may be
+    /// route reflectors in order to split them again. Returns `None` if the name does not follow
+    /// the format.
+    pub fn from_name(name: &str, routers: &[&str]) -> Option<Self> {
+        let mut parts = name.split('_');
+        let config = match parts.next()? {
+            "FullMesh" => ScenarioConfig::FullMesh,
+            c if c.starts_with("RouteReflection") => {
+                ScenarioConfig::RouteReflection(c["RouteReflection".len()..].parse().ok()?)
+            }
+            c => ScenarioConfig::RouteReflectors(split_router_names(
+                c.strip_prefix("Reflectors")?,
+                routers,
+            )?),
+        };
+        let prefix = match parts.next()? {
+            "SinglePrefix" => ScenarioPrefix::SinglePrefix,
+            p => ScenarioPrefix::MultiPrefix(p.strip_prefix("Prefix")?.parse().ok()?),
+        };
+        let event = match split_parameter(parts.next()?)? {
+            ("WithdrawBestRoute", Some(k)) => ScenarioEvent::WithdrawBestRoute(k),
+            ("WithdrawSimilarRoute", Some(k)) => ScenarioEvent::WithdrawSimilarRoute(k),
+            ("NewBestRoute", Some(k)) => ScenarioEvent::NewBestRoute(k),
+            ("NewSimilarRoute", Some(k)) => ScenarioEvent::NewSimilarRoute(k),
+            ("IncreaseRoutePreference", Some(k)) => ScenarioEvent::IncreaseRoutePreference(k),
+            ("DecreaseRoutePreference", Some(k)) => ScenarioEvent::DecreaseRoutePreference(k),
+            ("RemoveLink", None) => ScenarioEvent::RemoveLink,
+            ("AddLink", None) => ScenarioEvent::AddLink,
+            ("RemoveExternalLink", None) => ScenarioEvent::RemoveExternalLink,
+            ("AddExternalLink", None) => ScenarioEvent::AddExternalLink,
+            _ => return None,
+        };
+        let policy = match split_parameter(parts.next()?)? {
+            ("LoopFreedom", k) => ScenarioPolicy::LoopFreedom(k),
+            ("Reachability", k) => ScenarioPolicy::Reachability(k),
+            ("IgnoreWorstRoute", k) => ScenarioPolicy::IgnoreWorstRoute(k),
+            _ => return None,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            prefix,
+            config,
+            event,
+            policy,
+        })
     }
 
     /// Build the scenario from the given topology, and return an analyzer.
@@ -132,6 +209,34 @@ impl Scenario {
     }
 }
 
+/// Split a part of a scenario name into its name and its trailing numeric parameter, if any.
+/// Returns `None` if the parameter is not a number.
+fn split_parameter(part: &str) -> Option<(&str, Option<usize>)> {
+    let (name, parameter) = part.split_at(
+        part.find(|c: char| c.is_ascii_digit())
+            .unwrap_or(part.len()),
+    );
+    if parameter.is_empty() {
+        Some((name, None))
+    } else {
+        Some((name, Some(parameter.parse().ok()?)))
+    }
+}
+
+/// Split a concatenation of router names, always taking the longest matching router name first.
+fn split_router_names(mut s: &str, routers: &[&str]) -> Option<Vec<String>> {
+    let mut result = Vec::new();
+    while !s.is_empty() {
+        let router = routers
+            .iter()
+            .filter(|r| !r.is_empty() && s.starts_with(**r))
+            .max_by_key(|r| r.len())?;
+        result.push(router.to_string());
+        s = &s[router.len()..];
+    }
+    Some(result)
+}
+
 /// Configuration for how to setup the network configuration
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ScenarioConfig {
@@ -209,6 +314,19 @@ impl ScenarioConfig {
 
         Ok(())
     }
+
+    /// Replace the iBGP configuration of a given network by this config. This removes all iBGP
+    /// sessions between internal routers before applying the config, e.g., to turn the full mesh
+    /// of the topologies in `list_custom_topologies` into a route reflection topology.
+    pub fn reapply_to(&self, net: &mut Network<P>) -> Result<(), NetworkError> {
+        let routers = net.internal_indices().collect_vec();
+        for (i, src) in routers.iter().enumerate() {
+            for dst in &routers[i + 1..] {
+                net.set_bgp_session(*src, *dst, None)?;
+            }
+        }
+        self.apply_to(net)
+    }
 }
 
 /// Enumeration of several events.
@@ -632,4 +750,117 @@ pub enum ScenarioError {
     /// The topology seems to be not connected.
     #[error("The topology seems to be not connected.")]
     NotConnected,
+    /// The analysis could not read or write its stats store.
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        experiments::{list_custom_topologies, list_scenarios},
+        util,
+    };
+
+    #[test]
+    fn route_reflectors_on_custom_topology() {
+        let prefixes = ScenarioPrefix::SinglePrefix.prefixes();
+        let (_, mut net, ..) = list_custom_topologies(&prefixes)
+            .into_iter()
+            .find(|(name, ..)| *name == "Path04_FullMesh_ExtAtEnds")
+            .unwrap();
+        let [r0, r1, r2, r3] = ["r0", "r1", "r2", "r3"].map(|r| net.get_router_id(r).unwrap());
+
+        ScenarioConfig::RouteReflectors(vec!["r1".to_string(), "r2".to_string()])
+            .reapply_to(&mut net)
+            .unwrap();
+
+        // the reflectors peer with each other, and all other routers are clients of both
+        let mut ibgp_sessions = net
+            .get_bgp_sessions()
+            .into_iter()
+            .filter(|(_, _, ty, _)| *ty != BgpSessionType::EBgp)
+            .map(|(src, dst, ty, _)| match ty {
+                BgpSessionType::IBgpPeer => (src.min(dst), src.max(dst), ty),
+                _ => (src, dst, ty),
+            })
+            .collect_vec();
+        ibgp_sessions.sort_by_key(|(src, dst, _)| (*src, *dst));
+        ibgp_sessions.dedup();
+        assert_eq!(
+            ibgp_sessions,
+            vec![
+                (r1, r0, BgpSessionType::IBgpClient),
+                (r1, r2, BgpSessionType::IBgpPeer),
+                (r1, r3, BgpSessionType::IBgpClient),
+                (r2, r0, BgpSessionType::IBgpClient),
+                (r2, r3, BgpSessionType::IBgpClient),
+            ]
+        );
+
+        // the external sessions are untouched, and all routers still reach the prefix
+        assert!(net
+            .get_bgp_sessions()
+            .iter()
+            .any(|(_, _, ty, _)| *ty == BgpSessionType::EBgp));
+        let mut fw_state = net.get_forwarding_state();
+        for r in [r0, r1, r2, r3] {
+            assert!(fw_state.get_paths(r, prefixes[0]).is_ok());
+        }
+    }
+
+    #[test]
+    fn scenario_name_round_trip() {
+        let routers = ["Atlanta", "NewYork", "New", "Seattle"];
+        for scenario in list_scenarios() {
+            let name = scenario.to_name();
+            assert_eq!(
+                Scenario::from_name(&name, &routers),
+                Some(scenario),
+                "{name}"
+            );
+        }
+
+        let scenario = Scenario {
+            prefix: ScenarioPrefix::MultiPrefix(100),
+            config: ScenarioConfig::RouteReflectors(vec![
+                "Seattle".to_string(),
+                "NewYork".to_string(),
+            ]),
+            event: ScenarioEvent::WithdrawBestRoute(2),
+            policy: ScenarioPolicy::Reachability(Some(5)),
+        };
+        let name = "ReflectorsSeattleNewYork_Prefix100_WithdrawBestRoute2_Reachability5";
+        assert_eq!(scenario.to_name(), name);
+        assert_eq!(Scenario::from_name(name, &routers), Some(scenario));
+
+        let scenario = Scenario {
+            prefix: ScenarioPrefix::SinglePrefix,
+            config: ScenarioConfig::FullMesh,
+            event: ScenarioEvent::RemoveExternalLink,
+            policy: ScenarioPolicy::IgnoreWorstRoute(None),
+        };
+        let name = "FullMesh_SinglePrefix_RemoveExternalLink_IgnoreWorstRoute";
+        assert_eq!(scenario.to_name(), name);
+        assert_eq!(Scenario::from_name(name, &routers), Some(scenario));
+        assert_eq!(util::get_num_prefixes(name), None);
+        assert_eq!(
+            util::get_num_prefixes("FullMesh_Prefix100_WithdrawBestRoute2_Reachability"),
+            Some(100)
+        );
+
+        for name in [
+            "",
+            "FullMesh_Prefix1_WithdrawBestRoute2",
+            "FullMesh_Prefix1_WithdrawBestRoute2_Reachability_Other",
+            "FullMesh_Prefix1_WithdrawBestRoute_Reachability",
+            "FullMesh_Prefix1_RemoveLink2_Reachability",
+            "FullMesh_Prefix1x_WithdrawBestRoute2_Reachability",
+            "ReflectorsDenver_Prefix1_WithdrawBestRoute2_Reachability",
+            "ExtLosAngelesKansasCity_FullMesh_Prefix1_PhysicalExternalWithdrawAllPrefixesAtLosAngeles",
+        ] {
+            assert_eq!(Scenario::from_name(name, &routers), None, "{name}");
+        }
+    }
 }
@@ -51,6 +51,11 @@ struct ExtractedMeasurement {
     #[allow(dead_code)]
     updated: bool,
     t0: f64,
+    /// Checksum over the inputs of the sample (see `util::sample_checksum`), allowing to skip
+    /// samples whose inputs did not change since they were last processed. Only computed if the
+    /// replace mode depends on it, i.e., for `ReplaceMode::IfChanged`.
+    #[allow(dead_code)]
+    checksum: Option<u32>,
 }
 
 /// Extract BGP updates from the pcaps matching the given filter.
@@ -123,6 +128,7 @@ pub(crate) async fn extract_bgp_updates_to_csv(
                         num_prefixes: get_num_prefixes(scenario_name).unwrap(),
                         updated: false,
                         t0: record.event_start,
+                        checksum: None,
                     });
                     continue 'samples;
                 }
@@ -133,7 +139,7 @@ pub(crate) async fn extract_bgp_updates_to_csv(
                 let mut hardware_mapping_path = eval_path.to_path_buf();
                 hardware_mapping_path.push(&record.hardware_mapping_filename);
                 let serialized_hardware_mapping =
-                    fs::read_to_string(&hardware_mapping_path).unwrap();
+                    util::read_maybe_gzipped(&hardware_mapping_path).unwrap();
                 let hardware_mapping: HardwareMapping =
                     serde_json::from_str(&serialized_hardware_mapping).unwrap();
 
@@ -365,6 +371,7 @@ pub(crate) async fn extract_bgp_updates_to_csv(
                     num_prefixes: get_num_prefixes(scenario_name).unwrap(),
                     updated: true,
                     t0: record.event_start,
+                    checksum: None,
                 });
             }
 
@@ -393,14 +400,51 @@ struct Args {
     /// Overwrite the scenario_id filter for extracting BGP updates.
     #[arg(short = 'i', long = "sample", default_value = "")]
     sample_id: String,
+    /// Only extract the time series of scenarios with at least this many prefixes.
+    #[arg(long = "min-prefixes")]
+    min_prefixes: Option<usize>,
+    /// Only extract the time series of scenarios with at most this many prefixes.
+    #[arg(long = "max-prefixes")]
+    max_prefixes: Option<usize>,
     /// Replace all files, instead of skipping those that already exist
     #[arg(long)]
     replace: bool,
+    /// Replace the extracted time series of forwarding updates only for samples whose inputs
+    /// changed since they were last extracted. BGP updates extracted from pcaps are not replaced.
+    #[arg(long = "replace-if-newer", conflicts_with = "replace")]
+    replace_if_newer: bool,
     /// directly show the plot
     #[arg(long)]
     show_plot: bool,
 }
 
+impl Args {
+    /// Get the mode in which existing time series of forwarding updates are replaced.
+    fn replace_mode(&self) -> time_series_of_fw_updates::ReplaceMode {
+        use time_series_of_fw_updates::ReplaceMode;
+        if self.replace {
+            ReplaceMode::Always
+        } else if self.replace_if_newer {
+            ReplaceMode::IfChanged
+        } else {
+            ReplaceMode::Never
+        }
+    }
+
+    /// Check whether the number of prefixes encoded in `scenario_name` lies within the range given
+    /// by `--min-prefixes` and `--max-prefixes`.
+    fn num_prefixes_in_range(&self, scenario_name: &str) -> bool {
+        if self.min_prefixes.is_none() && self.max_prefixes.is_none() {
+            return true;
+        }
+        let Some(num_prefixes) = get_num_prefixes(scenario_name) else {
+            return false;
+        };
+        self.min_prefixes.is_none_or(|min| num_prefixes >= min)
+            && self.max_prefixes.is_none_or(|max| num_prefixes <= max)
+    }
+}
+
 #[tokio::main]
 #[allow(unused)]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -443,6 +487,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
              num_prefixes,
              updated,
              t0,
+             ..
          }| {
             if updated || args.replace {
                 visualize_bgp_updates(
@@ -460,3 +505,58 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn num_prefixes_in_range() {
+        let scenarios = [
+            "ExtLosAngelesKansasCity_FullMesh_Prefix1_WithdrawAtLosAngeles",
+            "ExtLosAngelesKansasCity_FullMesh_Prefix10_WithdrawAtLosAngeles",
+            "ExtLosAngelesKansasCity_FullMesh_Prefix100_WithdrawAtLosAngeles",
+            "ExtLosAngelesKansasCity_FullMesh_Prefix1000_WithdrawAtLosAngeles",
+            "ExtLosAngelesKansasCity_FullMesh_Prefix100000_WithdrawAtLosAngeles",
+        ];
+        let selected = |args: &[&str]| {
+            let args = Args::parse_from(
+                std::iter::once("extract_bgp_updates").chain(args.iter().copied()),
+            );
+            scenarios
+                .iter()
+                .map(|scenario| args.num_prefixes_in_range(scenario))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(selected(&[]), vec![true; 5]);
+        assert_eq!(
+            selected(&["--min-prefixes", "10", "--max-prefixes", "1000"]),
+            vec![false, true, true, true, false]
+        );
+        assert_eq!(
+            selected(&["--min-prefixes", "1000"]),
+            vec![false, false, false, true, true]
+        );
+        assert_eq!(
+            selected(&["--max-prefixes", "10"]),
+            vec![true, true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn replace_mode() {
+        use time_series_of_fw_updates::ReplaceMode;
+        let mode = |args: &[&str]| {
+            Args::parse_from(std::iter::once("extract_bgp_updates").chain(args.iter().copied()))
+                .replace_mode()
+        };
+        assert_eq!(mode(&[]), ReplaceMode::Never);
+        assert_eq!(mode(&["--replace"]), ReplaceMode::Always);
+        assert_eq!(mode(&["--replace-if-newer"]), ReplaceMode::IfChanged);
+        assert!(
+            Args::try_parse_from(["extract_bgp_updates", "--replace", "--replace-if-newer"])
+                .is_err()
+        );
+    }
+}
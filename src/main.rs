@@ -19,6 +19,7 @@ use std::time::Instant;
 use rayon::{iter::ParallelIterator, prelude::IntoParallelIterator};
 
 use trix::{
+    analyzer::num_workers,
     experiments::{
         build_analyzer_from_experiment_description,
         //runner::get_data_point,
@@ -26,13 +27,34 @@ use trix::{
         DataPoint,
         ExperimentDescription,
     },
-    prelude::AnalyzerEvent,
+    prelude::{AnalysisResult, AnalyzerEvent},
     routing_inputs::RoutingInputs,
     topology::{LinkDelayBuilder, Topology},
     util,
 };
 use bgpsim::topology_zoo::TopologyZoo;
 
+/// Percentiles of the pooled violation times reported for each point of the sweep.
+const PERCENTILES: [usize; 13] = [0, 1, 5, 10, 25, 45, 50, 55, 75, 90, 95, 99, 100];
+
+/// Format the row of a single point of the sweep, containing the `PERCENTILES` and the mean of all
+/// violation times. Results without any samples yield a "no samples" row instead.
+fn summary_row(num_prefixes: usize, result: &AnalysisResult) -> String {
+    let Some(percentiles) = result.percentiles(&PERCENTILES) else {
+        return format!("{num_prefixes},no samples");
+    };
+    let mean = result.mean_violation_time().unwrap_or_default();
+    format!(
+        "{num_prefixes},{}",
+        percentiles
+            .iter()
+            .chain([&mean])
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     util::init_logging();
     util::set_conf_dir()?;
@@ -94,6 +116,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             //event: AnalyzerEvent::PhysicalExternalWithdrawRoutingInputs(la_inputs.clone()), // Withdraw
             //event: AnalyzerEvent::PhysicalExternalUpdateWorseRoutingInputs(worse_inputs.clone()), // UpdateWorse
             event: AnalyzerEvent::PhysicalExternalAnnounceRoutingInputs(la_inputs.clone()), // Announce
+            confidence: None,
+            precision: None,
         };
 
         let now = Instant::now();
@@ -106,47 +130,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 delays: _,
                 static_routing_inputs: _,
                 event: _,
+                confidence: _,
+                precision: _,
             },
             analyzer,
         ) = build_analyzer_from_experiment_description(experiment).unwrap();
         let build_time = now.elapsed();
 
         //log::info!("running scenario {topo_name}/{scenario_name}");
-        let result = analyzer.analyze();
+        let result = analyzer
+            .analyze_with(analyzer.required_num_samples(), num_workers())
+            .unwrap();
         //log::debug!("result: {result:?}");
 
-        let mut samples: Vec<f64> = result
-            .violation_time_distributions
-            .iter()
-            .flat_map(|((_rid, _prefix), samples)| samples.iter())
-            .cloned()
-            .collect();
-        samples.sort_by(f64::total_cmp);
-
-        let percentiles = [
-            samples[0],
-            samples[samples.len() / 100],
-            samples[5 * samples.len() / 100],
-            samples[10 * samples.len() / 100],
-            samples[25 * samples.len() / 100],
-            samples[45 * samples.len() / 100],
-            samples[50 * samples.len() / 100],
-            samples[55 * samples.len() / 100],
-            samples[75 * samples.len() / 100],
-            samples[90 * samples.len() / 100],
-            samples[95 * samples.len() / 100],
-            samples[99 * samples.len() / 100],
-            samples[samples.len() - 1],
-            samples.iter().sum::<f64>() / samples.len() as f64,
-        ];
-        println!(
-            "{num_prefixes},{}",
-            percentiles
-                .iter()
-                .map(|x| x.to_string())
-                .collect::<Vec<_>>()
-                .join(",")
-        );
+        println!("{}", summary_row(num_prefixes, &result));
 
         /*
         let data_point = DataPoint {
@@ -166,3 +163,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use bgpsim::types::RouterId;
+    use trix::Prefix;
+
+    use super::*;
+
+    #[test]
+    fn summary_row_without_samples() {
+        let mut result = AnalysisResult::default();
+        assert_eq!(summary_row(1, &result), "1,no samples");
+        result.violation_time_distributions =
+            BTreeMap::from([((RouterId::from(0), Prefix::from(0)), vec![])]);
+        assert_eq!(summary_row(1, &result), "1,no samples");
+
+        result.violation_time_distributions =
+            BTreeMap::from([((RouterId::from(0), Prefix::from(0)), vec![1.0, 3.0])]);
+        assert_eq!(summary_row(2, &result), "2,1,1,1,1,1,1,3,3,3,3,3,3,3,2");
+    }
+}
@@ -181,7 +181,7 @@ fn process_sample(
 
     // open hardware mapping
     let serialized_hardware_mapping =
-        std::fs::read_to_string(eval_path.then(&record.hardware_mapping_filename))
+        util::read_maybe_gzipped(&eval_path.then(&record.hardware_mapping_filename))
             .context("Cannot read the hardware mapping!")?;
     let mapping: HardwareMapping = serde_json::from_str(&serialized_hardware_mapping)
         .context("Cannot deserialize the hardware mapping")?;
@@ -253,7 +253,8 @@ pub(crate) async fn process_pcaps(
                 let mut hardware_mapping_path = data_path.clone();
                 hardware_mapping_path.push(&record.hardware_mapping_filename);
                 log::trace!("reading hw mapping from {hardware_mapping_path:?}");
-                let serialized_hardware_mapping = fs::read_to_string(&hardware_mapping_path).unwrap();
+                let serialized_hardware_mapping =
+                    util::read_maybe_gzipped(&hardware_mapping_path).unwrap();
                 let hardware_mapping: HardwareMapping =
                     serde_json::from_str(&serialized_hardware_mapping).unwrap();
 
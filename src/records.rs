@@ -14,14 +14,19 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 //! Module defining record data types to (de-)serialize BGP and DP updates to CSV.
-use std::net::Ipv4Addr;
+use std::{
+    collections::HashMap,
+    fs, io,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+};
 
 use mac_address::MacAddress;
 use serde::{de::IntoDeserializer, Deserialize, Deserializer, Serialize, Serializer};
 
 use bgpsim::types::RouterId;
 
-use crate::Prefix;
+use crate::{event::AnalyzerEvent, Prefix};
 
 #[derive(
     Debug,
@@ -199,6 +204,176 @@ pub struct PathRecord {
     pub path_names: Vec<Option<Router>>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Context of a csv of `PathRecord`s, e.g., the path intervals written by
+/// `compute_violation_times`. Stored as a JSON sidecar next to the csv (see `sidecar_path`), such
+/// that the csv itself remains readable by any `PathRecord` reader.
+pub struct IntervalsMetadata {
+    pub scenario: String,
+    pub event: AnalyzerEvent<RouterId>,
+    /// Source of the `TimingModel` used to compute the intervals, e.g., `geo_location` or `delays`.
+    pub timing_model: String,
+}
+
+impl IntervalsMetadata {
+    /// Path of the sidecar belonging to the csv at `csv_path`, i.e., with the extension replaced
+    /// by `meta.json`.
+    pub fn sidecar_path(csv_path: impl AsRef<Path>) -> PathBuf {
+        csv_path.as_ref().with_extension("meta.json")
+    }
+
+    /// Write the metadata to the sidecar of the csv at `csv_path`.
+    pub fn write(&self, csv_path: impl AsRef<Path>) -> io::Result<()> {
+        let file = fs::File::create(Self::sidecar_path(csv_path))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Read the metadata from the sidecar of the csv at `csv_path`, or `None` if the csv has no
+    /// sidecar.
+    pub fn read(csv_path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        let sidecar = Self::sidecar_path(csv_path);
+        if !sidecar.exists() {
+            return Ok(None);
+        }
+        let file = io::BufReader::new(fs::File::open(sidecar)?);
+        Ok(Some(serde_json::from_reader(file)?))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+/// `PathRecord` with its path interned into a `PathEntry` of the path dictionary, see
+/// `write_compact_path_records`.
+pub struct CompactPathRecord {
+    pub time: f64,
+    pub src: RouterId,
+    pub src_name: Option<Router>,
+    pub prefix: Ipv4Addr,
+    #[serde(default)]
+    pub seq: Option<u64>,
+    /// Index of the path in the path dictionary.
+    pub path_id: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+/// Entry of the path dictionary referenced by `CompactPathRecord::path_id`.
+pub struct PathEntry {
+    pub path_id: usize,
+    #[serde(
+        serialize_with = "serialize_rid_list",
+        deserialize_with = "deserialize_rid_list"
+    )]
+    pub path: Vec<RouterId>,
+    #[serde(
+        serialize_with = "serialize_option_list",
+        deserialize_with = "deserialize_option_list"
+    )]
+    pub path_names: Vec<Option<Router>>,
+}
+
+/// Path of the path dictionary belonging to the compact csv at `csv_path`, i.e., with the
+/// extension replaced by `paths.csv`.
+pub fn path_dictionary_path(csv_path: impl AsRef<Path>) -> PathBuf {
+    csv_path.as_ref().with_extension("paths.csv")
+}
+
+/// Write `records` to `csv_path` as `CompactPathRecord`s, storing every distinct path only once
+/// in the path dictionary next to it (see `path_dictionary_path`). Use
+/// `read_compact_path_records` to load them again. For 1000 updates alternating between two paths
+/// of seven routers, this reduces the size by about 70% compared to plain `PathRecord`s.
+pub fn write_compact_path_records<'a>(
+    csv_path: impl AsRef<Path>,
+    records: impl IntoIterator<Item = &'a PathRecord>,
+) -> Result<(), csv::Error> {
+    let mut dictionary: HashMap<(&[RouterId], &[Option<Router>]), usize> = HashMap::new();
+    let mut entries = Vec::new();
+
+    let mut csv = csv::WriterBuilder::new()
+        .has_headers(true)
+        .delimiter(b';')
+        .from_path(csv_path.as_ref())?;
+    for record in records {
+        let key = (record.path.as_slice(), record.path_names.as_slice());
+        let path_id = *dictionary.entry(key).or_insert_with(|| {
+            entries.push(PathEntry {
+                path_id: entries.len(),
+                path: record.path.clone(),
+                path_names: record.path_names.clone(),
+            });
+            entries.len() - 1
+        });
+        csv.serialize(CompactPathRecord {
+            time: record.time,
+            src: record.src,
+            src_name: record.src_name,
+            prefix: record.prefix,
+            seq: record.seq,
+            path_id,
+        })?;
+    }
+    csv.flush()?;
+
+    let mut csv = csv::WriterBuilder::new()
+        .has_headers(true)
+        .delimiter(b';')
+        .from_path(path_dictionary_path(csv_path))?;
+    for entry in entries {
+        csv.serialize(entry)?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+/// Read the `CompactPathRecord`s written by `write_compact_path_records` to `csv_path` and
+/// expand them back into `PathRecord`s using the path dictionary.
+pub fn read_compact_path_records(
+    csv_path: impl AsRef<Path>,
+) -> Result<Vec<PathRecord>, csv::Error> {
+    let dictionary = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .delimiter(b';')
+        .from_path(path_dictionary_path(csv_path.as_ref()))?
+        .deserialize::<PathEntry>()
+        .map(|entry| entry.map(|e| (e.path_id, e)))
+        .collect::<Result<HashMap<_, _>, _>>()?;
+
+    csv::ReaderBuilder::new()
+        .has_headers(true)
+        .delimiter(b';')
+        .from_path(csv_path)?
+        .deserialize::<CompactPathRecord>()
+        .map(|record| -> Result<PathRecord, csv::Error> {
+            let record = record?;
+            let entry = dictionary.get(&record.path_id).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("path {} missing in the path dictionary", record.path_id),
+                )
+            })?;
+            Ok(PathRecord {
+                time: record.time,
+                src: record.src,
+                src_name: record.src_name,
+                prefix: record.prefix,
+                seq: record.seq,
+                path: entry.path.clone(),
+                path_names: entry.path_names.clone(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+/// Violation time of a single policy in a single sample of `Analyzer::analyze`, in long format.
+pub struct SampleRecord {
+    pub sample: usize,
+    /// Index of the policy in `Analyzer::policies`.
+    pub policy: usize,
+    pub router: Option<String>,
+    pub prefix: Option<Ipv4Addr>,
+    pub violation_time: f64,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 /// Timestamps when the reachability of router for a prefix changes.
 pub struct DPRecord {
@@ -379,4 +554,95 @@ mod test {
         let de: PathRecord = csv.deserialize().next().unwrap().unwrap();
         assert_eq!(de, x);
     }
+
+    #[test]
+    fn intervals_metadata() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let csv_path = dir.join("path_intervals_sample.csv");
+        assert!(IntervalsMetadata::read(&csv_path).unwrap().is_none());
+
+        let metadata = IntervalsMetadata {
+            scenario: "ExtAtLeaves_WithdrawBestRoute".to_string(),
+            event: AnalyzerEvent::WithdrawRoute(vec![Prefix::from(0)], 3.into(), vec![]),
+            timing_model: "geo_location".to_string(),
+        };
+        metadata.write(&csv_path).unwrap();
+        assert_eq!(
+            IntervalsMetadata::sidecar_path(&csv_path),
+            dir.join("path_intervals_sample.meta.json")
+        );
+
+        let read = IntervalsMetadata::read(&csv_path).unwrap().unwrap();
+        assert_eq!(read.scenario, metadata.scenario);
+        assert_eq!(read.timing_model, metadata.timing_model);
+        assert_eq!(format!("{:?}", read.event), format!("{:?}", metadata.event));
+    }
+
+    #[test]
+    fn compact_path_records() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+
+        // representative trace: many updates of few prefixes, switching between few long paths
+        let paths: Vec<(Vec<RouterId>, Vec<Option<Router>>)> = [
+            vec![
+                (0, Router::Seattle),
+                (1, Router::Denver),
+                (2, Router::KansasCity),
+                (3, Router::Indianapolis),
+                (4, Router::Chicago),
+                (5, Router::NewYork),
+                (6, Router::NewYorkExt),
+            ],
+            vec![
+                (0, Router::Seattle),
+                (7, Router::Sunnyvale),
+                (8, Router::LosAngeles),
+                (9, Router::Houston),
+                (10, Router::Atlanta),
+                (11, Router::WashingtonDC),
+                (12, Router::WashingtonDCExt),
+            ],
+        ]
+        .into_iter()
+        .map(|p| {
+            p.into_iter()
+                .map(|(r, n)| (RouterId::from(r), Some(n)))
+                .unzip()
+        })
+        .collect();
+        let records: Vec<PathRecord> = (0..1000)
+            .map(|i| PathRecord {
+                time: i as f64 * 0.001,
+                src: 0.into(),
+                src_name: Some(Router::Seattle),
+                prefix: Ipv4Addr::new(100, 0, (i % 100) as u8, 0),
+                seq: Some(i as u64),
+                path: paths[i % 2].0.clone(),
+                path_names: paths[i % 2].1.clone(),
+            })
+            .collect();
+
+        let plain_path = dir.join("path_intervals.csv");
+        let mut csv = csv::WriterBuilder::new()
+            .has_headers(true)
+            .delimiter(b';')
+            .from_path(&plain_path)
+            .unwrap();
+        records.iter().for_each(|r| csv.serialize(r).unwrap());
+        csv.flush().unwrap();
+
+        let compact_path = dir.join("path_intervals_compact.csv");
+        write_compact_path_records(&compact_path, &records).unwrap();
+        assert_eq!(read_compact_path_records(&compact_path).unwrap(), records);
+
+        let plain_size = fs::metadata(&plain_path).unwrap().len();
+        let compact_size = fs::metadata(&compact_path).unwrap().len()
+            + fs::metadata(path_dictionary_path(&compact_path))
+                .unwrap()
+                .len();
+        // about 119 kB vs. 35 kB
+        assert!(3 * compact_size < plain_size);
+    }
 }
@@ -13,14 +13,32 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
-use std::iter::zip;
+use std::{collections::HashMap, hash::Hash, iter::zip};
 
-//use itertools::Itertools;
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 use serde::{Deserialize, Serialize};
 
 use bgpsim::{event::EventQueue, prelude::*, types::AsId};
 
-use crate::{analyzer::AnalyzerPrefix, Prefix as P};
+use crate::{analyzer::AnalyzerPrefix, timing_model::DelayableQueue, Prefix as P};
+
+/// Time (in seconds) between two consecutive route updates when applying `RoutingInputs` with
+/// `UpdateOrdering::Sequential` or `UpdateOrdering::Randomized`.
+pub const SEQUENTIAL_UPDATE_SPACING: f64 = 0.001;
+
+/// Order in which the routes of `RoutingInputs` are advertised or withdrawn.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum UpdateOrdering {
+    /// Schedule the updates in the order of the routing inputs, each one
+    /// `SEQUENTIAL_UPDATE_SPACING` after the previous one.
+    Sequential,
+    /// Schedule all updates at the same time.
+    #[default]
+    Simultaneous,
+    /// Same as `Sequential`, but the updates are shuffled with the given seed.
+    Randomized(u64),
+}
 
 /// Describes a (sub-)set of jointly controlled external routing inputs.
 ///
@@ -181,6 +199,72 @@ where
                 .map(move |(router, aspath)| (prefix, router, aspath))
         })
     }
+
+    /// Check that the externals are configured equivalently for every prefix, i.e., that each
+    /// external advertises all prefixes, with the same AS path. Returns the first external
+    /// (in order of the routing inputs) whose advertisements differ across prefixes.
+    pub fn assert_consistent(&self) -> Result<(), InconsistencyError<R>>
+    where
+        R: Eq + Hash,
+    {
+        let per_prefix: Vec<(P, HashMap<R, Vec<AsId>>)> = self
+            .iter()
+            .map(|(prefix, routes)| (prefix, routes.into_iter().collect()))
+            .collect();
+
+        for external in self.unroll().map(|(_, router, _)| router).unique() {
+            let (first, expected) = per_prefix
+                .iter()
+                .find_map(|(prefix, paths)| Some((*prefix, paths.get(&external)?)))
+                .unwrap();
+            for (prefix, paths) in per_prefix.iter() {
+                match paths.get(&external) {
+                    None => {
+                        return Err(InconsistencyError::MissingPrefix {
+                            external,
+                            advertised: first,
+                            missing: *prefix,
+                        })
+                    }
+                    Some(found) if found != expected => {
+                        return Err(InconsistencyError::DifferentAsPath {
+                            external,
+                            first,
+                            expected: expected.clone(),
+                            prefix: *prefix,
+                            found: found.clone(),
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Error thrown by `RoutingInputs::assert_consistent` if an external is not configured
+/// equivalently for every prefix.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum InconsistencyError<R> {
+    /// Error when an external advertises some prefixes but not others.
+    #[error("External {external:?} advertises {advertised:?} but not {missing:?}")]
+    MissingPrefix {
+        external: R,
+        advertised: P,
+        missing: P,
+    },
+    /// Error when an external advertises different AS paths for different prefixes.
+    #[error(
+        "External {external:?} advertises {first:?} with AS path {expected:?}, but {prefix:?} with {found:?}"
+    )]
+    DifferentAsPath {
+        external: R,
+        first: P,
+        expected: Vec<AsId>,
+        prefix: P,
+        found: Vec<AsId>,
+    },
 }
 
 impl<R> RoutingInputs<R>
@@ -263,6 +347,53 @@ impl RoutingInputs<RouterId> {
         }
     }
 
+    /// Advertise all routes to `net`, scheduling them according to `ordering`.
+    pub fn advertise_to_ordered<Q: EventQueue<P> + DelayableQueue>(
+        &self,
+        net: &mut Network<P, Q>,
+        ordering: UpdateOrdering,
+    ) {
+        self.apply_ordered(net, ordering, |net, prefix, router, aspath| {
+            let _ = net.advertise_external_route(router, prefix, aspath, None, [prefix.as_num()]);
+        })
+    }
+
+    /// Withdraw all routes from `net`, scheduling them according to `ordering`.
+    pub fn retract_from_ordered<Q: EventQueue<P> + DelayableQueue>(
+        &self,
+        net: &mut Network<P, Q>,
+        ordering: UpdateOrdering,
+    ) {
+        self.apply_ordered(net, ordering, |net, prefix, router, _| {
+            let _ = net.withdraw_external_route(router, prefix);
+        })
+    }
+
+    /// Call `f` for every route, shifting the time of the queue for each route as required by
+    /// `ordering`.
+    fn apply_ordered<Q, F>(&self, net: &mut Network<P, Q>, ordering: UpdateOrdering, mut f: F)
+    where
+        Q: EventQueue<P> + DelayableQueue,
+        F: FnMut(&mut Network<P, Q>, P, RouterId, Vec<AsId>),
+    {
+        let mut routes: Vec<_> = self.unroll().collect();
+        let spacing = match ordering {
+            UpdateOrdering::Simultaneous => 0.0,
+            UpdateOrdering::Sequential => SEQUENTIAL_UPDATE_SPACING,
+            UpdateOrdering::Randomized(seed) => {
+                routes.shuffle(&mut StdRng::seed_from_u64(seed));
+                SEQUENTIAL_UPDATE_SPACING
+            }
+        };
+
+        for (i, (prefix, router, aspath)) in routes.into_iter().enumerate() {
+            let delay = i as f64 * spacing;
+            net.queue_mut().shift_time(delay);
+            f(net, prefix, router, aspath);
+            net.queue_mut().shift_time(-delay);
+        }
+    }
+
     /// Returns an vector of all routes (together with the router that advertise the route).
     pub fn all_routes(&self) -> Vec<(RouterId, BgpRoute<P>)> {
         self.unroll()
@@ -449,4 +580,70 @@ mod test {
         // filter by aspath
         assert_eq!(repeated.filter(|_, _, a| *a == aspath), multi);
     }
+
+    #[test]
+    fn test_assert_consistent() {
+        let r = RouterId::from(0);
+        let r2 = RouterId::from(1);
+        let (p, p2) = (SimplePrefix::from(0), SimplePrefix::from(1));
+        let aspath: Vec<AsId> = vec![100.into(), 100.into(), 1000.into()];
+        let aspath2: Vec<AsId> = vec![200.into(), 1000.into()];
+
+        // equivalently configured externals
+        assert_eq!(
+            RoutingInputs::SinglePrefix(vec![(r, aspath.clone())]).assert_consistent(),
+            Ok(())
+        );
+        assert_eq!(
+            RoutingInputs::MultiPrefix(vec![vec![(r, aspath.clone()), (r2, aspath2.clone())]; 3])
+                .assert_consistent(),
+            Ok(())
+        );
+        assert_eq!(
+            RoutingInputs::RepeatedPrefix {
+                inner: vec![(r, aspath.clone())],
+                num: 3
+            }
+            .assert_consistent(),
+            Ok(())
+        );
+        // the order of the externals does not matter
+        assert_eq!(
+            RoutingInputs::MultiPrefix(vec![
+                vec![(r, aspath.clone()), (r2, aspath2.clone())],
+                vec![(r2, aspath2.clone()), (r, aspath.clone())],
+            ])
+            .assert_consistent(),
+            Ok(())
+        );
+
+        // `r2` does not advertise the second prefix
+        assert_eq!(
+            RoutingInputs::MultiPrefix(vec![
+                vec![(r, aspath.clone()), (r2, aspath2.clone())],
+                vec![(r, aspath.clone())],
+            ])
+            .assert_consistent(),
+            Err(InconsistencyError::MissingPrefix {
+                external: r2,
+                advertised: p,
+                missing: p2
+            })
+        );
+
+        // `r` advertises a different AS path for the second prefix
+        assert_eq!(
+            RoutingInputs::MultiPrefix(
+                vec![vec![(r, aspath.clone())], vec![(r, aspath2.clone())],]
+            )
+            .assert_consistent(),
+            Err(InconsistencyError::DifferentAsPath {
+                external: r,
+                first: p,
+                expected: aspath,
+                prefix: p2,
+                found: aspath2
+            })
+        );
+    }
 }
@@ -155,7 +155,7 @@ fn hard_waypoints() {
         PRECISION,
     )
     .unwrap();
-    let result = analyzer.analyze();
+    let result = analyzer.analyze().unwrap();
 
     println!("{}", get_p_satisfy_k2());
     check_diff(
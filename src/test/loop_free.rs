@@ -108,7 +108,7 @@ fn p_one_half_iter(k: usize) {
         PRECISION,
     )
     .unwrap();
-    let result = analyzer.analyze();
+    let result = analyzer.analyze().unwrap();
 
     check_diff(
         0.5f64.powi(k as i32),
@@ -149,7 +149,7 @@ fn p_one_eigths_iter(k: usize) {
     )
     .unwrap();
 
-    let result = analyzer.analyze();
+    let result = analyzer.analyze().unwrap();
 
     check_diff(
         0.875f64.powi(k as i32),
@@ -130,7 +130,7 @@ fn node_waypoints_iter(k: usize) {
         PRECISION,
     )
     .unwrap();
-    let result = analyzer.analyze();
+    let result = analyzer.analyze().unwrap();
 
     check_diff(
         1.0f64 / (k as f64),
@@ -167,7 +167,7 @@ fn edge_waypoints_iter(k: usize) {
         PRECISION,
     )
     .unwrap();
-    let result = analyzer.analyze();
+    let result = analyzer.analyze().unwrap();
 
     check_diff(
         1.0f64 / (k as f64),
@@ -32,7 +32,7 @@ use trix::{
 use bgpsim::{prelude::SimplePrefix, types::RouterId};
 use itertools::Itertools;
 
-use super::{Error, ParseableRecord, TransformParseableRecord, UpdateKind};
+use super::{csv_reader, log_path, Error, ParseableRecord, TransformParseableRecord, UpdateKind};
 
 #[allow(dead_code)]
 const MIN_DELTA: f64 = 0.0001;
@@ -71,11 +71,10 @@ pub(crate) fn process_sample(
     lut: &super::Lut,
     replace: bool,
 ) -> Result<bool, Error> {
-    let mut filename = eval_path.to_path_buf();
-    filename.push(format!(
-        "bgp_prefix_log_{}.csv",
-        metadata.execution_timestamp
-    ));
+    let filename = log_path(
+        eval_path,
+        format!("bgp_prefix_log_{}.csv", metadata.execution_timestamp),
+    );
     if !filename.exists() {
         log::warn!(
             "BGP prefixes log doesn't exist! Skipping this sample. Path: {}",
@@ -110,7 +109,7 @@ pub(crate) fn process_sample(
 
     let mut sequence: HashMap<(RouterId, Ipv4Addr), Vec<FWRecord>> = HashMap::new();
 
-    for record in csv::Reader::from_path(&filename)?.into_deserialize() {
+    for record in csv_reader(&csv::ReaderBuilder::new(), &filename)?.into_deserialize() {
         let record: BgpPrefixesRecord = record?;
 
         // transform the record
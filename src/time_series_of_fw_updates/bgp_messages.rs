@@ -32,7 +32,7 @@ use bgpsim::{
     types::{AsId, NetworkDeviceRef, SimplePrefix, StepUpdate},
 };
 
-use super::{is_event_prefix, Error, Lut};
+use super::{csv_reader, is_event_prefix, log_path, Error, Lut};
 
 pub(crate) fn process_sample(
     analyzer: &Analyzer<TimingModel<SimplePrefix>>,
@@ -50,8 +50,10 @@ pub(crate) fn process_sample(
         );
         return Ok(false);
     }
-    filename.pop();
-    filename.push(format!("bgp_updates_{}.csv", metadata.pcap_filename));
+    let filename = log_path(
+        eval_path,
+        format!("bgp_updates_{}.csv", metadata.pcap_filename),
+    );
     if !filename.exists() {
         log::warn!(
             "BGP updates trace doesn't exist! Skipping this sample. Path: {}",
@@ -78,10 +80,8 @@ pub(crate) fn process_sample(
 
     let mut net = analyzer.original_net.clone();
 
-    for record in csv::ReaderBuilder::new()
-        .delimiter(b';')
-        .from_path(&filename)?
-        .into_deserialize()
+    for record in
+        csv_reader(csv::ReaderBuilder::new().delimiter(b';'), &filename)?.into_deserialize()
     {
         let record: Record = record?;
 
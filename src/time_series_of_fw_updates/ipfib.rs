@@ -32,7 +32,10 @@ use trix::{
     records::{FWRecord, Router},
 };
 
-use super::{is_event_prefix, Error, Lut, ParseableRecord, TransformParseableRecord, UpdateKind};
+use super::{
+    csv_reader, is_event_prefix, log_path, Error, Lut, ParseableRecord, TransformParseableRecord,
+    UpdateKind,
+};
 
 /// Special record that always returns no next-hop.
 impl ParseableRecord<Ipv4Addr, RouterId> for IpfibRecord {
@@ -159,8 +162,10 @@ pub(crate) fn process_sample(
     lut: &Lut,
     replace: bool,
 ) -> Result<bool, Error> {
-    let mut ipfib_filename = eval_path.to_path_buf();
-    ipfib_filename.push(format!("ipfib_log_{}.csv", metadata.execution_timestamp));
+    let ipfib_filename = log_path(
+        eval_path,
+        format!("ipfib_log_{}.csv", metadata.execution_timestamp),
+    );
     let mut ufdm_filename = eval_path.to_path_buf();
     ufdm_filename.push(format!(
         "time_series_of_forwarding_states_{}",
@@ -209,7 +214,7 @@ pub(crate) fn process_sample(
     }
 
     // read all ipfib entries and transform them to FWRecords
-    for r in csv::Reader::from_path(&ipfib_filename)?.into_deserialize() {
+    for r in csv_reader(&csv::ReaderBuilder::new(), &ipfib_filename)?.into_deserialize() {
         let record: IpfibRecord = r?;
         let Some(r) = record.transform(lut, metadata)? else {
             continue;
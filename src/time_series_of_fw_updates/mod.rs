@@ -15,10 +15,17 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 //! Functions to extract the time series of forwarding state updates from individual records.
 
-use std::{collections::HashMap, net::Ipv4Addr, path::Path, str::FromStr};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufReader, Read},
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use trix::{
-    analyzer::{CiscoAnalyzerData, HardwareMapping},
+    analyzer::{ufdm_log_parser::UfdmRecord, CiscoAnalyzerData, HardwareMapping},
     experiments::Filter,
     prelude::{Analyzer, TimingModel},
     records::{FWRecord, Router},
@@ -26,9 +33,10 @@ use trix::{
 };
 use bgpsim::{
     ospf::OspfProcess,
-    prelude::NetworkFormatter,
+    prelude::{Network, NetworkFormatter},
     types::{RouterId, SimplePrefix},
 };
+use flate2::bufread::GzDecoder;
 use rayon::iter::ParallelIterator;
 
 mod bgp;
@@ -59,6 +67,69 @@ pub enum Error {
     InconsistentData(&'static str),
 }
 
+/// Whether the time series of a sample are extracted again if they already exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReplaceMode {
+    /// Keep all existing time series.
+    Never,
+    /// Replace all existing time series.
+    Always,
+    /// Replace the existing time series of a sample only if its inputs changed since they were
+    /// last extracted, or if it is unknown from which inputs they were extracted.
+    IfChanged,
+}
+
+impl ReplaceMode {
+    /// Decide whether to replace the existing time series of a sample, given the `checksum` of its
+    /// current inputs (see `util::sample_checksum`) and the file `checksum_path` storing the
+    /// checksum of the inputs they were last extracted from. The `checksum` is only required for
+    /// `IfChanged`; if it is `None`, the inputs are considered changed.
+    fn replace(&self, checksum_path: &Path, checksum: Option<u32>) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Always => true,
+            Self::IfChanged => {
+                checksum.is_none()
+                    || fs::read_to_string(checksum_path)
+                        .ok()
+                        .and_then(|stored| stored.trim().parse::<u32>().ok())
+                        != checksum
+            }
+        }
+    }
+}
+
+/// Name of the file in the time series directory of a sample that stores the checksum of the inputs
+/// the time series were last extracted from.
+const INPUT_CHECKSUM_FILE: &str = "inputs.crc32";
+
+/// Get the path of the log file `name` in `eval_path`. If it does not exist, but a gzipped version
+/// `{name}.gz` does (e.g., after archiving the logs), the path of the gzipped file is returned.
+fn log_path(eval_path: &Path, name: impl AsRef<str>) -> PathBuf {
+    let path = eval_path.then(name.as_ref());
+    let gz_path = eval_path.then(format!("{}.gz", name.as_ref()));
+    if !path.exists() && gz_path.exists() {
+        gz_path
+    } else {
+        path
+    }
+}
+
+/// Open a csv reader for the file at `path` using the given `builder`. Files with a `.gz` extension
+/// are transparently decompressed.
+fn csv_reader(
+    builder: &csv::ReaderBuilder,
+    path: &Path,
+) -> Result<csv::Reader<Box<dyn Read>>, Error> {
+    let file = BufReader::new(File::open(path)?);
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(builder.from_reader(reader))
+}
+
 pub(crate) fn run(args: &super::Args) -> Result<Vec<super::ExtractedMeasurement>, Error> {
     util::par_map_data(
         args.data_root.clone(),
@@ -84,6 +155,11 @@ fn process_directory(
 ) -> Result<Vec<super::ExtractedMeasurement>, Error> {
     let mut new_measurements = Vec::new();
 
+    if !args.num_prefixes_in_range(scenario_name) {
+        log::trace!("Skipping scenario {scenario_name} as its number of prefixes is out of range.");
+        return Ok(new_measurements);
+    }
+
     // evaluate the data captured by the cisco_analyzer
     let analyzer_csv_path = eval_path.then("cisco_analyzer.csv");
     if !analyzer_csv_path.exists() {
@@ -94,7 +170,7 @@ fn process_directory(
     let analyzer_csv = std::fs::File::open(analyzer_csv_path.clone())?;
     let mut csv = csv::Reader::from_reader(analyzer_csv);
 
-    let Ok(analyzer) = util::get_analyzer(topo_name, scenario_name) else {
+    let Ok(analyzer) = util::get_analyzer_cached(topo_name, scenario_name) else {
         log::trace!("Could not build `Analyzer` for experiment in {analyzer_csv_path:?}.");
         return Ok(new_measurements);
     };
@@ -123,15 +199,52 @@ fn process_directory(
         }
 
         let t0 = record.event_start;
-        match process_sample(&analyzer, record, eval_path, args.replace) {
-            Ok(updated) => new_measurements.push(super::ExtractedMeasurement {
-                scenario_name: format!("{topo_name}_{scenario_name}"),
-                root: eval_path.to_path_buf(),
-                timestamp,
-                num_prefixes: get_num_prefixes(scenario_name).unwrap(),
-                updated,
-                t0,
-            }),
+        let checksum_path = eval_path
+            .then_ts("time_series_of_forwarding_states_{}", &timestamp)
+            .then(INPUT_CHECKSUM_FILE);
+        // the checksum streams the entire pcap and logs of the sample, so only compute it if the
+        // replace mode depends on it.
+        let replace_mode = args.replace_mode();
+        let checksum = if replace_mode == ReplaceMode::IfChanged {
+            match util::sample_checksum(eval_path, &record) {
+                Ok(checksum) => Some(checksum),
+                Err(e) => {
+                    log::error!(
+                        "skipping {} as its inputs cannot be read: {e}",
+                        record.pcap_filename
+                    );
+                    continue;
+                }
+            }
+        } else {
+            None
+        };
+        let replace = replace_mode.replace(&checksum_path, checksum);
+        match process_sample(&analyzer, record, eval_path, replace) {
+            Ok((updated, complete)) => {
+                // remember the inputs from which the time series were extracted, but only if all
+                // of them were extracted. Otherwise, they are extracted again the next time. If
+                // the inputs are unknown, a previously stored checksum is stale.
+                match checksum {
+                    Some(checksum) if updated && complete => {
+                        fs::create_dir_all(checksum_path.parent().unwrap())?;
+                        fs::write(&checksum_path, checksum.to_string())?;
+                    }
+                    _ if (updated || !complete) && checksum_path.exists() => {
+                        fs::remove_file(&checksum_path)?;
+                    }
+                    _ => {}
+                }
+                new_measurements.push(super::ExtractedMeasurement {
+                    scenario_name: format!("{topo_name}_{scenario_name}"),
+                    root: eval_path.to_path_buf(),
+                    timestamp,
+                    num_prefixes: get_num_prefixes(scenario_name).unwrap(),
+                    updated,
+                    t0,
+                    checksum,
+                })
+            }
             Err(e) => {
                 log::error!(
                     "Error processing the sample {}\nError: {e}",
@@ -144,11 +257,19 @@ fn process_directory(
     Ok(new_measurements)
 }
 
+/// Log the error of processing `kind`, if any, and clear `complete`. Returns whether the time
+/// series were updated.
 #[track_caller]
-fn warn<E: std::error::Error>(result: Result<bool, E>, kind: &str, path: &Path) -> bool {
+fn warn<E: std::error::Error>(
+    result: Result<bool, E>,
+    kind: &str,
+    path: &Path,
+    complete: &mut bool,
+) -> bool {
     match result {
         Ok(r) => r,
         Err(e) => {
+            *complete = false;
             log::warn!(
                 "Error processing {kind} of experiment {}\n    Error:{e}",
                 path.as_os_str().to_string_lossy()
@@ -158,29 +279,33 @@ fn warn<E: std::error::Error>(result: Result<bool, E>, kind: &str, path: &Path)
     }
 }
 
+/// Extract all time series of a sample. Returns whether any of them was updated, and whether all of
+/// them were extracted without errors.
 fn process_sample(
     analyzer: &Analyzer<TimingModel<SimplePrefix>>,
     metadata: CiscoAnalyzerData,
     eval_path: &Path,
     replace: bool,
-) -> Result<bool, Error> {
+) -> Result<(bool, bool), Error> {
     // extract hardware mapping
     let mut hm_path = eval_path.to_path_buf();
     hm_path.push(&metadata.hardware_mapping_filename);
-    let hm: HardwareMapping = serde_json::from_str(&std::fs::read_to_string(&hm_path)?)?;
+    let hm: HardwareMapping = serde_json::from_str(&util::read_maybe_gzipped(&hm_path)?)?;
 
     let lut = Lut {
-        ospf_nh: next_hop_lut(analyzer),
+        ospf_nh: next_hop_lut(&analyzer.original_net),
         addrs: router_ip_lut(&hm),
         names: router_name_lut(analyzer),
     };
 
     let mut updated = false;
+    let mut complete = true;
 
     updated |= warn(
         bgp_messages::process_sample(analyzer, &metadata, eval_path, &lut, replace),
         "BGP messages",
         eval_path,
+        &mut complete,
     );
 
     // Skip BGP Log, needs additional modeling
@@ -188,24 +313,28 @@ fn process_sample(
     //     bgp::process_sample(analyzer, &metadata, eval_path, &lut, replace),
     //     "BGP log",
     //     eval_path,
+    //     &mut complete,
     // );
 
     updated |= warn(
         urib::process_sample(analyzer, &metadata, eval_path, &lut, replace),
         "URIB log",
         eval_path,
+        &mut complete,
     );
 
     updated |= warn(
         ufdm::process_sample(&metadata, eval_path, &lut, replace),
         "UFDM log",
         eval_path,
+        &mut complete,
     );
 
     updated |= warn(
         ipfib::process_sample(&metadata, eval_path, &lut, replace),
         "IPFIB log",
         eval_path,
+        &mut complete,
     );
 
     /*
@@ -213,6 +342,7 @@ fn process_sample(
         sim_model::process_sample(analyzer, &metadata, eval_path, &lut, replace),
         "BGPsim model",
         eval_path,
+        &mut complete,
     );
     */
 
@@ -220,13 +350,73 @@ fn process_sample(
         fib_delay::process_fw_records(&metadata, eval_path, replace),
         "FIB delay model",
         eval_path,
+        &mut complete,
+    );
+
+    // cross-check the event_start, which all records are filtered against
+    match first_fw_update(&metadata, eval_path) {
+        Ok(first_update) => {
+            if let Some(msg) = check_event_start(metadata.event_start, first_update) {
+                log::warn!(
+                    "Implausible event_start of experiment {}: {msg}",
+                    eval_path.as_os_str().to_string_lossy()
+                );
+            }
+        }
+        Err(e) => log::warn!(
+            "Cannot read the forwarding updates of experiment {}\n    Error:{e}",
+            eval_path.as_os_str().to_string_lossy()
+        ),
+    }
+
+    Ok((updated, complete))
+}
+
+/// Maximum plausible time [s] between the `event_start` of a sample and its first forwarding
+/// update.
+const MAX_EVENT_START_OFFSET: f64 = 10.0;
+
+/// Cross-check the `event_start` of a sample against the time of its earliest forwarding update.
+/// Returns a description of the inconsistency if the `event_start` is zero (the fallback of
+/// `analyze_router_lab` if the event could not be detected in the capture), or if the first
+/// forwarding update happens more than `MAX_EVENT_START_OFFSET` away from it.
+fn check_event_start(event_start: f64, first_update: Option<f64>) -> Option<String> {
+    if event_start == 0.0 {
+        return Some("event_start is 0.0, the event was not detected in the capture".to_string());
+    }
+    let offset = first_update? - event_start;
+    (offset.abs() > MAX_EVENT_START_OFFSET)
+        .then(|| format!("the first forwarding update happens {offset:.3}s after event_start"))
+}
+
+/// Get the time of the earliest forwarding update of the sample from its raw UFDM log. The
+/// extracted `ufdm.csv` cannot be used, as it only contains the records after the `event_start`,
+/// which would hide all updates before an `event_start` that is too late.
+fn first_fw_update(metadata: &CiscoAnalyzerData, eval_path: &Path) -> Result<Option<f64>, Error> {
+    let filename = log_path(
+        eval_path,
+        format!("ufdm_log_{}.csv", metadata.execution_timestamp),
     );
+    if !filename.exists() {
+        return Ok(None);
+    }
+    let records =
+        csv_reader(&csv::ReaderBuilder::new(), &filename)?.into_deserialize::<UfdmRecord>();
+    Ok(itertools::process_results(records, earliest_event_update)?)
+}
 
-    Ok(updated)
+/// Get the time of the earliest record that adds or deletes a route towards an event prefix,
+/// regardless of the `event_start`.
+fn earliest_event_update<R: ParseableRecord>(records: impl IntoIterator<Item = R>) -> Option<f64> {
+    records
+        .into_iter()
+        .filter(|r| r.kind().is_some() && r.addr().is_some_and(|addr| is_event_prefix(&addr)))
+        .map(|r| r.time())
+        .reduce(f64::min)
 }
 
 struct Lut {
-    ospf_nh: HashMap<(RouterId, RouterId), RouterId>,
+    ospf_nh: HashMap<(RouterId, RouterId), Vec<RouterId>>,
     addrs: HashMap<Ipv4Addr, RouterId>,
     names: HashMap<RouterId, Router>,
 }
@@ -243,10 +433,20 @@ impl Lut {
         self.names.get(&r).copied()
     }
 
+    /// Get the unique OSPF next-hop from `src` towards `dst`. Returns an error if there are
+    /// multiple equal-cost next-hops, as the path cannot be reconstructed unambiguously.
     pub fn ospf_nh(&self, src: RouterId, dst: RouterId) -> Result<RouterId, Error> {
+        match self.ospf_nhs(src, dst)? {
+            [nh] => Ok(*nh),
+            nhs => Err(Error::MultipleNextHops(src, nhs.to_vec())),
+        }
+    }
+
+    /// Get all equal-cost OSPF next-hops from `src` towards `dst`.
+    pub fn ospf_nhs(&self, src: RouterId, dst: RouterId) -> Result<&[RouterId], Error> {
         self.ospf_nh
             .get(&(src, dst))
-            .copied()
+            .map(|nhs| nhs.as_slice())
             .ok_or(Error::NoOspfNextHop(src, dst))
     }
 }
@@ -271,17 +471,14 @@ fn router_ip_lut(hm: &HardwareMapping) -> HashMap<Ipv4Addr, RouterId> {
     mapping
 }
 
-fn next_hop_lut(
-    analyzer: &Analyzer<TimingModel<SimplePrefix>>,
-) -> HashMap<(RouterId, RouterId), RouterId> {
-    let net = &analyzer.original_net;
+fn next_hop_lut<Q>(net: &Network<SimplePrefix, Q>) -> HashMap<(RouterId, RouterId), Vec<RouterId>> {
     let mut lut = HashMap::new();
     for r in net.internal_routers() {
         let src = r.router_id();
         for (dst, (next_hops, _)) in r.ospf.get_table() {
-            // only get the first next-hop, as ECMP is disabled.
-            if let Some(nh) = next_hops.first().copied() {
-                lut.insert((src, *dst), nh);
+            // keep all equal-cost next-hops, such that ECMP can be detected
+            if !next_hops.is_empty() {
+                lut.insert((src, *dst), next_hops.clone());
             }
         }
     }
@@ -392,3 +589,153 @@ impl<T: ParseableRecord<Ipv4Addr, Ipv4Addr>> TransformParseableRecord for T {
 fn is_event_prefix(addr: &Ipv4Addr) -> bool {
     addr.octets()[0] >= 100
 }
+
+#[cfg(test)]
+mod test {
+    use bgpsim::{
+        builder::{constant_link_weight, NetworkBuilder},
+        event::BasicEventQueue,
+    };
+    use itertools::Itertools;
+    use trix::analyzer::ufdm_log_parser::UfdmKind;
+
+    use super::*;
+
+    #[test]
+    fn event_start_consistency() {
+        // the fallback of `analyze_router_lab` if the event was not detected
+        assert!(check_event_start(0.0, Some(1_729_339_478.2)).is_some());
+        assert!(check_event_start(0.0, None).is_some());
+
+        assert_eq!(
+            check_event_start(1_729_339_478.0, Some(1_729_339_478.2)),
+            None
+        );
+        assert_eq!(check_event_start(1_729_339_478.0, None), None);
+        assert!(check_event_start(1_729_339_478.0, Some(1_729_339_578.0)).is_some());
+    }
+
+    #[test]
+    fn event_start_raw_log() {
+        let record = |time: f64, kind: UfdmKind, prefix: &str| UfdmRecord {
+            rid: 0.into(),
+            router_name: None,
+            time,
+            kind,
+            prefix: prefix.parse().unwrap(),
+            next_hop: None,
+        };
+        let event_start = 1_729_339_478.0;
+        let records = vec![
+            record(event_start + 0.2, UfdmKind::Del, "100.0.0.0/24"),
+            // long before the `event_start`, which the extracted time series drop
+            record(event_start - 60.0, UfdmKind::Add, "100.0.1.0/24"),
+            // not an event prefix
+            record(event_start - 120.0, UfdmKind::Add, "10.0.0.0/24"),
+        ];
+        let first_update = earliest_event_update(records);
+        assert_eq!(first_update, Some(event_start - 60.0));
+        assert!(check_event_start(event_start, first_update).is_some());
+        assert_eq!(earliest_event_update(Vec::<UfdmRecord>::new()), None);
+    }
+
+    #[test]
+    fn replace_mode() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let input = dir.join("ipfib_log.csv");
+        let checksum_path = dir.join(INPUT_CHECKSUM_FILE);
+        fs::write(&input, "time,router\n1.5,r0\n").unwrap();
+        let checksum = util::checksum_files([&input]).unwrap();
+
+        // without a stored checksum, the inputs of the existing time series are unknown
+        assert!(!ReplaceMode::Never.replace(&checksum_path, Some(checksum)));
+        assert!(ReplaceMode::Always.replace(&checksum_path, Some(checksum)));
+        assert!(ReplaceMode::IfChanged.replace(&checksum_path, Some(checksum)));
+
+        // unchanged inputs
+        fs::write(&checksum_path, checksum.to_string()).unwrap();
+        assert!(!ReplaceMode::Never.replace(&checksum_path, Some(checksum)));
+        assert!(ReplaceMode::Always.replace(&checksum_path, Some(checksum)));
+        assert!(!ReplaceMode::IfChanged.replace(&checksum_path, Some(checksum)));
+
+        // changed inputs
+        fs::write(&input, "time,router\n1.5,r0\n2.5,r1\n").unwrap();
+        let checksum = util::checksum_files([&input]).unwrap();
+        assert!(!ReplaceMode::Never.replace(&checksum_path, Some(checksum)));
+        assert!(ReplaceMode::Always.replace(&checksum_path, Some(checksum)));
+        assert!(ReplaceMode::IfChanged.replace(&checksum_path, Some(checksum)));
+
+        // the checksum of the inputs is unknown
+        assert!(!ReplaceMode::Never.replace(&checksum_path, None));
+        assert!(ReplaceMode::IfChanged.replace(&checksum_path, None));
+    }
+
+    #[test]
+    fn next_hop_lut_ecmp() {
+        let mut net: Network<SimplePrefix, BasicEventQueue<SimplePrefix>> =
+            Network::new(BasicEventQueue::new());
+        let r0 = net.add_router("r0");
+        let r1 = net.add_router("r1");
+        let r2 = net.add_router("r2");
+        let r3 = net.add_router("r3");
+        for (a, b) in [(r0, r1), (r0, r2), (r1, r3), (r2, r3)] {
+            net.add_link(a, b).unwrap();
+        }
+        net.build_link_weights(constant_link_weight, 1.0).unwrap();
+
+        let ospf_nh = next_hop_lut(&net);
+        assert_eq!(ospf_nh[&(r0, r1)], vec![r1]);
+        assert_eq!(
+            ospf_nh[&(r0, r3)].iter().copied().sorted().collect_vec(),
+            vec![r1, r2]
+        );
+
+        let lut = Lut {
+            ospf_nh,
+            addrs: HashMap::new(),
+            names: HashMap::new(),
+        };
+        assert_eq!(lut.ospf_nh(r0, r1).unwrap(), r1);
+        assert_eq!(lut.ospf_nhs(r0, r3).unwrap().len(), 2);
+        assert!(matches!(
+            lut.ospf_nh(r0, r3),
+            Err(Error::MultipleNextHops(r, _)) if r == r0
+        ));
+    }
+
+    #[test]
+    fn read_gzipped_log() {
+        use std::io::Write;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let content = "time,router\n1.5,r0\n2.5,r1\n";
+
+        // store the log only in its gzipped form
+        let mut encoder = flate2::write::GzEncoder::new(
+            File::create(dir.join("ufdm_log_0.csv.gz")).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+        std::fs::write(dir.join("urib_log_0.csv"), content).unwrap();
+
+        let gz_path = log_path(&dir, "ufdm_log_0.csv");
+        assert_eq!(gz_path, dir.join("ufdm_log_0.csv.gz"));
+        let plain_path = log_path(&dir, "urib_log_0.csv");
+        assert_eq!(plain_path, dir.join("urib_log_0.csv"));
+
+        for path in [gz_path, plain_path] {
+            let records: Vec<(f64, String)> = csv_reader(&csv::ReaderBuilder::new(), &path)
+                .unwrap()
+                .into_deserialize()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(
+                records,
+                vec![(1.5, "r0".to_string()), (2.5, "r1".to_string())]
+            );
+        }
+    }
+}
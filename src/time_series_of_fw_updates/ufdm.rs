@@ -27,7 +27,7 @@ use trix::analyzer::{
 };
 use bgpsim::types::RouterId;
 
-use super::{Lut, ParseableRecord, TransformParseableRecord, UpdateKind};
+use super::{csv_reader, log_path, Lut, ParseableRecord, TransformParseableRecord, UpdateKind};
 
 impl ParseableRecord for UfdmRecord {
     fn time(&self) -> f64 {
@@ -60,8 +60,10 @@ pub(crate) fn process_sample(
     lut: &Lut,
     replace: bool,
 ) -> Result<bool, super::Error> {
-    let mut filename = eval_path.to_path_buf();
-    filename.push(format!("ufdm_log_{}.csv", metadata.execution_timestamp));
+    let filename = log_path(
+        eval_path,
+        format!("ufdm_log_{}.csv", metadata.execution_timestamp),
+    );
     if !filename.exists() {
         log::warn!(
             "UFDM log doesn't exist! Skipping this sample. Path: {}",
@@ -87,7 +89,7 @@ pub(crate) fn process_sample(
 
     let mut last: HashMap<(RouterId, Ipv4Addr), Option<RouterId>> = HashMap::new();
 
-    for record in csv::Reader::from_path(&filename)?.into_deserialize() {
+    for record in csv_reader(&csv::ReaderBuilder::new(), &filename)?.into_deserialize() {
         let record: UfdmRecord = record?;
 
         // transform the record
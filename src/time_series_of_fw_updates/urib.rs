@@ -32,7 +32,7 @@ use trix::{
 use bgpsim::{prelude::SimplePrefix, types::RouterId};
 use itertools::Itertools;
 
-use super::{Lut, ParseableRecord, TransformParseableRecord, UpdateKind};
+use super::{csv_reader, log_path, Lut, ParseableRecord, TransformParseableRecord, UpdateKind};
 
 const MIN_DELTA: f64 = 0.0001;
 
@@ -69,8 +69,10 @@ pub(crate) fn process_sample(
     lut: &Lut,
     replace: bool,
 ) -> Result<bool, super::Error> {
-    let mut filename = eval_path.to_path_buf();
-    filename.push(format!("urib_log_{}.csv", metadata.execution_timestamp));
+    let filename = log_path(
+        eval_path,
+        format!("urib_log_{}.csv", metadata.execution_timestamp),
+    );
     if !filename.exists() {
         log::warn!(
             "URIB log doesn't exist! Skipping this sample. Path: {}",
@@ -105,7 +107,7 @@ pub(crate) fn process_sample(
 
     let mut sequence: HashMap<(RouterId, Ipv4Addr), Vec<FWRecord>> = HashMap::new();
 
-    for record in csv::Reader::from_path(&filename)?.into_deserialize() {
+    for record in csv_reader(&csv::ReaderBuilder::new(), &filename)?.into_deserialize() {
         let record: UribRecord = record?;
 
         // transform the record
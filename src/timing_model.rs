@@ -26,14 +26,15 @@ use geoutils::Location;
 use itertools::Itertools;
 use ordered_float::NotNan;
 use priority_queue::PriorityQueue;
-use rand::distributions::Distribution;
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
 use statrs::distribution::Empirical;
 
 use bgpsim::{
     bgp::BgpEvent,
-    event::{Event, EventQueue},
+    event::{BasicEventQueue, Event, EventQueue},
+    network::Network,
     prelude::OspfProcess,
     types::{NetworkDevice, PhysicalNetwork, Prefix, RouterId},
 };
@@ -61,10 +62,49 @@ use bgpsim::{
 /// [`crate::event::BasicEventQueue`], and build the initial configuration. Then, swap out the
 /// queue using [`crate::network::Network::swap_queue`] before simulating the specific event.
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 pub enum TimingModelVariants {
     /// Basic model sampling a reaction time from basic hardware measurements for each individual
     /// event (on a per-prefix basis).
+    #[default]
     Basic,
+    /// Model each router as a single server processing all BGP messages in order of their arrival,
+    /// each taking `service_us` microseconds (plus the router's processing delay). A message
+    /// arriving at `arrival` thus completes at `max(arrival, previous_completion) + service_time`,
+    /// such that bursts of messages (from any session) wait behind each other. Importance sampling
+    /// is ignored by this variant. `service_us` must be finite and non-negative.
+    Queueing { service_us: f64 },
+}
+
+impl TimingModelVariants {
+    /// Check that the parameters of the variant are valid.
+    pub fn validate(&self) -> Result<(), InvalidServiceTime> {
+        match *self {
+            Self::Basic => Ok(()),
+            Self::Queueing { service_us } if service_us.is_finite() && service_us >= 0.0 => Ok(()),
+            Self::Queueing { service_us } => Err(InvalidServiceTime(service_us)),
+        }
+    }
+}
+
+/// Error returned for a `TimingModelVariants::Queueing` whose service time is negative or not a
+/// finite number.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+#[error("The service time of the queueing model must be finite and non-negative, got {0}us")]
+pub struct InvalidServiceTime(pub f64);
+
+/// Error returned for a processing delay of a router that is negative or not a finite number.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+#[error("The processing delay of a router must be finite and non-negative, got {0}us")]
+pub struct InvalidProcessingDelay(pub f64);
+
+fn deserialize_variant<'de, D>(deserializer: D) -> Result<TimingModelVariants, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let variant = TimingModelVariants::deserialize(deserializer)?;
+    variant.validate().map_err(serde::de::Error::custom)?;
+    Ok(variant)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +126,29 @@ pub struct TimingModel<P: Prefix> {
     distances: Option<HashMap<(RouterId, RouterId), NotNan<f64>>>,
     #[serde(default, with = "crate::serde_generic_hashmap::in_option")]
     delays: Option<HashMap<(RouterId, RouterId), NotNan<f64>>>,
+    /// Additional time [s] each router takes to process a BGP message, e.g., due to a high CPU
+    /// load.
+    #[serde(default, with = "crate::serde_generic_hashmap")]
+    processing_delays: HashMap<RouterId, NotNan<f64>>,
+    /// Draw the processing times from `processing_dist` instead of using the constant
+    /// `WITHDRAW_PROCESSING_TIME` and `UPDATE_PROCESSING_TIME`.
+    #[serde(default)]
+    measured_processing_times: bool,
+    #[serde(default)]
+    importance_sampling: Option<ImportanceSampling>,
+    /// Random number generator for the processing times of the current sample (see
+    /// `DelayableQueue::set_rng`). Uses the thread's generator if not set.
+    #[serde(skip)]
+    rng: Option<StdRng>,
+    /// Logarithm of the importance weight accumulated since the last `take_log_weight`.
+    #[serde(skip)]
+    log_weight: f64,
+    #[serde(default, deserialize_with = "deserialize_variant")]
+    variant: TimingModelVariants,
+    /// Time at which each router completes processing all messages delivered so far, used by
+    /// `TimingModelVariants::Queueing`.
+    #[serde(default, with = "crate::serde_generic_hashmap")]
+    router_busy: HashMap<RouterId, NotNan<f64>>,
     current_time: NotNan<f64>,
 }
 
@@ -94,13 +157,119 @@ const BASIC_TIMING_MODEL_MAX_DELAY: f64 = 10.0;
 /// Speed of light in a fiber cable is ~2/3 of the speed of light
 /// https://en.wikipedia.org/wiki/Fiber-optic_cable#Propagation_speed_and_delay
 const SPEED_OF_LIGHT: f64 = 0.66 * 299_792_458.0;
-
-fn _init_processing_dist() -> Empirical {
-    let processing_params: Vec<f64> = include_str!("../timing-model/data.csv")
+/// Time [s] a router takes to process a BGP withdraw.
+pub const WITHDRAW_PROCESSING_TIME: f64 = 0.0004;
+/// Time [s] a router takes to process a BGP update.
+pub const UPDATE_PROCESSING_TIME: f64 = 0.000233;
+
+/// Processing times [s] measured on hardware routers.
+fn _measured_processing_times() -> Vec<f64> {
+    include_str!("../timing-model/data.csv")
         .lines()
         .map(|line| line.split(',').next().unwrap().parse::<f64>().unwrap() / 1000.0)
-        .collect();
-    Empirical::from_vec(processing_params)
+        .collect()
+}
+
+fn _init_processing_dist() -> Empirical {
+    Empirical::from_vec(_measured_processing_times())
+}
+
+/// Importance sampling of the processing times of BGP messages, biasing the `TimingModel` towards
+/// orderings that stress a policy.
+///
+/// The tilts bias the processing times measured on hardware routers, so they only apply if the
+/// `TimingModel` draws its processing times from them (see
+/// `TimingModel::set_measured_processing_times`), which is the nominal model. At each router `r`
+/// with a tilt `θ_r` (in 1/ms), the measured processing time `x` (in ms) is then drawn with a
+/// probability proportional to `exp(θ_r * x)` instead of uniformly, such that positive tilts make
+/// the router react late, and negative tilts make it react early. Each biased draw is compensated
+/// by the likelihood ratio of the nominal and the biased probability of `x`. The product of these
+/// ratios across all draws of a sample is the sample's importance weight (see
+/// `DelayableQueue::take_log_weight`). With the constant processing times, the nominal model is
+/// deterministic, and tilting it leaves it unchanged, i.e., the tilts have no effect and all
+/// weights are 1.
+///
+/// `Analyzer::analyze` estimates the probability of all policies being satisfied with the
+/// self-normalized importance sampling estimator `Σ_i w_i * 1[sample i satisfied] / Σ_i w_i`, where
+/// `w_i` is the weight of sample `i`. The estimator is consistent, but the more the tilts bias the
+/// processing times, the fewer samples effectively contribute to it. Without tilts, all weights are
+/// 1, and the estimator is the fraction of satisfied samples.
+#[derive(Debug, Clone)]
+pub struct ImportanceSampling {
+    processing_times: Vec<f64>,
+    /// Biased distribution of the index of the processing time, and the logarithm of the
+    /// normalization constant of the likelihood ratio, for each tilted router.
+    tilts: HashMap<RouterId, (f64, WeightedIndex<f64>, f64)>,
+}
+
+impl ImportanceSampling {
+    /// Create the importance sampling with the given tilts (in 1/ms) for each router. Routers
+    /// without a tilt keep the nominal processing times.
+    pub fn new(tilts: &HashMap<RouterId, f64>) -> Self {
+        let processing_times = _measured_processing_times();
+        let n = processing_times.len() as f64;
+        let tilts = tilts
+            .iter()
+            .filter(|(_, tilt)| **tilt != 0.0)
+            .map(|(router, tilt)| {
+                // shift the exponents by their maximum to avoid overflows
+                let exponent = |x: f64| tilt * x * 1000.0;
+                let max = processing_times
+                    .iter()
+                    .map(|x| exponent(*x))
+                    .max_by(f64::total_cmp)
+                    .unwrap();
+                let weights = processing_times
+                    .iter()
+                    .map(|x| (exponent(*x) - max).exp())
+                    .collect_vec();
+                let log_norm = (weights.iter().sum::<f64>() / n).ln() + max;
+                let dist = WeightedIndex::new(weights).unwrap();
+                (*router, (*tilt, dist, log_norm))
+            })
+            .collect();
+        Self {
+            processing_times,
+            tilts,
+        }
+    }
+
+    /// Get the tilt (in 1/ms) of each tilted router.
+    pub fn tilts(&self) -> HashMap<RouterId, f64> {
+        self.tilts
+            .iter()
+            .map(|(router, (tilt, _, _))| (*router, *tilt))
+            .collect()
+    }
+
+    /// Draw the processing time [s] of a message processed by `router`, together with the
+    /// logarithm of the likelihood ratio of the nominal and the biased probability of the draw.
+    /// Returns `None` if `router` is not tilted.
+    fn sample<R: Rng>(&self, router: RouterId, rng: &mut R) -> Option<(f64, f64)> {
+        let (tilt, dist, log_norm) = self.tilts.get(&router)?;
+        let x = self.processing_times[dist.sample(rng)];
+        Some((x, log_norm - tilt * x * 1000.0))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedImportanceSampling {
+    #[serde(with = "crate::serde_generic_hashmap")]
+    tilts: HashMap<RouterId, f64>,
+}
+
+impl From<SerializedImportanceSampling> for ImportanceSampling {
+    fn from(value: SerializedImportanceSampling) -> Self {
+        Self::new(&value.tilts)
+    }
+}
+
+impl From<ImportanceSampling> for SerializedImportanceSampling {
+    fn from(value: ImportanceSampling) -> Self {
+        Self {
+            tilts: value.tilts(),
+        }
+    }
 }
 
 impl<P: Prefix> TimingModel<P> {
@@ -134,6 +303,13 @@ impl<P: Prefix> TimingModel<P> {
             fixed_next_hops: false,
             distances,
             delays: None,
+            processing_delays: HashMap::new(),
+            measured_processing_times: false,
+            importance_sampling: None,
+            rng: None,
+            log_weight: 0.0,
+            variant: TimingModelVariants::default(),
+            router_busy: HashMap::new(),
             current_time: NotNan::default(),
         }
     }
@@ -165,6 +341,13 @@ impl<P: Prefix> TimingModel<P> {
             fixed_next_hops: false,
             distances: None,
             delays,
+            processing_delays: HashMap::new(),
+            measured_processing_times: false,
+            importance_sampling: None,
+            rng: None,
+            log_weight: 0.0,
+            variant: TimingModelVariants::default(),
+            router_busy: HashMap::new(),
             current_time: NotNan::default(),
         }
     }
@@ -180,10 +363,48 @@ impl<P: Prefix> TimingModel<P> {
         }
     }
 
+    /// Set the additional time in [µs] that `router` takes to process each BGP message, on top of
+    /// the processing time of an update or withdraw, e.g., to model a router under high CPU load.
+    /// Returns an error (and keeps the current delay) if `delay_us` is negative or not finite.
+    pub fn set_router_processing_delay(
+        &mut self,
+        router: RouterId,
+        delay_us: f64,
+    ) -> Result<(), InvalidProcessingDelay> {
+        if !delay_us.is_finite() || delay_us < 0.0 {
+            return Err(InvalidProcessingDelay(delay_us));
+        }
+        self.processing_delays
+            .insert(router, NotNan::new(delay_us / 1_000_000.0).unwrap());
+        Ok(())
+    }
+
+    /// Draw the processing time of each BGP message from the processing times measured on hardware
+    /// routers, instead of using the constant `WITHDRAW_PROCESSING_TIME` and
+    /// `UPDATE_PROCESSING_TIME`. Only affects `TimingModelVariants::Basic`.
+    pub fn set_measured_processing_times(&mut self, measured: bool) {
+        self.measured_processing_times = measured;
+    }
+
+    /// Bias the measured processing times of the tilted routers as given by `importance_sampling`
+    /// (see `ImportanceSampling`). Pass `None` to draw the processing times of all routers from the
+    /// nominal model again.
+    pub fn set_importance_sampling(&mut self, importance_sampling: Option<ImportanceSampling>) {
+        self.importance_sampling = importance_sampling;
+    }
+
+    /// Select how the processing times of BGP messages are modeled (see `TimingModelVariants`).
+    /// Returns an error (and keeps the current variant) if the parameters of `variant` are invalid.
+    pub fn set_variant(&mut self, variant: TimingModelVariants) -> Result<(), InvalidServiceTime> {
+        variant.validate()?;
+        self.variant = variant;
+        Ok(())
+    }
+
     /// Get the direct delay between two nodes in [s]
     ///
     /// NOTE: This function assumes the reduced speed of light observed in optical network cables.
-    pub fn get_delay(&mut self, src: RouterId, dst: RouterId) -> f64 {
+    pub fn get_delay(&self, src: RouterId, dst: RouterId) -> f64 {
         if let Some(distances) = &self.distances {
             distances
                 .get(&(src, dst))
@@ -199,6 +420,18 @@ impl<P: Prefix> TimingModel<P> {
         }
     }
 
+    /// Get the delay in [s] that `get_delay` uses for each link of `net`, in both directions.
+    /// Compare these with the link delays configured on the testbed to debug mismatches between
+    /// the model and the hardware.
+    pub fn effective_delays<Q>(&self, net: &Network<P, Q>) -> HashMap<(RouterId, RouterId), f64> {
+        let g = net.get_topology();
+        g.edge_indices()
+            .map(|e| g.edge_endpoints(e).unwrap())
+            .flat_map(|(a, b)| [(a, b), (b, a)])
+            .map(|(src, dst)| ((src, dst), self.get_delay(src, dst)))
+            .collect()
+    }
+
     /// Reset the current time to zero. This function will only have an effect if the
     /// queue is empty. Otherwise, nothing will happen.
     pub fn reset_time(&mut self) {
@@ -225,8 +458,6 @@ impl<P: Prefix> TimingModel<P> {
         let mut next_processed = self.processing_queue.peek();
         let mut next_delivery = self.delivery_queue.peek();
 
-        let mut rng = thread_rng();
-
         // As long as there is another delivery and
         // - there is either no processed event enqueued, or
         // - the next enqueued delivery happens before that,
@@ -268,28 +499,68 @@ impl<P: Prefix> TimingModel<P> {
                     },
                     _,
                 ) => {
-                    // compute the next time
-                    let session = (src, dst);
-                    // sample a processing time for the packet
-                    let processing_time = NotNan::new(match e {
-                        BgpEvent::Withdraw(_) => 0.0004,
-                        BgpEvent::Update(_) => 0.000233,
-                    })
-                    .unwrap();
-                    //NotNan::new(self.processing_dist.sample(&mut rng)).unwrap();
-                    let mut next_time = arrival_time + processing_time;
-
-                    // check if there is already something enqueued for this session
-                    if let Some((ref mut num, ref mut time)) = self.messages.get_mut(&session) {
-                        // start processing earliest when previous message is delivered
-                        if *num > 0 && *time > arrival_time {
-                            next_time = *time + processing_time;
+                    let processing_delay = self
+                        .processing_delays
+                        .get(&dst)
+                        .copied()
+                        .unwrap_or_default();
+                    let next_time = match self.variant {
+                        TimingModelVariants::Basic => {
+                            // compute the next time
+                            let session = (src, dst);
+                            // sample a processing time for the packet. Tilting the constant
+                            // processing times leaves them unchanged (see `ImportanceSampling`).
+                            let (processing_time, log_ratio) = match e {
+                                _ if self.measured_processing_times => {
+                                    let mut thread_rng = thread_rng();
+                                    let mut rng: &mut dyn RngCore = match self.rng.as_mut() {
+                                        Some(rng) => rng,
+                                        None => &mut thread_rng,
+                                    };
+                                    self.importance_sampling
+                                        .as_ref()
+                                        .and_then(|is| is.sample(dst, &mut rng))
+                                        .unwrap_or_else(|| {
+                                            (self.processing_dist.sample(&mut rng), 0.0)
+                                        })
+                                }
+                                BgpEvent::Withdraw(_) => (WITHDRAW_PROCESSING_TIME, 0.0),
+                                BgpEvent::Update(_) => (UPDATE_PROCESSING_TIME, 0.0),
+                            };
+                            self.log_weight += log_ratio;
+                            let processing_time =
+                                NotNan::new(processing_time).unwrap() + processing_delay;
+                            let mut next_time = arrival_time + processing_time;
+
+                            // check if there is already something enqueued for this session
+                            if let Some((ref mut num, ref mut time)) =
+                                self.messages.get_mut(&session)
+                            {
+                                // start processing earliest when previous message is delivered
+                                if *num > 0 && *time > arrival_time {
+                                    next_time = *time + processing_time;
+                                }
+                                *num += 1;
+                                *time = next_time;
+                            } else {
+                                self.messages.insert(session, (1, next_time));
+                            }
+                            next_time
                         }
-                        *num += 1;
-                        *time = next_time;
-                    } else {
-                        self.messages.insert(session, (1, next_time));
-                    }
+                        TimingModelVariants::Queueing { service_us } => {
+                            // `service_us` is validated by `set_variant` and upon deserialization
+                            let service_time =
+                                NotNan::new(service_us / 1_000_000.0).unwrap() + processing_delay;
+                            // start processing once the router is done with all earlier messages
+                            let start = self
+                                .router_busy
+                                .get(&dst)
+                                .map_or(arrival_time, |busy| (*busy).max(arrival_time));
+                            let next_time = start + service_time;
+                            self.router_busy.insert(dst, next_time);
+                            next_time
+                        }
+                    };
                     *t = next_time;
 
                     // enqueue with the computed time
@@ -434,6 +705,7 @@ impl<P: Prefix> EventQueue<P> for TimingModel<P> {
         self.delivery_queue.clear();
         self.processing_queue.clear();
         self.messages.clear();
+        self.router_busy.clear();
         self.current_time = NotNan::default();
     }
 
@@ -464,8 +736,60 @@ impl<P: Prefix> EventQueue<P> for TimingModel<P> {
             delivery_queue: self.delivery_queue.clone(),
             processing_queue: self.processing_queue.clone(),
             messages: self.messages.clone(),
+            router_busy: self.router_busy.clone(),
             current_time: self.current_time,
             ..conquered
         }
     }
 }
+
+/// Event queues that allow scheduling events at a later point in time, as required by
+/// `AnalyzerEvent::Flap`.
+pub trait DelayableQueue {
+    /// Shift the time at which newly pushed events are sent by `delay` seconds. Shift by the
+    /// negative delay afterwards to restore the queue's current time.
+    fn shift_time(&mut self, delay: f64);
+
+    /// Get the logarithm of the importance weight of all events processed since the last call (see
+    /// `ImportanceSampling`), and reset it. Queues without importance sampling always return 0.
+    fn take_log_weight(&mut self) -> f64 {
+        0.0
+    }
+
+    /// Get the time at which the next event is processed, without processing it. Queues without a
+    /// notion of time always return `None`.
+    fn next_event_time(&mut self) -> Option<f64> {
+        None
+    }
+
+    /// Take all random draws of the events processed from now on from `rng`, e.g., to reproduce a
+    /// sample. Queues without random draws ignore it.
+    fn set_rng(&mut self, _rng: StdRng) {}
+}
+
+impl<P: Prefix> DelayableQueue for TimingModel<P> {
+    fn shift_time(&mut self, delay: f64) {
+        self.current_time = NotNan::new(self.current_time.into_inner() + delay).unwrap();
+    }
+
+    fn take_log_weight(&mut self) -> f64 {
+        std::mem::take(&mut self.log_weight)
+    }
+
+    fn next_event_time(&mut self) -> Option<f64> {
+        // deliver all messages that arrive before the next one is processed, as in `pop`
+        self.internal_advance_current_time();
+        self.processing_queue
+            .peek()
+            .map(|(_, Reverse(time))| time.into_inner())
+    }
+
+    fn set_rng(&mut self, rng: StdRng) {
+        self.rng = Some(rng);
+    }
+}
+
+impl<P: Prefix> DelayableQueue for BasicEventQueue<P> {
+    /// The `BasicEventQueue` has no notion of time, events are always processed in order.
+    fn shift_time(&mut self, _delay: f64) {}
+}
@@ -16,7 +16,7 @@
 use geoutils::Location;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, error::Error};
+use std::{collections::HashMap, error::Error, iter::zip};
 
 use bgpsim::{
     builder::{constant_link_weight, NetworkBuilder},
@@ -46,6 +46,67 @@ impl Topology {
         }
     }
 
+    /// parse a topology from its string representation produced by `Topology::fmt`
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(k) = name.strip_prefix("Path_") {
+            return k.parse().ok().map(Self::Path);
+        }
+        if let Some(k) = name.strip_prefix("Star_") {
+            return k.parse().ok().map(Self::Star);
+        }
+        if let Some(dims) = name.strip_prefix("Grid_") {
+            let (rows, cols) = dims.split_once('_')?;
+            return Some(Self::Grid(rows.parse().ok()?, cols.parse().ok()?));
+        }
+        TopologyZoo::topologies_increasing_nodes()
+            .iter()
+            .find(|topo| format!("{topo:?}") == name)
+            .map(|topo| Self::TopologyZoo(*topo))
+    }
+
+    /// Get the names and ids of all internal routers, as they are created by `build_network`.
+    pub fn internal_routers(&self) -> Vec<(String, RouterId)> {
+        let names: Vec<String> = match self {
+            Self::Path(k) => (0..*k).map(|i| format!("r{i}")).collect(),
+            Self::Star(k) => std::iter::once("center".to_string())
+                .chain((0..k - 1).map(|i| format!("r{i}")))
+                .collect(),
+            Self::Grid(rows, cols) => (0..*rows)
+                .cartesian_product(0..*cols)
+                .map(|(i, j)| format!("r_{i}_{j}"))
+                .collect(),
+            Self::TopologyZoo(topo) => {
+                let net: Network<P> = topo.build(BasicEventQueue::new());
+                return net
+                    .internal_indices()
+                    .map(|r| (r.fmt(&net).to_string(), r))
+                    .sorted_by_key(|(_, r)| *r)
+                    .collect();
+            }
+        };
+        names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| (name, RouterId::new(i)))
+            .collect()
+    }
+
+    /// Get the names and ids of the external routers that are part of the topology itself, i.e.,
+    /// without the external routers added for the routing inputs. Only topologies from the
+    /// TopologyZoo may contain external routers.
+    pub fn external_routers(&self) -> Vec<(String, RouterId)> {
+        match self {
+            Self::TopologyZoo(topo) => {
+                let net: Network<P> = topo.build(BasicEventQueue::new());
+                net.external_indices()
+                    .map(|r| (r.fmt(&net).to_string(), r))
+                    .sorted_by_key(|(_, r)| *r)
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
     pub fn build_network<R: AsRef<str> + Clone + Eq + std::hash::Hash>(
         &self,
         static_inputs: &RoutingInputs<R>,
@@ -328,4 +389,218 @@ impl LinkDelayBuilder<RouterId> {
 
         delays
     }
+
+    /// Synthesize geographic locations for all routers, such that the great-circle distances along
+    /// the links approximately reproduce the delays generated by `generate_delays`. This allows
+    /// topologies with explicit delays to be used with APIs that only accept geographic locations
+    /// (e.g., `RouterLab::set_link_delays_from_geolocation`).
+    ///
+    /// The locations are an approximation: The internal routers are embedded into the plane using
+    /// classical multidimensional scaling (MDS) of the shortest-path distances between them, and
+    /// the plane is then mapped onto the earth around `(0, 0)`. Delays are only reproduced exactly
+    /// if these distances are Euclidean in two dimensions (e.g., for a path), and even then, the
+    /// projection distorts them slightly. External routers are placed at the location of their
+    /// first internal neighbor.
+    pub fn to_pseudo_geo<Q>(
+        &self,
+        net: &Network<P, Q>,
+        topo: &Topology,
+    ) -> HashMap<RouterId, Location> {
+        let delays = self.generate_delays(net, topo);
+        let routers = net.internal_indices().sorted().collect_vec();
+        let n = routers.len();
+        let index: HashMap<RouterId, usize> =
+            routers.iter().enumerate().map(|(i, r)| (*r, i)).collect();
+
+        // all-pairs shortest-path distances (in meters) using Floyd-Warshall
+        let mut dist = vec![vec![f64::INFINITY; n]; n];
+        for (i, row) in dist.iter_mut().enumerate() {
+            row[i] = 0.0;
+        }
+        for ((a, b), delay) in delays.iter() {
+            let (i, j) = (index[a], index[b]);
+            let d = delay / 1_000_000.0 * self.speed_of_light;
+            dist[i][j] = dist[i][j].min(d);
+            dist[j][i] = dist[j][i].min(d);
+        }
+        #[allow(clippy::needless_range_loop)]
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    let d = dist[i][k] + dist[k][j];
+                    if d < dist[i][j] {
+                        dist[i][j] = d;
+                    }
+                }
+            }
+        }
+        // routers in different components are placed as far apart as the farthest reachable ones
+        let max_dist = dist
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|d| d.is_finite())
+            .fold(0.0, f64::max);
+        dist.iter_mut()
+            .flatten()
+            .filter(|d| !d.is_finite())
+            .for_each(|d| *d = max_dist);
+
+        let coordinates = classical_mds(&dist);
+        let mut geo: HashMap<RouterId, Location> = routers
+            .iter()
+            .zip(coordinates)
+            .map(|(r, (x, y))| {
+                let lat = (y / EARTH_RADIUS).to_degrees();
+                let lon = (x / EARTH_RADIUS).to_degrees();
+                (*r, Location::new(lat, lon))
+            })
+            .collect();
+
+        let g = net.get_topology();
+        for ext in net.external_indices() {
+            let location = g
+                .neighbors(ext)
+                .filter_map(|r| geo.get(&r))
+                .next()
+                .copied()
+                .unwrap_or(Location::new(0.0, 0.0));
+            geo.insert(ext, location);
+        }
+
+        geo
+    }
+}
+
+/// Mean radius of the earth in meters.
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Embed the points with the given (symmetric) distance matrix into the plane using classical
+/// multidimensional scaling, returning the coordinates of each point. The two largest eigenpairs
+/// of the double-centered matrix are computed with power iteration.
+fn classical_mds(dist: &[Vec<f64>]) -> Vec<(f64, f64)> {
+    let n = dist.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    // double centering of the squared distances: B = -1/2 J D^2 J
+    let sq = dist
+        .iter()
+        .map(|row| row.iter().map(|d| d * d).collect_vec())
+        .collect_vec();
+    let row_means = sq
+        .iter()
+        .map(|row| row.iter().sum::<f64>() / n as f64)
+        .collect_vec();
+    let total_mean = row_means.iter().sum::<f64>() / n as f64;
+    let mut b = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| -0.5 * (sq[i][j] - row_means[i] - row_means[j] + total_mean))
+                .collect_vec()
+        })
+        .collect_vec();
+
+    // shift the spectrum such that the largest eigenvalues are also the largest in magnitude
+    let shift = b
+        .iter()
+        .map(|row| row.iter().map(|x| x.abs()).sum::<f64>())
+        .fold(0.0, f64::max);
+
+    let mut axes = Vec::new();
+    for _ in 0..2 {
+        let mut v = (0..n).map(|i| ((i + 1) as f64).sin()).collect_vec();
+        let mut eigenvalue = 0.0;
+        for _ in 0..1000 {
+            let mut w = (0..n)
+                .map(|i| (0..n).map(|j| b[i][j] * v[j]).sum::<f64>() + shift * v[i])
+                .collect_vec();
+            let norm = w.iter().map(|x| x * x).sum::<f64>().sqrt();
+            if norm == 0.0 {
+                break;
+            }
+            w.iter_mut().for_each(|x| *x /= norm);
+            let converged = zip(&v, &w).all(|(a, b)| (a - b).abs() < 1e-12);
+            eigenvalue = norm - shift;
+            v = w;
+            if converged {
+                break;
+            }
+        }
+        let eigenvalue = eigenvalue.max(0.0);
+        // deflate the matrix to compute the next eigenpair
+        for (row, vi) in b.iter_mut().zip(&v) {
+            for (x, vj) in row.iter_mut().zip(&v) {
+                *x -= eigenvalue * vi * vj;
+            }
+        }
+        axes.push(v.into_iter().map(|x| x * eigenvalue.sqrt()).collect_vec());
+    }
+
+    zip(&axes[0], &axes[1]).map(|(x, y)| (*x, *y)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(topo: Topology) {
+        let parsed = Topology::from_name(&topo.fmt()).unwrap();
+        assert_eq!(parsed.fmt(), topo.fmt());
+    }
+
+    #[test]
+    fn from_name_round_trip() {
+        round_trip(Topology::Path(3));
+        round_trip(Topology::Star(12));
+        round_trip(Topology::Grid(2, 5));
+        round_trip(Topology::TopologyZoo(TopologyZoo::Abilene));
+        round_trip(Topology::TopologyZoo(TopologyZoo::Cogentco));
+        round_trip(Topology::TopologyZoo(TopologyZoo::Sprint));
+    }
+
+    #[test]
+    fn from_name_all_topology_zoo() {
+        for topo in TopologyZoo::topologies_increasing_nodes() {
+            round_trip(Topology::TopologyZoo(*topo));
+        }
+    }
+
+    #[test]
+    fn pseudo_geo_reproduces_delays() {
+        let topo = Topology::Path(4);
+        let mut net: Network<P> = Network::new(BasicEventQueue::new());
+        let r = (0..4)
+            .map(|i| net.add_router(&format!("r{i}")))
+            .collect_vec();
+        for (a, b) in r.iter().tuple_windows() {
+            net.add_link(*a, *b).unwrap();
+        }
+        let delays = LinkDelayBuilder::<&str>::new()
+            .default_delay(1_000.0)
+            .overwrite("r1", "r2", 3_000.0)
+            .build(&net)
+            .unwrap();
+
+        let geo = delays.to_pseudo_geo(&net, &topo);
+        assert_eq!(geo.len(), 4);
+        for ((a, b), delay) in delays.generate_delays(&net, &topo) {
+            let distance = geo[&a].distance_to(&geo[&b]).unwrap().meters();
+            let recovered = distance / SPEED_OF_LIGHT * 1_000_000.0;
+            assert!(
+                (recovered - delay).abs() < 0.01 * delay,
+                "delay between {a:?} and {b:?}: {recovered} != {delay}"
+            );
+        }
+    }
+
+    #[test]
+    fn from_name_invalid() {
+        assert!(Topology::from_name("").is_none());
+        assert!(Topology::from_name("Path_").is_none());
+        assert!(Topology::from_name("Grid_3").is_none());
+        assert!(Topology::from_name("Star_x").is_none());
+        assert!(Topology::from_name("NoSuchTopology").is_none());
+    }
 }
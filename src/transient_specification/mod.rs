@@ -13,13 +13,21 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
-use std::{collections::HashMap, fs, net::Ipv4Addr, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    net::Ipv4Addr,
+    str::FromStr,
+};
+
+use itertools::Itertools;
 
 use bgpsim::{
     event::{EventQueue, FmtPriority},
     forwarding_state::ForwardingState,
     policies::Policy,
     prelude::*,
+    record::{AlwaysEq, FwDelta},
 };
 
 use crate::{
@@ -31,14 +39,160 @@ use crate::{
 mod path;
 mod policy;
 
+pub use path::Path;
 use path::*;
-pub use policy::TransientPolicy;
+pub use policy::{CompositionError, PolicyKind, TransientPolicy};
 
 /// maximum number of hops considered for data-plane packets
 const MAX_HOPS: usize = 25;
 
+/// Tolerance (in seconds) for negative durations. Timestamps of real captures are subject to
+/// floating-point rounding, such that consecutive events may appear to be out of order by a tiny
+/// amount (e.g., `-1e-12`). Durations that are negative by at most this amount are clamped to zero,
+/// whereas durations that are even more negative are reported as errors.
+pub const DURATION_EPSILON: f64 = 1e-9;
+
+/// Compute the duration from `t_start` to `t_end`, clamping slightly negative durations (within
+/// `DURATION_EPSILON`) to zero.
+fn duration(t_start: f64, t_end: f64) -> Result<f64, EvaluationError> {
+    let duration = t_end - t_start;
+    if duration >= 0.0 {
+        Ok(duration)
+    } else if duration >= -DURATION_EPSILON {
+        Ok(0.0)
+    } else {
+        Err(EvaluationError::NegativeDuration(t_start, t_end))
+    }
+}
+
 pub type Interval = (f64, f64, Path);
 
+/// Violation times computed by `compute_violation_times`.
+#[derive(Clone, Debug, Default)]
+pub struct ViolationTimes<T> {
+    /// Aggregated violations of each violated policy.
+    pub times: HashMap<TransientPolicy, T>,
+    /// Policies that remain violated after convergence, i.e., whose final interval is violating.
+    /// Their violation time is not transient, but grows with the length of the trace.
+    pub persistent: HashSet<TransientPolicy>,
+}
+
+/// How `compute_violation_times_with_reset` restores the shared `ForwardingState` after handling
+/// the trace of each prefix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FwStateReset {
+    /// Apply the forwarding deltas of the prefix's trace in reverse. This avoids copying the
+    /// forwarding state, but leaves it modified if the computation is interrupted, e.g., by a
+    /// panic.
+    #[default]
+    Revert,
+    /// Work on a copy of the forwarding state for each prefix and discard it afterwards. The shared
+    /// state is never modified, making the computation of each prefix independent.
+    Clone,
+}
+
+/// Metric aggregating the intervals during which a transient policy is violated.
+///
+/// `compute_violation_times` calls `accumulate` once for every interval `[t_start, t_end)` in which
+/// a router's traffic takes a `path` that violates a policy, folding it into that policy's value.
+pub trait ViolationMetric {
+    /// Aggregated value per policy. Policies that are never violated are absent from the result.
+    type Output: Default + Clone + std::fmt::Debug;
+
+    /// Fold a single violating interval into the accumulated value.
+    fn accumulate(&self, acc: &mut Self::Output, t_start: f64, t_end: f64, path: &Path);
+}
+
+/// The default `ViolationMetric`, summing up the total time during which a policy is violated.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TotalDuration;
+
+impl ViolationMetric for TotalDuration {
+    type Output = f64;
+
+    fn accumulate(&self, acc: &mut f64, t_start: f64, t_end: f64, _path: &Path) {
+        *acc += t_end - t_start;
+    }
+}
+
+/// `ViolationMetric` summing up the violation time of a policy, weighting each interval by the
+/// severity of the path taken during it. By default, black holes count twice and forwarding loops
+/// one and a half times as much as a violating route (e.g., a detour).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeverityWeighted {
+    /// Weight of intervals in which traffic reaches the destination on a violating path.
+    pub route: f64,
+    /// Weight of intervals in which traffic is caught in a forwarding loop.
+    pub forwarding_loop: f64,
+    /// Weight of intervals in which traffic is dropped.
+    pub black_hole: f64,
+}
+
+impl Default for SeverityWeighted {
+    fn default() -> Self {
+        Self {
+            route: 1.0,
+            forwarding_loop: 1.5,
+            black_hole: 2.0,
+        }
+    }
+}
+
+impl SeverityWeighted {
+    /// Get the weight of an interval during which traffic takes `path`.
+    pub fn severity(&self, path: &Path) -> f64 {
+        match path {
+            Path::Route(_) => self.route,
+            Path::Loop(_, _) => self.forwarding_loop,
+            Path::BlackHole(_) => self.black_hole,
+        }
+    }
+}
+
+impl ViolationMetric for SeverityWeighted {
+    type Output = f64;
+
+    fn accumulate(&self, acc: &mut f64, t_start: f64, t_end: f64, path: &Path) {
+        *acc += self.severity(path) * (t_end - t_start);
+    }
+}
+
+/// Group the given policies by the `(RouterId, Prefix)` pair they concern, as required by
+/// `compute_violation_times` and `compute_baseline`. Policies that do not concern a dedicated
+/// router and prefix are skipped. Within each group, the order of the given policies is kept.
+pub fn group_policies(
+    policies: &[TransientPolicy],
+) -> HashMap<(RouterId, Prefix), Vec<TransientPolicy>> {
+    policies
+        .iter()
+        .filter_map(|policy| Some(((policy.router()?, policy.prefix()?), policy.clone())))
+        .into_group_map()
+}
+
+/// Check the ordering between the violation times computed by `compute_baseline` and by
+/// `compute_violation_times` on the same trace.
+///
+/// The baseline assumes traffic to traverse the network at infinite speed, whereas the interval
+/// algorithm accounts for the propagation delay. Traffic that is still in flight can be caught by
+/// additional forwarding updates, so the propagation-aware violation time of each policy must be
+/// at least its baseline violation time. With zero propagation delay, both coincide. Policies
+/// missing from either map are never violated.
+///
+/// Test helper that panics if the invariant does not hold for any policy.
+#[cfg(test)]
+pub(crate) fn assert_violation_monotonicity(
+    baseline: &HashMap<TransientPolicy, f64>,
+    interval: &HashMap<TransientPolicy, f64>,
+) {
+    for (policy, t_baseline) in baseline {
+        let t_interval = interval.get(policy).copied().unwrap_or_default();
+        assert!(
+            t_interval >= t_baseline - 1e-9,
+            "violation time of {policy:?} is {t_interval} with propagation delay, but {t_baseline} at infinite speed",
+        );
+    }
+}
+
 /// Performs a data-plane simulation based on the global-perspective time-series of forwarding
 /// states. In other words, this algorithm computes the transient violation times enountered by a
 /// network during convergence, if traffic would traverse the network at infinite speed.
@@ -48,7 +202,7 @@ pub fn compute_baseline<Q>(
     fw_state: &mut ForwardingState<Prefix>,
     trace: &MultiPrefixConvergenceTrace,
     transient_policies: &HashMap<(RouterId, Prefix), Vec<TransientPolicy>>,
-) -> HashMap<TransientPolicy, f64>
+) -> Result<HashMap<TransientPolicy, f64>, EvaluationError>
 where
     Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
     Q::Priority: Default + FmtPriority + Clone,
@@ -66,14 +220,15 @@ where
             },
             prefix,
             prefix_trace,
-        );
+        )?;
 
         check_route_intervals(
             prefix,
             &route_intervals,
             &mut violation_times,
             transient_policies,
-        );
+            &TotalDuration,
+        )?;
     }
 
     log::trace!(
@@ -81,72 +236,67 @@ where
         violation_times
     );
 
-    violation_times
+    Ok(violation_times)
 }
 
 /// Performs a data-plane simulation based on the global-perspective time-series of forwarding
 /// states for the given prefix. In other words, this algorithm computes the transient violation
 /// times encountered by a network during convergence, if traffic would traverse the network at
-/// infinite speed.
+/// infinite speed. Fails with `EvaluationError::LoadBalancing` if traffic is load-balanced over
+/// multiple paths.
 fn compute_baseline_for_prefix<Q>(
     params: &mut IAParams<'_, Q>,
     prefix: &Prefix,
     prefix_trace: &PrefixTrace,
-) -> HashMap<RouterId, Vec<Interval>>
+) -> Result<HashMap<RouterId, Vec<Interval>>, EvaluationError>
 where
     Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
     Q::Priority: Default + FmtPriority + Clone,
 {
     // get the first and last event's time from the `MultiPrefixConvergenceTrace`
     assert!(!prefix_trace.is_empty());
-    let t_first_event = prefix_trace[0].1.into_inner().unwrap();
+    let t_first_event = step_time(&prefix_trace[0].1);
 
     // init all route_intervals as t_first with the initial path
-    let mut route_intervals: HashMap<RouterId, Vec<(f64, Path)>> =
-        HashMap::from_iter(params.net.internal_routers().flat_map(|r| {
-            let paths = params.fw_state.get_paths(r.router_id(), *prefix).unwrap();
-            if paths.len() > 1 {
-                todo!("extend algorithm for load-balancing!");
-            }
-            let mut intervals = Vec::new();
-            for path in paths.iter() {
-                intervals.push((
-                    r.router_id(),
-                    vec![(t_first_event, Path::Route(path.clone()))],
-                ));
-            }
-            intervals
-        }));
+    let mut route_intervals: HashMap<RouterId, Vec<(f64, Path)>> = HashMap::new();
+    for r in params.net.internal_indices() {
+        let path = get_path_from_fw_state(&r, prefix, params.fw_state)?;
+        route_intervals.insert(r, vec![(t_first_event, path)]);
+    }
 
     // traverse the time series of forwarding states and split the route_intervals as required
-    for (fw_deltas, time) in prefix_trace.iter() {
-        // assume single updates per step
-        assert_eq!(fw_deltas.len(), 1);
-        let (affected_router, _, new_nh) = fw_deltas.first().unwrap();
-        // assume no multi-path
-        assert!(new_nh.len() <= 1);
-
-        log::trace!(
-            "\n[t = {}] FW-UPDATE at {}: new_nh: {}",
-            time.as_ref().unwrap(),
-            affected_router.fmt(params.net),
-            new_nh.fmt(params.net),
-        );
-
-        params
-            .fw_state
-            .update(*affected_router, *prefix, new_nh.clone());
+    let mut traversed = Ok(());
+    'steps: for (fw_deltas, time) in prefix_trace.iter() {
+        let time = step_time(time);
+        for (affected_router, _, new_nh) in fw_deltas.iter() {
+            log::trace!(
+                "\n[t = {time}] FW-UPDATE at {}: new_nh: {}",
+                affected_router.fmt(params.net),
+                new_nh.fmt(params.net),
+            );
+        }
+        if let Err(e) = apply_fw_deltas(params.fw_state, prefix, fw_deltas) {
+            traversed = Err(e);
+            break;
+        }
 
         for r in params.net.internal_indices() {
-            let path = get_path_from_fw_state(&r, prefix, params.fw_state);
+            let path = match get_path_from_fw_state(&r, prefix, params.fw_state) {
+                Ok(path) => path,
+                Err(e) => {
+                    traversed = Err(e);
+                    break 'steps;
+                }
+            };
             let intervals = route_intervals.get_mut(&r).unwrap();
             if intervals.last().unwrap().1 != path {
-                intervals.push((time.as_ref().unwrap(), path));
+                intervals.push((time, path));
             }
         }
     }
 
-    // reset the `ForwardingState` after handling each prefix
+    // reset the `ForwardingState` after handling each prefix. Reverting all steps also restores it
+    // if the traversal was interrupted by an error.
     for (fw_deltas, _) in prefix_trace.iter().rev() {
         for (affected_router, old_nh, _) in fw_deltas {
             params
@@ -154,7 +304,8 @@ where
                 .update(*affected_router, *prefix, old_nh.clone());
         }
     }
-    route_intervals
+    traversed?;
+    Ok(route_intervals
         .into_iter()
         .map(|(rid, updates)| {
             let t_ends: Vec<_> = updates.iter().map(|(t, _)| *t).skip(1).collect();
@@ -165,7 +316,7 @@ where
                 .collect();
             (rid, intervals)
         })
-        .collect()
+        .collect())
 }
 
 /// Compute the propagation delay experienced on a given path.
@@ -189,21 +340,62 @@ fn propagation_delay(queue: &mut TimingModel<Prefix>, path: &[RouterId]) -> f64
 /// Note that the paths taken through the network may not necessarily coincide with paths observed
 /// in any of the forwarding states. This may be caused by convergence updates during a packet's
 /// network traversal.
-pub fn compute_violation_times<Q, PathRef>(
+///
+/// The violating intervals of each policy are aggregated using the given `metric`. Use
+/// `TotalDuration` to obtain the total violation time of each policy.
+///
+/// This function models the delays experienced by traffic with the given `queue`. For measured
+/// data, where the paths and their timestamps are observed directly, use
+/// `measured_violation_times` instead.
+pub fn compute_violation_times<Q, PathRef, M>(
     net: &Network<Prefix, Q>,
     queue: &mut TimingModel<Prefix>,
     fw_state: &mut ForwardingState<Prefix>,
     trace: &MultiPrefixConvergenceTrace,
     transient_policies: &HashMap<(RouterId, Prefix), Vec<TransientPolicy>>,
     log_intervals_path: Option<PathRef>,
-) -> HashMap<TransientPolicy, f64>
+    metric: &M,
+) -> Result<ViolationTimes<M::Output>, EvaluationError>
 where
     Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
     Q::Priority: Default + FmtPriority + Clone,
     PathRef: AsRef<std::path::Path>,
+    M: ViolationMetric,
+{
+    compute_violation_times_with_reset(
+        net,
+        queue,
+        fw_state,
+        trace,
+        transient_policies,
+        log_intervals_path,
+        metric,
+        FwStateReset::default(),
+    )
+}
+
+/// Same as `compute_violation_times`, but restoring the given `fw_state` after handling each
+/// prefix as selected by `reset`.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_violation_times_with_reset<Q, PathRef, M>(
+    net: &Network<Prefix, Q>,
+    queue: &mut TimingModel<Prefix>,
+    fw_state: &mut ForwardingState<Prefix>,
+    trace: &MultiPrefixConvergenceTrace,
+    transient_policies: &HashMap<(RouterId, Prefix), Vec<TransientPolicy>>,
+    log_intervals_path: Option<PathRef>,
+    metric: &M,
+    reset: FwStateReset,
+) -> Result<ViolationTimes<M::Output>, EvaluationError>
+where
+    Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
+    Q::Priority: Default + FmtPriority + Clone,
+    PathRef: AsRef<std::path::Path>,
+    M: ViolationMetric,
 {
     // Initialize a list of violation times to return at the end
     let mut violation_times = HashMap::new();
+    let mut persistent = HashSet::new();
 
     // Initialize logger to write computed path intervals if `log_intervals_path` is given.
     let mut csv = log_intervals_path.map(|csv_path| {
@@ -239,13 +431,21 @@ where
             },
             prefix,
             prefix_trace,
-        );
+            reset,
+        )?;
 
         check_route_intervals(
             prefix,
             &route_intervals,
             &mut violation_times,
             transient_policies,
+            metric,
+        )?;
+        check_final_intervals(
+            prefix,
+            &route_intervals,
+            &mut persistent,
+            transient_policies,
         );
 
         if let Some(ref mut csv) = csv {
@@ -276,8 +476,322 @@ where
         "[transient-analyzer] violation_times:\n{:#?}",
         violation_times
     );
+    if !persistent.is_empty() {
+        log::debug!("[transient-analyzer] persistent violations:\n{persistent:#?}");
+    }
 
-    violation_times
+    Ok(ViolationTimes {
+        times: violation_times,
+        persistent,
+    })
+}
+
+/// Compute the violation of the policy `kind` for every internal router and every prefix of the
+/// `trace`, aggregated using `metric`. Routers that never violate the policy are reported with the
+/// default value of the metric.
+///
+/// This is equivalent to calling `compute_violation_times` with `kind` instantiated for all
+/// internal routers, but avoids building and looking up these policies. The route intervals of
+/// each prefix are computed only once for all routers.
+pub fn compute_all_violation_times<Q, M>(
+    net: &Network<Prefix, Q>,
+    queue: &mut TimingModel<Prefix>,
+    fw_state: &mut ForwardingState<Prefix>,
+    trace: &MultiPrefixConvergenceTrace,
+    kind: PolicyKind,
+    metric: &M,
+) -> Result<HashMap<(RouterId, Prefix), M::Output>, EvaluationError>
+where
+    Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
+    Q::Priority: Default + FmtPriority + Clone,
+    M: ViolationMetric,
+{
+    let mut result = HashMap::new();
+    for (prefix, prefix_trace) in trace {
+        let route_intervals = compute_violation_times_for_prefix(
+            &mut IAParams {
+                net,
+                queue,
+                fw_state,
+            },
+            prefix,
+            prefix_trace,
+            FwStateReset::Revert,
+        )?;
+
+        for (rid, intervals) in route_intervals {
+            let policy = kind.instantiate(rid, *prefix);
+            let mut violation_times = HashMap::new();
+            for interval in intervals.iter() {
+                check_interval(
+                    interval,
+                    &mut violation_times,
+                    std::slice::from_ref(&policy),
+                    metric,
+                )?;
+            }
+            result.insert(
+                (rid, *prefix),
+                violation_times.remove(&policy).unwrap_or_default(),
+            );
+        }
+    }
+    Ok(result)
+}
+
+/// Compute the round-trip violation time of each transient policy, accounting for both the traffic
+/// of a router towards the policy's prefix and the return traffic back to the router.
+///
+/// Routers are not destinations of the BGP data plane, so the return traffic of a router is sent
+/// towards its return prefix in `return_prefixes`, e.g., a customer prefix reachable through the
+/// router. Return traffic leaves the network at the egress router of the forward path, as soon as
+/// the forward traffic reaches it. A policy is violated whenever the forward path violates it, or
+/// when the forward path satisfies it but the return traffic does not reach the return prefix.
+/// Routers without a return prefix are only checked in the forward direction.
+///
+/// Prefixes without any forwarding update keep their initial path during the entire trace. Only
+/// the total duration of the violations is computed.
+pub fn compute_round_trip_violation_times<Q>(
+    net: &Network<Prefix, Q>,
+    queue: &mut TimingModel<Prefix>,
+    fw_state: &mut ForwardingState<Prefix>,
+    trace: &MultiPrefixConvergenceTrace,
+    transient_policies: &HashMap<(RouterId, Prefix), Vec<TransientPolicy>>,
+    return_prefixes: &HashMap<RouterId, Prefix>,
+) -> Result<HashMap<TransientPolicy, f64>, EvaluationError>
+where
+    Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
+    Q::Priority: Default + FmtPriority + Clone,
+{
+    let mut violation_times = HashMap::new();
+
+    // compute the route intervals of all updated prefixes once, for both directions
+    let route_intervals: HashMap<Prefix, HashMap<RouterId, Vec<Interval>>> = trace
+        .iter()
+        .filter(|(_, prefix_trace)| !prefix_trace.is_empty())
+        .map(|(prefix, prefix_trace)| {
+            compute_violation_times_for_prefix(
+                &mut IAParams {
+                    net,
+                    queue,
+                    fw_state,
+                },
+                prefix,
+                prefix_trace,
+                FwStateReset::Revert,
+            )
+            .map(|intervals| (*prefix, intervals))
+        })
+        .collect::<Result<_, _>>()?;
+    let all_intervals = || route_intervals.values().flat_map(|r| r.values()).flatten();
+    let Some(t_min) = all_intervals().map(|(t, _, _)| *t).min_by(f64::total_cmp) else {
+        return Ok(violation_times);
+    };
+    let t_max = all_intervals()
+        .map(|(_, t, _)| *t)
+        .max_by(f64::total_cmp)
+        .unwrap();
+
+    // intervals of `router` towards `prefix`, spanning the entire trace for prefixes that are
+    // never updated
+    let mut intervals_of =
+        |router: RouterId, prefix: Prefix| -> Result<Vec<Interval>, EvaluationError> {
+            match route_intervals.get(&prefix).and_then(|r| r.get(&router)) {
+                Some(intervals) => Ok(intervals.clone()),
+                None => Ok(vec![(
+                    t_min,
+                    t_max,
+                    get_path_from_fw_state(&router, &prefix, fw_state)?,
+                )]),
+            }
+        };
+
+    for ((rid, prefix), policies) in transient_policies {
+        let return_prefix = return_prefixes.get(rid).copied();
+        for (t_start, t_end, path) in intervals_of(*rid, *prefix)? {
+            let duration = duration(t_start, t_end)?;
+            let violated = policies
+                .iter()
+                .filter(|policy| !policy.check_path_no_loops_of_length_2(&path))
+                .collect_vec();
+            for policy in violated.iter() {
+                *violation_times.entry((*policy).clone()).or_default() += duration;
+            }
+
+            // check the return traffic of the remaining policies
+            let (Some(return_prefix), Path::Route(hops)) = (return_prefix, &path) else {
+                continue;
+            };
+            let Some(egress) = hops.len().checked_sub(2).map(|i| hops[i]) else {
+                continue;
+            };
+            let delay = propagation_delay(queue, &hops[..hops.len() - 1]);
+            let broken: f64 = intervals_of(egress, return_prefix)?
+                .iter()
+                .filter(|(_, _, return_path)| !matches!(return_path, Path::Route(_)))
+                .map(|(t0, t1, _)| {
+                    (t1.min(t_start + duration + delay) - t0.max(t_start + delay)).max(0.0)
+                })
+                .sum();
+            if broken > 0.0 {
+                for policy in policies.iter().filter(|p| !violated.contains(p)) {
+                    *violation_times.entry(policy.clone()).or_default() += broken;
+                }
+            }
+        }
+    }
+
+    Ok(violation_times)
+}
+
+/// Compute the violations of all `TransientPolicy::ConvergenceDeadline` policies among `policies`.
+/// All other policies are ignored, as they are checked by `compute_violation_times`.
+///
+/// A prefix has converged once its last forwarding update (`t_last_event`) is applied, with times
+/// relative to the event as produced by `Analyzer::build_trace`. The violation of a policy is the
+/// time by which the convergence exceeds its deadline. If not all internal routers reach the
+/// prefix in the resulting forwarding state, the policy is also reported as persistent.
+pub fn compute_convergence_violations<Q>(
+    net: &Network<Prefix, Q>,
+    fw_state: &ForwardingState<Prefix>,
+    trace: &MultiPrefixConvergenceTrace,
+    policies: &[TransientPolicy],
+) -> ViolationTimes<f64> {
+    let mut result = ViolationTimes::default();
+    for policy in policies {
+        let TransientPolicy::ConvergenceDeadline {
+            prefix,
+            deadline_ms,
+        } = policy
+        else {
+            continue;
+        };
+        // the prefix is never updated, so it remains in its (converged) initial state
+        let Some(prefix_trace) = trace.get(prefix).filter(|t| !t.is_empty()) else {
+            continue;
+        };
+
+        let t_last_event = step_time(&prefix_trace[prefix_trace.len() - 1].1);
+        let violation = t_last_event - *deadline_ms as f64 / 1_000.0;
+        if violation > 0.0 {
+            result.times.insert(policy.clone(), violation);
+        }
+
+        // check that the final forwarding state satisfies reachability from all routers
+        let mut final_fw_state = fw_state.clone();
+        for (fw_deltas, _) in prefix_trace.iter() {
+            for (affected_router, _, new_nh) in fw_deltas {
+                final_fw_state.update(*affected_router, *prefix, new_nh.clone());
+            }
+        }
+        if net
+            .internal_indices()
+            .any(|r| final_fw_state.get_paths(r, *prefix).is_err())
+        {
+            result.persistent.insert(policy.clone());
+        }
+    }
+    result
+}
+
+/// Streaming variant of `compute_violation_times` for the `policies` of a single monitored `router`
+/// and `prefix`.
+///
+/// Instead of keeping the intervals of all internal routers until the end of the trace, only the
+/// intervals of `router` are tracked. Intervals along a `Path::Route` or `Path::BlackHole` are
+/// checked and dropped as soon as all traffic sent during the interval has left the network, as
+/// later forwarding updates can no longer affect them. Looping intervals are kept until the end.
+///
+/// The results are equal to the ones of `compute_violation_times` for these policies, up to
+/// floating-point rounding when accumulating the intervals in a different order. Like
+/// `compute_violation_times`, this fails with `EvaluationError::LoadBalancing` if the traffic of
+/// `router` is load-balanced over multiple paths.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_violation_times_streaming<Q, M>(
+    net: &Network<Prefix, Q>,
+    queue: &mut TimingModel<Prefix>,
+    fw_state: &mut ForwardingState<Prefix>,
+    router: RouterId,
+    prefix: &Prefix,
+    prefix_trace: &PrefixTrace,
+    policies: &[TransientPolicy],
+    metric: &M,
+) -> Result<HashMap<TransientPolicy, M::Output>, EvaluationError>
+where
+    Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
+    Q::Priority: Default + FmtPriority + Clone,
+    M: ViolationMetric,
+{
+    let mut violation_times = HashMap::new();
+    let mut params = IAParams {
+        net,
+        queue,
+        fw_state,
+    };
+
+    // get the first and last event's time from the `MultiPrefixConvergenceTrace`
+    assert!(!prefix_trace.is_empty());
+    let t_first_event = step_time(&prefix_trace[0].1);
+    let t_last_event = step_time(&prefix_trace[prefix_trace.len() - 1].1);
+
+    // init the router's interval as (t_first - propagation_delay(path), t_last)
+    let path = get_path_from_fw_state(&router, prefix, params.fw_state)?;
+    let t_start = t_first_event - propagation_delay(params.queue, path.hops());
+    let mut intervals: Vec<Interval> = vec![(t_start, t_last_event, path)];
+
+    // traverse the time series of forwarding states and split the intervals as required
+    let mut checked = Ok(());
+    'steps: for (fw_deltas, time) in prefix_trace.iter() {
+        let time = step_time(time);
+        // apply all deltas of the step before splitting, as they happen simultaneously
+        if let Err(e) = apply_fw_deltas(params.fw_state, prefix, fw_deltas) {
+            checked = Err(e);
+            break;
+        }
+        for affected_router in fw_deltas.iter().map(|(r, _, _)| r).unique() {
+            let path_suffix = match get_path_from_fw_state(affected_router, prefix, params.fw_state)
+            {
+                Ok(path_suffix) => path_suffix,
+                Err(e) => {
+                    checked = Err(e);
+                    break 'steps;
+                }
+            };
+            intervals =
+                split_intervals(&mut params, time, affected_router, &path_suffix, intervals);
+        }
+
+        // check and drop all intervals that can no longer be affected by any later update
+        let (done, pending): (Vec<_>, Vec<_>) =
+            intervals.into_iter().partition(|(_, t_end, path)| {
+                !path.is_loop() && t_end + propagation_delay(params.queue, path.hops()) <= time
+            });
+        intervals = pending;
+        checked = done.iter().try_for_each(|interval| {
+            check_interval(interval, &mut violation_times, policies, metric)
+        });
+        if checked.is_err() {
+            break;
+        }
+    }
+
+    if checked.is_ok() {
+        checked = intervals.iter().try_for_each(|interval| {
+            check_interval(interval, &mut violation_times, policies, metric)
+        });
+    }
+
+    // reset the `ForwardingState` after handling the prefix. Reverting all steps also restores it
+    // if the traversal was interrupted by an error.
+    for (fw_deltas, _) in prefix_trace.iter().rev() {
+        for (affected_router, old_nh, _) in fw_deltas {
+            params
+                .fw_state
+                .update(*affected_router, *prefix, old_nh.clone());
+        }
+    }
+
+    checked.map(|_| violation_times)
 }
 
 struct IAParams<'a, Q> {
@@ -286,9 +800,73 @@ struct IAParams<'a, Q> {
     fw_state: &'a mut ForwardingState<Prefix>,
 }
 
+/// Compute the route intervals of all routers for the given prefix (see `compute_route_intervals`),
+/// restoring the `ForwardingState` of `params` afterwards as selected by `reset`, even if the
+/// computation fails.
+fn compute_violation_times_for_prefix<Q>(
+    params: &mut IAParams<'_, Q>,
+    prefix: &Prefix,
+    prefix_trace: &PrefixTrace,
+    reset: FwStateReset,
+) -> Result<HashMap<RouterId, Vec<Interval>>, EvaluationError>
+where
+    Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
+    Q::Priority: Default + FmtPriority + Clone,
+{
+    match reset {
+        FwStateReset::Revert => {
+            #[cfg(debug_assertions)]
+            let initial = next_hops_of_prefix(params, prefix);
+            let route_intervals = compute_route_intervals(params, prefix, prefix_trace);
+            // reset the `ForwardingState` after handling each prefix. Reverting all steps also
+            // restores it if the computation was interrupted by an error.
+            for (fw_deltas, _) in prefix_trace.iter().rev() {
+                for (affected_router, old_nh, _) in fw_deltas {
+                    params
+                        .fw_state
+                        .update(*affected_router, *prefix, old_nh.clone());
+                }
+            }
+            // other prefixes reuse the state, so every delta must be perfectly invertible
+            #[cfg(debug_assertions)]
+            assert_eq!(
+                initial,
+                next_hops_of_prefix(params, prefix),
+                "Reverting the forwarding updates of prefix {prefix} did not restore the ForwardingState!",
+            );
+            route_intervals
+        }
+        FwStateReset::Clone => {
+            let mut fw_state = params.fw_state.clone();
+            compute_route_intervals(
+                &mut IAParams {
+                    net: params.net,
+                    queue: params.queue,
+                    fw_state: &mut fw_state,
+                },
+                prefix,
+                prefix_trace,
+            )
+        }
+    }
+}
+
+/// Get the next hops of all internal routers for `prefix` in the `ForwardingState` of `params`.
+#[cfg(debug_assertions)]
+fn next_hops_of_prefix<Q>(params: &IAParams<'_, Q>, prefix: &Prefix) -> Vec<Vec<RouterId>> {
+    params
+        .net
+        .internal_indices()
+        .map(|r| params.fw_state.get_next_hops(r, *prefix).to_vec())
+        .collect()
+}
+
 /// Performs a time-interval based data-plane simulation, computing along which route(s) traffic
 /// sent at time t from any router is forwarded towards the given destination prefix.
 ///
+/// This applies all forwarding deltas of `prefix_trace` to the `ForwardingState` of `params`
+/// without restoring it.
+///
 /// This works as we know that traffic sent from a router before time `t_first_event` minus the
 /// propagation delay of the router's initial path, and traffic sent after `t_last_event` may not
 /// experience any violation.
@@ -296,84 +874,92 @@ struct IAParams<'a, Q> {
 /// Note that the paths taken through the network may not necessarily coincide with paths observed
 /// in any of the forwarding states. This may be caused by convergence updates during a packet's
 /// network traversal.
-fn compute_violation_times_for_prefix<Q>(
+///
+/// Fails with `EvaluationError::LoadBalancing` if traffic is load-balanced over multiple paths.
+fn compute_route_intervals<Q>(
     params: &mut IAParams<'_, Q>,
     prefix: &Prefix,
     prefix_trace: &PrefixTrace,
-) -> HashMap<RouterId, Vec<Interval>>
+) -> Result<HashMap<RouterId, Vec<Interval>>, EvaluationError>
 where
     Q: EventQueue<Prefix> + Clone + Send + Sync + std::fmt::Debug + PartialEq,
     Q::Priority: Default + FmtPriority + Clone,
 {
     // get the first and last event's time from the `MultiPrefixConvergenceTrace`
     assert!(!prefix_trace.is_empty());
-    let t_first_event = prefix_trace[0].1.into_inner().unwrap();
-    let t_last_event = prefix_trace[prefix_trace.len() - 1].1.into_inner().unwrap();
+    let t_first_event = step_time(&prefix_trace[0].1);
+    let t_last_event = step_time(&prefix_trace[prefix_trace.len() - 1].1);
 
     // init all route_intervals as (t_first - propagation_delay(path), t_last)
-    let mut route_intervals = HashMap::from_iter(params.net.internal_routers().flat_map(|r| {
-        let paths = params.fw_state.get_paths(r.router_id(), *prefix).unwrap();
-        if paths.len() > 1 {
-            todo!("extend algorithm for load-balancing!");
-        }
-        let mut intervals = Vec::new();
-        for path in paths.iter() {
-            intervals.push((
-                r.router_id(),
-                vec![(
-                    t_first_event - propagation_delay(params.queue, path),
-                    t_last_event,
-                    Path::Route(path.clone()),
-                )],
-            ));
-        }
-        intervals
-    }));
+    let mut route_intervals = HashMap::new();
+    for r in params.net.internal_indices() {
+        let path = get_path_from_fw_state(&r, prefix, params.fw_state)?;
+        let t_start = t_first_event - propagation_delay(params.queue, path.hops());
+        route_intervals.insert(r, vec![(t_start, t_last_event, path)]);
+    }
 
     // traverse the time series of forwarding states and split the route_intervals as required
     for (fw_deltas, time) in prefix_trace.iter() {
-        // assume single updates per step
-        assert_eq!(fw_deltas.len(), 1);
-        let (affected_router, _, new_nh) = fw_deltas.first().unwrap();
-        // assume no multi-path
-        assert!(new_nh.len() <= 1);
+        let time = step_time(time);
+        // apply all deltas of the step before splitting, as they happen simultaneously
+        apply_fw_deltas(params.fw_state, prefix, fw_deltas)?;
 
-        log::trace!(
-            "\n[t = {}] FW-UPDATE at {}: new_nh: {}",
-            time.as_ref().unwrap(),
-            affected_router.fmt(params.net),
-            new_nh.fmt(params.net),
-        );
-
-        params
-            .fw_state
-            .update(*affected_router, *prefix, new_nh.clone());
-
-        // get new path slice that will be experienced by the traffic from the
-        // `affected_router` onwards
-        let path_suffix = get_path_from_fw_state(affected_router, prefix, params.fw_state);
+        for affected_router in fw_deltas.iter().map(|(r, _, _)| r).unique() {
+            // get new path slice that will be experienced by the traffic from the
+            // `affected_router` onwards
+            let path_suffix = get_path_from_fw_state(affected_router, prefix, params.fw_state)?;
 
-        for r in params.net.internal_routers() {
-            let intervals = route_intervals.get_mut(&r.router_id()).unwrap();
-            *intervals = split_intervals(
-                params,
-                time.as_ref().unwrap(),
-                affected_router,
-                &path_suffix,
-                std::mem::take(intervals),
+            log::trace!(
+                "\n[t = {time}] FW-UPDATE at {}: new_nh: {}, new path: {}",
+                affected_router.fmt(params.net),
+                params
+                    .fw_state
+                    .get_next_hops(*affected_router, *prefix)
+                    .fmt(params.net),
+                path_suffix.fmt_named(params.net),
             );
+
+            for r in params.net.internal_routers() {
+                let intervals = route_intervals.get_mut(&r.router_id()).unwrap();
+                *intervals = split_intervals(
+                    params,
+                    time,
+                    affected_router,
+                    &path_suffix,
+                    std::mem::take(intervals),
+                );
+            }
         }
     }
 
-    // reset the `ForwardingState` after handling each prefix
-    for (fw_deltas, _) in prefix_trace.iter().rev() {
-        for (affected_router, old_nh, _) in fw_deltas {
-            params
-                .fw_state
-                .update(*affected_router, *prefix, old_nh.clone());
-        }
+    Ok(route_intervals)
+}
+
+/// Get the time of a step of a trace, which must be present to place the forwarding deltas of the
+/// step in time.
+fn step_time(time: &AlwaysEq<Option<f64>>) -> f64 {
+    time.into_inner()
+        .expect("all forwarding updates of a trace must have a time")
+}
+
+/// Apply all forwarding deltas of a single step of a trace to `fw_state`. The deltas of a step
+/// happen simultaneously, e.g., if multiple forwarding updates were measured at the same time.
+/// Fails with `EvaluationError::LoadBalancing`, without applying any delta of the step, if a delta
+/// load-balances traffic over multiple next hops.
+fn apply_fw_deltas(
+    fw_state: &mut ForwardingState<Prefix>,
+    prefix: &Prefix,
+    fw_deltas: &[FwDelta],
+) -> Result<(), EvaluationError> {
+    // assume no multi-path
+    if let Some((affected_router, _, _)) = fw_deltas.iter().find(|(_, _, new_nh)| new_nh.len() > 1)
+    {
+        return Err(EvaluationError::LoadBalancing(*affected_router, *prefix));
     }
-    route_intervals
+    for (affected_router, _, new_nh) in fw_deltas {
+        fw_state.update(*affected_router, *prefix, new_nh.clone());
+    }
+    Ok(())
 }
 
 /// Find the interval(s) which are affected by a forwarding change at `affected_router`.
@@ -460,9 +1046,33 @@ where
     intervals
 }
 
+/// Compute the violation times of the `policies` directly from measured `records`, e.g., the path
+/// updates extracted from the prober packets captured on the hardware.
+///
+/// Use this function for measured data, where the paths taken by traffic and the times at which
+/// they change are observed directly. Each record starts a new interval of its `(src, prefix)`
+/// flow, which ends with the next record of the same flow, such that no `TimingModel` is involved.
+/// Use `compute_violation_times` instead for simulated convergence traces, whose per-hop delays
+/// must be modeled to derive the paths taken by traffic.
+///
+/// The records must be in chronological order. Returns `EvaluationError::NoData` if there are no
+/// records, and `EvaluationError::PersistentViolation` if the last path of a flow violates a
+/// policy (see `check_path_updates`).
+pub fn measured_violation_times(
+    records: &[PathRecord],
+    policies: &[TransientPolicy],
+) -> Result<HashMap<TransientPolicy, f64>, EvaluationError> {
+    if records.is_empty() {
+        return Err(EvaluationError::NoData);
+    }
+    check_path_updates(records, &group_policies(policies))
+}
+
 /// Checks a given csv file with `PathRecord`s for violations with the given `transient_policies`.
 /// Uses the router_names property and `Router::is_external()` to determine whether a route is
 /// reaching the destination, or not and adequately generating a `Path::Route` or `Path::BlackHole`.
+///
+/// See `measured_violation_times` for a wrapper taking the policies ungrouped.
 pub fn check_path_updates(
     path_updates: &[PathRecord],
     transient_policies: &HashMap<(RouterId, Prefix), Vec<TransientPolicy>>,
@@ -480,13 +1090,13 @@ pub fn check_path_updates(
     {
         // Remove and process last interval for this flow
         if let Some((last_time, path)) = last_records.remove(&(*src, *prefix)) {
-            assert!(time - last_time >= 0.0);
+            let duration = duration(last_time, *time)?;
 
             if let Some(policies) = transient_policies.get(&(*src, Prefix::from(*prefix))) {
                 for policy in policies {
                     // check and add violation times
                     if !policy.check_path(&path) {
-                        *violation_times.entry(policy.clone()).or_default() += time - last_time;
+                        *violation_times.entry(policy.clone()).or_default() += duration;
                     }
                 }
             }
@@ -536,62 +1146,141 @@ pub enum EvaluationError {
     /// Error when the last forwarding state doesn't satisfy all policies
     #[error("The violation of policy {0:?} did not end, but ended up being persistend instead!")]
     PersistentViolation(TransientPolicy),
+    /// Error when an interval ends before it starts by more than `DURATION_EPSILON`.
+    #[error("The interval from {0} to {1} has a negative duration!")]
+    NegativeDuration(f64, f64),
+    /// Error when the traffic of a router is load-balanced over multiple (ECMP) paths, which the
+    /// interval algorithm does not support.
+    #[error("The traffic of router {0:?} towards {1} is load-balanced over multiple paths!")]
+    LoadBalancing(RouterId, Prefix),
 }
 
-/// Check all route intervals for violations of each transient_policy
-fn check_route_intervals(
+/// Check all route intervals for violations of each transient_policy, aggregating the violating
+/// intervals using `metric`. Fails if any violating interval has a negative duration.
+fn check_route_intervals<M: ViolationMetric>(
     prefix: &Prefix,
     route_intervals: &HashMap<RouterId, Vec<Interval>>,
-    violation_times: &mut HashMap<TransientPolicy, f64>,
+    violation_times: &mut HashMap<TransientPolicy, M::Output>,
     transient_policies: &HashMap<(RouterId, Prefix), Vec<TransientPolicy>>,
-) {
-    transient_policies
-        .iter()
-        .filter(|((_, p), _)| p == prefix)
-        .for_each(|(_, policies)| {
-            for policy in policies.iter() {
-                let rid = policy
-                    .router()
-                    .expect("Did not expect a policy that doesn't concern a dedicated router!");
-                for (t_start, t_end, path) in route_intervals.get(&rid).unwrap().iter() {
-                    // check and add violation times
-                    if !policy.check_path_no_loops_of_length_2(path) {
-                        assert!(t_end - t_start >= 0.0);
-                        *violation_times.entry(policy.clone()).or_default() += t_end - t_start;
-                    }
-                }
+    metric: &M,
+) -> Result<(), EvaluationError> {
+    for (_, policies) in transient_policies.iter().filter(|((_, p), _)| p == prefix) {
+        for policy in policies.iter() {
+            let rid = policy
+                .router()
+                .expect("Did not expect a policy that doesn't concern a dedicated router!");
+            for interval in route_intervals.get(&rid).unwrap().iter() {
+                check_interval(
+                    interval,
+                    violation_times,
+                    std::slice::from_ref(policy),
+                    metric,
+                )?;
             }
-        });
+        }
+    }
+    Ok(())
 }
 
-fn get_path_from_fw_state(
-    affected_router: &RouterId,
+/// Collect all policies of `prefix` that are violated during the final interval of their router,
+/// i.e., by the path taken after convergence.
+fn check_final_intervals(
     prefix: &Prefix,
-    fw_state: &mut ForwardingState<Prefix>,
-) -> Path {
-    match fw_state.get_paths(*affected_router, *prefix) {
-        Ok(paths) => {
-            if paths.len() > 1 {
-                todo!("extend algorithm for load-balancing!");
-            }
-            Path::Route(paths[0].clone())
+    route_intervals: &HashMap<RouterId, Vec<Interval>>,
+    persistent: &mut HashSet<TransientPolicy>,
+    transient_policies: &HashMap<(RouterId, Prefix), Vec<TransientPolicy>>,
+) {
+    for ((rid, _), policies) in transient_policies.iter().filter(|((_, p), _)| p == prefix) {
+        let Some((_, _, path)) = route_intervals
+            .get(rid)
+            .and_then(|intervals| intervals.iter().max_by(|a, b| a.0.total_cmp(&b.0)))
+        else {
+            continue;
+        };
+        persistent.extend(
+            policies
+                .iter()
+                .filter(|policy| !policy.check_path_no_loops_of_length_2(path))
+                .cloned(),
+        );
+    }
+}
+
+/// Check a single interval for violations of the given policies, aggregating its duration using
+/// `metric` for each violated policy. Fails with `EvaluationError::NegativeDuration` if the
+/// interval is violating and ends before it starts (see `DURATION_EPSILON`).
+fn check_interval<M: ViolationMetric>(
+    (t_start, t_end, path): &Interval,
+    violation_times: &mut HashMap<TransientPolicy, M::Output>,
+    policies: &[TransientPolicy],
+    metric: &M,
+) -> Result<(), EvaluationError> {
+    for policy in policies {
+        // check and add violation times
+        if !policy.check_path_no_loops_of_length_2(path) {
+            let duration = duration(*t_start, *t_end)?;
+            metric.accumulate(
+                violation_times.entry(policy.clone()).or_default(),
+                *t_start,
+                t_start + duration,
+                path,
+            );
         }
-        Err(NetworkError::ForwardingBlackHole(path)) => Path::BlackHole(path),
+    }
+    Ok(())
+}
+
+/// Get the paths of `router` towards `prefix` in the `fw_state`. With multiple (ECMP) paths, all of
+/// them are returned. If the traffic is dropped or caught in a loop, the black hole or the loop is
+/// returned as the only path.
+pub fn paths_from_fw_state(
+    router: RouterId,
+    prefix: Prefix,
+    fw_state: &mut ForwardingState<Prefix>,
+) -> Vec<Path> {
+    match fw_state.get_paths(router, prefix) {
+        Ok(paths) => paths.into_iter().map(Path::Route).collect(),
+        Err(NetworkError::ForwardingBlackHole(path)) => vec![Path::BlackHole(path)],
         Err(NetworkError::ForwardingLoop {
             mut to_loop,
             first_loop,
         }) => {
             to_loop.push(first_loop[0]);
-            Path::Loop(to_loop, first_loop)
+            vec![Path::Loop(to_loop, first_loop)]
         }
-        _ => unreachable!("Other errors should not occur!"),
+        Err(e) => unreachable!("Unexpected error while computing paths: {e}"),
     }
 }
 
+/// Get the single path of `affected_router` towards `prefix` in the `fw_state` (see
+/// `paths_from_fw_state`). The interval algorithm follows a single path per interval, so it fails
+/// with `EvaluationError::LoadBalancing` if traffic is load-balanced over multiple paths.
+fn get_path_from_fw_state(
+    affected_router: &RouterId,
+    prefix: &Prefix,
+    fw_state: &mut ForwardingState<Prefix>,
+) -> Result<Path, EvaluationError> {
+    let mut paths = paths_from_fw_state(*affected_router, *prefix, fw_state);
+    if paths.len() > 1 {
+        return Err(EvaluationError::LoadBalancing(*affected_router, *prefix));
+    }
+    Ok(paths.pop().expect("there is always at least one path"))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    use bgpsim::policies::FwPolicy;
+
+    use crate::analyzer::{
+        test::{
+            assert_same_next_hops, path_analyzer, path_analyzer_with_delay, sample_trace,
+            total_violation_times,
+        },
+        Analyzer,
+    };
+
     fn prop(p: &[RouterId]) -> f64 {
         (p.len() - 1) as f64
     }
@@ -664,4 +1353,552 @@ mod test {
             vec![(0.0, 5.0, Path::Route(vec![A, B, D]))]
         );
     }
+
+    #[test]
+    fn severity_weighted() {
+        let (src, dst) = (RouterId::from(0), RouterId::from(1));
+        let prefix = Prefix::from(Ipv4Addr::new(100, 0, 0, 0));
+        let policy = PolicyKind::Reachable.instantiate(src, prefix);
+        let policies = HashMap::from([((src, prefix), vec![policy.clone()])]);
+        let route_intervals = HashMap::from([(
+            src,
+            vec![
+                (0.0, 1.0, Path::Route(vec![src, dst])),
+                (1.0, 3.0, Path::BlackHole(vec![src])),
+                (3.0, 4.0, Path::Loop(vec![src], vec![src, dst])),
+                (4.0, 10.0, Path::Route(vec![src, dst])),
+            ],
+        )]);
+
+        let mut unweighted = HashMap::new();
+        check_route_intervals(
+            &prefix,
+            &route_intervals,
+            &mut unweighted,
+            &policies,
+            &TotalDuration,
+        )
+        .unwrap();
+        let mut weighted = HashMap::new();
+        check_route_intervals(
+            &prefix,
+            &route_intervals,
+            &mut weighted,
+            &policies,
+            &SeverityWeighted::default(),
+        )
+        .unwrap();
+
+        // the black hole counts twice, the loop one and a half times
+        assert_eq!(unweighted, HashMap::from([(policy.clone(), 3.0)]));
+        assert_eq!(
+            weighted,
+            HashMap::from([(policy.clone(), 2.0 * 2.0 + 1.5 * 1.0)])
+        );
+
+        // unit weights reproduce the unweighted duration
+        let unit = SeverityWeighted {
+            route: 1.0,
+            forwarding_loop: 1.0,
+            black_hole: 1.0,
+        };
+        let mut weighted = HashMap::new();
+        check_route_intervals(&prefix, &route_intervals, &mut weighted, &policies, &unit).unwrap();
+        assert_eq!(weighted, unweighted);
+    }
+
+    #[test]
+    fn measured_violation_window() {
+        let (r0, r1, ext) = (RouterId::from(0), RouterId::from(1), RouterId::from(2));
+        let prefix = Ipv4Addr::new(100, 0, 0, 0);
+        let record = |time: f64, path: Vec<RouterId>, path_names: Vec<Router>| PathRecord {
+            time,
+            src: r0,
+            src_name: Some(Router::Atlanta),
+            prefix,
+            seq: None,
+            path,
+            path_names: path_names.into_iter().map(Some).collect(),
+        };
+        let route = |time| {
+            record(
+                time,
+                vec![r0, r1, ext],
+                vec![Router::Atlanta, Router::Chicago, Router::AtlantaExt],
+            )
+        };
+        let black_hole = |time| record(time, vec![r0, r1], vec![Router::Atlanta, Router::Chicago]);
+
+        // traffic is dropped from 1.25 s to 1.75 s
+        let records = [
+            route(0.0),
+            route(1.0),
+            black_hole(1.25),
+            route(1.75),
+            route(3.0),
+        ];
+        let reachable = PolicyKind::Reachable.instantiate(r0, Prefix::from(prefix));
+        let loop_free = PolicyKind::LoopFree.instantiate(r0, Prefix::from(prefix));
+        let violations =
+            measured_violation_times(&records, &[reachable.clone(), loop_free]).unwrap();
+        assert_eq!(violations, HashMap::from([(reachable.clone(), 0.5)]));
+
+        assert!(matches!(
+            measured_violation_times(&[], &[reachable.clone()]),
+            Err(EvaluationError::NoData)
+        ));
+        assert!(matches!(
+            measured_violation_times(&records[..3], &[reachable]),
+            Err(EvaluationError::PersistentViolation(..))
+        ));
+    }
+
+    #[test]
+    fn negative_duration() {
+        assert_eq!(duration(1.0, 3.0).unwrap(), 2.0);
+        assert_eq!(duration(1.0, 1.0 - 1e-13).unwrap(), 0.0);
+        assert!(matches!(
+            duration(1.0, 0.5),
+            Err(EvaluationError::NegativeDuration(..))
+        ));
+
+        let (src, dst) = (RouterId::from(0), RouterId::from(1));
+        let prefix = Ipv4Addr::new(100, 0, 0, 0);
+        let record = |time: f64| PathRecord {
+            time,
+            src,
+            src_name: None,
+            prefix,
+            seq: None,
+            path: vec![src, dst],
+            path_names: vec![None, None],
+        };
+        let policies = HashMap::from([(
+            (src, Prefix::from(prefix)),
+            vec![PolicyKind::LoopFree.instantiate(src, Prefix::from(prefix))],
+        )]);
+
+        // tiny negative durations from rounding are clamped to zero
+        let updates = [record(1.0), record(1.0 - 1e-13)];
+        assert!(check_path_updates(&updates, &policies).unwrap().is_empty());
+
+        // meaningfully-negative durations are reported
+        let updates = [record(1.0), record(0.5)];
+        assert!(matches!(
+            check_path_updates(&updates, &policies),
+            Err(EvaluationError::NegativeDuration(..))
+        ));
+
+        // as are violating intervals of simulated traces
+        let policy = PolicyKind::Reachable.instantiate(src, Prefix::from(prefix));
+        let mut violation_times = HashMap::new();
+        assert!(matches!(
+            check_interval(
+                &(1.0, 0.5, Path::BlackHole(vec![src])),
+                &mut violation_times,
+                std::slice::from_ref(&policy),
+                &TotalDuration,
+            ),
+            Err(EvaluationError::NegativeDuration(..))
+        ));
+    }
+
+    /// Run both the baseline and the interval algorithm on a number of sampled traces, asserting
+    /// that the violation times are ordered as expected. Returns the total violation time computed
+    /// by the interval algorithm.
+    fn check_violation_monotonicity(analyzer: &Analyzer<TimingModel<Prefix>>) -> f64 {
+        let mut total = 0.0;
+        let mut queue = analyzer.build_queue();
+        for _ in 0..20 {
+            let trace = sample_trace(analyzer);
+
+            let mut fw_state = analyzer.original_fw.clone();
+            let baseline = compute_baseline(
+                &analyzer.original_net,
+                &mut queue,
+                &mut fw_state,
+                &trace,
+                analyzer.grouped_policies(),
+            )
+            .unwrap();
+            let interval = compute_violation_times(
+                &analyzer.original_net,
+                &mut queue,
+                &mut fw_state,
+                &trace,
+                analyzer.grouped_policies(),
+                None::<&std::path::Path>,
+                &TotalDuration,
+            )
+            .unwrap()
+            .times;
+            assert_violation_monotonicity(&baseline, &interval);
+            total += interval.values().sum::<f64>();
+        }
+        total
+    }
+
+    /// Link delays (in microseconds) for which the monotonicity is checked. With zero delay, the
+    /// baseline and the interval algorithm coincide, so only nonzero delays test the invariant.
+    const MONOTONICITY_DELAYS: [f64; 3] = [100.0, 1_000.0, 10_000.0];
+
+    #[test]
+    fn violation_monotonicity_loop_free() {
+        let mut total = 0.0;
+        for delay in MONOTONICITY_DELAYS {
+            let mut analyzer = path_analyzer_with_delay(2, delay);
+            let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+            let r1 = analyzer.original_net.get_router_id("r1").unwrap();
+            let mut policies = TransientPolicy::for_all_prefixes(
+                r0,
+                PolicyKind::LoopFree,
+                &analyzer.event.prefixes(),
+            );
+            policies.extend(TransientPolicy::for_all_prefixes(
+                r1,
+                PolicyKind::LoopFree,
+                &analyzer.event.prefixes(),
+            ));
+            analyzer.set_policies(policies);
+
+            total += check_violation_monotonicity(&analyzer);
+        }
+        // r0 reroutes via r1 before r1 learns about the withdraw, forming a transient loop
+        assert!(total > 0.0);
+    }
+
+    #[test]
+    fn violation_monotonicity_waypoints() {
+        for delay in MONOTONICITY_DELAYS {
+            let mut analyzer = path_analyzer_with_delay(2, delay);
+            let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+            let r1 = analyzer.original_net.get_router_id("r1").unwrap();
+            analyzer.set_policy_template(r0, PolicyKind::Waypoint(r1));
+
+            check_violation_monotonicity(&analyzer);
+        }
+    }
+
+    /// Metric keeping the longest single interval during which a policy is violated.
+    struct MaxContinuousViolation;
+
+    impl ViolationMetric for MaxContinuousViolation {
+        type Output = f64;
+
+        fn accumulate(&self, acc: &mut f64, t_start: f64, t_end: f64, _path: &Path) {
+            *acc = acc.max(t_end - t_start);
+        }
+    }
+
+    #[test]
+    fn custom_violation_metric() {
+        let mut analyzer = path_analyzer(2);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        analyzer.set_policy_template(r0, PolicyKind::Reachable);
+
+        let mut queue = analyzer.build_queue();
+        for _ in 0..20 {
+            let trace = sample_trace(&analyzer);
+
+            let total = total_violation_times(&analyzer, &mut queue, &trace)
+                .unwrap()
+                .times;
+            let max = compute_violation_times(
+                &analyzer.original_net,
+                &mut queue,
+                &mut analyzer.original_fw.clone(),
+                &trace,
+                analyzer.grouped_policies(),
+                None::<&std::path::Path>,
+                &MaxContinuousViolation,
+            )
+            .unwrap()
+            .times;
+
+            // both metrics see exactly the same violating intervals
+            assert_eq!(
+                total.keys().collect::<HashSet<_>>(),
+                max.keys().collect::<HashSet<_>>()
+            );
+            for (policy, t_max) in max {
+                assert!(t_max >= 0.0);
+                assert!(t_max <= total[&policy] + 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_violation_times() {
+        let mut analyzer = path_analyzer(2);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        let r1 = analyzer.original_net.get_router_id("r1").unwrap();
+        let prefixes = analyzer.event.prefixes();
+        let mut policies = TransientPolicy::for_all_prefixes(r0, PolicyKind::Reachable, &prefixes);
+        policies.extend(TransientPolicy::for_all_prefixes(
+            r1,
+            PolicyKind::LoopFree,
+            &prefixes,
+        ));
+        analyzer.set_policies(policies);
+
+        let mut queue = analyzer.build_queue();
+        for _ in 0..20 {
+            let trace = sample_trace(&analyzer);
+            let mut fw_state = analyzer.original_fw.clone();
+
+            let full = compute_violation_times(
+                &analyzer.original_net,
+                &mut queue,
+                &mut fw_state,
+                &trace,
+                analyzer.grouped_policies(),
+                None::<&std::path::Path>,
+                &TotalDuration,
+            )
+            .unwrap()
+            .times;
+
+            for ((router, prefix), policies) in analyzer.grouped_policies() {
+                let Some(prefix_trace) = trace.get(prefix) else {
+                    continue;
+                };
+                let streaming = compute_violation_times_streaming(
+                    &analyzer.original_net,
+                    &mut queue,
+                    &mut fw_state,
+                    *router,
+                    prefix,
+                    prefix_trace,
+                    policies,
+                    &TotalDuration,
+                )
+                .unwrap();
+                for policy in policies {
+                    let t_full = full.get(policy).copied().unwrap_or_default();
+                    let t_streaming = streaming.get(policy).copied().unwrap_or_default();
+                    assert!((t_full - t_streaming).abs() < 1e-9);
+                }
+            }
+        }
+
+        // load-balancing is reported instead of panicking
+        let r2 = analyzer.original_net.get_router_id("r2").unwrap();
+        let mut fw_state = analyzer.original_fw.clone();
+        fw_state.update(r2, prefixes[0], vec![r0, r1]);
+        let trace = sample_trace(&analyzer);
+        let result = compute_violation_times_streaming(
+            &analyzer.original_net,
+            &mut queue,
+            &mut fw_state,
+            r2,
+            &prefixes[0],
+            &trace[&prefixes[0]],
+            &[TransientPolicy::Atomic(FwPolicy::Reachable(
+                r2,
+                prefixes[0],
+            ))],
+            &TotalDuration,
+        );
+        assert!(matches!(result, Err(EvaluationError::LoadBalancing(r, _)) if r == r2));
+    }
+
+    #[test]
+    fn fw_state_reset_clone() {
+        let mut analyzer = path_analyzer(2);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        let prefixes = analyzer.event.prefixes();
+        analyzer.set_policies(TransientPolicy::for_all_prefixes(
+            r0,
+            PolicyKind::Reachable,
+            &prefixes,
+        ));
+
+        let mut queue = analyzer.build_queue();
+        let trace = sample_trace(&analyzer);
+        let expected = total_violation_times(&analyzer, &mut queue, &trace)
+            .unwrap()
+            .times;
+
+        // append a step load-balancing traffic, which makes the interval algorithm fail after
+        // applying all previous deltas of the prefix
+        let mut broken_trace = trace.clone();
+        let prefix_trace = broken_trace.get_mut(&prefixes[0]).unwrap();
+        let (mut fw_deltas, time) = prefix_trace.last().unwrap().clone();
+        fw_deltas[0].2 = vec![r0; 2];
+        let load_balancing = fw_deltas[0].0;
+        prefix_trace.push((fw_deltas, time));
+
+        let mut fw_state = analyzer.original_fw.clone();
+        let result = compute_violation_times_with_reset(
+            &analyzer.original_net,
+            &mut queue,
+            &mut fw_state,
+            &broken_trace,
+            analyzer.grouped_policies(),
+            None::<&std::path::Path>,
+            &TotalDuration,
+            FwStateReset::Clone,
+        );
+        assert!(matches!(
+            result,
+            Err(EvaluationError::LoadBalancing(r, p)) if r == load_balancing && p == prefixes[0]
+        ));
+        assert_same_next_hops(&analyzer, &fw_state, &analyzer.original_fw);
+
+        // the shared forwarding state is still usable for later prefixes
+        let times = compute_violation_times_with_reset(
+            &analyzer.original_net,
+            &mut queue,
+            &mut fw_state,
+            &trace,
+            analyzer.grouped_policies(),
+            None::<&std::path::Path>,
+            &TotalDuration,
+            FwStateReset::Clone,
+        )
+        .unwrap()
+        .times;
+        assert_eq!(times.len(), expected.len());
+        for (policy, t) in expected {
+            assert!((times[&policy] - t).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "did not restore the ForwardingState")]
+    fn fw_state_reset_not_invertible() {
+        let mut analyzer = path_analyzer(1);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        analyzer.set_policy_template(r0, PolicyKind::Reachable);
+
+        let mut trace = sample_trace(&analyzer);
+        // the old next hops of the first delta do not match the forwarding state before it, such
+        // that reverting the delta does not restore the forwarding state
+        let prefix_trace = trace.values_mut().next().unwrap();
+        prefix_trace[0].0[0].1.push(r0);
+
+        compute_violation_times_with_reset(
+            &analyzer.original_net,
+            &mut analyzer.build_queue(),
+            &mut analyzer.original_fw.clone(),
+            &trace,
+            analyzer.grouped_policies(),
+            None::<&std::path::Path>,
+            &TotalDuration,
+            FwStateReset::Revert,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn simultaneous_fw_deltas() {
+        let mut analyzer = path_analyzer(1);
+        let policies = analyzer
+            .original_net
+            .internal_indices()
+            .map(|r| PolicyKind::Reachable.instantiate(r, analyzer.event.prefixes()[0]))
+            .collect();
+        analyzer.set_policies(policies);
+
+        let mut queue = analyzer.build_queue();
+        let trace = sample_trace(&analyzer);
+        let (prefix, prefix_trace) = trace.iter().next().unwrap();
+        assert!(prefix_trace.len() >= 2);
+        assert_ne!(prefix_trace[0].0[0].0, prefix_trace[1].0[0].0);
+
+        // the first two updates happen at the same time, either as separate steps or as a single
+        // step with two deltas
+        let time = prefix_trace[1].1.clone();
+        let mut separate = prefix_trace.clone();
+        separate[0].1 = time.clone();
+        let mut merged = separate.clone();
+        let (second, _) = merged.remove(1);
+        merged[0].0.extend(second);
+
+        let compute = |queue: &mut TimingModel<Prefix>, prefix_trace: PrefixTrace| {
+            total_violation_times(&analyzer, queue, &HashMap::from([(*prefix, prefix_trace)]))
+                .unwrap()
+        };
+        let expected = compute(&mut queue, separate);
+        let result = compute(&mut queue, merged);
+        assert_eq!(result.persistent, expected.persistent);
+        for policy in analyzer.policies.iter() {
+            let t = |times: &HashMap<TransientPolicy, f64>| times.get(policy).copied();
+            let (t_result, t_expected) = (t(&result.times), t(&expected.times));
+            assert!((t_result.unwrap_or_default() - t_expected.unwrap_or_default()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn persistent_violations() {
+        let mut analyzer = path_analyzer(1);
+        let r0 = analyzer.original_net.get_router_id("r0").unwrap();
+        let prefix = analyzer.event.prefixes()[0];
+        let reachable = TransientPolicy::Atomic(FwPolicy::Reachable(r0, prefix));
+        let not_reachable = TransientPolicy::Atomic(FwPolicy::NotReachable(r0, prefix));
+        analyzer.set_policies(vec![reachable.clone(), not_reachable.clone()]);
+
+        let mut queue = analyzer.build_queue();
+        let trace = sample_trace(&analyzer);
+        let result = total_violation_times(&analyzer, &mut queue, &trace).unwrap();
+
+        // r0 only transiently drops traffic, but still reaches the prefix via r2_ext after
+        // convergence
+        assert!(result.times[&reachable] > 0.0);
+        assert!(result.times[&not_reachable] > 0.0);
+        assert_eq!(result.persistent, HashSet::from([not_reachable]));
+    }
+
+    #[test]
+    fn all_violation_times() {
+        let mut analyzer = path_analyzer(2);
+        let prefixes = analyzer.event.prefixes();
+        let policies = prefixes
+            .iter()
+            .flat_map(|p| TransientPolicy::reachability_all(&analyzer.original_net, *p))
+            .collect_vec();
+        analyzer.set_policies(policies.clone());
+
+        let mut queue = analyzer.build_queue();
+        for _ in 0..10 {
+            let trace = sample_trace(&analyzer);
+            let mut fw_state = analyzer.original_fw.clone();
+
+            let all = compute_all_violation_times(
+                &analyzer.original_net,
+                &mut queue,
+                &mut fw_state,
+                &trace,
+                PolicyKind::Reachable,
+                &TotalDuration,
+            )
+            .unwrap();
+            assert_same_next_hops(&analyzer, &fw_state, &analyzer.original_fw);
+            assert_eq!(
+                all.len(),
+                analyzer.original_net.internal_indices().count() * prefixes.len()
+            );
+
+            // each router's violation equals the one of its own policy, computed individually
+            for policy in policies.iter() {
+                let key = (policy.router().unwrap(), policy.prefix().unwrap());
+                let individual = compute_violation_times(
+                    &analyzer.original_net,
+                    &mut queue,
+                    &mut fw_state,
+                    &trace,
+                    &group_policies(std::slice::from_ref(policy)),
+                    None::<&std::path::Path>,
+                    &TotalDuration,
+                )
+                .unwrap();
+                let t_individual = individual.times.get(policy).copied().unwrap_or_default();
+                assert!((all[&key] - t_individual).abs() < 1e-9);
+            }
+            // r1 drops traffic towards the withdrawn prefix while converging
+            let r1 = analyzer.original_net.get_router_id("r1").unwrap();
+            assert!(prefixes.iter().all(|p| all[&(r1, *p)] > 0.0));
+        }
+    }
 }
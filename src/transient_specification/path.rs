@@ -65,6 +65,93 @@ impl Path {
         }
     }
 
+    /// Check whether traffic following the `Path` passes the given `router`, e.g., a waypoint.
+    /// This includes all hops of a `Route` or `BlackHole`, and the hops towards and on the loop of
+    /// a `Loop`. Equivalent to `contains`, but taking the `RouterId` by value.
+    pub fn reaches(&self, router: RouterId) -> bool {
+        self.contains(&router)
+    }
+
+    /// Returns the hops of a `Route` or `BlackHole`. For a `Loop`, only the hops towards (and
+    /// including) the first router on the loop are returned.
+    pub fn hops(&self) -> &[RouterId] {
+        match self {
+            Self::Route(p) | Self::BlackHole(p) | Self::Loop(p, _) => p,
+        }
+    }
+
+    /// Returns the egress of a `Route`, i.e., the last router on the path towards the destination.
+    /// A `Loop` or `BlackHole` has no egress.
+    pub fn egress(&self) -> Option<RouterId> {
+        match self {
+            Self::Route(p) => p.last().copied(),
+            Self::Loop(_, _) | Self::BlackHole(_) => None,
+        }
+    }
+
+    /// Locate the first hop where the `Path` and `other` diverge, e.g., to compare a measured path
+    /// with a simulated one. Returns the index of the hop and the two differing routers, or `None`
+    /// if both paths are identical. The loop of a `Loop` is walked once, back to its first router.
+    ///
+    /// If the hops of one path are a prefix of the other's (e.g., `BlackHole([A, B])` and
+    /// `Route([A, B, C])`), or both have the same hops but differing variants (e.g., `Route([A,
+    /// B])` and `BlackHole([A, B])`), the paths diverge at the last hop of the shorter path, where
+    /// one drops or delivers the traffic while the other forwards it. Both returned routers are
+    /// then that last hop.
+    pub fn first_divergence(&self, other: &Self) -> Option<(usize, RouterId, RouterId)> {
+        if self == other {
+            return None;
+        }
+        let (a, b) = (self.walk(), other.walk());
+        if let Some((i, (x, y))) = a.iter().zip(&b).enumerate().find(|(_, (x, y))| x != y) {
+            return Some((i, *x, *y));
+        }
+        let i = a.len().min(b.len()).checked_sub(1)?;
+        Some((i, a[i], b[i]))
+    }
+
+    /// Returns the hops of the `Path`, walking the loop of a `Loop` once, back to its first router.
+    fn walk(&self) -> Vec<RouterId> {
+        match self {
+            Self::Route(p) | Self::BlackHole(p) => p.clone(),
+            Self::Loop(p, l) => p
+                .iter()
+                .chain(l.iter().skip(1))
+                .chain(l.first())
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// Returns a canonical form of the `Path`, repeatedly collapsing immediate bounces
+    /// `A -> B -> A` into `A`. For instance, the unrolled loop `[A, B, C, B, C, B, D]` is
+    /// normalized to `[A, B, D]`. The loop of a `Path::Loop` is kept as is, as it already visits
+    /// each router once.
+    ///
+    /// The first and last hop, and hence, whether the path reaches the destination, are never
+    /// changed. However, routers only traversed during a bounce are removed, so the normalized
+    /// path must not be used to check waypoint policies.
+    pub fn normalized(&self) -> Self {
+        fn collapse(hops: &[RouterId]) -> Vec<RouterId> {
+            let mut result: Vec<RouterId> = Vec::with_capacity(hops.len());
+            for hop in hops {
+                if result.len() >= 2 && result[result.len() - 2] == *hop {
+                    // bounce back to the previous router
+                    result.pop();
+                } else {
+                    result.push(*hop);
+                }
+            }
+            result
+        }
+
+        match self {
+            Self::Route(p) => Self::Route(collapse(p)),
+            Self::BlackHole(p) => Self::BlackHole(collapse(p)),
+            Self::Loop(p, l) => Self::Loop(collapse(p), l.clone()),
+        }
+    }
+
     /// Returns a `Vec<RouterId>` representation of the `Path`; potentially looses information.
     pub fn get_rid_vec(&self) -> Vec<RouterId> {
         match self {
@@ -179,6 +266,22 @@ impl Path {
             .map(|position| self.split_at(position + 1))
     }
 
+    /// Render the `Path` using the names of the routers in `net`, e.g., `LosAngeles -> KansasCity
+    /// -> Denver`. A `BlackHole` is terminated with `-> (drop)`, and the loop of a `Loop` is
+    /// appended in brackets, e.g., `A -> B -> [B -> C -> B ...]`.
+    pub fn fmt_named<Q, Ospf: OspfImpl>(&self, net: &Network<Prefix, Q, Ospf>) -> String {
+        let names = |p: &[RouterId]| p.iter().map(|r| r.fmt(net).to_string()).collect::<Vec<_>>();
+        match self {
+            Self::Route(p) => names(p).join(" -> "),
+            Self::BlackHole(p) => format!("{} -> (drop)", names(p).join(" -> ")),
+            Self::Loop(p, l) => {
+                let mut cycle = names(l);
+                cycle.extend(l.first().map(|r| r.fmt(net).to_string()));
+                format!("{} -> [{} ...]", names(p).join(" -> "), cycle.join(" -> "))
+            }
+        }
+    }
+
     /// Split a `Path` at the given `position`, returning a `Vec<RouterId>` that contains the path
     /// to (but not including) the `RouterId` at the given `position`. If the `Path` is shorter
     /// than `position + 1` elements, either the same path is returned or, in case of a
@@ -234,6 +337,30 @@ impl<'a, 'n, Q, Ospf: OspfImpl> bgpsim::formatter::NetworkFormatter<'a, 'n, Pref
 mod test {
     use super::*;
 
+    use bgpsim::event::BasicEventQueue;
+
+    #[test]
+    fn fmt_named() {
+        let mut net: Network<Prefix, BasicEventQueue<Prefix>> =
+            Network::new(BasicEventQueue::new());
+        let la = net.add_router("LosAngeles");
+        let kc = net.add_router("KansasCity");
+        let de = net.add_router("Denver");
+
+        assert_eq!(
+            Path::Route(vec![la, kc, de]).fmt_named(&net),
+            "LosAngeles -> KansasCity -> Denver"
+        );
+        assert_eq!(
+            Path::BlackHole(vec![la, kc]).fmt_named(&net),
+            "LosAngeles -> KansasCity -> (drop)"
+        );
+        assert_eq!(
+            Path::Loop(vec![la, kc], vec![kc, de]).fmt_named(&net),
+            "LosAngeles -> KansasCity -> [KansasCity -> Denver -> KansasCity ...]"
+        );
+    }
+
     #[test]
     fn path_manipulation() {
         let route = Path::Route(vec![0.into(), 1.into()]);
@@ -250,6 +377,11 @@ mod test {
             Path::Loop(vec![0.into(), 1.into(), 2.into()], vec![2.into(), 3.into()]),
         );
 
+        // egress
+        assert_eq!(route.egress(), Some(1.into()));
+        assert_eq!(blackhole.egress(), None);
+        assert_eq!(loopy.egress(), None);
+
         // split_at
         assert_eq!(route.split_at(1), vec![0.into()]);
 
@@ -330,4 +462,129 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn normalize() {
+        #[allow(non_snake_case)]
+        let (A, B, C, D) = (
+            RouterId::from(1),
+            RouterId::from(2),
+            RouterId::from(3),
+            RouterId::from(4),
+        );
+
+        // loop-unrolled paths from the `split_loop` test
+        for unrolled in [
+            vec![A, B, C, B, C, B, D],
+            vec![A, B, C, B, C, B, C, B, D],
+            vec![A, B, C, B, C, B, C, B, C, B, D],
+        ] {
+            let route = Path::Route(unrolled.clone());
+            assert_eq!(route.hops(), unrolled.as_slice());
+            assert_eq!(route.normalized(), Path::Route(vec![A, B, D]));
+            assert_eq!(
+                Path::BlackHole(unrolled).normalized(),
+                Path::BlackHole(vec![A, B, D])
+            );
+        }
+
+        // the endpoints are kept, even if the path bounces at its start or end
+        assert_eq!(
+            Path::Route(vec![A, B, A, C, D, C]).normalized(),
+            Path::Route(vec![A, C])
+        );
+        assert_eq!(
+            Path::Route(vec![A, B, C, D]).normalized(),
+            Path::Route(vec![A, B, C, D])
+        );
+
+        // the loop itself is never collapsed
+        let loopy = Path::Loop(vec![A, B, A, C], vec![C, D]);
+        assert_eq!(loopy.hops(), &[A, B, A, C]);
+        assert_eq!(loopy.normalized(), Path::Loop(vec![A, C], vec![C, D]));
+    }
+
+    #[test]
+    fn reaches() {
+        #[allow(non_snake_case)]
+        let (A, B, C, D, E) = (
+            RouterId::from(1),
+            RouterId::from(2),
+            RouterId::from(3),
+            RouterId::from(4),
+            RouterId::from(5),
+        );
+
+        let route = Path::Route(vec![A, B, C]);
+        assert!(route.reaches(A));
+        assert!(route.reaches(C));
+        assert!(!route.reaches(D));
+
+        // the router dropping the traffic is reached
+        let black_hole = Path::BlackHole(vec![A, B]);
+        assert!(black_hole.reaches(B));
+        assert!(!black_hole.reaches(C));
+
+        // waypoints on the path towards the loop and inside the loop segment are reached
+        let loopy = Path::Loop(vec![A, B], vec![B, C, D]);
+        assert!(loopy.reaches(A));
+        assert!(loopy.reaches(B));
+        assert!(loopy.reaches(D));
+        assert!(!loopy.reaches(E));
+    }
+
+    #[test]
+    fn first_divergence() {
+        #[allow(non_snake_case)]
+        let (A, B, C, D, E) = (
+            RouterId::from(1),
+            RouterId::from(2),
+            RouterId::from(3),
+            RouterId::from(4),
+            RouterId::from(5),
+        );
+
+        // identical paths
+        let route = Path::Route(vec![A, B, C]);
+        assert_eq!(route.first_divergence(&route.clone()), None);
+        let loopy = Path::Loop(vec![A, B], vec![B, C, D]);
+        assert_eq!(loopy.first_divergence(&loopy.clone()), None);
+
+        // diverging paths
+        assert_eq!(
+            route.first_divergence(&Path::Route(vec![A, D, C])),
+            Some((1, B, D))
+        );
+        assert_eq!(
+            route.first_divergence(&Path::Route(vec![A, B, E])),
+            Some((2, C, E))
+        );
+        assert_eq!(
+            loopy.first_divergence(&Path::Loop(vec![A, B], vec![B, C, E])),
+            Some((3, D, E))
+        );
+        // the loop is walked back to its first router
+        assert_eq!(
+            loopy.first_divergence(&Path::Route(vec![A, B, C, D, E])),
+            Some((4, B, E))
+        );
+
+        // different variants
+        assert_eq!(
+            route.first_divergence(&Path::BlackHole(vec![A, B, C])),
+            Some((2, C, C))
+        );
+        assert_eq!(
+            Path::BlackHole(vec![A, B]).first_divergence(&route),
+            Some((1, B, B))
+        );
+        assert_eq!(
+            route.first_divergence(&Path::BlackHole(vec![A, E])),
+            Some((1, B, E))
+        );
+        assert_eq!(
+            route.first_divergence(&Path::Loop(vec![A, B], vec![B, D])),
+            Some((2, C, D))
+        );
+    }
 }
@@ -17,9 +17,11 @@
 
 use std::collections::HashMap;
 
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use bgpsim::{
+    event::EventQueue,
     forwarding_state::ForwardingState,
     policies::{FwPolicy, PathCondition, Policy, PolicyError},
     prelude::*,
@@ -36,6 +38,85 @@ pub enum TransientPolicy {
     Atomic(FwPolicy<Prefix>),
     Strict(FwPolicy<Prefix>),
     Experimental(FwPolicy<Prefix>),
+    /// Requires the whole network to converge to a forwarding state in which all internal routers
+    /// reach `prefix` within `deadline_ms` milliseconds after the event. This is a property of the
+    /// entire trace rather than of individual paths. Hence, it is evaluated by
+    /// `compute_convergence_violations` instead of the interval algorithm.
+    ConvergenceDeadline {
+        prefix: Prefix,
+        deadline_ms: u64,
+    },
+    /// Requires traffic from `router` towards the anycast `prefix` to leave the network at any of
+    /// the `acceptable_egresses`, rather than at one specific egress.
+    AnycastReachable {
+        router: RouterId,
+        prefix: Prefix,
+        acceptable_egresses: Vec<RouterId>,
+    },
+    /// Requires traffic from `router` towards `prefix` to leave the network at an egress whose
+    /// advertised AS path for `prefix` has at most `max` ASes. `as_path_lengths` holds the length
+    /// of the AS path advertised by each egress, ordered by egress. Traffic leaving at an egress
+    /// with unknown AS path length violates the policy, whereas traffic that does not leave the
+    /// network at all does not. Use `TransientPolicy::max_as_path_length` to look up the lengths
+    /// in a network.
+    ///
+    /// The lengths are a snapshot taken when building the policy, and are not updated while the
+    /// network converges. An event changing the advertised AS paths (e.g.,
+    /// `AnalyzerEvent::PrependAsPath`) is only reflected if the lengths are looked up in a network
+    /// where the event has already happened, such as `Analyzer::scheduled_net`, in which case the
+    /// paths before the event are judged by the new lengths as well.
+    MaxAsPathLength {
+        router: RouterId,
+        prefix: Prefix,
+        max: usize,
+        as_path_lengths: Vec<(RouterId, usize)>,
+    },
+    /// Satisfied only if all sub-policies are satisfied. All sub-policies must concern the same
+    /// `(router, prefix)` pair. Use `TransientPolicy::and` to construct it.
+    And(Vec<TransientPolicy>),
+    /// Satisfied if any sub-policy is satisfied. All sub-policies must concern the same `(router,
+    /// prefix)` pair. Use `TransientPolicy::or` to construct it.
+    Or(Vec<TransientPolicy>),
+}
+
+/// Error when composing `TransientPolicy`s with `TransientPolicy::and` or `TransientPolicy::or`.
+#[derive(Debug, thiserror::Error)]
+pub enum CompositionError {
+    /// Error when no sub-policies were given.
+    #[error("Cannot compose an empty list of policies")]
+    Empty,
+    /// Error when the sub-policies concern different `(router, prefix)` pairs.
+    #[error("Cannot compose policies concerning {0:?} and {1:?}")]
+    DifferentTargets(
+        (Option<RouterId>, Option<Prefix>),
+        (Option<RouterId>, Option<Prefix>),
+    ),
+}
+
+/// Kind of an atomic `TransientPolicy`, without the router and prefix it concerns. Used as a
+/// template to instantiate the same policy for many prefixes.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
+pub enum PolicyKind {
+    /// Instantiates `FwPolicy::Reachable`
+    Reachable,
+    /// Instantiates `FwPolicy::NotReachable`
+    NotReachable,
+    /// Instantiates `FwPolicy::LoopFree`
+    LoopFree,
+    /// Instantiates `FwPolicy::PathCondition` with a `PathCondition::Node` for the given waypoint
+    Waypoint(RouterId),
+}
+
+impl PolicyKind {
+    /// Instantiate the atomic `TransientPolicy` for the given router and prefix.
+    pub fn instantiate(&self, router: RouterId, prefix: Prefix) -> TransientPolicy {
+        TransientPolicy::Atomic(match self {
+            Self::Reachable => FwPolicy::Reachable(router, prefix),
+            Self::NotReachable => FwPolicy::NotReachable(router, prefix),
+            Self::LoopFree => FwPolicy::LoopFree(router, prefix),
+            Self::Waypoint(w) => FwPolicy::PathCondition(router, prefix, PathCondition::Node(*w)),
+        })
+    }
 }
 
 impl Policy<Prefix> for TransientPolicy {
@@ -47,6 +128,62 @@ impl Policy<Prefix> for TransientPolicy {
             Self::Atomic(fw_policy) | Self::Strict(fw_policy) | Self::Experimental(fw_policy) => {
                 fw_policy.check(fw_state)
             }
+            // a single forwarding state cannot violate a deadline
+            Self::ConvergenceDeadline { .. } => Ok(()),
+            Self::AnycastReachable {
+                router,
+                prefix,
+                acceptable_egresses,
+            } => {
+                FwPolicy::Reachable(*router, *prefix).check(fw_state)?;
+                FwPolicy::PathCondition(
+                    *router,
+                    *prefix,
+                    PathCondition::Or(
+                        acceptable_egresses
+                            .iter()
+                            .map(|egress| PathCondition::Node(*egress))
+                            .collect(),
+                    ),
+                )
+                .check(fw_state)
+            }
+            Self::MaxAsPathLength {
+                router,
+                prefix,
+                max,
+                as_path_lengths,
+            } => {
+                // like `check_path`, traffic that does not leave the network (a black hole or a
+                // loop) satisfies the policy.
+                if fw_state.get_paths(*router, *prefix).is_err() {
+                    return Ok(());
+                }
+                // if no egress qualifies, the empty `Or` rejects every path.
+                FwPolicy::PathCondition(
+                    *router,
+                    *prefix,
+                    PathCondition::Or(
+                        as_path_lengths
+                            .iter()
+                            .filter(|(_, len)| len <= max)
+                            .map(|(egress, _)| PathCondition::Node(*egress))
+                            .collect(),
+                    ),
+                )
+                .check(fw_state)
+            }
+            Self::And(policies) => policies.iter().try_for_each(|p| p.check(fw_state)),
+            Self::Or(policies) => {
+                let mut result = Ok(());
+                for policy in policies {
+                    result = policy.check(fw_state);
+                    if result.is_ok() {
+                        break;
+                    }
+                }
+                result
+            }
         }
     }
 
@@ -56,6 +193,11 @@ impl Policy<Prefix> for TransientPolicy {
             Self::Atomic(fw_policy) | Self::Strict(fw_policy) | Self::Experimental(fw_policy) => {
                 fw_policy.router()
             }
+            Self::ConvergenceDeadline { .. } => None,
+            Self::AnycastReachable { router, .. } | Self::MaxAsPathLength { router, .. } => {
+                Some(*router)
+            }
+            Self::And(policies) | Self::Or(policies) => policies.first()?.router(),
         }
     }
 
@@ -65,11 +207,89 @@ impl Policy<Prefix> for TransientPolicy {
             Self::Atomic(fw_policy) | Self::Strict(fw_policy) | Self::Experimental(fw_policy) => {
                 fw_policy.prefix()
             }
+            Self::ConvergenceDeadline { prefix, .. }
+            | Self::AnycastReachable { prefix, .. }
+            | Self::MaxAsPathLength { prefix, .. } => Some(*prefix),
+            Self::And(policies) | Self::Or(policies) => policies.first()?.prefix(),
         }
     }
 }
 
 impl TransientPolicy {
+    /// Expand the policy template `kind` at `router` to one policy per prefix.
+    pub fn for_all_prefixes(router: RouterId, kind: PolicyKind, prefixes: &[Prefix]) -> Vec<Self> {
+        prefixes
+            .iter()
+            .map(|prefix| kind.instantiate(router, *prefix))
+            .collect()
+    }
+
+    /// Compose `policies` into a single policy that is satisfied only if all of them are satisfied,
+    /// e.g., to require reaching a prefix via a waypoint. Fails if `policies` is empty or if they
+    /// concern different `(router, prefix)` pairs.
+    pub fn and(policies: Vec<Self>) -> Result<Self, CompositionError> {
+        Self::check_common_target(&policies)?;
+        Ok(Self::And(policies))
+    }
+
+    /// Compose `policies` into a single policy that is satisfied if any of them is satisfied.
+    /// Fails if `policies` is empty or if they concern different `(router, prefix)` pairs.
+    pub fn or(policies: Vec<Self>) -> Result<Self, CompositionError> {
+        Self::check_common_target(&policies)?;
+        Ok(Self::Or(policies))
+    }
+
+    fn check_common_target(policies: &[Self]) -> Result<(), CompositionError> {
+        let target = |p: &Self| (p.router(), p.prefix());
+        let first = target(policies.first().ok_or(CompositionError::Empty)?);
+        match policies.iter().map(target).find(|t| *t != first) {
+            Some(other) => Err(CompositionError::DifferentTargets(first, other)),
+            None => Ok(()),
+        }
+    }
+
+    /// Require every internal router of `net` to reach `prefix`, i.e., instantiate
+    /// `FwPolicy::Reachable` for each internal router, ordered by router id.
+    pub fn reachability_all<Q, Ospf: OspfImpl>(
+        net: &Network<Prefix, Q, Ospf>,
+        prefix: Prefix,
+    ) -> Vec<Self> {
+        net.internal_indices()
+            .sorted()
+            .map(|router| PolicyKind::Reachable.instantiate(router, prefix))
+            .collect()
+    }
+
+    /// Require traffic from `router` towards `prefix` to leave `net` at an egress advertising an AS
+    /// path of at most `max` ASes for `prefix`, using the routes currently advertised by the
+    /// external routers of `net`. Later changes of these routes are not reflected (see
+    /// `TransientPolicy::MaxAsPathLength`).
+    pub fn max_as_path_length<Q: EventQueue<Prefix>, Ospf: OspfImpl>(
+        net: &Network<Prefix, Q, Ospf>,
+        router: RouterId,
+        prefix: Prefix,
+        max: usize,
+    ) -> Self {
+        let as_path_lengths = net
+            .external_indices()
+            .sorted()
+            .filter_map(|ext| {
+                let device = net.get_device(ext).ok()?;
+                let route = device
+                    .unwrap_external()
+                    .get_advertised_routes()
+                    .get(&prefix)?;
+                Some((ext, route.as_path.len()))
+            })
+            .collect();
+        Self::MaxAsPathLength {
+            router,
+            prefix,
+            max,
+            as_path_lengths,
+        }
+    }
+
     /// Check that a forwarding path satisfies the policy.
     pub fn check_path(&self, path: &Path) -> bool {
         match self {
@@ -86,6 +306,24 @@ impl TransientPolicy {
             Self::Strict(_fw_policy) | Self::Experimental(_fw_policy) => {
                 todo!("implement remaining FwPolicy checks")
             }
+            Self::ConvergenceDeadline { .. } => true,
+            Self::AnycastReachable {
+                acceptable_egresses,
+                ..
+            } => path
+                .egress()
+                .is_some_and(|egress| acceptable_egresses.contains(&egress)),
+            Self::MaxAsPathLength {
+                max,
+                as_path_lengths,
+                ..
+            } => path.egress().is_none_or(|egress| {
+                as_path_lengths
+                    .iter()
+                    .any(|(e, len)| *e == egress && len <= max)
+            }),
+            Self::And(policies) => policies.iter().all(|p| p.check_path(path)),
+            Self::Or(policies) => policies.iter().any(|p| p.check_path(path)),
         }
     }
 
@@ -94,17 +332,7 @@ impl TransientPolicy {
     pub fn check_path_no_loops_of_length_2(&self, path: &Path) -> bool {
         match self {
             Self::Atomic(fw_policy) => match fw_policy {
-                FwPolicy::Reachable(_, _) => {
-                    path.is_route() && {
-                        // check that there was no loop of length 2
-                        let path_nodes = path.get_rid_vec();
-                        let p1 = path_nodes.iter();
-                        let p2 = path_nodes.iter().skip(2);
-
-                        // check that all of these are different
-                        !p1.zip(p2).any(|(x, y)| x == y)
-                    }
-                }
+                FwPolicy::Reachable(_, _) => path.is_route() && !has_loop_of_length_2(path),
                 FwPolicy::NotReachable(_, _) => !path.is_route(),
                 FwPolicy::LoopFree(_, _) => !path.is_loop(),
                 FwPolicy::PathCondition(_, _, PathCondition::Node(w)) => match path {
@@ -116,6 +344,15 @@ impl TransientPolicy {
             Self::Strict(_fw_policy) | Self::Experimental(_fw_policy) => {
                 todo!("implement remaining FwPolicy checks")
             }
+            Self::ConvergenceDeadline { .. } => true,
+            Self::AnycastReachable { .. } => self.check_path(path) && !has_loop_of_length_2(path),
+            Self::MaxAsPathLength { .. } => self.check_path(path),
+            Self::And(policies) => policies
+                .iter()
+                .all(|p| p.check_path_no_loops_of_length_2(path)),
+            Self::Or(policies) => policies
+                .iter()
+                .any(|p| p.check_path_no_loops_of_length_2(path)),
         }
     }
 
@@ -124,17 +361,7 @@ impl TransientPolicy {
     pub fn check_path_strict(&self, path: &Path) -> bool {
         match self {
             Self::Atomic(fw_policy) => match fw_policy {
-                FwPolicy::Reachable(_, _) => {
-                    path.is_route() && {
-                        // check that there was no loop
-                        let mut frequencies = HashMap::new();
-                        for rid in path.get_rid_vec() {
-                            *frequencies.entry(rid).or_insert(0) += 1;
-                        }
-                        // by checking maximum occurrence of each router is 1
-                        *frequencies.values().max().unwrap_or(&0) <= 1
-                    }
-                }
+                FwPolicy::Reachable(_, _) => path.is_route() && !has_repeated_router(path),
                 FwPolicy::NotReachable(_, _) => !path.is_route(),
                 FwPolicy::LoopFree(_, _) => !path.is_loop(),
                 FwPolicy::PathCondition(_, _, PathCondition::Node(w)) => match path {
@@ -146,6 +373,105 @@ impl TransientPolicy {
             Self::Strict(_fw_policy) | Self::Experimental(_fw_policy) => {
                 todo!("implement remaining FwPolicy checks")
             }
+            Self::ConvergenceDeadline { .. } => true,
+            Self::AnycastReachable { .. } => self.check_path(path) && !has_repeated_router(path),
+            Self::MaxAsPathLength { .. } => self.check_path(path),
+            Self::And(policies) => policies.iter().all(|p| p.check_path_strict(path)),
+            Self::Or(policies) => policies.iter().any(|p| p.check_path_strict(path)),
         }
     }
 }
+
+/// Check whether the path bounces back to a router immediately, i.e., has a loop of length 2.
+fn has_loop_of_length_2(path: &Path) -> bool {
+    let path_nodes = path.get_rid_vec();
+    let p1 = path_nodes.iter();
+    let p2 = path_nodes.iter().skip(2);
+
+    // check whether any of these are equal
+    p1.zip(p2).any(|(x, y)| x == y)
+}
+
+/// Check whether the path traverses any router more than once, e.g., by running a loop once.
+fn has_repeated_router(path: &Path) -> bool {
+    let mut frequencies = HashMap::new();
+    for rid in path.get_rid_vec() {
+        *frequencies.entry(rid).or_insert(0) += 1;
+    }
+    // by checking maximum occurrence of each router is 1
+    *frequencies.values().max().unwrap_or(&0) > 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::experiments::{list_custom_topologies, ScenarioPrefix};
+
+    #[test]
+    fn reachability_all() {
+        let prefixes = ScenarioPrefix::SinglePrefix.prefixes();
+        let (_, net, ..) = list_custom_topologies(&prefixes)
+            .into_iter()
+            .find(|(name, ..)| *name == "Path03_FullMesh_ExtAtEnds")
+            .unwrap();
+        let [r0, r1, r2] = ["r0", "r1", "r2"].map(|r| net.get_router_id(r).unwrap());
+
+        let policies = TransientPolicy::reachability_all(&net, prefixes[0]);
+        assert_eq!(
+            policies,
+            [r0, r1, r2]
+                .into_iter()
+                .sorted()
+                .map(|r| TransientPolicy::Atomic(FwPolicy::Reachable(r, prefixes[0])))
+                .collect_vec()
+        );
+    }
+
+    #[test]
+    fn composite_policies() {
+        let (r0, r1, r2, w) = (0.into(), 1.into(), 2.into(), 3.into());
+        let p0 = Prefix::from(0);
+        let reachable = PolicyKind::Reachable.instantiate(r0, p0);
+        let waypoint = PolicyKind::Waypoint(w).instantiate(r0, p0);
+
+        let and = TransientPolicy::and(vec![reachable.clone(), waypoint.clone()]).unwrap();
+        let or = TransientPolicy::or(vec![reachable.clone(), waypoint.clone()]).unwrap();
+        assert_eq!(and.router(), Some(r0));
+        assert_eq!(or.prefix(), Some(p0));
+
+        let via_w = Path::Route(vec![r0, w, r2]);
+        let not_via_w = Path::Route(vec![r0, r1, r2]);
+        let black_hole = Path::BlackHole(vec![r0, r1]);
+
+        // AND is violated as soon as either condition fails
+        assert!(and.check_path(&via_w));
+        assert!(!and.check_path(&not_via_w));
+        assert!(!and.check_path(&black_hole));
+
+        // OR is satisfied if either condition holds, and violated only if both fail
+        assert!(or.check_path(&via_w));
+        assert!(or.check_path(&not_via_w));
+        // the waypoint is trivially satisfied if the destination is not reached
+        assert!(or.check_path(&black_hole));
+        let via_w_or_r1 =
+            TransientPolicy::or(vec![waypoint, PolicyKind::Waypoint(r1).instantiate(r0, p0)])
+                .unwrap();
+        assert!(via_w_or_r1.check_path(&via_w));
+        assert!(via_w_or_r1.check_path(&not_via_w));
+        assert!(!via_w_or_r1.check_path(&Path::Route(vec![r0, r2])));
+
+        // sub-policies must concern the same target
+        assert!(matches!(
+            TransientPolicy::and(vec![]),
+            Err(CompositionError::Empty)
+        ));
+        assert!(matches!(
+            TransientPolicy::or(vec![reachable, PolicyKind::Reachable.instantiate(r1, p0)]),
+            Err(CompositionError::DifferentTargets(
+                (Some(_), _),
+                (Some(_), _)
+            ))
+        ));
+    }
+}
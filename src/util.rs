@@ -16,33 +16,57 @@
 //! Utility module collection of functions
 
 use std::{
-    env, fs,
-    num::ParseIntError,
+    cmp::Ordering,
+    collections::{HashMap, VecDeque},
+    env,
+    ffi::OsStr,
+    fs,
+    hash::Hash,
+    io::{self, BufReader, Read, Write},
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
+use flate2::{bufread::GzDecoder, write::GzEncoder, Compression, CrcWriter};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use rayon::prelude::*;
-use regex::Regex;
+
+use bgpsim::types::RouterId;
 
 use crate::{
-    analyzer::Analyzer,
+    analyzer::{Analyzer, CiscoAnalyzerData},
     experiments::{iterate_experiments, Filter},
     prelude::TimingModel,
-    Prefix,
+    FwUpdate, MultiPrefixConvergenceTrace, Prefix, PrefixTrace,
 };
 
 pub fn init_logging() {
     log4rs::init_file("log4rs.yml", Default::default()).unwrap();
 }
 
-pub fn set_conf_dir() -> Result<(), Box<dyn std::error::Error>> {
+/// Environment variable overriding the configuration directory of router-lab (see `conf_dir`).
+pub const CONF_DIR_ENV: &str = "TRIX_CONF_DIR";
+
+/// Get the configuration directory of router-lab. This is `override_dir` if given and not empty,
+/// e.g., the value of the environment variable `TRIX_CONF_DIR`, and `router-lab-config` next to
+/// the `target/` directory of the running executable otherwise.
+pub fn conf_dir(override_dir: Option<&OsStr>) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(conf_dir) = override_dir.filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(conf_dir));
+    }
     let mut conf_dir = env::current_exe()?;
     conf_dir.pop(); // remove filename
     conf_dir.pop(); // move out of `src/`
     conf_dir.pop(); // move out of `target/`
     conf_dir.push("router-lab-config");
+    Ok(conf_dir)
+}
+
+/// Point router-lab to the configuration directory returned by `conf_dir`, overridden by the
+/// environment variable `TRIX_CONF_DIR`.
+pub fn set_conf_dir() -> Result<(), Box<dyn std::error::Error>> {
+    let conf_dir = conf_dir(env::var_os(CONF_DIR_ENV).as_deref())?;
     env::set_var("LAB_SETUP_CONFIG", conf_dir.display().to_string());
     Ok(())
 }
@@ -76,6 +100,79 @@ pub fn get_analyzer(
         .ok_or(format!("Found {count_experiments} experiments matching the filter!").into())
 }
 
+/// Maximum number of analyzers kept in memory by `get_analyzer_cached`.
+pub const ANALYZER_CACHE_SIZE: usize = 16;
+
+lazy_static! {
+    static ref ANALYZER_CACHE: BoundedCache<(String, String), Analyzer<TimingModel<Prefix>>> =
+        BoundedCache::new(ANALYZER_CACHE_SIZE);
+}
+
+/// Same as `get_analyzer`, but memoizes the built `Analyzer` for each `(topo_name, scenario_name)`
+/// pair, such that processing many directories of the same experiment only builds it once. At
+/// most `ANALYZER_CACHE_SIZE` analyzers are kept, evicting the least recently used one.
+pub fn get_analyzer_cached(
+    topo_name: impl AsRef<str>,
+    scenario_name: impl AsRef<str>,
+) -> Result<Arc<Analyzer<TimingModel<Prefix>>>, Box<dyn std::error::Error>> {
+    let topo_name = topo_name.as_ref();
+    let scenario_name = scenario_name.as_ref();
+    ANALYZER_CACHE
+        .get_or_try_insert_with((topo_name.to_string(), scenario_name.to_string()), || {
+            get_analyzer(topo_name, scenario_name)
+        })
+}
+
+/// Thread-safe memoization cache holding at most `capacity` values, evicting the least recently
+/// used entry when full.
+struct BoundedCache<K, V> {
+    capacity: usize,
+    entries: Mutex<VecDeque<(K, Arc<V>)>>,
+}
+
+impl<K: Eq + Hash, V> BoundedCache<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Get the cached value for `key`, or build and insert it using `build`. Errors are not
+    /// cached. The lock is not held while building, so concurrent misses may build the same
+    /// value twice, in which case the first inserted value is kept.
+    fn get_or_try_insert_with<E>(
+        &self,
+        key: K,
+        build: impl FnOnce() -> Result<V, E>,
+    ) -> Result<Arc<V>, E> {
+        if let Some(value) = self.lookup(&key) {
+            return Ok(value);
+        }
+        let value = Arc::new(build()?);
+
+        let mut entries = self.entries.lock().unwrap();
+        if let Some((_, cached)) = entries.iter().find(|(k, _)| *k == key) {
+            return Ok(cached.clone());
+        }
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back((key, value.clone()));
+        Ok(value)
+    }
+
+    /// Get the cached value for `key`, marking it as most recently used.
+    fn lookup(&self, key: &K) -> Option<Arc<V>> {
+        let mut entries = self.entries.lock().unwrap();
+        let pos = entries.iter().position(|(k, _)| k == key)?;
+        let entry = entries.remove(pos)?;
+        let value = entry.1.clone();
+        entries.push_back(entry);
+        Some(value)
+    }
+}
+
 /// Allows filtering data for all scenarios.
 pub fn filter_data(data_root: impl AsRef<Path>, filter: Filter) -> Vec<(String, String, PathBuf)> {
     fs::read_dir(data_root.as_ref())
@@ -158,20 +255,76 @@ where
         .map(move |(topo_name, scenario_name, eval_path)| f(&topo_name, &scenario_name, &eval_path))
 }
 
-lazy_static! {
-    static ref NUM_PREFIXES: Regex =
-        Regex::new(r"^.*Prefix(?P<num_prefixes>[1-9][0-9]*)_.*$").unwrap();
-}
 /// Extract the number of prefixes for a scenario from a given scenario name string (e.g., the data
-/// directory name).
-pub fn get_num_prefixes(scenario_name: impl AsRef<str>) -> Result<usize, ParseIntError> {
-    NUM_PREFIXES
-        .captures(scenario_name.as_ref())
-        .unwrap()
-        .name("num_prefixes")
-        .unwrap()
-        .as_str()
-        .parse()
+/// directory name), encoded as `Prefix{num_prefixes}` (see `Scenario::to_name`). Returns `None` if
+/// the name does not contain the number of prefixes.
+pub fn get_num_prefixes(scenario_name: impl AsRef<str>) -> Option<usize> {
+    scenario_name
+        .as_ref()
+        .split('_')
+        .find_map(|part| part.strip_prefix("Prefix")?.parse().ok())
+}
+
+/// Compute a CRC32 checksum over the content of the given files, in the given order. Directories
+/// are traversed recursively in the order of their file names. Files that do not exist are skipped.
+pub fn checksum_files<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> std::io::Result<u32> {
+    fn update(crc: &mut CrcWriter<io::Sink>, path: &Path) -> std::io::Result<()> {
+        if path.is_dir() {
+            let mut entries = fs::read_dir(path)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.sort();
+            for entry in entries {
+                update(crc, &entry)?;
+            }
+        } else if path.exists() {
+            // stream the file, as pcaps may not fit into memory
+            io::copy(&mut fs::File::open(path)?, crc)?;
+        }
+        Ok(())
+    }
+
+    let mut crc = CrcWriter::new(io::sink());
+    for path in paths {
+        update(&mut crc, path.as_ref())?;
+    }
+    Ok(crc.crc().sum())
+}
+
+/// Compute a checksum over all inputs of a sample, i.e., its hardware mapping, pcap and collected
+/// logs. This allows detecting whether the inputs have changed since they were last processed.
+pub fn sample_checksum(eval_path: &Path, record: &CiscoAnalyzerData) -> std::io::Result<u32> {
+    checksum_files([
+        eval_path.then(&record.hardware_mapping_filename),
+        eval_path.then(&record.pcap_filename),
+        eval_path.then_ts("logs_{}", &record.execution_timestamp),
+    ])
+}
+
+/// Read the file at `path` to a string. Files with a `.gz` extension (e.g., a hardware mapping
+/// stored as `hardware_mapping_{timestamp}.json.gz`) are transparently decompressed.
+pub fn read_maybe_gzipped(path: &Path) -> std::io::Result<String> {
+    let mut content = String::new();
+    let mut file = BufReader::new(fs::File::open(path)?);
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        GzDecoder::new(file).read_to_string(&mut content)?;
+    } else {
+        file.read_to_string(&mut content)?;
+    }
+    Ok(content)
+}
+
+/// Write `content` to the file at `path`, compressing it with gzip if `path` has a `.gz`
+/// extension. Counterpart of `read_maybe_gzipped`.
+pub fn write_maybe_gzipped(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        let mut encoder = GzEncoder::new(fs::File::create(path)?, Compression::default());
+        encoder.write_all(content)?;
+        encoder.finish()?;
+        Ok(())
+    } else {
+        fs::write(path, content)
+    }
 }
 
 pub trait PathBufExt: Sized {
@@ -201,8 +354,8 @@ impl PathBufExt for &Path {
     }
 }
 
-/// Load the data from the cisco_analyzer.csv
-pub fn get_records(eval_path: &Path) -> Option<csv::Reader<std::fs::File>> {
+/// Load the data from the cisco_analyzer.csv, upgrading rows of older schema versions.
+pub fn get_records(eval_path: &Path) -> Option<Vec<CiscoAnalyzerData>> {
     let mut analyzer_csv_path = eval_path.to_path_buf();
     analyzer_csv_path.push("cisco_analyzer.csv");
     if !analyzer_csv_path.exists() {
@@ -211,7 +364,282 @@ pub fn get_records(eval_path: &Path) -> Option<csv::Reader<std::fs::File>> {
     }
     log::info!("Loading: {analyzer_csv_path:?}");
     let analyzer_csv = fs::File::open(analyzer_csv_path.clone()).unwrap();
-    let csv = csv::Reader::from_reader(analyzer_csv);
 
-    Some(csv)
+    Some(CiscoAnalyzerData::read_all(analyzer_csv).unwrap())
+}
+
+/// Merge two `MultiPrefixConvergenceTrace`s, e.g., obtained from different sources or from
+/// per-prefix extractions done in parallel. The result contains the union of all prefixes. If a
+/// prefix is present in both traces, the two `PrefixTrace`s are merged in chronological order,
+/// keeping the relative order of the updates from each source.
+///
+/// Updates from both sources that happen at the same timestamp are conflicting, as their order
+/// cannot be determined. These are ordered by the router performing the (first) forwarding
+/// update, preferring `a` upon ties, and a warning is logged for each conflict.
+///
+/// Note that this function does not modify the old next-hops stored in the forwarding deltas.
+pub fn merge_traces(
+    a: MultiPrefixConvergenceTrace,
+    mut b: MultiPrefixConvergenceTrace,
+) -> MultiPrefixConvergenceTrace {
+    let mut merged = MultiPrefixConvergenceTrace::new();
+
+    for (prefix, prefix_trace_a) in a.into_iter() {
+        let prefix_trace = match b.remove(&prefix) {
+            Some(prefix_trace_b) => merge_prefix_traces(prefix, prefix_trace_a, prefix_trace_b),
+            None => prefix_trace_a,
+        };
+        merged.insert(prefix, prefix_trace);
+    }
+    // all remaining prefixes only exist in `b`
+    merged.extend(b);
+
+    merged
+}
+
+/// Extension of `MultiPrefixConvergenceTrace` with cheap summaries of the trace.
+pub trait ConvergenceTraceExt {
+    /// Get the earliest time at which each router changes its next hops for each prefix, e.g., to
+    /// measure how fast the event propagates without running the interval algorithm. Forwarding
+    /// deltas that keep the next hops unchanged, and updates without a timestamp, are ignored.
+    fn first_changes(&self) -> HashMap<(RouterId, Prefix), f64>;
+}
+
+impl ConvergenceTraceExt for MultiPrefixConvergenceTrace {
+    fn first_changes(&self) -> HashMap<(RouterId, Prefix), f64> {
+        let mut first_changes = HashMap::new();
+        for (prefix, prefix_trace) in self {
+            for (fw_deltas, time) in prefix_trace {
+                let Some(time) = time.into_inner() else {
+                    continue;
+                };
+                for (router, old, new) in fw_deltas {
+                    if old == new {
+                        continue;
+                    }
+                    first_changes
+                        .entry((*router, *prefix))
+                        .and_modify(|t: &mut f64| *t = t.min(time))
+                        .or_insert(time);
+                }
+            }
+        }
+        first_changes
+    }
+}
+
+/// Merge two `PrefixTrace`s in chronological order. See `merge_traces`.
+fn merge_prefix_traces(prefix: Prefix, a: PrefixTrace, b: PrefixTrace) -> PrefixTrace {
+    let time = |update: &FwUpdate| update.1.into_inner().unwrap_or(f64::NEG_INFINITY);
+    let router = |update: &FwUpdate| update.0.first().map(|(rid, _, _)| *rid);
+
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let mut a = a.into_iter().peekable();
+    let mut b = b.into_iter().peekable();
+
+    while let (Some(next_a), Some(next_b)) = (a.peek(), b.peek()) {
+        let take_a = match time(next_a).total_cmp(&time(next_b)) {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => {
+                log::warn!(
+                    "Conflicting forwarding updates for prefix {prefix:?} at time {}",
+                    time(next_a)
+                );
+                router(next_a) <= router(next_b)
+            }
+        };
+        merged.push(if take_a { a.next() } else { b.next() }.unwrap());
+    }
+    merged.extend(a);
+    merged.extend(b);
+
+    merged
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn update(rid: usize, time: f64) -> FwUpdate {
+        (
+            vec![(RouterId::from(rid), vec![], vec![RouterId::from(0)])],
+            Some(time).into(),
+        )
+    }
+
+    #[test]
+    fn conf_dir_override() {
+        let default = conf_dir(None).unwrap();
+        assert!(default.ends_with("router-lab-config"));
+
+        assert_eq!(
+            conf_dir(Some(OsStr::new("/tmp/trix-conf"))).unwrap(),
+            PathBuf::from("/tmp/trix-conf")
+        );
+
+        // an empty override falls back to the default
+        assert_eq!(conf_dir(Some(OsStr::new(""))).unwrap(), default);
+    }
+
+    #[test]
+    fn bounded_cache() {
+        let cache = BoundedCache::new(2);
+        let builds = std::cell::Cell::new(0);
+        let get = |key: &str| {
+            cache
+                .get_or_try_insert_with(key.to_string(), || {
+                    builds.set(builds.get() + 1);
+                    Ok::<_, ()>(key.len())
+                })
+                .unwrap()
+        };
+
+        // the second call returns the cached instance
+        let a = get("a");
+        assert!(Arc::ptr_eq(&a, &get("a")));
+        assert_eq!(builds.get(), 1);
+
+        // inserting a third key evicts the least recently used one
+        get("bb");
+        get("a");
+        get("ccc");
+        assert_eq!(builds.get(), 3);
+        assert!(Arc::ptr_eq(&a, &get("a")));
+        get("bb");
+        assert_eq!(builds.get(), 4);
+
+        // errors are not cached
+        assert_eq!(
+            cache.get_or_try_insert_with("d".to_string(), || Err(())),
+            Err(())
+        );
+        assert_eq!(
+            *cache
+                .get_or_try_insert_with("d".to_string(), || Ok::<_, ()>(1))
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn maybe_gzipped_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let content = r#"{"0": {"name": "r0"}}"#;
+
+        for name in ["hardware_mapping.json", "hardware_mapping.json.gz"] {
+            let path = dir.join(name);
+            write_maybe_gzipped(&path, content.as_bytes()).unwrap();
+            assert_eq!(read_maybe_gzipped(&path).unwrap(), content);
+        }
+
+        // the gzipped file is actually compressed
+        assert_ne!(
+            fs::read(dir.join("hardware_mapping.json.gz")).unwrap(),
+            content.as_bytes()
+        );
+        assert!(read_maybe_gzipped(&dir.join("missing.json.gz")).is_err());
+    }
+
+    #[test]
+    fn checksum_files_detects_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let logs = dir.join("logs_2024-10-19_12-04-38");
+        fs::create_dir_all(&logs).unwrap();
+        fs::write(dir.join("hardware_mapping.json"), "{}").unwrap();
+        fs::write(logs.join("r0.log"), "first").unwrap();
+        fs::write(logs.join("r1.log"), "second").unwrap();
+        let inputs = [
+            dir.join("hardware_mapping.json"),
+            dir.join("missing.pcap.gz"),
+            logs.clone(),
+        ];
+
+        let checksum = checksum_files(&inputs).unwrap();
+        // unchanged inputs yield the same checksum
+        assert_eq!(checksum_files(&inputs).unwrap(), checksum);
+
+        // modified inputs yield a different checksum
+        fs::write(logs.join("r1.log"), "modified").unwrap();
+        let modified = checksum_files(&inputs).unwrap();
+        assert_ne!(modified, checksum);
+
+        // new files yield a different checksum
+        fs::write(logs.join("r2.log"), "third").unwrap();
+        assert_ne!(checksum_files(&inputs).unwrap(), modified);
+    }
+
+    #[test]
+    fn merge_disjoint_prefixes() {
+        let (p0, p1) = (Prefix::from(0), Prefix::from(1));
+        let a = MultiPrefixConvergenceTrace::from([(p0, vec![update(1, 0.1), update(2, 0.2)])]);
+        let b = MultiPrefixConvergenceTrace::from([(p1, vec![update(3, 0.15)])]);
+
+        let merged = merge_traces(a.clone(), b.clone());
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[&p0], a[&p0]);
+        assert_eq!(merged[&p1], b[&p1]);
+    }
+
+    #[test]
+    fn merge_overlapping_prefixes() {
+        let p = Prefix::from(0);
+        let a = MultiPrefixConvergenceTrace::from([(
+            p,
+            vec![update(1, 0.1), update(4, 0.3), update(2, 0.5)],
+        )]);
+        let b = MultiPrefixConvergenceTrace::from([(
+            p,
+            vec![update(3, 0.2), update(2, 0.3), update(5, 0.6)],
+        )]);
+
+        let expected = vec![
+            update(1, 0.1),
+            update(3, 0.2),
+            // conflict at time 0.3 is resolved by the router id
+            update(2, 0.3),
+            update(4, 0.3),
+            update(2, 0.5),
+            update(5, 0.6),
+        ];
+
+        assert_eq!(merge_traces(a.clone(), b.clone())[&p], expected);
+        // resolution of conflicts does not depend on the order of the arguments
+        assert_eq!(merge_traces(b, a)[&p], expected);
+    }
+
+    #[test]
+    fn first_changes() {
+        let (p0, p1) = (Prefix::from(0), Prefix::from(1));
+        let (r1, r2) = (RouterId::from(1), RouterId::from(2));
+        // `r1` keeps its next hop at 0.05, and an update without timestamp is ignored
+        let unchanged = (
+            vec![(r1, vec![RouterId::from(0)], vec![RouterId::from(0)])],
+            Some(0.05).into(),
+        );
+        let untimed = (vec![(r2, vec![], vec![RouterId::from(0)])], None.into());
+        let trace = MultiPrefixConvergenceTrace::from([
+            (
+                p0,
+                vec![
+                    unchanged,
+                    update(1, 0.1),
+                    update(2, 0.2),
+                    update(1, 0.3),
+                    untimed,
+                ],
+            ),
+            (p1, vec![update(2, 0.15)]),
+        ]);
+
+        assert_eq!(
+            trace.first_changes(),
+            HashMap::from([((r1, p0), 0.1), ((r2, p0), 0.2), ((r2, p1), 0.15)])
+        );
+        assert!(MultiPrefixConvergenceTrace::new()
+            .first_changes()
+            .is_empty());
+    }
 }
@@ -2,8 +2,9 @@
 
 use std::{
     collections::{HashMap, HashSet},
+    fmt, fs, io,
     net::Ipv4Addr,
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
@@ -12,7 +13,7 @@ use serde::{Deserialize, Deserializer, Serialize};
 
 use bgpsim::types::Prefix;
 
-use crate::pcap_utils::{PacketFilter, PcapFilter};
+use crate::pcap_utils::PacketFilter;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct BGPMessage<P: Prefix> {
@@ -76,12 +77,18 @@ pub fn parse_bgp_message_vec_with_delayers<P: Prefix>(
 
     log::trace!("Parsing BGPMessage from {inputs:?}");
 
-    let timestamp = inputs[0].parse().unwrap();
-    let src_ip = Ipv4Addr::from_str(&inputs[1]).unwrap();
-    let dst_ip = Ipv4Addr::from_str(&inputs[2]).unwrap();
+    let timestamp = inputs[0]
+        .parse()
+        .map_err(|e| format!("Invalid timestamp {:?}: {e}", inputs[0]))?;
+    let src_ip = Ipv4Addr::from_str(&inputs[1])
+        .map_err(|e| format!("Invalid source address {:?}: {e}", inputs[1]))?;
+    let dst_ip = Ipv4Addr::from_str(&inputs[2])
+        .map_err(|e| format!("Invalid destination address {:?}: {e}", inputs[2]))?;
     let src_mac = inputs[3].clone();
     let dst_mac = inputs[4].clone();
-    let tcp_seq = inputs[5].parse().unwrap();
+    let tcp_seq = inputs[5]
+        .parse()
+        .map_err(|e| format!("Invalid TCP sequence number {:?}: {e}", inputs[5]))?;
 
     // read prefixes from tshark, and update the lookup dictionary or vice versa
     let mut prefixes = parse_prefix_list(&inputs[6]);
@@ -108,14 +115,51 @@ pub fn parse_bgp_message_vec_with_delayers<P: Prefix>(
     })
 }
 
+/// Error raised when filtering the BGP messages of a pcap file.
+#[derive(Debug)]
+pub enum BgpFilterError {
+    /// `tcpdump` or `tshark` cannot be executed, e.g., because they are not installed.
+    TsharkNotFound(io::Error),
+    /// The pcap file cannot be opened.
+    PcapOpen(PathBuf, io::Error),
+    /// A filtered packet cannot be parsed as a `BGPMessage`.
+    Parse(BGPParseError),
+}
+
+impl fmt::Display for BgpFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TsharkNotFound(e) => write!(f, "Cannot execute tcpdump or tshark: {e}"),
+            Self::PcapOpen(path, e) => write!(f, "Cannot open pcap {path:?}: {e}"),
+            Self::Parse(e) => write!(f, "Cannot parse BGP message: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for BgpFilterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::TsharkNotFound(e) | Self::PcapOpen(_, e) => Some(e),
+            Self::Parse(_) => None,
+        }
+    }
+}
+
 pub enum BGPFilter {
     Announcements,
     Withdraws,
 }
 
 impl BGPFilter {
-    fn _filter(&self, pcap_path: &Path) -> impl Iterator<Item = Vec<String>> {
-        PcapFilter::All(PacketFilter {
+    fn _filter(
+        &self,
+        pcap_path: &Path,
+    ) -> Result<impl Iterator<Item = Vec<String>>, BgpFilterError> {
+        // check the pcap explicitly, as tcpdump would only report the error on stderr
+        fs::File::open(pcap_path)
+            .map_err(|e| BgpFilterError::PcapOpen(pcap_path.to_path_buf(), e))?;
+
+        let packet_filter = PacketFilter {
             port: Some(179),
             filter: "!bgp.type || bgp.type == 2".to_string(),
             outputs: [
@@ -134,32 +178,44 @@ impl BGPFilter {
             .map(|x| x.to_string())
             .collect_vec(),
             ..Default::default()
-        })
-        .filter(pcap_path)
-        .into_iter()
+        };
+        Ok(packet_filter
+            .try_filter_pcap(pcap_path)
+            .map_err(BgpFilterError::TsharkNotFound)?
+            .into_iter())
     }
 
     /// Filter a pcap file with the given `BGPFilter` and parse the result as `BGPMessage`s.
+    ///
+    /// Returns an error if the pcap cannot be opened or `tshark` cannot be executed. Packets that
+    /// cannot be parsed are reported as `BgpFilterError::Parse` by the returned iterator.
     pub fn filter<'a, P: Prefix>(
         &self,
         pcap_path: &'a Path,
-    ) -> impl Iterator<Item = BGPMessage<P>> + 'a {
-        self._filter(pcap_path)
-            .filter_map(|msg| parse_bgp_message_vec(msg).ok())
+    ) -> Result<impl Iterator<Item = Result<BGPMessage<P>, BgpFilterError>> + 'a, BgpFilterError>
+    {
+        Ok(self
+            ._filter(pcap_path)?
+            .map(|msg| parse_bgp_message_vec(msg).map_err(BgpFilterError::Parse)))
     }
 
     /// Filter a pcap file with the given `BGPFilter` and parse the result as `BGPMessage`s. This
     /// function is delayer-aware, i.e., it can handle duplicate packets that would normally not be
     /// parsed by tshark as it identifies them as TCP retransmissions.
+    ///
+    /// Returns an error if the pcap cannot be opened or `tshark` cannot be executed. Packets that
+    /// cannot be parsed are reported as `BgpFilterError::Parse` by the returned iterator.
     pub fn filter_with_delayers<'a, P: Prefix>(
         &self,
         pcap_path: &'a Path,
         lookup_prefixes: &'a mut HashMap<(Ipv4Addr, Ipv4Addr, usize), Vec<P>>,
         delayer_tracking: &'a mut HashSet<(Ipv4Addr, Ipv4Addr, String, String, usize)>,
-    ) -> impl Iterator<Item = BGPMessage<P>> + 'a {
-        self._filter(pcap_path).filter_map(|msg| {
-            parse_bgp_message_vec_with_delayers::<P>(msg, lookup_prefixes, delayer_tracking).ok()
-        })
+    ) -> Result<impl Iterator<Item = Result<BGPMessage<P>, BgpFilterError>> + 'a, BgpFilterError>
+    {
+        Ok(self._filter(pcap_path)?.map(|msg| {
+            parse_bgp_message_vec_with_delayers::<P>(msg, lookup_prefixes, delayer_tracking)
+                .map_err(BgpFilterError::Parse)
+        }))
     }
 }
 
@@ -169,7 +225,7 @@ mod test {
 
     use bgpsim::types::Ipv4Prefix as P;
 
-    use super::{parse_prefix_list, BGPMessage};
+    use super::{parse_bgp_message_vec, parse_prefix_list, BGPFilter, BGPMessage, BgpFilterError};
 
     #[test]
     fn parsing() {
@@ -179,6 +235,36 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_errors() {
+        let fields = |xs: &[&str]| xs.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert!(parse_bgp_message_vec::<P>(fields(&["0.0", "1.0.0.1"])).is_err());
+        assert!(parse_bgp_message_vec::<P>(fields(&[
+            "0.0", "1.0.0.1", "invalid", "aa", "bb", "1337", ""
+        ]))
+        .is_err());
+        let msg = parse_bgp_message_vec::<P>(fields(&[
+            "0.5",
+            "1.0.0.1",
+            "1.0.0.2",
+            "aa",
+            "bb",
+            "1337",
+            "100.0.0.0",
+        ]))
+        .unwrap();
+        assert_eq!(msg.tcp_seq, 1337);
+        assert_eq!(msg.prefixes, vec![P::from(0)]);
+    }
+
+    #[test]
+    fn filter_missing_pcap() {
+        let tmp = tempfile::tempdir().unwrap();
+        let pcap_path = tmp.path().join("nonexistent.pcap");
+        let result = BGPFilter::Announcements.filter::<P>(&pcap_path);
+        assert!(matches!(result, Err(BgpFilterError::PcapOpen(path, _)) if path == pcap_path));
+    }
+
     #[test]
     #[allow(non_snake_case)]
     fn serde_BGPMessage() {
@@ -3,24 +3,43 @@ use std::{
     fs,
     net::Ipv4Addr,
     path::Path,
-    process::Command,
 };
 
 use clap::Parser;
+use ipnet::Ipv4Net;
 use itertools::Itertools;
 
-use bgpsim::prelude::*;
+use bgpsim::{event::BasicEventQueue, prelude::*};
 use router_lab::hardware_mapping::HardwareMapping;
 
 use trix_utils::{
     bgp_utils::BGPFilter,
-    pcap_utils::{process_pcaps, process_scenarios},
+    pcap_utils::{prepare_pcap, process_pcaps, process_scenarios, ProcessedManifest},
     reaction_times::*,
     serde::CiscoAnalyzerData,
 };
 
 type Prefix = Ipv4Prefix;
 
+/// Load the original network from the `Analyzer` serialized in the scenario's `scenario.json`. Only
+/// the network's configuration is required, so the serialized queue is replaced by an empty
+/// `BasicEventQueue`.
+fn load_original_net(
+    scenario_path: &Path,
+) -> Result<Network<SimplePrefix, BasicEventQueue<SimplePrefix>>, Box<dyn std::error::Error>> {
+    let serialized = fs::read_to_string(scenario_path.join("scenario.json"))?;
+    let mut analyzer: serde_json::Value = serde_json::from_str(&serialized)?;
+    let mut net = analyzer
+        .get_mut("original_net")
+        .ok_or("missing field `original_net`")?
+        .take();
+    let queue = net
+        .get_mut("queue")
+        .ok_or("missing field `original_net.queue`")?;
+    *queue = serde_json::to_value(BasicEventQueue::<SimplePrefix>::new())?;
+    Ok(serde_json::from_value(net)?)
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about)] // get author/version information from Cargo.toml
 struct Args {
@@ -38,9 +57,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let filter_scenario_not = "KeepOther";
     let filter_scenario_end = "";
     let filter_sample_id = "";
+    let replace = false;
 
+    // skip the pcaps processed by previous runs
+    let manifest = ProcessedManifest::open(tmp_pcap_dir.join("processed_pcaps.csv"), replace)?;
     let _ = process_pcaps(
         tmp_pcap_dir,
+        Some(&manifest),
         filter_topo,
         filter_scenario,
         filter_scenario_not,
@@ -50,13 +73,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         },
     );
 
+    // skip the samples evaluated by previous runs
+    let scenario_manifest =
+        ProcessedManifest::open(tmp_pcap_dir.join("processed_scenarios.csv"), replace)?;
     let _ = process_scenarios(
         tmp_pcap_dir,
         filter_topo,
         filter_scenario,
         filter_scenario_not,
         filter_scenario_end,
-        |_topo_name, _scenario_name, scenario_path, data_path, _csv| {
+        |topo_name, scenario_name, scenario_path, data_path, _csv| {
+            let scenario = format!("{topo_name}/{scenario_name}");
             // evaluate the data captured by the cisco_analyzer
             let mut analyzer_csv_path = data_path.clone();
             analyzer_csv_path.push("cisco_analyzer.csv");
@@ -67,6 +94,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 return; // `return;` in a `for_each(...)` loop is equivalent to `continue;`
             }
             log::info!("Loading: {scenario_path:?}");
+            let net = match load_original_net(&scenario_path) {
+                Ok(net) => net,
+                Err(e) => {
+                    log::warn!(
+                        "Skipping scenario from {scenario_path:?} as its network cannot be loaded: {e}"
+                    );
+                    return;
+                }
+            };
             let analyzer_csv = fs::File::open(analyzer_csv_path).unwrap();
             let mut csv = csv::Reader::from_reader(analyzer_csv);
 
@@ -84,6 +120,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     );
                     continue;
                 }
+                if scenario_manifest.contains(&scenario, &record.execution_timestamp) {
+                    log::trace!(
+                        "skipping {} as it has been processed already",
+                        record.pcap_filename
+                    );
+                    continue;
+                }
 
                 assert!(record.packets_dropped == 0);
 
@@ -91,24 +134,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let mut orig_pcap_path = data_path.clone();
                 orig_pcap_path.push(&record.pcap_filename);
 
-                // set new location for faster unzip
-                let mut pcap_path = tmp_pcap_dir.to_path_buf();
-                pcap_path.push(&record.pcap_filename);
-
-                // unzip the pcap file
-                let _ = Command::new("cp")
-                    .args([
-                        &orig_pcap_path.to_string_lossy().to_string(),
-                        &pcap_path.to_string_lossy().to_string(),
-                    ])
-                    .output();
-
-                log::trace!("unzipping {pcap_path:?}");
-                let _ = Command::new("gunzip")
-                    .args([pcap_path.to_string_lossy().to_string()])
-                    .output();
-                // drop the .gz part of the filename
-                pcap_path.set_extension("");
+                // unzip the pcap file, reusing the unzipped file from previous runs
+                let pcap_path = prepare_pcap(&orig_pcap_path, tmp_pcap_dir);
 
                 // get probed_prefixes for optimization
                 let mut prober_result_path = data_path.clone();
@@ -126,6 +153,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     .unique()
                     .collect_vec();
                 log::trace!("probed_prefixes: {probed_prefixes:?}");
+                check_probed_prefixes(
+                    probed_prefixes.iter().map(|prefix| **prefix),
+                    net.get_known_prefixes().map(|prefix| {
+                        let prefix: Ipv4Net = (*prefix).into();
+                        Prefix::from(prefix)
+                    }),
+                );
 
                 // read hardware mapping and compose packet filter / map to forwarding updates for
                 // prober packets
@@ -136,32 +170,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let hardware_mapping: HardwareMapping =
                     serde_json::from_str(&serialized_hardware_mapping).unwrap();
 
-                // allows to get the `RouterId`, the router's BGP router-id, its local prober_src
-                // and its MAC prefix (first 4 bytes of the MAC)
-                let router_mapping: HashMap<String, Router> = HashMap::from_iter(
-                    hardware_mapping
-                        .iter()
-                        // external routers do not send prober packets
-                        .filter(|(_, router)| !router.is_external)
-                        .map(|(rid, router)| {
-                            (
-                                router.name.clone(),
-                                Router {
-                                    rid: *rid,
-                                    ip: router.ipv4,
-                                    prober_src_ip: router.prober_src_ip.unwrap(),
-                                    mac_prefix: router.ifaces[0]
-                                        .mac
-                                        .unwrap()
-                                        .to_string()
-                                        .split(':')
-                                        .take(4)
-                                        .join(":"),
-                                },
-                            )
-                        }),
-                );
-
                 // hashmap to store prefixes, allowing to fill the blanks for what tshark
                 // recognizes as a TCP retransmission
                 let mut lookup_prefixes = HashMap::new();
@@ -169,12 +177,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let mut delayer_tracking = HashSet::new();
 
                 // get all bgp messages
-                let bgp_announces = BGPFilter::Announcements
-                    .filter_with_delayers::<Prefix>(
-                        &pcap_path,
-                        &mut lookup_prefixes,
-                        &mut delayer_tracking,
-                    )
+                let messages = match BGPFilter::Announcements.filter_with_delayers::<Prefix>(
+                    &pcap_path,
+                    &mut lookup_prefixes,
+                    &mut delayer_tracking,
+                ) {
+                    Ok(messages) => messages,
+                    Err(e) => {
+                        log::error!("skipping {}: {e}", record.pcap_filename);
+                        continue;
+                    }
+                };
+                let bgp_announces = messages
+                    .filter_map(|bgp_message| {
+                        bgp_message
+                            .map_err(|e| log::warn!("{}: {e}", record.pcap_filename))
+                            .ok()
+                    })
                     //.filter(|bgp_message| !bgp_message.prefixes.is_empty())
                     .collect_vec()
                     .into_iter()
@@ -203,12 +222,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
                 let mut delayer_tracking = HashSet::new();
 
-                let bgp_withdraws = BGPFilter::Withdraws
-                    .filter_with_delayers::<Prefix>(
-                        &pcap_path,
-                        &mut lookup_prefixes,
-                        &mut delayer_tracking,
-                    )
+                let messages = match BGPFilter::Withdraws.filter_with_delayers::<Prefix>(
+                    &pcap_path,
+                    &mut lookup_prefixes,
+                    &mut delayer_tracking,
+                ) {
+                    Ok(messages) => messages,
+                    Err(e) => {
+                        log::error!("skipping {}: {e}", record.pcap_filename);
+                        continue;
+                    }
+                };
+                let bgp_withdraws = messages
+                    .filter_map(|bgp_message| {
+                        bgp_message
+                            .map_err(|e| log::warn!("{}: {e}", record.pcap_filename))
+                            .ok()
+                    })
                     //.filter(|bgp_message| !bgp_message.prefixes.is_empty())
                     .collect_vec()
                     .into_iter()
@@ -231,38 +261,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
                 log::trace!("withdraws: {bgp_withdraws:#?}");
 
-                // identify routers' roles based on the number of routers
-                let Some(first_router) = router_mapping.get("r0") else {
-                    log::trace!("skipping because router 'r0' could not be found!");
-                    continue;
-                };
-                log::trace!("first_router: {first_router:?}");
-                let Some(last_router) = router_mapping.iter().max_by_key(|(k, _)| *k).map(|x| x.1)
+                // identify routers' roles based on the BGP sessions of the original network
+                let Some(Roles {
+                    first: first_router,
+                    last: last_router,
+                    peers,
+                }) = identify_roles(&hardware_mapping, &net)
                 else {
-                    log::trace!("skipping because no router found!");
+                    log::trace!("skipping because the routers' roles could not be identified!");
                     continue;
                 };
+                log::trace!("first_router: {first_router:?}");
                 log::trace!("last_router: {last_router:?}");
-
-                // TODO: the following selection of peers only works for the full mesh case
-                let peers = router_mapping
-                    .values()
-                    .filter(|&Router { rid, .. }| *rid != last_router.rid)
-                    .collect_vec();
                 log::trace!("peers: {peers:#?}");
 
-                // TODO: requires peers to be configured equivalently for every prefix
-                // TODO: only gives current RIB out, i.e., before the convergence
-                /*
-                let bgp_state = analyzer.original_net.get_bgp_state(*analyzer.original_net.get_known_prefixes().next().unwrap());
-                let peers2 = router_mapping
-                    .iter()
-                    .map(|(_, router)| router)
-                    .filter(|(rid, _, _, _)| bgp_state.peers_outgoing(last_router.rid).contains(rid))
-                    .collect_vec();
-                log::debug!("peers2: {peers2:#?}");
-                */
-
                 // select messages caused by event and caused to resolve the routing
                 // TODO: match according to event type
                 let event_messages = bgp_withdraws;
@@ -311,19 +323,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         cp_reactions.push(cp_reaction);
                     }
 
-                    let min = cp_reactions.iter().copied().min_by(|a, b| a.total_cmp(b));
-                    let max = cp_reactions.iter().copied().max_by(|a, b| a.total_cmp(b));
                     reaction_times
                         .entry((notify_last.prefixes.len(), 0, peers.len(), **prefix))
                         .or_default()
-                        .push(ReactionTime {
-                            first_cp_reaction: min,
-                            last_cp_reaction: max,
-                            cp_reaction_increment: min
-                                .zip(max)
-                                .map(|(min, max)| (max - min) / (peers.len() - 1) as f64),
-                            dp_reaction: None,
-                        });
+                        .push(ReactionTime::from_cp_reactions(&cp_reactions, None));
+                }
+
+                // only record samples whose pcap has been evaluated completely
+                if let Err(e) = scenario_manifest.record(&scenario, &record.execution_timestamp) {
+                    log::error!("Cannot record {} as processed: {e}", record.pcap_filename);
                 }
             }
         },
@@ -1,10 +1,13 @@
 //! Module to handle PCAP files containing BGPseer experiment traffic
 
 use std::{
+    collections::HashSet,
     fs,
+    io::{self, BufRead, Write},
     net::Ipv4Addr,
     path::{Path, PathBuf},
     process::Command,
+    sync::Mutex,
 };
 
 use ipnet::Ipv4Net;
@@ -424,6 +427,11 @@ impl PacketFilter {
     /// For speeding up the processing, the pcap file is first prefiltered by `tcpdump` with all
     /// basic packet filters, and then piped into `tshark` to allow decoding more protocols.
     pub fn filter_pcap(&self, pcap_path: &Path) -> Vec<Vec<String>> {
+        self.try_filter_pcap(pcap_path).unwrap()
+    }
+
+    /// Same as `filter_pcap`, but returning an error if `tcpdump` or `tshark` cannot be executed.
+    pub fn try_filter_pcap(&self, pcap_path: &Path) -> std::io::Result<Vec<Vec<String>>> {
         #[rustfmt::skip]
         let tcpdump_args = [
             "-r", &pcap_path.to_string_lossy(),
@@ -453,35 +461,76 @@ impl PacketFilter {
             .args(tcpdump_args)
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
-            .spawn()
-            .unwrap();
+            .spawn()?;
 
         // extract output fields with tshark
         let tshark = Command::new("tshark")
             .args(tshark_args)
             .stdin(std::process::Stdio::from(tcpdump.stdout.unwrap()))
-            .output()
-            .unwrap()
+            .output()?
             .stdout;
 
         // split into rows and columns
-        String::from_utf8_lossy(&tshark)
+        Ok(String::from_utf8_lossy(&tshark)
             .split('\n')
             .filter(|line| !line.is_empty())
             .map(|line| line.split(';').map(|x| x.to_owned()).collect_vec())
-            .collect_vec()
+            .collect_vec())
     }
 }
 
-/// List all scenarios matching the filters.
+/// List all scenarios matching the filters (see `scenario_matches`).
 pub fn get_scenarios(
     filter_topo: &str,
     filter_scenario: &str,
     filter_scenario_not: &str,
     filter_scenario_end: &str,
 ) -> Vec<(String, String, PathBuf, PathBuf, csv::Reader<fs::File>)> {
-    fs::read_dir("./experiments/")
-        .expect("./experiments/ cannot be read")
+    //let data_root = "./data/";
+    let data_root = "/media/roschmi-data-hdd/orval-backup/data/";
+    get_scenarios_in(
+        Path::new("./experiments/"),
+        Path::new(data_root),
+        filter_topo,
+        filter_scenario,
+        filter_scenario_not,
+        filter_scenario_end,
+    )
+}
+
+/// Split a filter into its comma-separated patterns, ignoring empty ones.
+fn filter_patterns(filter: &str) -> impl Iterator<Item = &str> {
+    filter.split(',').filter(|pattern| !pattern.is_empty())
+}
+
+/// Check whether `scenario` matches the filters, each of which is a comma-separated list of
+/// patterns: The scenario must contain all patterns of `filter_scenario`, none of the patterns of
+/// `filter_scenario_not`, and end with one of the patterns of `filter_scenario_end`. Empty filters
+/// match all scenarios.
+pub fn scenario_matches(
+    scenario: &str,
+    filter_scenario: &str,
+    filter_scenario_not: &str,
+    filter_scenario_end: &str,
+) -> bool {
+    filter_patterns(filter_scenario).all(|pattern| scenario.contains(pattern))
+        && !filter_patterns(filter_scenario_not).any(|pattern| scenario.contains(pattern))
+        && (filter_scenario_end.is_empty()
+            || filter_patterns(filter_scenario_end).any(|pattern| scenario.ends_with(pattern)))
+}
+
+/// List all scenarios in `experiments_root` matching the filters, whose data is stored in
+/// `data_root`.
+pub fn get_scenarios_in(
+    experiments_root: &Path,
+    data_root: &Path,
+    filter_topo: &str,
+    filter_scenario: &str,
+    filter_scenario_not: &str,
+    filter_scenario_end: &str,
+) -> Vec<(String, String, PathBuf, PathBuf, csv::Reader<fs::File>)> {
+    fs::read_dir(experiments_root)
+        .unwrap_or_else(|_| panic!("{experiments_root:?} cannot be read"))
         .flat_map(|topo_dir| {
             let topo_path = topo_dir.unwrap().path();
 
@@ -501,9 +550,12 @@ pub fn get_scenarios(
                 })
                 .filter(|(topo_path, scenario)| {
                     topo_path.display().to_string().contains(filter_topo)
-                        && scenario.contains(filter_scenario)
-                        && !scenario.contains(filter_scenario_not)
-                        && scenario.ends_with(filter_scenario_end)
+                        && scenario_matches(
+                            scenario,
+                            filter_scenario,
+                            filter_scenario_not,
+                            filter_scenario_end,
+                        )
                 })
         })
         .unique()
@@ -521,9 +573,7 @@ pub fn get_scenarios(
             let scenario_name = scenario_path.file_name().unwrap();
             let topo_name = topo_path.file_name().unwrap();
 
-            //let data_root = "./data/";
-            let data_root = "/media/roschmi-data-hdd/orval-backup/data/";
-            let mut data_path = PathBuf::from(data_root);
+            let mut data_path = data_root.to_path_buf();
             data_path.push(format!("{}", topo_name.to_string_lossy()));
             data_path.push(format!("{}", scenario_name.to_string_lossy()));
 
@@ -605,52 +655,146 @@ pub fn get_pcaps(
     filter_scenario_not: &str,
     filter_scenario_end: &str,
 ) -> Vec<(PathBuf, CiscoAnalyzerData)> {
-    get_scenarios(
+    pcaps_of_scenarios(get_scenarios(
         filter_topo,
         filter_scenario,
         filter_scenario_not,
         filter_scenario_end,
-    )
+    ))
     .into_iter()
-    .flat_map(|(_, _, _, data_path, mut csv)| {
-        let mut result = Vec::new();
-        for record in csv.deserialize() {
-            let record: CiscoAnalyzerData = record.unwrap();
-            log::trace!("Reading from CSV:\n{record:#?}");
-
-            /*
-            if !record.execution_timestamp.contains(filter_sample_id) {
-                log::trace!(
-                    "skipping {} due to filter on sample_id...",
-                    record.pcap_filename
-                );
-                continue;
-            }
-            */
+    .map(|(_, pcap_path, record)| (pcap_path, record))
+    .collect_vec()
+}
 
-            if record.packets_dropped != 0 {
-                log::trace!(
-                    "skipping {} due to dropped packets...",
-                    record.pcap_filename
-                );
-                continue;
+/// Iterate over the (gzipped) pcaps of all samples in `experiments_root` matching the filters,
+/// whose data is stored in `data_root`. Samples with dropped packets are skipped.
+///
+/// In contrast to `process_pcaps`, the pcaps are neither copied nor unzipped. Use `prepare_pcap`
+/// to obtain an unzipped copy, e.g., in a parallel pipeline using rayon's `par_bridge`.
+pub fn iter_pcaps(
+    experiments_root: &Path,
+    data_root: &Path,
+    filter_topo: &str,
+    filter_scenario: &str,
+    filter_scenario_not: &str,
+    filter_scenario_end: &str,
+) -> impl Iterator<Item = PathBuf> {
+    pcaps_of_scenarios(get_scenarios_in(
+        experiments_root,
+        data_root,
+        filter_topo,
+        filter_scenario,
+        filter_scenario_not,
+        filter_scenario_end,
+    ))
+    .into_iter()
+    .map(|(_, pcap_path, _)| pcap_path)
+}
+
+/// List the pcaps of all samples of the given scenarios, skipping those with dropped packets. Each
+/// pcap is returned together with its scenario, as `{topo_name}/{scenario_name}`.
+fn pcaps_of_scenarios(
+    scenarios: Vec<(String, String, PathBuf, PathBuf, csv::Reader<fs::File>)>,
+) -> Vec<(String, PathBuf, CiscoAnalyzerData)> {
+    scenarios
+        .into_iter()
+        .flat_map(|(topo_name, scenario_name, _, data_path, mut csv)| {
+            let mut result = Vec::new();
+            for record in csv.deserialize() {
+                let record: CiscoAnalyzerData = record.unwrap();
+                log::trace!("Reading from CSV:\n{record:#?}");
+
+                /*
+                if !record.execution_timestamp.contains(filter_sample_id) {
+                    log::trace!(
+                        "skipping {} due to filter on sample_id...",
+                        record.pcap_filename
+                    );
+                    continue;
+                }
+                */
+
+                if record.packets_dropped != 0 {
+                    log::trace!(
+                        "skipping {} due to dropped packets...",
+                        record.pcap_filename
+                    );
+                    continue;
+                }
+
+                let mut orig_pcap_path = data_path.clone();
+                orig_pcap_path.push(&record.pcap_filename);
+
+                result.push((
+                    format!("{topo_name}/{scenario_name}"),
+                    orig_pcap_path,
+                    record,
+                ));
             }
+            result.into_iter()
+        })
+        .collect_vec()
+}
 
-            let mut orig_pcap_path = data_path.clone();
-            orig_pcap_path.push(&record.pcap_filename);
+/// Manifest of the samples processed by `process_pcaps` (or by the callback of
+/// `process_scenarios`), identified by their scenario and execution timestamp, such that later runs
+/// only process new samples. Each sample is appended to
+/// the manifest file as a line `{topo_name}/{scenario_name};{timestamp}` as soon as it is
+/// processed, such that an interrupted run can be resumed.
+#[derive(Debug)]
+pub struct ProcessedManifest {
+    processed: HashSet<(String, String)>,
+    file: Mutex<fs::File>,
+}
 
-            result.push((orig_pcap_path, record));
+impl ProcessedManifest {
+    /// Open the manifest at `path`, creating it if it does not exist yet. If `replace` is set, the
+    /// samples recorded so far are forgotten, such that all samples are processed again.
+    pub fn open(path: impl AsRef<Path>, replace: bool) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut processed = HashSet::new();
+        if replace {
+            let _ = fs::remove_file(path);
+        } else if path.exists() {
+            for line in io::BufReader::new(fs::File::open(path)?).lines() {
+                if let Some((scenario, timestamp)) = line?.split_once(';') {
+                    processed.insert((scenario.to_string(), timestamp.to_string()));
+                }
+            }
         }
-        result.into_iter()
-    })
-    .collect_vec()
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self {
+            processed,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Check whether the sample of `scenario` executed at `timestamp` was processed in a previous
+    /// run.
+    pub fn contains(&self, scenario: &str, timestamp: &str) -> bool {
+        self.processed
+            .contains(&(scenario.to_string(), timestamp.to_string()))
+    }
+
+    /// Record that the sample of `scenario` executed at `timestamp` has been processed.
+    pub fn record(&self, scenario: &str, timestamp: &str) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{scenario};{timestamp}")?;
+        file.flush()
+    }
 }
 
 /// Process pcaps in parallel for all experiments matching the filters.
 ///
-/// Copies pcaps to `tmp_pcap_dir` and applies the given function.
+/// Copies pcaps to `tmp_pcap_dir` and applies the given function. If a `manifest` is given,
+/// samples recorded in it are skipped, and all processed samples are recorded. Samples whose pcap
+/// cannot be unzipped are skipped (and not recorded).
 pub fn process_pcaps<F, T>(
     tmp_pcap_dir: &Path,
+    manifest: Option<&ProcessedManifest>,
     filter_topo: &str,
     filter_scenario: &str,
     filter_scenario_not: &str,
@@ -664,39 +808,66 @@ where
     fs::create_dir_all(tmp_pcap_dir)?;
 
     // get all (topo, scenario) combinations
-    Ok(get_pcaps(
+    let pcaps = pcaps_of_scenarios(get_scenarios(
         filter_topo,
         filter_scenario,
         filter_scenario_not,
         filter_scenario_end,
-    )
-    .into_par_iter()
-    .map(|(orig_pcap_path, record)| {
-        /*
-        #[cfg(feature = "incremental")]
-        if reaction_times
-            .iter()
-            .any(|s| s.sample_id == record.execution_timestamp)
-        {
-            log::trace!(
-                "skipping {} due to incremental processing...",
-                record.pcap_filename
-            );
-            continue;
-        }
-        */
-        let pcap_path = copy_and_unzip_pcap(&orig_pcap_path, tmp_pcap_dir, &record.pcap_filename);
-
-        let result = process_pcap(&pcap_path);
-
-        // remove the unzipped pcap file again
-        let _ = Command::new("rm")
-            .arg(pcap_path.to_string_lossy().to_string())
-            .output();
-
-        result
-    })
-    .collect::<Vec<_>>())
+    ));
+    process_samples(pcaps, tmp_pcap_dir, manifest, process_pcap)
+}
+
+/// Process the pcaps of the given samples in parallel, skipping those recorded in the `manifest`. A
+/// sample is only recorded once `process_pcap` returned on its unzipped pcap.
+fn process_samples<F, T>(
+    pcaps: Vec<(String, PathBuf, CiscoAnalyzerData)>,
+    tmp_pcap_dir: &Path,
+    manifest: Option<&ProcessedManifest>,
+    process_pcap: F,
+) -> Result<Vec<T>, Box<dyn std::error::Error>>
+where
+    F: Fn(&PathBuf) -> T + Send + Sync,
+    T: Clone + Send,
+{
+    pcaps
+        .into_par_iter()
+        .filter(|(scenario, _, record)| {
+            let processed =
+                manifest.is_some_and(|m| m.contains(scenario, &record.execution_timestamp));
+            if processed {
+                log::trace!(
+                    "skipping {} as it has been processed already",
+                    record.pcap_filename
+                );
+            }
+            !processed
+        })
+        .map(|(scenario, orig_pcap_path, record)| {
+            let pcap_path =
+                copy_and_unzip_pcap(&orig_pcap_path, tmp_pcap_dir, &record.pcap_filename);
+            if !pcap_path.exists() {
+                log::error!(
+                    "skipping {}: cannot copy and unzip {orig_pcap_path:?}",
+                    record.pcap_filename
+                );
+                return Ok(None);
+            }
+
+            let result = process_pcap(&pcap_path);
+
+            // remove the unzipped pcap file again
+            let _ = Command::new("rm")
+                .arg(pcap_path.to_string_lossy().to_string())
+                .output();
+
+            if let Some(manifest) = manifest {
+                manifest.record(&scenario, &record.execution_timestamp)?;
+            }
+            Ok(Some(result))
+        })
+        .collect::<Result<Vec<_>, io::Error>>()
+        .map(|results| results.into_iter().flatten().collect())
+        .map_err(|e| e.into())
 }
 
 /// Copies pcap to `tmp_pcap_dir` and unzips the file using `gunzip`.
@@ -727,6 +898,48 @@ pub fn copy_and_unzip_pcap(
     pcap_path
 }
 
+/// Unzips the pcap at `orig_pcap_path` into `cache_dir` and returns the path of the unzipped file.
+///
+/// The unzipped file is kept in `cache_dir`, such that later runs can reuse it. Decompression is
+/// skipped if the unzipped file is at least as recent as the original pcap.
+pub fn prepare_pcap(orig_pcap_path: &Path, cache_dir: &Path) -> PathBuf {
+    let mut pcap_path = cache_dir.to_path_buf();
+    pcap_path.push(orig_pcap_path.file_name().unwrap());
+    // drop the .gz part of the filename
+    pcap_path.set_extension("");
+
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    if let (Some(t_cached), Some(t_orig)) = (modified(&pcap_path), modified(orig_pcap_path)) {
+        if t_cached >= t_orig {
+            log::trace!("using cached {pcap_path:?}");
+            return pcap_path;
+        }
+    }
+
+    // unzip to a temporary file first, such that an interrupted run cannot leave a truncated pcap
+    // in the cache
+    log::trace!("unzipping {orig_pcap_path:?} to {pcap_path:?}");
+    let tmp_path = pcap_path.with_extension("pcap.part");
+    let _ = fs::create_dir_all(cache_dir);
+    match fs::File::create(&tmp_path).and_then(|file| {
+        Command::new("gunzip")
+            .arg("-c")
+            .arg(orig_pcap_path)
+            .stdout(file)
+            .status()
+    }) {
+        Ok(status) if status.success() => {
+            let _ = fs::rename(&tmp_path, &pcap_path);
+        }
+        result => {
+            log::error!("Could not unzip {orig_pcap_path:?}: {result:?}");
+            let _ = fs::remove_file(&tmp_path);
+        }
+    }
+
+    pcap_path
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -763,4 +976,188 @@ mod test {
         assert_eq!(MacPrefix::from("aa:bb:cc:dd").0, "0xaabbccdd");
         assert_eq!(MacPrefix::from("aa:bb:cc:dd:ee:ff").0, "0xaabbccdd");
     }
+
+    #[test]
+    fn scenario_filters() {
+        let scenario = "ExtAtEnds_FullMesh_Prefix100_WithdrawAtR0";
+        // single patterns keep working
+        assert!(scenario_matches(scenario, "", "", ""));
+        assert!(scenario_matches(scenario, "Withdraw", "KeepOther", "AtR0"));
+        assert!(!scenario_matches(scenario, "", "FullMesh", ""));
+
+        // two exclude patterns
+        assert!(scenario_matches(scenario, "", "KeepOther,Reflector", ""));
+        assert!(!scenario_matches(scenario, "", "KeepOther,Prefix100", ""));
+
+        // all include patterns must match, and any of the end patterns
+        assert!(scenario_matches(scenario, "FullMesh,Prefix100", "", ""));
+        assert!(!scenario_matches(scenario, "FullMesh,Prefix1000", "", ""));
+        assert!(scenario_matches(scenario, "", "", "AtR2,AtR0"));
+        assert!(!scenario_matches(scenario, "", "", "AtR2,AtR1"));
+    }
+
+    #[test]
+    fn iter_pcaps_filters() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let experiments_root = dir.join("experiments");
+        let data_root = dir.join("data");
+
+        // two scenarios with captured data, and one without
+        for (scenario, samples) in [
+            (
+                "Path03_WithdrawAtR0",
+                vec![("a.pcap.gz", 0), ("b.pcap.gz", 3)],
+            ),
+            ("Path03_WithdrawAtR2", vec![("c.pcap.gz", 0)]),
+            ("Path03_LinkFailure", vec![]),
+        ] {
+            let scenario_path = experiments_root.join("Path03").join(scenario);
+            fs::create_dir_all(&scenario_path).unwrap();
+            fs::write(scenario_path.join("scenario.json"), "{}").unwrap();
+            if samples.is_empty() {
+                continue;
+            }
+
+            let data_path = data_root.join("Path03").join(scenario);
+            fs::create_dir_all(&data_path).unwrap();
+            let mut csv = csv::Writer::from_path(data_path.join("cisco_analyzer.csv")).unwrap();
+            for (pcap_filename, packets_dropped) in samples {
+                csv.serialize(CiscoAnalyzerData {
+                    pcap_filename: pcap_filename.to_string(),
+                    packets_dropped,
+                    ..Default::default()
+                })
+                .unwrap();
+            }
+            csv.flush().unwrap();
+        }
+
+        let pcaps = |filter_scenario: &str, filter_scenario_not: &str| {
+            iter_pcaps(
+                &experiments_root,
+                &data_root,
+                "Path03",
+                filter_scenario,
+                filter_scenario_not,
+                "",
+            )
+            .filter_map(|p| p.file_name().map(|f| f.to_string_lossy().to_string()))
+            .sorted()
+            .collect_vec()
+        };
+
+        // samples with dropped packets are skipped
+        assert_eq!(pcaps("", ""), vec!["a.pcap.gz", "c.pcap.gz"]);
+        assert_eq!(pcaps("R2", ""), vec!["c.pcap.gz"]);
+        assert_eq!(pcaps("", "R2"), vec!["a.pcap.gz"]);
+        assert!(pcaps("Abilene", "").is_empty());
+        // multiple exclude patterns
+        assert!(pcaps("", "R0,R2").is_empty());
+        assert_eq!(pcaps("Withdraw", "R0,LinkFailure"), vec!["c.pcap.gz"]);
+    }
+
+    /// Write `content` gzipped to `path`.
+    fn gzip_fixture(path: &Path, content: &[u8]) {
+        let status = Command::new("gzip")
+            .arg("-c")
+            .stdin(std::process::Stdio::piped())
+            .stdout(fs::File::create(path).unwrap())
+            .spawn()
+            .and_then(|mut gzip| {
+                use std::io::Write;
+                gzip.stdin.take().unwrap().write_all(content)?;
+                gzip.wait()
+            })
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn processed_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let tmp_pcap_dir = dir.join("pcaps");
+        fs::create_dir_all(&tmp_pcap_dir).unwrap();
+        let manifest_path = dir.join("processed_pcaps.csv");
+        for ts in ["t0", "t1", "t2"] {
+            gzip_fixture(&dir.join(format!("{ts}.pcap.gz")), b"pcap data");
+        }
+
+        let samples = |timestamps: &[&str]| {
+            timestamps
+                .iter()
+                .map(|ts| {
+                    (
+                        "Path03/WithdrawAtR0".to_string(),
+                        dir.join(format!("{ts}.pcap.gz")),
+                        CiscoAnalyzerData {
+                            execution_timestamp: ts.to_string(),
+                            pcap_filename: format!("{ts}.pcap.gz"),
+                            ..Default::default()
+                        },
+                    )
+                })
+                .collect_vec()
+        };
+        let run = |timestamps: &[&str], replace: bool| {
+            let manifest = ProcessedManifest::open(&manifest_path, replace).unwrap();
+            process_samples(samples(timestamps), &tmp_pcap_dir, Some(&manifest), |p| {
+                p.file_name().unwrap().to_string_lossy().to_string()
+            })
+            .unwrap()
+            .into_iter()
+            .sorted()
+            .collect_vec()
+        };
+
+        assert_eq!(run(&["t0", "t1"], false), vec!["t0.pcap", "t1.pcap"]);
+        // a second run without new data does no work
+        assert!(run(&["t0", "t1"], false).is_empty());
+        // only new samples are processed
+        assert_eq!(run(&["t0", "t1", "t2"], false), vec!["t2.pcap"]);
+        // replacing processes all samples again
+        assert_eq!(
+            run(&["t0", "t1", "t2"], true),
+            vec!["t0.pcap", "t1.pcap", "t2.pcap"]
+        );
+        assert!(run(&["t0", "t1", "t2"], false).is_empty());
+
+        // samples whose pcap is missing are neither processed nor recorded
+        assert!(run(&["t3"], false).is_empty());
+        gzip_fixture(&dir.join("t3.pcap.gz"), b"pcap data");
+        assert_eq!(run(&["t3"], false), vec!["t3.pcap"]);
+    }
+
+    #[test]
+    fn prepare_pcap_cache() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let cache_dir = dir.join("cache");
+
+        // create a small gzipped fixture
+        let orig_pcap_path = dir.join("sample.pcap.gz");
+        gzip_fixture(&orig_pcap_path, b"pcap data");
+
+        // the first call unzips the pcap
+        let pcap_path = prepare_pcap(&orig_pcap_path, &cache_dir);
+        assert_eq!(pcap_path, cache_dir.join("sample.pcap"));
+        assert_eq!(fs::read(&pcap_path).unwrap(), b"pcap data");
+
+        // the second call is a cache hit and leaves the unzipped file untouched
+        fs::write(&pcap_path, b"cached").unwrap();
+        assert_eq!(prepare_pcap(&orig_pcap_path, &cache_dir), pcap_path);
+        assert_eq!(fs::read(&pcap_path).unwrap(), b"cached");
+
+        // an outdated unzipped file is replaced
+        let t_orig = fs::metadata(&orig_pcap_path).unwrap().modified().unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&pcap_path)
+            .unwrap()
+            .set_modified(t_orig - std::time::Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(prepare_pcap(&orig_pcap_path, &cache_dir), pcap_path);
+        assert_eq!(fs::read(&pcap_path).unwrap(), b"pcap data");
+    }
 }
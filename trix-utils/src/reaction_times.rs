@@ -1,19 +1,197 @@
 //! Module to evaluate BGP reaction times from a router
 
-use std::{collections::HashMap, net::Ipv4Addr};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    net::Ipv4Addr,
+};
 
+use itertools::{EitherOrBoth, Itertools};
 use serde::{Deserialize, Serialize};
 
-use bgpsim::types::RouterId;
+use bgpsim::{prelude::*, types::NetworkDeviceRef};
+use router_lab::hardware_mapping::{HardwareMapping, RouterMapping};
 
+/// Internal router sending prober packets. Two routers are equal if all their properties are equal.
+/// Use `Router::natural_cmp` to order routers by their name.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Router {
+    pub name: String,
     pub rid: RouterId,
     pub ip: Ipv4Addr,
     pub prober_src_ip: Ipv4Addr,
     pub mac_prefix: String,
 }
 
+impl Router {
+    /// Extract the `Router` from its `RouterMapping`, using the first 4 bytes of the first
+    /// interface's MAC address as its mac_prefix. Returns `None` for external routers, as they do
+    /// not send prober packets.
+    pub fn from_mapping(rid: RouterId, mapping: &RouterMapping) -> Option<Self> {
+        if mapping.is_external {
+            return None;
+        }
+        Some(Router {
+            name: mapping.name.clone(),
+            rid,
+            ip: mapping.ipv4,
+            prober_src_ip: mapping.prober_src_ip?,
+            mac_prefix: mapping
+                .ifaces
+                .first()?
+                .mac?
+                .to_string()
+                .split(':')
+                .take(4)
+                .join(":"),
+        })
+    }
+
+    /// Compare two routers by their names in natural order, i.e., comparing the numbers contained
+    /// in the names by their value, such that `r2` is ordered before `r10`. See `natural_cmp`.
+    pub fn natural_cmp(&self, other: &Self) -> Ordering {
+        natural_cmp(&self.name, &other.name)
+    }
+}
+
+/// Compare two strings in natural order. Both strings are split into runs of digits and runs of
+/// other characters. Runs of digits are compared by their numeric value, all other runs
+/// lexicographically. Strings that are equal in natural order (e.g., `r01` and `r1`) are ordered
+/// lexicographically.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    fn chunks(s: &str) -> impl Iterator<Item = (bool, &str)> {
+        s.as_bytes()
+            .chunk_by(|x, y| x.is_ascii_digit() == y.is_ascii_digit())
+            .scan(0, move |start, chunk| {
+                let range = *start..*start + chunk.len();
+                *start = range.end;
+                Some((chunk[0].is_ascii_digit(), &s[range]))
+            })
+    }
+
+    chunks(a)
+        .zip_longest(chunks(b))
+        .map(|pair| match pair {
+            EitherOrBoth::Both((true, x), (true, y)) => {
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            }
+            EitherOrBoth::Both((_, x), (_, y)) => x.cmp(y),
+            EitherOrBoth::Left(_) => Ordering::Greater,
+            EitherOrBoth::Right(_) => Ordering::Less,
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| a.cmp(b))
+}
+
+/// Roles of the internal routers when evaluating the reaction times to an event.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Roles {
+    /// Router `r0`, at which the event occurs and which sends the first BGP messages.
+    pub first: Router,
+    /// Router whose reaction is evaluated, i.e., the BGP neighbor of `first` whose name is last in
+    /// natural order (see `Router::natural_cmp`), such that `r10` is chosen over `r2`.
+    pub last: Router,
+    /// All internal BGP neighbors of `last`, to which it propagates its reaction, ordered by their
+    /// names in natural order.
+    pub peers: Vec<Router>,
+}
+
+/// Identify the routers' roles based on the BGP sessions configured in `net`, extracting the
+/// internal routers from the `hardware_mapping`. Returns `None` if router `r0` does not exist or
+/// has no internal BGP neighbor.
+pub fn identify_roles<P: Prefix, Q, Ospf: OspfImpl>(
+    hardware_mapping: &HardwareMapping,
+    net: &Network<P, Q, Ospf>,
+) -> Option<Roles> {
+    let routers = hardware_mapping
+        .iter()
+        .filter_map(|(rid, mapping)| {
+            Some((mapping.name.clone(), Router::from_mapping(*rid, mapping)?))
+        })
+        .collect();
+    identify_roles_from_routers(&routers, net)
+}
+
+/// Identify the roles of the given internal `routers` (indexed by their name) based on the BGP
+/// sessions configured in `net`. See `identify_roles`.
+pub fn identify_roles_from_routers<P: Prefix, Q, Ospf: OspfImpl>(
+    routers: &HashMap<String, Router>,
+    net: &Network<P, Q, Ospf>,
+) -> Option<Roles> {
+    let has_session = |a: RouterId, b: RouterId| {
+        a != b
+            && matches!(
+                net.get_device(a),
+                Ok(NetworkDeviceRef::InternalRouter(r)) if r.bgp.get_session_type(b).is_some()
+            )
+    };
+
+    let first = routers.get("r0")?.clone();
+    let last = routers
+        .values()
+        .filter(|router| has_session(first.rid, router.rid))
+        .max_by(|a, b| a.natural_cmp(b))?
+        .clone();
+    let peers = routers
+        .values()
+        .filter(|router| has_session(last.rid, router.rid))
+        .sorted_by(|a, b| a.natural_cmp(b))
+        .cloned()
+        .collect();
+
+    Some(Roles { first, last, peers })
+}
+
+/// Discrepancy between the prefixes probed by the prober and the prefixes known in the network.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrefixMismatch<P> {
+    /// Probed prefixes that are not known in the network, ordered.
+    pub not_in_network: Vec<P>,
+    /// Prefixes known in the network that were not probed, ordered.
+    pub not_probed: Vec<P>,
+    /// Whether no probed prefix is known in the network, such that no reaction time can be
+    /// matched at all.
+    pub disjoint: bool,
+}
+
+impl<P> PrefixMismatch<P> {
+    /// Check whether the probed prefixes match the known prefixes exactly.
+    pub fn is_empty(&self) -> bool {
+        self.not_in_network.is_empty() && self.not_probed.is_empty()
+    }
+}
+
+/// Compare the `probed` prefixes with the prefixes `known` in the network, logging a warning that
+/// lists any discrepancy. A misconfigured pairing of prober and topology would otherwise silently
+/// yield no reaction times for the affected prefixes.
+pub fn check_probed_prefixes<P: Prefix>(
+    probed: impl IntoIterator<Item = P>,
+    known: impl IntoIterator<Item = P>,
+) -> PrefixMismatch<P> {
+    let probed: HashSet<P> = probed.into_iter().collect();
+    let known: HashSet<P> = known.into_iter().collect();
+    let mismatch = PrefixMismatch {
+        not_in_network: probed.difference(&known).copied().sorted().collect(),
+        not_probed: known.difference(&probed).copied().sorted().collect(),
+        disjoint: probed.is_disjoint(&known),
+    };
+    if mismatch.disjoint {
+        log::warn!(
+            "None of the probed prefixes {:?} is known in the network {:?}!",
+            probed.iter().sorted().collect_vec(),
+            known.iter().sorted().collect_vec(),
+        );
+    } else if !mismatch.is_empty() {
+        log::warn!(
+            "Probed prefixes do not match the network! not in network: {:?}, not probed: {:?}",
+            mismatch.not_in_network,
+            mismatch.not_probed,
+        );
+    }
+    mismatch
+}
+
 pub type ReactionTimesMap<P> = HashMap<(usize, usize, usize, P), Vec<ReactionTime>>;
 pub type CPReactionTimesMap<P> = HashMap<(usize, usize, P), Vec<f64>>;
 pub type LastDPReactionTimesMap = HashMap<usize, Vec<f64>>;
@@ -29,7 +207,166 @@ pub struct ReactionTime {
     pub cp_reaction_increment: Option<f64>,
     #[serde(deserialize_with = "csv::invalid_option")]
     pub dp_reaction: Option<f64>,
+    /// Number of peers to which a reaction was observed, which may be less than the number of
+    /// peers of the router if not all of them received the update.
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub num_responding_peers: Option<usize>,
+}
+
+impl ReactionTime {
+    /// Summarize the control-plane reactions towards all peers that received the update, i.e.,
+    /// the time between receiving the event and sending the update to each responding peer. The
+    /// increment is the average time between two consecutive reactions, and is `None` if fewer
+    /// than two peers responded.
+    pub fn from_cp_reactions(cp_reactions: &[f64], dp_reaction: Option<f64>) -> Self {
+        let min = cp_reactions.iter().copied().min_by(|a, b| a.total_cmp(b));
+        let max = cp_reactions.iter().copied().max_by(|a, b| a.total_cmp(b));
+        let cp_reaction_increment = if cp_reactions.len() > 1 {
+            min.zip(max)
+                .map(|(min, max)| (max - min) / (cp_reactions.len() - 1) as f64)
+        } else {
+            None
+        };
+        Self {
+            first_cp_reaction: min,
+            last_cp_reaction: max,
+            cp_reaction_increment,
+            dp_reaction,
+            num_responding_peers: Some(cp_reactions.len()),
+        }
+    }
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use bgpsim::{builder::constant_link_weight, event::BasicEventQueue};
+
+    use super::*;
+
+    #[test]
+    fn reaction_time_single_peer() {
+        let reaction = ReactionTime::from_cp_reactions(&[0.25], None);
+        assert_eq!(reaction.first_cp_reaction, Some(0.25));
+        assert_eq!(reaction.last_cp_reaction, Some(0.25));
+        assert_eq!(reaction.cp_reaction_increment, None);
+        assert_eq!(reaction.num_responding_peers, Some(1));
+    }
+
+    #[test]
+    fn reaction_time_missing_peer() {
+        // three peers, of which only two received the update
+        let reaction = ReactionTime::from_cp_reactions(&[0.5, 0.1], Some(0.3));
+        assert_eq!(reaction.first_cp_reaction, Some(0.1));
+        assert_eq!(reaction.last_cp_reaction, Some(0.5));
+        assert!((reaction.cp_reaction_increment.unwrap() - 0.4).abs() < 1e-12);
+        assert_eq!(reaction.dp_reaction, Some(0.3));
+        assert_eq!(reaction.num_responding_peers, Some(2));
+
+        // no peer responded at all
+        let reaction = ReactionTime::from_cp_reactions(&[], None);
+        assert_eq!(reaction.first_cp_reaction, None);
+        assert_eq!(reaction.cp_reaction_increment, None);
+        assert_eq!(reaction.num_responding_peers, Some(0));
+    }
+
+    #[test]
+    fn probed_prefixes_mismatch() {
+        let p = |i: u32| SimplePrefix::from(i);
+
+        let mismatch = check_probed_prefixes([p(0), p(1)], [p(0), p(1)]);
+        assert!(mismatch.is_empty());
+        assert!(!mismatch.disjoint);
+
+        let mismatch = check_probed_prefixes([p(1), p(0)], [p(2), p(3)]);
+        assert_eq!(
+            mismatch,
+            PrefixMismatch {
+                not_in_network: vec![p(0), p(1)],
+                not_probed: vec![p(2), p(3)],
+                disjoint: true,
+            }
+        );
+
+        let mismatch = check_probed_prefixes([p(0), p(1)], [p(1), p(2)]);
+        assert_eq!(mismatch.not_in_network, vec![p(0)]);
+        assert_eq!(mismatch.not_probed, vec![p(2)]);
+        assert!(!mismatch.disjoint);
+    }
+
+    #[test]
+    fn natural_order() {
+        assert_eq!(natural_cmp("r2", "r10"), Ordering::Less);
+        assert_eq!(natural_cmp("r10", "r2"), Ordering::Greater);
+        assert_eq!(natural_cmp("r10", "r10"), Ordering::Equal);
+        assert_eq!(natural_cmp("r1", "r1_ext"), Ordering::Less);
+        assert_eq!(natural_cmp("r9_ext", "r10"), Ordering::Less);
+        assert_eq!(natural_cmp("r01", "r1"), Ordering::Less);
+
+        let router = |name: &str| Router {
+            name: name.to_string(),
+            rid: RouterId::from(0),
+            ip: Ipv4Addr::new(1, 0, 0, 0),
+            prober_src_ip: Ipv4Addr::new(2, 0, 0, 0),
+            mac_prefix: String::new(),
+        };
+        assert_eq!(router("r2").natural_cmp(&router("r10")), Ordering::Less);
+        let names = ["r10", "r2", "r1", "r20"].map(router);
+        assert_eq!(
+            names
+                .iter()
+                .sorted_by(|a, b| a.natural_cmp(b))
+                .map(|r| r.name.as_str())
+                .collect_vec(),
+            vec!["r1", "r2", "r10", "r20"]
+        );
+    }
+
+    #[test]
+    fn identify_roles_route_reflector() {
+        // r1 reflects routes between r0 and r2, r2 reflects routes to r3.
+        let mut net: Network<SimplePrefix, BasicEventQueue<SimplePrefix>> =
+            Network::new(BasicEventQueue::new());
+        let rids = (0..4)
+            .map(|i| net.add_router(format!("r{i}")))
+            .collect_vec();
+        for (a, b) in rids.iter().tuple_windows() {
+            net.add_link(*a, *b).unwrap();
+        }
+        net.build_link_weights(constant_link_weight, 1.0).unwrap();
+        for (rr, client) in [(1, 0), (1, 2), (2, 3)] {
+            net.set_bgp_session(rids[rr], rids[client], Some(BgpSessionType::IBgpClient))
+                .unwrap();
+        }
+
+        let routers: HashMap<String, Router> = rids
+            .iter()
+            .map(|rid| {
+                let i = rid.index() as u8;
+                (
+                    format!("r{}", rid.index()),
+                    Router {
+                        name: format!("r{}", rid.index()),
+                        rid: *rid,
+                        ip: Ipv4Addr::new(1, 0, 0, i),
+                        prober_src_ip: Ipv4Addr::new(2, 0, 0, i),
+                        mac_prefix: format!("de:ad:00:{i:02x}"),
+                    },
+                )
+            })
+            .collect();
+
+        let roles = identify_roles_from_routers(&routers, &net).unwrap();
+        assert_eq!(roles.first, routers["r0"]);
+        // the last router `r3` in natural order has no session with `r0`
+        assert_eq!(roles.last, routers["r1"]);
+        assert_eq!(
+            roles.peers,
+            vec![routers["r0"].clone(), routers["r2"].clone()]
+        );
+
+        // without `r0`, no roles can be identified
+        let mut routers = routers;
+        routers.remove("r0");
+        assert_eq!(identify_roles_from_routers(&routers, &net), None);
+    }
+}
@@ -113,3 +113,132 @@ pub mod in_option {
         }
     }
 }
+
+/// (De-)serialize a nested `HashMap<K, HashMap<K2, V>>`, where both the outer and the inner keys
+/// may be non-string types. Both levels are represented as a sequence of entries, as done for a
+/// flat `HashMap` in `generic_hashmap`.
+///
+/// Example:
+/// ```ignore
+/// #[derive(Serialize, Deserialize)]
+/// struct Delays {
+///     #[serde(with = "trix_utils::serde::generic_hashmap::nested")]
+///     delays: HashMap<Prefix, HashMap<(RouterId, RouterId), f64>>,
+/// }
+/// ```
+pub mod nested {
+    use std::{collections::HashMap, hash::Hash};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Entry;
+
+    /// Serializes a borrowed inner `HashMap` as a sequence of entries.
+    struct InnerRef<'a, K, V>(&'a HashMap<K, V>);
+
+    impl<K: Serialize, V: Serialize> Serialize for InnerRef<'_, K, V> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::serialize(self.0, serializer)
+        }
+    }
+
+    /// Deserializes an inner `HashMap` from a sequence of entries.
+    #[derive(Deserialize)]
+    #[serde(transparent)]
+    struct Inner<K, V>(
+        #[serde(with = "super")]
+        #[serde(bound(deserialize = "K: Deserialize<'de> + Eq + Hash, V: Deserialize<'de>"))]
+        HashMap<K, V>,
+    );
+
+    /// Serialize a nested `HashMap` whose keys are serializable
+    pub fn serialize<K: Serialize, K2: Serialize, V: Serialize, S: Serializer>(
+        map: &HashMap<K, HashMap<K2, V>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map.iter().map(|(key, val)| Entry {
+            key,
+            val: InnerRef(val),
+        }))
+    }
+
+    /// Deserialize a nested `HashMap` whose keys are serializable
+    pub fn deserialize<'de, K, K2, V, D>(
+        deserializer: D,
+    ) -> Result<HashMap<K, HashMap<K2, V>>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        K2: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Vec::<Entry<K, Inner<K2, V>>>::deserialize(deserializer).map(|v| {
+            v.into_iter()
+                .map(|entry: Entry<K, Inner<K2, V>>| (entry.key, entry.val.0))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use bgpsim::types::{RouterId, SimplePrefix};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Nested<K, K2, V>
+    where
+        K: Eq + std::hash::Hash,
+        K2: Eq + std::hash::Hash,
+    {
+        #[serde(with = "super::nested")]
+        #[serde(bound(
+            serialize = "K: Serialize, K2: Serialize, V: Serialize",
+            deserialize = "K: Deserialize<'de>, K2: Deserialize<'de>, V: Deserialize<'de>"
+        ))]
+        map: HashMap<K, HashMap<K2, V>>,
+    }
+
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> T {
+        serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn nested_tuple_keys() {
+        let (r0, r1, r2) = (RouterId::from(0), RouterId::from(1), RouterId::from(2));
+        let value = Nested {
+            map: HashMap::from([
+                ((r0, r1), HashMap::from([((r1, r2), 1.5), ((r2, r1), 2.5)])),
+                ((r1, r0), HashMap::new()),
+            ]),
+        };
+        assert_eq!(round_trip(&value), value);
+    }
+
+    #[test]
+    fn nested_prefix_keys() {
+        let (p0, p1) = (SimplePrefix::from(0), SimplePrefix::from(1));
+        let value = Nested {
+            map: HashMap::from([
+                (
+                    p0,
+                    HashMap::from([
+                        (RouterId::from(0), vec![0.1, 0.2]),
+                        (RouterId::from(3), vec![]),
+                    ]),
+                ),
+                (p1, HashMap::from([(RouterId::from(1), vec![1.0])])),
+            ]),
+        };
+        assert_eq!(round_trip(&value), value);
+
+        // the empty map is an empty sequence
+        let empty: Nested<SimplePrefix, RouterId, f64> = Nested {
+            map: HashMap::new(),
+        };
+        assert_eq!(serde_json::to_string(&empty).unwrap(), r#"{"map":[]}"#);
+        assert_eq!(round_trip(&empty), empty);
+    }
+}
@@ -2,8 +2,20 @@
 
 pub mod generic_hashmap;
 
+use std::{fs, io, path::Path};
+
 use serde::{Deserialize, Serialize};
 
+/// Current schema version of the `cisco_analyzer.csv` rows written by the `Analyzer`.
+///
+/// - `0`: legacy files without a `schema_version` column
+/// - `1`: adds the `iperf_clients`, `iperf_udp`, and `schema_version` columns
+pub const CISCO_ANALYZER_SCHEMA_VERSION: u32 = 1;
+
+/// Default number of parallel iperf clients generating background traffic, also assumed for rows
+/// written before the number of clients was recorded.
+pub const DEFAULT_IPERF_CLIENTS: usize = 1;
+
 /// Struct used to (de-)serialize BGPseer's `Analyzer` collected data for a single sample ran on
 /// hardware
 #[derive(Debug, Deserialize, Serialize)]
@@ -26,8 +38,187 @@ pub struct CiscoAnalyzerData {
     pub hardware_mapping_filename: String,
     #[serde(default)]
     pub packets_dropped: usize,
+    /// Number of parallel iperf clients (each ~1Gbps) generating background traffic, zero if
+    /// disabled
+    #[serde(default = "_default_iperf_clients")]
+    pub iperf_clients: usize,
+    /// Whether the background traffic used UDP instead of TCP
+    #[serde(default)]
+    pub iperf_udp: bool,
+    /// Schema version this row was written with, see [`CISCO_ANALYZER_SCHEMA_VERSION`]
+    #[serde(default)]
+    pub schema_version: u32,
+}
+
+impl Default for CiscoAnalyzerData {
+    /// Use the same defaults as for deserializing rows with missing columns.
+    fn default() -> Self {
+        Self {
+            execution_timestamp: String::new(),
+            execution_duration: 0.0,
+            event_start: 0.0,
+            prober_result_filename: String::new(),
+            pcap_filename: String::new(),
+            capture_frequency: _one_thousand_u64(),
+            hardware_mapping_filename: String::new(),
+            packets_dropped: 0,
+            iperf_clients: _default_iperf_clients(),
+            iperf_udp: false,
+            schema_version: 0,
+        }
+    }
+}
+
+impl CiscoAnalyzerData {
+    /// Get the CSV header of the current schema version.
+    pub fn headers() -> csv::StringRecord {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.serialize(Self::default()).unwrap();
+        let data = writer.into_inner().unwrap();
+        csv::Reader::from_reader(data.as_slice())
+            .headers()
+            .unwrap()
+            .clone()
+    }
+
+    /// Read all rows of a `cisco_analyzer.csv` file, upgrading rows of older schema versions.
+    ///
+    /// Rows are interpreted using the header of the file. Rows that were appended by a newer
+    /// version of the `Analyzer` to a file with an older header are interpreted using the current
+    /// header instead. Rows written with a schema version newer than
+    /// [`CISCO_ANALYZER_SCHEMA_VERSION`] are rejected.
+    pub fn read_all<R: io::Read>(reader: R) -> Result<Vec<Self>, csv::Error> {
+        let mut csv = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+        let file_headers = csv.headers()?.clone();
+        let headers = Self::headers();
+
+        csv.records()
+            .map(|record| {
+                let record = record?;
+                let record_headers =
+                    if record.len() != file_headers.len() && record.len() == headers.len() {
+                        &headers
+                    } else {
+                        &file_headers
+                    };
+                let data: Self = record.deserialize(Some(record_headers))?;
+                if data.schema_version > CISCO_ANALYZER_SCHEMA_VERSION {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "unsupported schema version {} (expected at most {})",
+                            data.schema_version, CISCO_ANALYZER_SCHEMA_VERSION
+                        ),
+                    )
+                    .into());
+                }
+                Ok(data)
+            })
+            .collect()
+    }
+
+    /// Rewrite the `cisco_analyzer.csv` file at `path` using the current header if it was written
+    /// with an older schema version, such that new rows can be appended safely. The rows are
+    /// written to a temporary file next to `path` first, which then replaces `path`, such that an
+    /// interrupted upgrade leaves the original file intact.
+    pub fn upgrade_file(path: &Path) -> Result<(), csv::Error> {
+        if !path.exists() || fs::metadata(path)?.len() == 0 {
+            return Ok(());
+        }
+        let file_headers = csv::Reader::from_path(path)?.headers()?.clone();
+        if file_headers == Self::headers() {
+            return Ok(());
+        }
+
+        log::info!("Upgrading {path:?} to schema version {CISCO_ANALYZER_SCHEMA_VERSION}");
+        let rows = Self::read_all(fs::File::open(path)?)?;
+        let tmp_path = path.with_extension("csv.tmp");
+        let mut csv = csv::Writer::from_path(&tmp_path)?;
+        for row in rows {
+            csv.serialize(row)?;
+        }
+        csv.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
 }
 
 const fn _one_thousand_u64() -> u64 {
     1_000
 }
+
+const fn _default_iperf_clients() -> usize {
+    DEFAULT_IPERF_CLIENTS
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{CiscoAnalyzerData, CISCO_ANALYZER_SCHEMA_VERSION, DEFAULT_IPERF_CLIENTS};
+
+    const LEGACY_DATA: &str = "\
+execution_timestamp,execution_duration,event_start,prober_result_filename,pcap_filename,capture_frequency,hardware_mapping_filename,packets_dropped
+2024-09-12_14-24-06,100.5,1726143889.1,prober_results_a.json,pcap_a.pcap.gz,1000,hardware_mapping_a.json,0
+";
+
+    #[test]
+    fn read_mixed_schema_versions() {
+        let data = format!(
+            "{LEGACY_DATA}2024-09-12_14-25-42,101.5,1726143989.1,prober_results_b.json,pcap_b.pcap.gz,1000,hardware_mapping_b.json,3,2,true,1\n"
+        );
+        let rows = CiscoAnalyzerData::read_all(data.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 2);
+
+        assert_eq!(rows[0].schema_version, 0);
+        assert_eq!(rows[0].iperf_clients, DEFAULT_IPERF_CLIENTS);
+        assert!(!rows[0].iperf_udp);
+
+        assert_eq!(rows[1].schema_version, CISCO_ANALYZER_SCHEMA_VERSION);
+        assert_eq!(rows[1].packets_dropped, 3);
+        assert_eq!(rows[1].iperf_clients, 2);
+        assert!(rows[1].iperf_udp);
+    }
+
+    #[test]
+    fn reject_future_schema_version() {
+        let mut data = CiscoAnalyzerData::headers()
+            .iter()
+            .collect::<Vec<_>>()
+            .join(",");
+        data.push_str("\nts,1.0,1.0,prober.json,pcap.pcap.gz,1000,hw.json,0,1,false,99\n");
+        assert!(CiscoAnalyzerData::read_all(data.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn default_matches_missing_columns() {
+        let legacy = CiscoAnalyzerData::read_all(LEGACY_DATA.as_bytes()).unwrap();
+        let default = CiscoAnalyzerData::default();
+        assert_eq!(default.iperf_clients, legacy[0].iperf_clients);
+        assert_eq!(default.iperf_udp, legacy[0].iperf_udp);
+        assert_eq!(default.capture_frequency, legacy[0].capture_frequency);
+        assert_eq!(default.schema_version, legacy[0].schema_version);
+    }
+
+    #[test]
+    fn upgrade_legacy_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("cisco_analyzer.csv");
+        fs::write(&path, LEGACY_DATA).unwrap();
+
+        CiscoAnalyzerData::upgrade_file(&path).unwrap();
+        let headers = csv::Reader::from_path(&path)
+            .unwrap()
+            .headers()
+            .unwrap()
+            .clone();
+        assert_eq!(headers, CiscoAnalyzerData::headers());
+        let rows = CiscoAnalyzerData::read_all(fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].execution_timestamp, "2024-09-12_14-24-06");
+        assert_eq!(rows[0].iperf_clients, DEFAULT_IPERF_CLIENTS);
+
+        // only the upgraded file remains in the directory
+        assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 1);
+    }
+}